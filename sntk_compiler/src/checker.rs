@@ -1,23 +1,52 @@
 use crate::{compiler::CompileResult, TypeError, TypeErrorKind};
 use sntk_core::{
-    parser::ast::{DataType, DataTypeKind, FunctionType, Parameter, Position},
+    diagnostic::Label,
+    formatter::format_operator,
+    parser::ast::{DataType, DataTypeKind, EnumType, FunctionType, Generic, Parameter, Span, StructType},
     tokenizer::token::TokenKind,
 };
-use sntk_ir::instruction::{InstructionType, IrExpression, LiteralValue};
-use std::collections::HashMap;
+use sntk_ir::{
+    builtin::builtin_types,
+    instruction::{Block, Instruction, InstructionType, IrExpression, IrExpressionKind, IrPattern, IrPatternKind, LiteralValue},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
-#[derive(Debug, Clone, PartialEq)]
+/// The names declared in a scope and the scopes around it. A checker takes a copy of the scopes at every sub-expression, so
+/// they are shared, and copied only when a scope is changed while a copy of it is still around.
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct DeclaredTypes {
-    pub types: HashMap<String, DataType>,
-    pub parent: Option<Box<DeclaredTypes>>,
+    pub types: Rc<HashMap<String, DataType>>,
+    pub mutables: Rc<HashSet<String>>,
+    pub spans: Rc<HashMap<String, Span>>, // where the names are declared, for errors to point back at
+    pub parent: Option<Rc<DeclaredTypes>>,
 }
 
 impl DeclaredTypes {
     #[inline]
     pub fn new(parent: Option<DeclaredTypes>) -> Self {
         Self {
-            types: HashMap::new(),
-            parent: parent.map(Box::new),
+            types: Rc::default(),
+            mutables: Rc::default(),
+            spans: Rc::default(),
+            parent: parent.map(Rc::new),
+        }
+    }
+
+    /// The outermost scope of a program, holding the builtins that need no `declare`.
+    pub fn builtins() -> Self {
+        Self {
+            types: Rc::new(
+                builtin_types()
+                    .into_iter()
+                    .map(|(name, data_type)| (name.to_string(), data_type))
+                    .collect(),
+            ),
+            mutables: Rc::default(),
+            spans: Rc::default(),
+            parent: None,
         }
     }
 
@@ -31,24 +60,77 @@ impl DeclaredTypes {
         }
     }
 
+    /// Declares `name` as immutable, shadowing any earlier declaration of the same name.
     #[inline]
     pub fn set(&mut self, name: String, value: DataType) {
-        self.types.insert(name, value);
+        if self.mutables.contains(&name) {
+            Rc::make_mut(&mut self.mutables).remove(&name);
+        }
+
+        self.forget_span(&name);
+        Rc::make_mut(&mut self.types).insert(name, value);
+    }
+
+    #[inline]
+    pub fn set_mutable(&mut self, name: String, value: DataType) {
+        self.forget_span(&name);
+        Rc::make_mut(&mut self.mutables).insert(name.clone());
+        Rc::make_mut(&mut self.types).insert(name, value);
+    }
+
+    /// Records where `name`, just declared in this scope, is declared.
+    #[inline]
+    pub fn set_span(&mut self, name: String, span: Span) {
+        Rc::make_mut(&mut self.spans).insert(name, span);
+    }
+
+    /// Where the declaration `name` refers to is, unless it was declared without one to point at, as a builtin is.
+    pub fn span(&self, name: &str) -> Option<Span> {
+        if self.types.contains_key(name) {
+            self.spans.get(name).copied()
+        } else {
+            self.parent.as_ref().and_then(|parent| parent.span(name))
+        }
+    }
+
+    /// Forgets where an earlier declaration of `name` in this scope is, which a new one shadows.
+    fn forget_span(&mut self, name: &str) {
+        if self.spans.contains_key(name) {
+            Rc::make_mut(&mut self.spans).remove(name);
+        }
+    }
+
+    /// Whether `name` is declared in a scope inside the outermost one.
+    pub fn is_local(&self, name: &str) -> bool {
+        self.parent
+            .as_ref()
+            .is_some_and(|parent| self.types.contains_key(name) || parent.is_local(name))
+    }
+
+    pub fn is_mutable(&self, name: &str) -> bool {
+        if self.types.contains_key(name) {
+            self.mutables.contains(name)
+        } else {
+            self.parent.as_ref().is_some_and(|parent| parent.is_mutable(name))
+        }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// The types declared in a scope and the scopes around it, shared like `DeclaredTypes`.
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct CustomTypes {
-    pub types: HashMap<String, DataType>,
-    pub parent: Option<Box<CustomTypes>>,
+    pub types: Rc<HashMap<String, DataType>>,
+    pub generics: Rc<HashMap<String, Vec<String>>>,
+    pub parent: Option<Rc<CustomTypes>>,
 }
 
 impl CustomTypes {
     #[inline]
     pub fn new(parent: Option<CustomTypes>) -> Self {
         Self {
-            types: HashMap::new(),
-            parent: parent.map(Box::new),
+            types: Rc::default(),
+            generics: Rc::default(),
+            parent: parent.map(Rc::new),
         }
     }
 
@@ -62,9 +144,30 @@ impl CustomTypes {
         }
     }
 
+    /// Type parameters of the type declared as `name`, empty if it is not generic.
+    pub fn get_generics(&self, name: String) -> Vec<String> {
+        match self.types.get(&name) {
+            Some(_) => self.generics.get(&name).cloned().unwrap_or_default(),
+            None => match &self.parent {
+                Some(parent) => parent.get_generics(name),
+                None => Vec::new(),
+            },
+        }
+    }
+
     #[inline]
     pub fn set(&mut self, name: String, value: DataType) {
-        self.types.insert(name, value);
+        if self.generics.contains_key(&name) {
+            Rc::make_mut(&mut self.generics).remove(&name);
+        }
+
+        Rc::make_mut(&mut self.types).insert(name, value);
+    }
+
+    /// Declares a generic type, whose type parameters must already be replaced with `DataTypeKind::TypeParameter`s in `value`.
+    pub fn set_generic(&mut self, name: String, generics: Vec<String>, value: DataType) {
+        Rc::make_mut(&mut self.types).insert(name.clone(), value);
+        Rc::make_mut(&mut self.generics).insert(name, generics);
     }
 }
 
@@ -73,40 +176,56 @@ pub struct Checker {
     data_type: Option<DataType>,
     declares: DeclaredTypes,
     customs: CustomTypes,
-    position: Position,
 }
 
 impl Checker {
     #[inline]
-    pub fn new(data_type: Option<&DataType>, declares: &DeclaredTypes, customs: &CustomTypes, position: Position) -> CompileResult<Self> {
+    pub fn new(data_type: Option<&DataType>, declares: &DeclaredTypes, customs: &CustomTypes) -> CompileResult<Self> {
         Ok(Self {
             data_type: data_type.map(|data_type| custom_data_type(data_type, customs)).transpose()?,
             declares: declares.clone(),
             customs: customs.clone(),
-            position,
         })
     }
 
     pub fn get_type_from_ir_expression(&self, expression: &IrExpression) -> CompileResult<DataType> {
-        let result = match expression.clone() {
-            IrExpression::Identifier(identifier) => match self.declares.get(identifier.clone()) {
+        let IrExpression { expression, span } = expression.clone();
+
+        let result = match expression {
+            IrExpressionKind::Identifier(identifier) => match self.declares.get(identifier.clone()) {
                 Some(data_type) => Ok(data_type),
-                None => Err(TypeError::new(TypeErrorKind::UndefinedIdentifier(identifier), self.position, 3)),
+                None => Err(TypeError::new(TypeErrorKind::UndefinedIdentifier(identifier), span)),
             },
-            IrExpression::Literal(literal) => self.get_type_from_literal_value(&literal),
-            IrExpression::Block(block) => self.get_type_from_ir_expression(match block.last() {
-                Some(instruction) => match instruction.instruction {
-                    InstructionType::Return(ref expression) | InstructionType::StoreName(_, ref expression) => expression,
-                    _ => return Ok(DataType::new(DataTypeKind::Boolean, self.position)),
-                },
-                None => return Ok(DataType::new(DataTypeKind::Boolean, self.position)),
-            }),
-            IrExpression::If(condition, consequence, alternative) => {
+            IrExpressionKind::Literal(literal) => self.get_type_from_literal_value(&literal, span),
+            IrExpressionKind::Block(block) => match block.split_last() {
+                Some((
+                    Instruction {
+                        instruction: InstructionType::Value(expression),
+                        ..
+                    },
+                    instructions,
+                )) => {
+                    let mut checker = self.within(&instructions.iter().map(Binding::Instruction).collect::<Vec<_>>())?;
+                    checker.data_type = self.data_type.clone();
+
+                    checker.get_type_from_ir_expression(expression)
+                }
+                _ => Ok(DataType::new(DataTypeKind::Void, span)),
+            },
+            IrExpressionKind::If(condition, consequence, alternative) => {
                 let condition_type = self.get_type_from_ir_expression(&condition)?;
                 let consequence_type = self.get_type_from_ir_expression(&consequence)?;
-                let alternative_type = match *alternative {
-                    Some(alternative) => self.get_type_from_ir_expression(&alternative)?,
-                    None => return Err(TypeError::new(TypeErrorKind::IfExpressionWithoutAlternative, self.position, 100)),
+                let (consequence_type, alternative_type) = match *alternative {
+                    Some(ref alternative) => (consequence_type, self.get_type_from_ir_expression(alternative)?),
+                    // an `if` without an `else` is a statement, whatever its branch gives
+                    None => (DataType::new(DataTypeKind::Void, span), DataType::new(DataTypeKind::Void, span)),
+                };
+
+                // a branch that never ends, as with a `return`, takes the type of the other one
+                let (consequence_type, alternative_type) = match (diverges(&consequence), alternative.as_ref().as_ref().is_some_and(diverges)) {
+                    (true, false) => (alternative_type.clone(), alternative_type),
+                    (false, true) => (consequence_type.clone(), consequence_type),
+                    _ => (consequence_type, alternative_type),
                 };
 
                 // check if condition is boolean
@@ -117,10 +236,10 @@ impl Checker {
                     }
                     // if consequence and alternative are not the same type
                     else {
-                        Err(TypeError::new(
+                        Err(TypeError::with_labels(
                             TypeErrorKind::ExpectedDataType(consequence_type.to_string(), alternative_type.to_string()),
-                            self.position,
-                            4,
+                            alternative.map_or(span, |alternative| alternative.span),
+                            vec![Label::secondary(consequence.span, Some(format!("this branch is `{consequence_type}`")))],
                         ))
                     }
                 }
@@ -128,29 +247,28 @@ impl Checker {
                 else {
                     Err(TypeError::new(
                         TypeErrorKind::ExpectedDataType(DataTypeKind::Boolean.to_string(), condition_type.to_string()),
-                        self.position,
-                        5,
+                        condition.span,
                     ))
                 }
             }
-            IrExpression::Call(function, arguments) => {
+            IrExpressionKind::Call(function, arguments) => {
                 let function_type = self.get_type_from_ir_expression(&function)?;
 
                 match function_type.data_type {
-                    DataTypeKind::Fn(FunctionType { parameters, return_type, .. }) => {
+                    DataTypeKind::Fn(function_type) => {
+                        let FunctionType { parameters, return_type, .. } = self.instantiate_function(function_type, &arguments, span)?;
                         let mut arguments_len = arguments.len();
 
                         for (index, ((parameter, spread), argument)) in parameters.iter().zip(arguments.iter()).enumerate() {
-                            let argument_type = self.get_type_from_ir_expression(argument)?;
-
                             // if parameter is spread
                             if *spread {
-                                let parameter = DataType::new(DataTypeKind::Array(Box::new(parameter.clone())), self.position);
+                                let parameter = DataType::new(DataTypeKind::Array(Box::new(parameter.clone())), parameter.span);
+                                let argument_type = self.expecting(Some(&parameter)).get_type_from_ir_expression(argument)?;
+
                                 if parameter != argument_type {
                                     return Err(TypeError::new(
                                         TypeErrorKind::ExpectedDataType(parameter.to_string(), argument_type.to_string()),
-                                        self.position,
-                                        6,
+                                        argument.span,
                                     ));
                                 }
 
@@ -158,81 +276,307 @@ impl Checker {
                                 break;
                             }
 
+                            let argument_type = self.expecting(Some(parameter)).get_type_from_ir_expression(argument)?;
+
                             // if parameter and argument are not the same type
                             if parameter != &argument_type {
                                 return Err(TypeError::new(
                                     TypeErrorKind::ExpectedDataType(parameter.to_string(), argument_type.to_string()),
-                                    self.position,
-                                    7,
+                                    argument.span,
                                 ));
                             }
                         }
 
                         // if parameters and arguments are not the same length
                         if parameters.len() != arguments_len {
-                            return Err(TypeError::new(
-                                TypeErrorKind::ExpectedArguments(parameters.len(), arguments.len()),
-                                self.position,
-                                8,
-                            ));
+                            return Err(TypeError::new(TypeErrorKind::ExpectedArguments(parameters.len(), arguments.len()), span));
                         }
 
                         Ok(*return_type)
                     }
-                    _ => Err(TypeError::new(TypeErrorKind::NotCallable(function_type.to_string()), self.position, 9)),
+                    _ => Err(TypeError::new(TypeErrorKind::NotCallable(function_type.to_string()), function.span)),
                 }
             }
-            IrExpression::Index(left, index) => {
-                let left_type = self.get_type_from_ir_expression(&left)?;
-                let index_type = self.get_type_from_ir_expression(&index)?;
+            IrExpressionKind::Index(left, index) => {
+                let left_type = self.expecting(None).get_type_from_ir_expression(&left)?;
+                let index_type = self.expecting(None).get_type_from_ir_expression(&index)?;
 
                 match left_type.data_type {
                     DataTypeKind::Array(data_type) => {
-                        if index_type.data_type == DataTypeKind::Number {
+                        if index_type.data_type == DataTypeKind::Int {
                             Ok(*data_type)
                         } else {
                             Err(TypeError::new(
-                                TypeErrorKind::ExpectedDataType(DataTypeKind::Number.to_string(), index_type.to_string()),
-                                self.position,
-                                10,
+                                TypeErrorKind::ExpectedDataType(DataTypeKind::Int.to_string(), index_type.to_string()),
+                                index.span,
                             ))
                         }
                     }
-                    _ => Err(TypeError::new(TypeErrorKind::NotIndexable(left_type.to_string()), self.position, 11)),
+                    _ => Err(TypeError::new(TypeErrorKind::NotIndexable(left_type.to_string()), left.span)),
                 }
             }
-            IrExpression::Prefix(_, expression) => self.get_type_from_ir_expression(&expression),
-            IrExpression::Infix(left, operator, right) => Ok({
+            IrExpressionKind::Field(left, field) => {
                 let left_type = self.get_type_from_ir_expression(&left)?;
-                let right_type = self.get_type_from_ir_expression(&right)?;
+
+                match left_type.data_type {
+                    DataTypeKind::Struct(ref struct_type) => match struct_type.field(&field) {
+                        Some(data_type) => Ok(data_type.clone()),
+                        None => Err(TypeError::new(TypeErrorKind::UnknownField(struct_type.name.clone(), field), span)),
+                    },
+                    _ => Err(TypeError::new(TypeErrorKind::NotAStruct(left_type.to_string()), left.span)),
+                }
+            }
+            IrExpressionKind::Prefix(operator, expression) => {
+                let data_type = self.get_type_from_ir_expression(&expression)?;
+
+                match (&operator, &data_type.data_type) {
+                    (TokenKind::Minus, DataTypeKind::Int | DataTypeKind::Float) => Ok(data_type),
+                    (TokenKind::Minus, _) => Err(TypeError::new(TypeErrorKind::NotANumber(data_type.to_string()), expression.span)),
+                    (TokenKind::Bang, DataTypeKind::Boolean) | (TokenKind::Tilde, DataTypeKind::Int) => Ok(data_type),
+                    (_, _) => Err(TypeError::new(
+                        TypeErrorKind::ExpectedDataType(
+                            match operator {
+                                TokenKind::Bang => DataTypeKind::Boolean,
+                                _ => DataTypeKind::Int,
+                            }
+                            .to_string(),
+                            data_type.to_string(),
+                        ),
+                        expression.span,
+                    )),
+                }
+            }
+            IrExpressionKind::Logical(left, _, right) => {
+                for operand in [&left, &right] {
+                    let data_type = self.get_type_from_ir_expression(operand)?;
+
+                    if data_type.data_type != DataTypeKind::Boolean {
+                        return Err(TypeError::new(
+                            TypeErrorKind::ExpectedDataType(DataTypeKind::Boolean.to_string(), data_type.to_string()),
+                            operand.span,
+                        ));
+                    }
+                }
+
+                Ok(DataType::new(DataTypeKind::Boolean, span))
+            }
+            IrExpressionKind::Assign(target, operator, value) => {
+                let target_type = self.expecting(None).get_type_from_ir_expression(&target)?;
+
+                // the variable an element or field belongs to must be mutable
+                let mut root = &*target;
+                while let IrExpressionKind::Index(left, _) | IrExpressionKind::Field(left, _) = &root.expression {
+                    root = left;
+                }
+
+                if let IrExpressionKind::Identifier(name) = &root.expression {
+                    if !self.declares.is_mutable(name) {
+                        return Err(TypeError::with_labels(
+                            TypeErrorKind::ImmutableAssignment(name.clone()),
+                            root.span,
+                            self.declares
+                                .span(name)
+                                .map(|declaration| Label::secondary(declaration, Some(format!("`{name}` is declared immutable here"))))
+                                .into_iter()
+                                .collect(),
+                        ));
+                    }
+                }
+
+                let value_type = match operator {
+                    TokenKind::Assign => self.expecting(Some(&target_type)).get_type_from_ir_expression(&value)?,
+                    operator => {
+                        self.get_type_from_ir_expression(&IrExpression::new(IrExpressionKind::Infix(target.clone(), operator, value.clone()), span))?
+                    }
+                };
+
+                if target_type == value_type {
+                    Ok(target_type)
+                } else {
+                    Err(TypeError::new(
+                        TypeErrorKind::ExpectedDataType(target_type.to_string(), value_type.to_string()),
+                        value.span,
+                    ))
+                }
+            }
+            IrExpressionKind::Loop(label, condition, body) => {
+                if let Some(condition) = *condition {
+                    let condition_type = self.get_type_from_ir_expression(&condition)?;
+
+                    if condition_type.data_type != DataTypeKind::Boolean {
+                        return Err(TypeError::new(
+                            TypeErrorKind::ExpectedDataType(DataTypeKind::Boolean.to_string(), condition_type.to_string()),
+                            condition.span,
+                        ));
+                    }
+                }
+
+                // a loop gives the value of the `break`s out of it
+                let mut loop_type: Option<DataType> = None;
+
+                for (value, break_span, bindings) in loop_breaks(&body, &label) {
+                    let (value_type, value_span) = match value {
+                        Some(value) => (self.within(&bindings)?.get_type_from_ir_expression(value)?, value.span),
+                        None => (DataType::new(DataTypeKind::Void, break_span), break_span),
+                    };
+
+                    match &loop_type {
+                        Some(loop_type) if *loop_type != value_type => {
+                            return Err(TypeError::new(
+                                TypeErrorKind::ExpectedDataType(loop_type.to_string(), value_type.to_string()),
+                                value_span,
+                            ))
+                        }
+                        Some(_) => {}
+                        None => loop_type = Some(value_type),
+                    }
+                }
+
+                Ok(loop_type.unwrap_or(DataType::new(DataTypeKind::Void, span)))
+            }
+            IrExpressionKind::For(_, _, iterable, _) => match self.get_type_from_ir_expression(&iterable)?.data_type {
+                DataTypeKind::Array(_) => Ok(DataType::new(DataTypeKind::Void, span)),
+                data_type => Err(TypeError::new(TypeErrorKind::NotIterable(data_type.to_string()), iterable.span)),
+            },
+            IrExpressionKind::Break(..) | IrExpressionKind::Continue(_) => Ok(DataType::new(DataTypeKind::Void, span)),
+            IrExpressionKind::Match(value, arms) => {
+                let mut match_type: Option<DataType> = None;
+
+                for (pattern, guard, body) in arms.iter() {
+                    // the arms after the first take its type as expected, so that `Option::None` can follow `Option::Some(1)`
+                    let mut checker = self.within(&[Binding::Pattern(pattern, &value)])?;
+
+                    if let Some(guard) = guard {
+                        let guard_type = checker.get_type_from_ir_expression(guard)?;
+
+                        if guard_type.data_type != DataTypeKind::Boolean {
+                            return Err(TypeError::new(
+                                TypeErrorKind::ExpectedDataType(DataTypeKind::Boolean.to_string(), guard_type.to_string()),
+                                guard.span,
+                            ));
+                        }
+                    }
+
+                    checker.data_type = self.data_type.clone().or_else(|| match_type.clone());
+
+                    let body_type = checker.get_type_from_ir_expression(body)?;
+
+                    // an arm that never ends, as with a `return`, takes the type of the others
+                    if diverges(body) {
+                        continue;
+                    }
+
+                    match &match_type {
+                        Some(match_type) if *match_type != body_type => {
+                            return Err(TypeError::new(
+                                TypeErrorKind::ExpectedDataType(match_type.to_string(), body_type.to_string()),
+                                body.span,
+                            ))
+                        }
+                        Some(_) => {}
+                        None => match_type = Some(body_type),
+                    }
+                }
+
+                // an arm with a guard may not match what its pattern does, so it covers nothing
+                let patterns = arms
+                    .iter()
+                    .filter(|(_, guard, _)| guard.is_none())
+                    .map(|(pattern, ..)| pattern)
+                    .collect::<Vec<_>>();
+                let missing = missing_cases(&patterns, &self.within(&[])?.get_type_from_ir_expression(&value)?, &self.customs)?;
+
+                if !missing.is_empty() {
+                    let shown = missing.iter().take(3).map(|case| format!("`{case}`")).collect::<Vec<_>>().join(", ");
+
+                    return Err(TypeError::new(
+                        TypeErrorKind::NonExhaustiveMatch(match missing.len() {
+                            1..=3 => shown,
+                            length => format!("{shown} and {} more", length - 3),
+                        }),
+                        value.span,
+                    ));
+                }
+
+                match match_type {
+                    Some(match_type) => Ok(match_type),
+                    None => match arms.first() {
+                        Some((pattern, _, body)) => self.within(&[Binding::Pattern(pattern, &value)])?.get_type_from_ir_expression(body),
+                        None => Ok(DataType::new(DataTypeKind::Void, span)),
+                    },
+                }
+            }
+            IrExpressionKind::Spread(arguments) => self.get_type_from_ir_expression(&arguments),
+            IrExpressionKind::Try(value) => {
+                // the expected type is that of the payload, not of the value itself
+                let value_type = self.within(&[])?.get_type_from_ir_expression(&value)?;
+
+                match propagated(&value_type) {
+                    Some(data_type) => Ok(data_type),
+                    None => Err(TypeError::new(TypeErrorKind::NotPropagatable(value_type.to_string()), value.span)),
+                }
+            }
+            IrExpressionKind::Infix(left, operator, right) => Ok({
+                // the right operand is checked against the type of the left one, which it must have
+                let left_type = self.expecting(None).get_type_from_ir_expression(&left)?;
+                let right_type = self.expecting(Some(&left_type)).get_type_from_ir_expression(&right)?;
 
                 DataType::new(
                     match operator {
-                        TokenKind::Plus | TokenKind::Minus | TokenKind::Asterisk | TokenKind::Slash | TokenKind::Percent => {
-                            if left_type.data_type == DataTypeKind::Number && right_type.data_type == DataTypeKind::Number {
-                                Ok(DataTypeKind::Number)
-                            } else {
-                                Err(TypeError::new(
-                                    TypeErrorKind::ExpectedDataType(DataTypeKind::Number.to_string(), left_type.to_string()),
-                                    self.position,
-                                    12,
-                                ))
+                        TokenKind::Plus
+                        | TokenKind::Minus
+                        | TokenKind::Asterisk
+                        | TokenKind::Slash
+                        | TokenKind::Percent
+                        | TokenKind::DoubleAsterisk => {
+                            match left_type.data_type {
+                                DataTypeKind::Int | DataTypeKind::Float if left_type != right_type => Err(TypeError::new(
+                                    TypeErrorKind::ExpectedDataType(left_type.to_string(), right_type.to_string()),
+                                    right.span,
+                                )),
+                                // dividing ints gives a float
+                                DataTypeKind::Int if operator == TokenKind::Slash => Ok(DataTypeKind::Float),
+                                DataTypeKind::Int | DataTypeKind::Float => Ok(left_type.data_type),
+                                _ => Err(TypeError::new(TypeErrorKind::NotANumber(left_type.to_string()), left.span)),
+                            }
+                        }
+                        TokenKind::Ampersand | TokenKind::Pipe | TokenKind::Caret | TokenKind::LShift | TokenKind::RShift => {
+                            match [(&left_type, &left), (&right_type, &right)]
+                                .into_iter()
+                                .find(|(data_type, _)| data_type.data_type != DataTypeKind::Int)
+                            {
+                                Some((data_type, operand)) => Err(TypeError::new(
+                                    TypeErrorKind::ExpectedDataType(DataTypeKind::Int.to_string(), data_type.to_string()),
+                                    operand.span,
+                                )),
+                                None => Ok(DataTypeKind::Int),
                             }
                         }
                         TokenKind::EQ | TokenKind::NEQ | TokenKind::LT | TokenKind::GT | TokenKind::LTE | TokenKind::GTE => {
-                            if left_type == right_type {
+                            // arrays, structs and enums would be compared by reference in JavaScript, so only plain values are
+                            let comparable = match left_type.data_type {
+                                DataTypeKind::Int | DataTypeKind::Float | DataTypeKind::String => true,
+                                DataTypeKind::Boolean | DataTypeKind::Void => matches!(operator, TokenKind::EQ | TokenKind::NEQ),
+                                _ => false,
+                            };
+
+                            if !comparable {
+                                Err(TypeError::new(
+                                    TypeErrorKind::NotComparable(left_type.to_string(), format_operator(&operator)),
+                                    left.span,
+                                ))
+                            } else if left_type == right_type {
                                 Ok(DataTypeKind::Boolean)
                             } else {
                                 Err(TypeError::new(
                                     TypeErrorKind::ExpectedDataType(left_type.to_string(), right_type.to_string()),
-                                    self.position,
-                                    13,
+                                    right.span,
                                 ))
                             }
                         }
                         _ => unreachable!(),
                     }?,
-                    self.position,
+                    span,
                 )
             }),
         };
@@ -240,18 +584,31 @@ impl Checker {
         custom_data_type(&result?, &self.customs)
     }
 
-    fn get_type_from_literal_value(&self, literal: &LiteralValue) -> CompileResult<DataType> {
+    fn get_type_from_literal_value(&self, literal: &LiteralValue, span: Span) -> CompileResult<DataType> {
         Ok(DataType::new(
             match literal {
-                LiteralValue::Number(_) => Ok(DataTypeKind::Number),
+                LiteralValue::Int(_) => Ok(DataTypeKind::Int),
+                LiteralValue::Float(_) => Ok(DataTypeKind::Float),
                 LiteralValue::String(_) => Ok(DataTypeKind::String),
                 LiteralValue::Boolean(_) => Ok(DataTypeKind::Boolean),
+                LiteralValue::Void => Ok(DataTypeKind::Void),
                 LiteralValue::Array(elements) => {
                     let mut element_type = DataTypeKind::Unknown;
+                    let expected = match self.data_type.as_ref().map(|data_type| &data_type.data_type) {
+                        Some(DataTypeKind::Array(expected)) => Some(*expected.clone()),
+                        _ => None,
+                    };
 
-                    // check elements
-                    for element in elements {
-                        let data_type = self.get_type_from_ir_expression(element)?;
+                    // check elements; those after the first take its type as expected, so that `Option::None` can follow `Option::Some(1)`
+                    for element in elements.iter() {
+                        let data_type = match (&expected, &element_type) {
+                            (Some(expected), _) => self.expecting(Some(expected)).get_type_from_ir_expression(element)?,
+                            (None, DataTypeKind::Unknown) => self.expecting(None).get_type_from_ir_expression(element)?,
+                            (None, element_type) => {
+                                let element_type = DataType::new(element_type.clone(), span);
+                                self.expecting(Some(&element_type)).get_type_from_ir_expression(element)?
+                            }
+                        };
 
                         if element_type == DataTypeKind::Unknown {
                             element_type = data_type.data_type; // default element type
@@ -260,8 +617,7 @@ impl Checker {
                         else if element_type != data_type.data_type {
                             return Err(TypeError::new(
                                 TypeErrorKind::ExpectedDataType(element_type.to_string(), data_type.to_string()),
-                                self.position,
-                                14,
+                                element.span,
                             ));
                         }
                     }
@@ -271,56 +627,207 @@ impl Checker {
                             if element_type == DataTypeKind::Unknown {
                                 element_type = match data_type.data_type.clone() {
                                     DataTypeKind::Array(data_type) => data_type.data_type.clone(),
-                                    _ => unreachable!(),
+                                    _ => DataTypeKind::Unknown,
                                 };
                             }
 
-                            if data_type.data_type != DataTypeKind::Array(Box::new(DataType::new(element_type.clone(), self.position))) {
+                            if data_type.data_type != DataTypeKind::Array(Box::new(DataType::new(element_type.clone(), span))) {
                                 return Err(TypeError::new(
                                     TypeErrorKind::ExpectedDataType(
                                         data_type.to_string(),
-                                        DataTypeKind::Array(Box::new(DataType::new(element_type, self.position))).to_string(),
+                                        DataTypeKind::Array(Box::new(DataType::new(element_type, span))).to_string(),
                                     ),
-                                    self.position,
-                                    15,
+                                    span,
                                 ));
                             }
                         }
                         None => {
                             if element_type == DataTypeKind::Unknown {
-                                return Err(TypeError::new(TypeErrorKind::UnknownArrayType, self.position, 16));
+                                return Err(TypeError::new(TypeErrorKind::UnknownArrayType, span));
+                            }
+                        }
+                    }
+
+                    Ok(DataTypeKind::Array(Box::new(DataType::new(element_type, span))))
+                }
+                LiteralValue::Struct(name, fields) => {
+                    let mut struct_type = match self.customs.get(name.clone()) {
+                        Some(DataType {
+                            data_type: DataTypeKind::Struct(struct_type),
+                            ..
+                        }) => struct_type,
+                        Some(_) => return Err(TypeError::new(TypeErrorKind::NotAStruct(name.clone()), span)),
+                        None => return Err(TypeError::new(TypeErrorKind::UndefinedType(name.clone()), span)),
+                    };
+
+                    let generics = self.customs.get_generics(name.clone());
+
+                    // infer the type arguments of a generic struct from its field values
+                    if !generics.is_empty() {
+                        let mut inferred = HashMap::new();
+
+                        for (field, value) in fields.iter() {
+                            if let Some(field_type) = struct_type.field(field) {
+                                let field_type = custom_data_type(field_type, &self.customs)?;
+                                let value_type = self.get_type_from_ir_expression(value)?;
+                                infer_type_parameters(&field_type, &value_type, value.span, &generics, &mut inferred)?;
+                            }
+                        }
+
+                        struct_type = instantiate_struct(&struct_type, &generics, &resolve_type_parameters(&generics, &inferred, Some(name), span)?);
+                    }
+
+                    for (index, (field, value)) in fields.iter().enumerate() {
+                        // if field is given more than once
+                        if fields[..index].iter().any(|(previous, _)| previous == field) {
+                            return Err(TypeError::new(TypeErrorKind::DuplicateField(name.clone(), field.clone()), value.span));
+                        }
+
+                        let field_type = match struct_type.field(field) {
+                            Some(field_type) => custom_data_type(field_type, &self.customs)?,
+                            None => return Err(TypeError::new(TypeErrorKind::UnknownField(name.clone(), field.clone()), value.span)),
+                        };
+
+                        let value_type = Checker::new(Some(&field_type), &self.declares, &self.customs)?.get_type_from_ir_expression(value)?;
+
+                        if field_type != value_type {
+                            return Err(TypeError::new(
+                                TypeErrorKind::ExpectedDataType(field_type.to_string(), value_type.to_string()),
+                                value.span,
+                            ));
+                        }
+                    }
+
+                    // check every declared field is given
+                    for (field, _) in struct_type.fields.iter() {
+                        if !fields.iter().any(|(given, _)| given == field) {
+                            return Err(TypeError::new(TypeErrorKind::MissingField(name.clone(), field.clone()), span));
+                        }
+                    }
+
+                    Ok(DataTypeKind::Struct(struct_type))
+                }
+                LiteralValue::Enum(name, variant, payload) => {
+                    let mut enum_type = match self.customs.get(name.clone()) {
+                        Some(DataType {
+                            data_type: DataTypeKind::Enum(enum_type),
+                            ..
+                        }) => enum_type,
+                        Some(_) => return Err(TypeError::new(TypeErrorKind::NotAnEnum(name.clone()), span)),
+                        None => return Err(TypeError::new(TypeErrorKind::UndefinedType(name.clone()), span)),
+                    };
+
+                    let parameters = match enum_type.variant(variant) {
+                        Some(parameters) => parameters.clone(),
+                        None => return Err(TypeError::new(TypeErrorKind::UnknownVariant(name.clone(), variant.clone()), span)),
+                    };
+
+                    if parameters.len() != payload.len() {
+                        return Err(TypeError::new(TypeErrorKind::ExpectedArguments(parameters.len(), payload.len()), span));
+                    }
+
+                    let generics = self.customs.get_generics(name.clone());
+
+                    // infer the type arguments of a generic enum from its payload, or from the expected type for those it does not use
+                    if !generics.is_empty() {
+                        let mut inferred = HashMap::new();
+
+                        for (parameter, value) in parameters.iter().zip(payload.iter()) {
+                            // a value that needs its expected type, as a nested `Option::None`, is only checked below
+                            if let Ok(value_type) = Checker::new(None, &self.declares, &self.customs)?.get_type_from_ir_expression(value) {
+                                infer_type_parameters(
+                                    &custom_data_type(parameter, &self.customs)?,
+                                    &value_type,
+                                    value.span,
+                                    &generics,
+                                    &mut inferred,
+                                )?;
+                            }
+                        }
+
+                        if let Some(DataType {
+                            data_type: DataTypeKind::Enum(expected),
+                            ..
+                        }) = &self.data_type
+                        {
+                            if expected.name == *name {
+                                for (generic, argument) in generics.iter().zip(expected.generics.iter()) {
+                                    inferred.entry(generic.clone()).or_insert_with(|| argument.clone());
+                                }
                             }
                         }
+
+                        enum_type = instantiate_enum(&enum_type, &generics, &resolve_type_parameters(&generics, &inferred, Some(name), span)?);
+                    }
+
+                    for (parameter, value) in enum_type.variant(variant).into_iter().flatten().zip(payload.iter()) {
+                        let parameter = custom_data_type(parameter, &self.customs)?;
+                        let value_type = Checker::new(Some(&parameter), &self.declares, &self.customs)?.get_type_from_ir_expression(value)?;
+
+                        if parameter != value_type {
+                            return Err(TypeError::new(
+                                TypeErrorKind::ExpectedDataType(parameter.to_string(), value_type.to_string()),
+                                value.span,
+                            ));
+                        }
                     }
 
-                    Ok(DataTypeKind::Array(Box::new(DataType::new(element_type, self.position))))
+                    Ok(DataTypeKind::Enum(enum_type))
                 }
-                LiteralValue::Function(parameters, body, return_type, _) => {
-                    let block_return_type = Box::new(self.get_type_from_ir_expression(&IrExpression::Block(body.clone()))?);
+                LiteralValue::Function(generics, parameters, body, return_type, _) => {
+                    let block = IrExpression::new(IrExpressionKind::Block(body.to_vec()), span);
+                    let mut checker = self.within(&parameters.iter().map(Binding::Parameter).collect::<Vec<_>>())?;
+
+                    if *return_type == DataTypeKind::Auto {
+                        return Err(TypeError::new(
+                            TypeErrorKind::ExpectedDataType(return_type.to_string(), checker.get_type_from_ir_expression(&block)?.to_string()),
+                            span,
+                        ));
+                    }
+
+                    // every `return` and the value of the body, unless it never ends, give the return type, which they are expected to
+                    // have so that `Option::None` can be returned
+                    checker.data_type = Some(DataType::new(return_type.clone(), span));
+
+                    for (value, bindings) in function_returns(body) {
+                        let mut returned = checker.within(&bindings)?;
+                        returned.data_type = checker.data_type.clone();
+
+                        let value_type = returned.get_type_from_ir_expression(value)?;
+
+                        if *return_type != value_type.data_type {
+                            return Err(TypeError::new(
+                                TypeErrorKind::ExpectedDataType(return_type.to_string(), value_type.to_string()),
+                                value.span,
+                            ));
+                        }
+                    }
+
+                    if !diverges(&block) {
+                        let body_type = checker.get_type_from_ir_expression(&block)?;
+
+                        if *return_type != body_type.data_type {
+                            return Err(TypeError::new(
+                                TypeErrorKind::ExpectedDataType(return_type.to_string(), body_type.to_string()),
+                                span,
+                            ));
+                        }
+                    }
 
                     let function_type = DataTypeKind::Fn(FunctionType {
-                        generics: None,
+                        generics: generics.clone(),
                         parameters: parameters
                             .iter()
                             .map(|Parameter { data_type, spread, .. }| (data_type.clone(), *spread))
                             .collect(),
-                        return_type: block_return_type.clone(),
+                        return_type: Box::new(DataType::new(return_type.clone(), span)),
                     });
 
-                    if return_type.clone() == DataTypeKind::Auto || return_type.clone() != block_return_type.data_type {
-                        return Err(TypeError::new(
-                            TypeErrorKind::ExpectedDataType(return_type.to_string(), block_return_type.to_string()),
-                            self.position,
-                            17,
-                        ));
-                    }
-
                     if let Some(data_type) = &self.data_type {
                         if data_type.data_type != function_type {
                             return Err(TypeError::new(
                                 TypeErrorKind::ExpectedDataType(data_type.to_string(), function_type.to_string()),
-                                self.position,
-                                18,
+                                span,
                             ));
                         }
                     }
@@ -328,228 +835,1587 @@ impl Checker {
                     Ok(function_type)
                 }
             }?,
-            self.position,
+            span,
         ))
     }
-}
 
-pub fn custom_data_type(data_type: &DataType, customs: &CustomTypes) -> CompileResult<DataType> {
-    let data_type_ @ DataType { data_type, position } = data_type;
+    /// Replaces the type parameters of a generic function type with the types inferred from the call's arguments.
+    fn instantiate_function(&self, function_type: FunctionType, arguments: &[IrExpression], span: Span) -> CompileResult<FunctionType> {
+        let generics = match &function_type.generics {
+            Some(generics) => generics.iter().map(|generic| generic.value.clone()).collect::<Vec<_>>(),
+            None => return Ok(function_type),
+        };
 
-    Ok(match &data_type {
-        DataTypeKind::Custom(name) => match customs.get(name.clone()) {
-            Some(custom) => custom,
-            None => return Err(TypeError::new(TypeErrorKind::UndefinedType(name.clone()), *position, 19)),
-        },
-        DataTypeKind::Fn(FunctionType {
-            generics,
-            parameters,
-            return_type,
-        }) => DataType::new(
-            DataTypeKind::Fn(FunctionType {
-                generics: generics.clone(),
-                parameters: parameters.clone(),
-                return_type: Box::new(custom_data_type(return_type, customs)?),
-            }),
-            *position,
-        ),
-        _ => data_type_.clone(),
-    })
-}
+        let mut inferred = HashMap::new();
 
-#[cfg(test)]
-mod tests {
-    use super::{Checker, CustomTypes, DeclaredTypes};
-    use sntk_core::{
-        parser::ast::{DataType, DataTypeKind, FunctionType, Position},
-        tokenizer::token::TokenKind,
-    };
-    use sntk_ir::instruction::{Instruction, InstructionType, IrExpression, LiteralValue};
-    use std::collections::HashMap;
+        for ((parameter, spread), argument) in function_type.parameters.iter().zip(arguments.iter()) {
+            let parameter = if *spread {
+                DataType::new(DataTypeKind::Array(Box::new(parameter.clone())), parameter.span)
+            } else {
+                parameter.clone()
+            };
 
-    const POSITION: Position = Position(1, 0);
+            // an argument that needs its expected type, as `Option::None`, is only checked once the others have given it
+            if let Ok(argument_type) = self.expecting(None).get_type_from_ir_expression(argument) {
+                infer_type_parameters(&parameter, &argument_type, argument.span, &generics, &mut inferred)?;
+            }
+        }
 
-    #[test]
-    fn identifier_type_test() {
-        let declarations = DeclaredTypes {
-            types: HashMap::from([("a".to_string(), DataType::new(DataTypeKind::Number, POSITION))]),
-            parent: Some(Box::new(DeclaredTypes {
-                types: HashMap::from([("a".to_string(), DataType::new(DataTypeKind::String, POSITION))]),
-                parent: None,
-            })),
-        };
+        let arguments = resolve_type_parameters(&generics, &inferred, None, span)?;
 
-        assert_eq!(
-            Checker::new(None, &declarations, &CustomTypes::new(None), POSITION)
-                .unwrap()
-                .get_type_from_ir_expression(&IrExpression::Identifier("a".to_string()))
-                .unwrap(),
-            DataType::new(DataTypeKind::Number, POSITION)
-        );
+        Ok(FunctionType {
+            generics: None,
+            parameters: function_type
+                .parameters
+                .iter()
+                .map(|(parameter, spread)| (substitute_type_parameters(parameter, &arguments), *spread))
+                .collect(),
+            return_type: Box::new(substitute_type_parameters(&function_type.return_type, &arguments)),
+        })
     }
+}
 
-    #[test]
-    fn block_return_type_test() {
-        let declarations = DeclaredTypes {
-            types: HashMap::from([
-                ("a".to_string(), DataType::new(DataTypeKind::Number, POSITION)),
-                ("b".to_string(), DataType::new(DataTypeKind::Number, POSITION)),
-            ]),
-            parent: None,
-        };
+/// A name or type declared inside a block, which the expressions after it can use.
+#[derive(Debug, Clone, Copy)]
+enum Binding<'a> {
+    Instruction(&'a Instruction),
+    Parameter(&'a Parameter),
+    Element(&'a str, &'a IrExpression),       /* name, array of a `for` loop */
+    Pattern(&'a IrPattern, &'a IrExpression), /* pattern of a match arm, value matched */
+}
 
-        assert_eq!(
-            Checker::new(None, &declarations, &CustomTypes::new(None), POSITION)
-                .unwrap()
-                .get_type_from_ir_expression(&IrExpression::Block(vec![
-                    Instruction::new(
-                        InstructionType::StoreName("a".to_string(), IrExpression::Literal(LiteralValue::Number(5.))),
-                        POSITION
-                    ),
-                    Instruction::new(
-                        InstructionType::StoreName("b".to_string(), IrExpression::Literal(LiteralValue::Number(5.))),
-                        POSITION
-                    ),
-                    Instruction::new(
-                        InstructionType::Return(IrExpression::Infix(
-                            Box::new(IrExpression::Identifier("a".to_string())),
-                            TokenKind::Plus,
-                            Box::new(IrExpression::Identifier("b".to_string()))
-                        )),
-                        POSITION
-                    )
-                ]))
-                .unwrap(),
-            DataType::new(DataTypeKind::Number, POSITION)
-        );
+impl Checker {
+    /// A checker for a part of the expression being checked, which is expected to have `data_type` rather than its type.
+    fn expecting(&self, data_type: Option<&DataType>) -> Checker {
+        Checker {
+            data_type: data_type.cloned(),
+            declares: self.declares.clone(),
+            customs: self.customs.clone(),
+        }
+    }
+
+    /// A checker for a child scope holding the bindings, so that expressions inside blocks can be checked from outside them.
+    fn within(&self, bindings: &[Binding]) -> CompileResult<Checker> {
+        let mut checker = Checker {
+            data_type: None,
+            declares: DeclaredTypes::new(Some(self.declares.clone())),
+            customs: CustomTypes::new(Some(self.customs.clone())),
+        };
+
+        for binding in bindings.iter() {
+            match binding {
+                Binding::Instruction(instruction) => match &instruction.instruction {
+                    InstructionType::StoreName(name, _, data_type, true) => checker.declares.set_mutable(name.clone(), data_type.clone()),
+                    InstructionType::StoreName(name, _, data_type, false) | InstructionType::DeclareName(name, data_type) => {
+                        checker.declares.set(name.clone(), data_type.clone())
+                    }
+                    InstructionType::DeclareType(name, generics, data_type) if generics.is_empty() => {
+                        checker.customs.set(name.clone(), data_type.clone())
+                    }
+                    InstructionType::DeclareType(name, generics, data_type) => {
+                        checker.customs.set_generic(name.clone(), generics.clone(), data_type.clone())
+                    }
+                    _ => {}
+                },
+                Binding::Parameter(Parameter {
+                    name,
+                    data_type,
+                    spread,
+                    span,
+                }) => checker.declares.set(
+                    name.value.clone(),
+                    match spread {
+                        true => DataType::new(DataTypeKind::Array(Box::new(data_type.clone())), *span),
+                        false => data_type.clone(),
+                    },
+                ),
+                Binding::Element(name, array) => {
+                    if let DataTypeKind::Array(element) = checker.get_type_from_ir_expression(array)?.data_type {
+                        checker.declares.set(name.to_string(), *element);
+                    }
+                }
+                Binding::Pattern(pattern, value) => {
+                    let value_type = checker.get_type_from_ir_expression(value)?;
+
+                    for (name, data_type) in pattern_bindings(pattern, &value_type, &checker.customs)? {
+                        checker.declares.set(name, data_type);
+                    }
+                }
+            }
+        }
+
+        Ok(checker)
+    }
+}
+
+/// The values of the `break`s out of a loop with the given label and body, with their spans and what is declared where they are.
+#[allow(clippy::type_complexity)]
+fn loop_breaks<'a>(body: &'a Block, label: &Option<String>) -> Vec<(Option<&'a IrExpression>, Span, Vec<Binding<'a>>)> {
+    type Breaks<'a> = Vec<(Option<&'a IrExpression>, Span, Vec<Binding<'a>>)>;
+
+    fn block<'a>(body: &'a Block, label: &Option<String>, nested: bool, bindings: &mut Vec<Binding<'a>>, breaks: &mut Breaks<'a>) {
+        let depth = bindings.len();
+
+        for instruction in body.iter() {
+            if let Some(expression) = instruction.expression() {
+                collect(expression, label, nested, bindings, breaks);
+            }
+
+            bindings.push(Binding::Instruction(instruction));
+        }
+
+        bindings.truncate(depth);
+    }
+
+    fn collect<'a>(expression: &'a IrExpression, label: &Option<String>, nested: bool, bindings: &mut Vec<Binding<'a>>, breaks: &mut Breaks<'a>) {
+        match &expression.expression {
+            IrExpressionKind::Break(target, value) if (target.is_none() && !nested) || (target.is_some() && target == label) => {
+                breaks.push(((**value).as_ref(), expression.span, bindings.clone()));
+            }
+            // `break` cannot leave a function, and a loop with the same label shadows this one
+            IrExpressionKind::Literal(LiteralValue::Function(..)) => return,
+            IrExpressionKind::Loop(inner, ..) | IrExpressionKind::For(inner, ..) if inner.is_some() && inner == label => return,
+            IrExpressionKind::Block(body) => return block(body, label, nested, bindings, breaks),
+            IrExpressionKind::Loop(_, condition, body) => {
+                if let Some(condition) = condition.as_ref() {
+                    collect(condition, label, true, bindings, breaks);
+                }
+
+                return block(body, label, true, bindings, breaks);
+            }
+            IrExpressionKind::For(_, name, array, body) => {
+                collect(array, label, true, bindings, breaks);
+
+                bindings.push(Binding::Element(name, array));
+                block(body, label, true, bindings, breaks);
+                bindings.pop();
+
+                return;
+            }
+            IrExpressionKind::Match(value, arms) => {
+                collect(value, label, nested, bindings, breaks);
+
+                for (pattern, guard, body) in arms.iter() {
+                    bindings.push(Binding::Pattern(pattern, value));
+                    guard
+                        .iter()
+                        .chain(std::iter::once(body))
+                        .for_each(|expression| collect(expression, label, nested, bindings, breaks));
+                    bindings.pop();
+                }
+
+                return;
+            }
+            _ => {}
+        }
+
+        for child in expression.children() {
+            collect(child, label, nested, bindings, breaks);
+        }
+    }
+
+    let mut breaks = Vec::new();
+    block(body, label, false, &mut Vec::new(), &mut breaks);
+    breaks
+}
+
+/// The values of the `return`s in a function body, with what is declared where they are, leaving out those of the functions
+/// inside it.
+fn function_returns(body: &Block) -> Vec<(&IrExpression, Vec<Binding<'_>>)> {
+    type Returns<'a> = Vec<(&'a IrExpression, Vec<Binding<'a>>)>;
+
+    fn block<'a>(body: &'a Block, bindings: &mut Vec<Binding<'a>>, returns: &mut Returns<'a>) {
+        let depth = bindings.len();
+
+        for instruction in body.iter() {
+            if let InstructionType::Return(value) = &instruction.instruction {
+                returns.push((value, bindings.clone()));
+            }
+
+            if let Some(expression) = instruction.expression() {
+                collect(expression, bindings, returns);
+            }
+
+            bindings.push(Binding::Instruction(instruction));
+        }
+
+        bindings.truncate(depth);
+    }
+
+    fn collect<'a>(expression: &'a IrExpression, bindings: &mut Vec<Binding<'a>>, returns: &mut Returns<'a>) {
+        match &expression.expression {
+            IrExpressionKind::Literal(LiteralValue::Function(..)) => {}
+            IrExpressionKind::Block(body) => block(body, bindings, returns),
+            IrExpressionKind::Loop(_, condition, body) => {
+                if let Some(condition) = condition.as_ref() {
+                    collect(condition, bindings, returns);
+                }
+
+                block(body, bindings, returns);
+            }
+            IrExpressionKind::For(_, name, array, body) => {
+                collect(array, bindings, returns);
+
+                bindings.push(Binding::Element(name, array));
+                block(body, bindings, returns);
+                bindings.pop();
+            }
+            IrExpressionKind::Match(value, arms) => {
+                collect(value, bindings, returns);
+
+                for (pattern, guard, body) in arms.iter() {
+                    bindings.push(Binding::Pattern(pattern, value));
+                    guard
+                        .iter()
+                        .chain(std::iter::once(body))
+                        .for_each(|expression| collect(expression, bindings, returns));
+                    bindings.pop();
+                }
+            }
+            _ => expression.children().into_iter().for_each(|child| collect(child, bindings, returns)),
+        }
+    }
+
+    let mut returns = Vec::new();
+    block(body, &mut Vec::new(), &mut returns);
+    returns
+}
+
+/// Whether the end of an expression is never reached, because it always leaves its function or loop first.
+fn diverges(expression: &IrExpression) -> bool {
+    match &expression.expression {
+        IrExpressionKind::Block(block) => block.iter().any(|instruction| match &instruction.instruction {
+            InstructionType::Return(_) => true,
+            _ => instruction.expression().is_some_and(diverges),
+        }),
+        IrExpressionKind::If(_, consequence, alternative) => diverges(consequence) && alternative.as_ref().as_ref().is_some_and(diverges),
+        IrExpressionKind::Loop(label, condition, body) => condition.is_none() && loop_breaks(body, label).is_empty(),
+        IrExpressionKind::Match(_, arms) => !arms.is_empty() && arms.iter().all(|(_, _, body)| diverges(body)),
+        IrExpressionKind::Break(..) | IrExpressionKind::Continue(_) => true,
+        _ => false,
+    }
+}
+
+/// The type `?` gives for a value of `data_type`, the `T` of an `Option<T>` or a `Result<T, E>`.
+pub fn propagated(data_type: &DataType) -> Option<DataType> {
+    match &data_type.data_type {
+        DataTypeKind::Enum(EnumType { name, generics, .. }) if name == "Option" || name == "Result" => generics.first().cloned(),
+        _ => None,
+    }
+}
+
+/// The names `pattern` binds when it matches a value of `data_type`, with their types, checking that it can match such a value.
+pub fn pattern_bindings(pattern: &IrPattern, data_type: &DataType, customs: &CustomTypes) -> CompileResult<Vec<(String, DataType)>> {
+    fn bind(pattern: &IrPattern, data_type: &DataType, customs: &CustomTypes, bindings: &mut Vec<(String, DataType)>) -> CompileResult<()> {
+        let data_type = custom_data_type(data_type, customs)?;
+
+        match (&pattern.pattern, &data_type.data_type) {
+            (IrPatternKind::Wildcard, _) => {}
+            (IrPatternKind::Binding(name), _) => {
+                if bindings.iter().any(|(bound, _)| bound == name) {
+                    return Err(TypeError::new(TypeErrorKind::DuplicateBinding(name.clone()), pattern.span));
+                }
+
+                bindings.push((name.clone(), data_type));
+            }
+            (IrPatternKind::Literal(literal), expected) => {
+                let literal_type = match literal {
+                    LiteralValue::Int(_) => DataTypeKind::Int,
+                    LiteralValue::Float(_) => DataTypeKind::Float,
+                    LiteralValue::String(_) => DataTypeKind::String,
+                    LiteralValue::Boolean(_) => DataTypeKind::Boolean,
+                    _ => unreachable!("only literals are parsed as literal patterns"),
+                };
+
+                if literal_type != *expected {
+                    return Err(TypeError::new(
+                        TypeErrorKind::ExpectedDataType(data_type.to_string(), literal_type.to_string()),
+                        pattern.span,
+                    ));
+                }
+            }
+            (IrPatternKind::Array(elements, rest), DataTypeKind::Array(element)) => {
+                for pattern in elements.iter() {
+                    bind(pattern, element, customs, bindings)?;
+                }
+
+                if let Some(rest) = rest {
+                    bind(rest, &data_type, customs, bindings)?;
+                }
+            }
+            (IrPatternKind::Struct(name, fields), DataTypeKind::Struct(struct_type)) if *name == struct_type.name => {
+                for (index, (field, field_pattern)) in fields.iter().enumerate() {
+                    if fields[..index].iter().any(|(previous, _)| previous == field) {
+                        return Err(TypeError::new(TypeErrorKind::DuplicateField(name.clone(), field.clone()), pattern.span));
+                    }
+
+                    match struct_type.fields.iter().find(|(declared, _)| declared == field) {
+                        Some((_, field_type)) => bind(field_pattern, field_type, customs, bindings)?,
+                        None => return Err(TypeError::new(TypeErrorKind::UnknownField(name.clone(), field.clone()), pattern.span)),
+                    }
+                }
+            }
+            (IrPatternKind::Enum(name, variant, payload), DataTypeKind::Enum(enum_type)) if *name == enum_type.name => {
+                let parameters = match enum_type.variant(variant) {
+                    Some(parameters) => parameters,
+                    None => return Err(TypeError::new(TypeErrorKind::UnknownVariant(name.clone(), variant.clone()), pattern.span)),
+                };
+
+                if parameters.len() != payload.len() {
+                    return Err(TypeError::new(
+                        TypeErrorKind::ExpectedArguments(parameters.len(), payload.len()),
+                        pattern.span,
+                    ));
+                }
+
+                for (pattern, parameter) in payload.iter().zip(parameters.iter()) {
+                    bind(pattern, parameter, customs, bindings)?;
+                }
+            }
+            _ => {
+                return Err(TypeError::new(
+                    TypeErrorKind::MismatchedPattern(pattern.to_string(), data_type.to_string()),
+                    pattern.span,
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut bindings = Vec::new();
+    bind(pattern, data_type, customs, &mut bindings)?;
+
+    Ok(bindings)
+}
+
+/// What a pattern requires of the outside of a value, to tell which arms can match the same values.
+#[derive(Debug, Clone, PartialEq)]
+enum Constructor {
+    Boolean(bool),
+    Variant(String),
+    Struct,
+    Length(usize),  /* arrays of exactly this many elements */
+    AtLeast(usize), /* arrays of this many elements or more */
+    Literal,        /* ints, floats and strings, of which there are too many to list */
+}
+
+impl Constructor {
+    fn of(pattern: &IrPattern) -> Option<Constructor> {
+        match &pattern.pattern {
+            IrPatternKind::Wildcard | IrPatternKind::Binding(_) => None,
+            IrPatternKind::Literal(LiteralValue::Boolean(boolean)) => Some(Constructor::Boolean(*boolean)),
+            IrPatternKind::Literal(_) => Some(Constructor::Literal),
+            IrPatternKind::Array(elements, None) => Some(Constructor::Length(elements.len())),
+            IrPatternKind::Array(elements, Some(_)) => Some(Constructor::AtLeast(elements.len())),
+            IrPatternKind::Struct(..) => Some(Constructor::Struct),
+            IrPatternKind::Enum(_, variant, _) => Some(Constructor::Variant(variant.clone())),
+        }
+    }
+}
+
+/// The values of `data_type` that none of `patterns` match, written as patterns such as `Shape::Empty` or `[_, ..._]`.
+///
+/// This is the usefulness algorithm of "Warnings for pattern matching" (Maranget, 2007): the rows of patterns are split by the
+/// constructor of their first column, the values of types with a few constructors (booleans, enums, structs and arrays, by their
+/// length) being covered only if each constructor is, and those of other types only by a pattern matching anything.
+pub fn missing_cases(patterns: &[&IrPattern], data_type: &DataType, customs: &CustomTypes) -> CompileResult<Vec<String>> {
+    let wildcard = IrPattern::new(IrPatternKind::Wildcard, data_type.span);
+    let rows = patterns.iter().map(|pattern| vec![*pattern]).collect::<Vec<_>>();
+
+    Ok(uncovered(&rows, std::slice::from_ref(data_type), customs, &wildcard)?
+        .into_iter()
+        .map(|mut witness| witness.remove(0))
+        .collect())
+}
+
+/// The rows of values, one per column of `types`, that none of `rows` match.
+fn uncovered<'a>(rows: &[Vec<&'a IrPattern>], types: &[DataType], customs: &CustomTypes, wildcard: &'a IrPattern) -> CompileResult<Vec<Vec<String>>> {
+    let Some((data_type, rest)) = types.split_first() else {
+        return Ok(match rows.is_empty() {
+            true => vec![Vec::new()],
+            false => Vec::new(),
+        });
+    };
+
+    if rows.is_empty() {
+        return Ok(vec![vec!["_".to_string(); types.len()]]);
+    }
+
+    let data_type = custom_data_type(data_type, customs)?;
+    let heads = rows.iter().filter_map(|row| Constructor::of(row[0])).collect::<Vec<_>>();
+
+    // values are only told apart by constructor where a pattern does, which also keeps recursive types from being unfolded forever
+    let constructors = match heads.is_empty() {
+        true => None,
+        false => constructors(&data_type, &heads, customs)?,
+    };
+
+    let Some(constructors) = constructors else {
+        let rows = rows
+            .iter()
+            .filter(|row| Constructor::of(row[0]).is_none())
+            .map(|row| row[1..].to_vec())
+            .collect::<Vec<_>>();
+
+        return Ok(uncovered(&rows, rest, customs, wildcard)?
+            .into_iter()
+            .map(|witness| std::iter::once("_".to_string()).chain(witness).collect())
+            .collect());
+    };
+
+    let mut witnesses = Vec::new();
+
+    for (constructor, fields) in constructors {
+        let rows = rows
+            .iter()
+            .filter_map(|row| specialize(row, &constructor, fields.len(), &data_type, wildcard))
+            .collect::<Vec<_>>();
+        let types = fields.iter().chain(rest).cloned().collect::<Vec<_>>();
+
+        for mut witness in uncovered(&rows, &types, customs, wildcard)? {
+            let others = witness.split_off(fields.len());
+            witnesses.push(
+                std::iter::once(show_constructor(&constructor, witness, &data_type))
+                    .chain(others)
+                    .collect(),
+            );
+        }
+    }
+
+    Ok(witnesses)
+}
+
+/// A constructor with the types of its fields.
+type Signature = (Constructor, Vec<DataType>);
+
+/// Every constructor of `data_type` with the types of its fields, if it has few enough to list.
+fn constructors(data_type: &DataType, heads: &[Constructor], customs: &CustomTypes) -> CompileResult<Option<Vec<Signature>>> {
+    let resolve = |data_types: &[DataType]| {
+        data_types
+            .iter()
+            .map(|data_type| custom_data_type(data_type, customs))
+            .collect::<CompileResult<Vec<_>>>()
+    };
+
+    Ok(Some(match &data_type.data_type {
+        DataTypeKind::Boolean => vec![(Constructor::Boolean(true), Vec::new()), (Constructor::Boolean(false), Vec::new())],
+        DataTypeKind::Enum(enum_type) => enum_type
+            .variants
+            .iter()
+            .map(|(variant, payload)| Ok((Constructor::Variant(variant.clone()), resolve(payload)?)))
+            .collect::<CompileResult<_>>()?,
+        DataTypeKind::Struct(struct_type) => vec![(
+            Constructor::Struct,
+            resolve(&struct_type.fields.iter().map(|(_, data_type)| data_type.clone()).collect::<Vec<_>>())?,
+        )],
+        // arrays longer than any pattern lists are told apart by none of them
+        DataTypeKind::Array(element) => {
+            let longest = heads
+                .iter()
+                .filter_map(|head| match head {
+                    Constructor::Length(length) | Constructor::AtLeast(length) => Some(*length),
+                    _ => None,
+                })
+                .max()
+                .unwrap_or(0);
+
+            (0..=longest)
+                .map(Constructor::Length)
+                .chain(std::iter::once(Constructor::AtLeast(longest + 1)))
+                .map(|constructor| {
+                    let length = match constructor {
+                        Constructor::Length(length) | Constructor::AtLeast(length) => length,
+                        _ => unreachable!(),
+                    };
+
+                    (constructor, vec![(**element).clone(); length])
+                })
+                .collect()
+        }
+        _ => return Ok(None),
+    }))
+}
+
+/// The row without its first pattern, preceded by the patterns of its fields, if it can match a value built by `constructor`.
+fn specialize<'a>(
+    row: &[&'a IrPattern],
+    constructor: &Constructor,
+    arity: usize,
+    data_type: &DataType,
+    wildcard: &'a IrPattern,
+) -> Option<Vec<&'a IrPattern>> {
+    let fields = match (&row[0].pattern, constructor) {
+        (IrPatternKind::Wildcard | IrPatternKind::Binding(_), _) => vec![wildcard; arity],
+        (IrPatternKind::Literal(LiteralValue::Boolean(boolean)), Constructor::Boolean(expected)) if boolean == expected => Vec::new(),
+        (IrPatternKind::Enum(_, variant, payload), Constructor::Variant(expected)) if variant == expected => payload.iter().collect(),
+        // fields left out of a struct pattern match anything
+        (IrPatternKind::Struct(_, fields), Constructor::Struct) => match &data_type.data_type {
+            DataTypeKind::Struct(struct_type) => struct_type
+                .fields
+                .iter()
+                .map(|(field, _)| fields.iter().find(|(name, _)| name == field).map_or(wildcard, |(_, pattern)| pattern))
+                .collect(),
+            _ => return None,
+        },
+        (IrPatternKind::Array(elements, None), Constructor::Length(length)) if elements.len() == *length => elements.iter().collect(),
+        (IrPatternKind::Array(elements, Some(_)), Constructor::Length(length) | Constructor::AtLeast(length)) if elements.len() <= *length => {
+            elements.iter().chain(std::iter::repeat_n(wildcard, length - elements.len())).collect()
+        }
+        _ => return None,
+    };
+
+    Some(fields.into_iter().chain(row[1..].iter().copied()).collect())
+}
+
+fn show_constructor(constructor: &Constructor, fields: Vec<String>, data_type: &DataType) -> String {
+    match (constructor, &data_type.data_type) {
+        (Constructor::Boolean(boolean), _) => boolean.to_string(),
+        (Constructor::Variant(variant), DataTypeKind::Enum(enum_type)) => match fields.is_empty() {
+            true => format!("{}::{}", enum_type.name, variant),
+            false => format!("{}::{}({})", enum_type.name, variant, fields.join(", ")),
+        },
+        (Constructor::Struct, DataTypeKind::Struct(struct_type)) => {
+            let fields = struct_type
+                .fields
+                .iter()
+                .zip(fields.iter())
+                .filter(|(_, pattern)| *pattern != "_")
+                .map(|((field, _), pattern)| format!("{field}: {pattern}"))
+                .collect::<Vec<_>>();
+
+            match fields.is_empty() {
+                true => format!("struct {} {{}}", struct_type.name),
+                false => format!("struct {} {{ {} }}", struct_type.name, fields.join(", ")),
+            }
+        }
+        (Constructor::Length(_), _) => format!("[{}]", fields.join(", ")),
+        (Constructor::AtLeast(_), _) => format!(
+            "[{}]",
+            fields
+                .into_iter()
+                .chain(std::iter::once("..._".to_string()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => "_".to_string(),
+    }
+}
+
+pub fn custom_data_type(data_type: &DataType, customs: &CustomTypes) -> CompileResult<DataType> {
+    let data_type_ @ DataType { data_type, span } = data_type;
+
+    Ok(match &data_type {
+        DataTypeKind::Custom(name) => match customs.get(name.clone()) {
+            Some(custom) => {
+                let generics = customs.get_generics(name.clone());
+
+                if !generics.is_empty() {
+                    return Err(TypeError::new(
+                        TypeErrorKind::ExpectedTypeArguments(name.clone(), generics.len(), 0),
+                        *span,
+                    ));
+                }
+
+                custom_data_type(&custom, customs)?
+            }
+            None => return Err(TypeError::new(TypeErrorKind::UndefinedType(name.clone()), *span)),
+        },
+        DataTypeKind::Generic(Generic(data_type, arguments)) => {
+            let name = match &data_type.data_type {
+                DataTypeKind::Custom(name) => name.clone(),
+                data_type => return Err(TypeError::new(TypeErrorKind::UndefinedType(data_type.to_string()), *span)),
+            };
+
+            let custom = match customs.get(name.clone()) {
+                Some(custom) => custom,
+                None => return Err(TypeError::new(TypeErrorKind::UndefinedType(name), *span)),
+            };
+
+            let generics = customs.get_generics(name.clone());
+
+            if generics.len() != arguments.len() {
+                return Err(TypeError::new(
+                    TypeErrorKind::ExpectedTypeArguments(name, generics.len(), arguments.len()),
+                    *span,
+                ));
+            }
+
+            let arguments = generics
+                .iter()
+                .cloned()
+                .zip(arguments.iter())
+                .map(|(generic, argument)| Ok((generic, custom_data_type(argument, customs)?)))
+                .collect::<CompileResult<HashMap<_, _>>>()?;
+
+            match custom.data_type {
+                DataTypeKind::Struct(struct_type) => {
+                    DataType::new(DataTypeKind::Struct(instantiate_struct(&struct_type, &generics, &arguments)), *span)
+                }
+                DataTypeKind::Enum(enum_type) => DataType::new(DataTypeKind::Enum(instantiate_enum(&enum_type, &generics, &arguments)), *span),
+                _ => custom_data_type(&substitute_type_parameters(&custom, &arguments), customs)?,
+            }
+        }
+        DataTypeKind::Array(data_type) => DataType::new(DataTypeKind::Array(Box::new(custom_data_type(data_type, customs)?)), *span),
+        DataTypeKind::Fn(FunctionType {
+            generics,
+            parameters,
+            return_type,
+        }) => {
+            // type parameters of a generic function type are only visible in its own parameters and return type
+            let customs = &match generics {
+                Some(generics) => {
+                    let mut customs = CustomTypes::new(Some(customs.clone()));
+
+                    for generic in generics.iter() {
+                        customs.set(
+                            generic.value.clone(),
+                            DataType::new(DataTypeKind::TypeParameter(generic.value.clone()), generic.span),
+                        );
+                    }
+
+                    customs
+                }
+                None => customs.clone(),
+            };
+
+            DataType::new(
+                DataTypeKind::Fn(FunctionType {
+                    generics: generics.clone(),
+                    parameters: parameters
+                        .iter()
+                        .map(|(parameter, spread)| Ok((custom_data_type(parameter, customs)?, *spread)))
+                        .collect::<CompileResult<Vec<_>>>()?,
+                    return_type: Box::new(custom_data_type(return_type, customs)?),
+                }),
+                *span,
+            )
+        }
+        _ => data_type_.clone(),
+    })
+}
+
+/// Applies `f` to every type nested in `data_type`, replacing the types for which it returns `Some`.
+pub fn map_data_type(data_type: &DataType, f: &impl Fn(&DataTypeKind) -> Option<DataTypeKind>) -> DataType {
+    let DataType { data_type, span } = data_type;
+
+    if let Some(data_type) = f(data_type) {
+        return DataType::new(data_type, *span);
+    }
+
+    DataType::new(
+        match data_type {
+            DataTypeKind::Array(data_type) => DataTypeKind::Array(Box::new(map_data_type(data_type, f))),
+            DataTypeKind::Fn(FunctionType {
+                generics,
+                parameters,
+                return_type,
+            }) => DataTypeKind::Fn(FunctionType {
+                generics: generics.clone(),
+                parameters: parameters
+                    .iter()
+                    .map(|(parameter, spread)| (map_data_type(parameter, f), *spread))
+                    .collect(),
+                return_type: Box::new(map_data_type(return_type, f)),
+            }),
+            DataTypeKind::Struct(StructType { name, generics, fields }) => DataTypeKind::Struct(StructType::new(
+                name.clone(),
+                generics.iter().map(|generic| map_data_type(generic, f)).collect(),
+                fields
+                    .iter()
+                    .map(|(field, data_type)| (field.clone(), map_data_type(data_type, f)))
+                    .collect(),
+            )),
+            DataTypeKind::Enum(EnumType { name, generics, variants }) => DataTypeKind::Enum(EnumType::new(
+                name.clone(),
+                generics.iter().map(|generic| map_data_type(generic, f)).collect(),
+                variants
+                    .iter()
+                    .map(|(variant, payload)| (variant.clone(), payload.iter().map(|data_type| map_data_type(data_type, f)).collect()))
+                    .collect(),
+            )),
+            DataTypeKind::Generic(Generic(data_type, arguments)) => DataTypeKind::Generic(Generic(
+                data_type.clone(),
+                arguments.iter().map(|argument| map_data_type(argument, f)).collect(),
+            )),
+            data_type => data_type.clone(),
+        },
+        *span,
+    )
+}
+
+/// Replaces references to `generics` with `DataTypeKind::TypeParameter`s, used when declaring a generic type.
+pub fn declare_type_parameters(data_type: &DataType, generics: &[String]) -> DataType {
+    map_data_type(data_type, &|data_type| match data_type {
+        DataTypeKind::Custom(name) if generics.contains(name) => Some(DataTypeKind::TypeParameter(name.clone())),
+        _ => None,
+    })
+}
+
+pub fn substitute_type_parameters(data_type: &DataType, arguments: &HashMap<String, DataType>) -> DataType {
+    map_data_type(data_type, &|data_type| match data_type {
+        DataTypeKind::TypeParameter(name) => arguments.get(name).map(|argument| argument.data_type.clone()),
+        _ => None,
+    })
+}
+
+fn instantiate_struct(struct_type: &StructType, generics: &[String], arguments: &HashMap<String, DataType>) -> StructType {
+    StructType::new(
+        struct_type.name.clone(),
+        generics.iter().filter_map(|generic| arguments.get(generic).cloned()).collect(),
+        struct_type
+            .fields
+            .iter()
+            .map(|(field, data_type)| (field.clone(), substitute_type_parameters(data_type, arguments)))
+            .collect(),
+    )
+}
+
+fn instantiate_enum(enum_type: &EnumType, generics: &[String], arguments: &HashMap<String, DataType>) -> EnumType {
+    EnumType::new(
+        enum_type.name.clone(),
+        generics.iter().filter_map(|generic| arguments.get(generic).cloned()).collect(),
+        enum_type
+            .variants
+            .iter()
+            .map(|(variant, payload)| {
+                (
+                    variant.clone(),
+                    payload.iter().map(|data_type| substitute_type_parameters(data_type, arguments)).collect(),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Binds the type parameters in `generics` that occur in `parameter` to the corresponding parts of `argument`, the type of the
+/// value at `at`. The inferred types are given the span of the value they are inferred from.
+fn infer_type_parameters(
+    parameter: &DataType,
+    argument: &DataType,
+    at: Span,
+    generics: &[String],
+    inferred: &mut HashMap<String, DataType>,
+) -> CompileResult<()> {
+    match (&parameter.data_type, &argument.data_type) {
+        (DataTypeKind::TypeParameter(name), _) if generics.contains(name) => match inferred.get(name) {
+            Some(previous) if previous != argument => Err(TypeError::with_labels(
+                TypeErrorKind::MismatchedTypeParameter(name.clone(), previous.to_string(), argument.to_string()),
+                at,
+                vec![Label::secondary(
+                    previous.span,
+                    Some(format!("`{name}` is inferred as `{previous}` here")),
+                )],
+            )),
+            Some(_) => Ok(()),
+            None => {
+                inferred.insert(name.clone(), DataType::new(argument.data_type.clone(), at));
+                Ok(())
+            }
+        },
+        (DataTypeKind::Array(parameter), DataTypeKind::Array(argument)) => infer_type_parameters(parameter, argument, at, generics, inferred),
+        (DataTypeKind::Fn(parameter), DataTypeKind::Fn(argument)) => {
+            for ((parameter, _), (argument, _)) in parameter.parameters.iter().zip(argument.parameters.iter()) {
+                infer_type_parameters(parameter, argument, at, generics, inferred)?;
+            }
+
+            infer_type_parameters(&parameter.return_type, &argument.return_type, at, generics, inferred)
+        }
+        (
+            DataTypeKind::Struct(StructType {
+                name, generics: parameters, ..
+            }),
+            DataTypeKind::Struct(StructType {
+                name: argument,
+                generics: arguments,
+                ..
+            }),
+        )
+        | (
+            DataTypeKind::Enum(EnumType {
+                name, generics: parameters, ..
+            }),
+            DataTypeKind::Enum(EnumType {
+                name: argument,
+                generics: arguments,
+                ..
+            }),
+        ) if name == argument => {
+            for (parameter, argument) in parameters.iter().zip(arguments.iter()) {
+                infer_type_parameters(parameter, argument, at, generics, inferred)?;
+            }
+
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Orders the inferred type arguments of a generic function, or of the generic type named `owner`, failing on any type
+/// parameter that could not be inferred.
+fn resolve_type_parameters(
+    generics: &[String],
+    inferred: &HashMap<String, DataType>,
+    owner: Option<&String>,
+    span: Span,
+) -> CompileResult<HashMap<String, DataType>> {
+    generics
+        .iter()
+        .map(|generic| match inferred.get(generic) {
+            Some(data_type) => Ok((generic.clone(), data_type.clone())),
+            None => Err(TypeError::new(
+                TypeErrorKind::UnresolvedTypeParameter(generic.clone(), owner.cloned()),
+                span,
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{missing_cases, pattern_bindings, Checker, CustomTypes, DeclaredTypes};
+    use crate::CompileError;
+    use sntk_core::{
+        parser::ast::{DataType, DataTypeKind, EnumType, FileId, FunctionType, Identifier, Parameter, Span, StructType},
+        tokenizer::token::TokenKind,
+    };
+    use sntk_ir::instruction::{Instruction, InstructionType, IrExpression, IrExpressionKind, IrPattern, IrPatternKind, LiteralValue};
+    use std::{collections::HashMap, rc::Rc};
+
+    const SPAN: Span = Span {
+        file: FileId(0),
+        start: 0,
+        end: 0,
+    };
+
+    fn ir(expression: IrExpressionKind) -> IrExpression {
+        IrExpression::new(expression, SPAN)
+    }
+
+    #[test]
+    fn identifier_type_test() {
+        let declarations = DeclaredTypes {
+            types: Rc::new(HashMap::from([("a".to_string(), DataType::new(DataTypeKind::Int, SPAN))])),
+            mutables: Rc::default(),
+            spans: Rc::default(),
+            parent: Some(Rc::new(DeclaredTypes {
+                types: Rc::new(HashMap::from([("a".to_string(), DataType::new(DataTypeKind::String, SPAN))])),
+                mutables: Rc::default(),
+                spans: Rc::default(),
+                parent: None,
+            })),
+        };
+
+        assert_eq!(
+            Checker::new(None, &declarations, &CustomTypes::new(None))
+                .unwrap()
+                .get_type_from_ir_expression(&ir(IrExpressionKind::Identifier("a".to_string())))
+                .unwrap(),
+            DataType::new(DataTypeKind::Int, SPAN)
+        );
+    }
+
+    #[test]
+    fn shared_scope_test() {
+        let mut declarations = DeclaredTypes::new(Some(DeclaredTypes::builtins()));
+        declarations.set("a".to_string(), DataType::new(DataTypeKind::Int, SPAN));
+
+        // checkers for sub-expressions and the scopes inside them share the scopes around them rather than copying them
+        let checker = Checker::new(None, &declarations, &CustomTypes::new(None)).unwrap();
+        let inner = checker.within(&[]).unwrap();
+
+        assert!(Rc::ptr_eq(&checker.expecting(None).declares.types, &declarations.types));
+        assert!(Rc::ptr_eq(&inner.declares.parent.as_ref().unwrap().types, &declarations.types));
+
+        // until the scope is changed, which leaves them as they were
+        declarations.set("b".to_string(), DataType::new(DataTypeKind::Int, SPAN));
+
+        assert!(inner.declares.get("a".to_string()).is_some());
+        assert!(inner.declares.get("b".to_string()).is_none());
+    }
+
+    #[test]
+    fn block_return_type_test() {
+        let declarations = DeclaredTypes {
+            types: Rc::new(HashMap::from([
+                ("a".to_string(), DataType::new(DataTypeKind::Int, SPAN)),
+                ("b".to_string(), DataType::new(DataTypeKind::Int, SPAN)),
+            ])),
+            mutables: Rc::default(),
+            spans: Rc::default(),
+            parent: None,
+        };
+
+        assert_eq!(
+            Checker::new(None, &declarations, &CustomTypes::new(None))
+                .unwrap()
+                .get_type_from_ir_expression(&ir(IrExpressionKind::Block(vec![
+                    Instruction::new(
+                        InstructionType::StoreName(
+                            "a".to_string(),
+                            ir(IrExpressionKind::Literal(LiteralValue::Int(5))),
+                            DataType::new(DataTypeKind::Int, SPAN),
+                            false
+                        ),
+                        SPAN
+                    ),
+                    Instruction::new(
+                        InstructionType::StoreName(
+                            "b".to_string(),
+                            ir(IrExpressionKind::Literal(LiteralValue::Int(5))),
+                            DataType::new(DataTypeKind::Int, SPAN),
+                            false
+                        ),
+                        SPAN
+                    ),
+                    Instruction::new(
+                        InstructionType::Value(ir(IrExpressionKind::Infix(
+                            Box::new(ir(IrExpressionKind::Identifier("a".to_string()))),
+                            TokenKind::Plus,
+                            Box::new(ir(IrExpressionKind::Identifier("b".to_string())))
+                        ))),
+                        SPAN
+                    )
+                ])))
+                .unwrap(),
+            DataType::new(DataTypeKind::Int, SPAN)
+        );
     }
 
     #[test]
     fn if_return_type_test() {
         let declarations = DeclaredTypes {
-            types: HashMap::from([
-                ("a".to_string(), DataType::new(DataTypeKind::Number, POSITION)),
-                ("b".to_string(), DataType::new(DataTypeKind::Number, POSITION)),
-            ]),
+            types: Rc::new(HashMap::from([
+                ("a".to_string(), DataType::new(DataTypeKind::Int, SPAN)),
+                ("b".to_string(), DataType::new(DataTypeKind::Int, SPAN)),
+            ])),
+            mutables: Rc::default(),
+            spans: Rc::default(),
             parent: None,
         };
 
-        let consequence = IrExpression::Block(vec![Instruction::new(
-            InstructionType::Return(IrExpression::Literal(LiteralValue::Number(5.))),
-            POSITION,
-        )]);
-        let alternative = IrExpression::Block(vec![Instruction::new(
-            InstructionType::Return(IrExpression::Literal(LiteralValue::Number(10.))),
-            POSITION,
-        )]);
+        let consequence = ir(IrExpressionKind::Block(vec![Instruction::new(
+            InstructionType::Value(ir(IrExpressionKind::Literal(LiteralValue::Int(5)))),
+            SPAN,
+        )]));
+        let alternative = ir(IrExpressionKind::Block(vec![Instruction::new(
+            InstructionType::Value(ir(IrExpressionKind::Literal(LiteralValue::Int(10)))),
+            SPAN,
+        )]));
+
+        let checker = Checker::new(None, &declarations, &CustomTypes::new(None)).unwrap();
+        let condition = || Box::new(ir(IrExpressionKind::Literal(LiteralValue::Boolean(true))));
 
         assert_eq!(
-            Checker::new(None, &declarations, &CustomTypes::new(None), POSITION)
-                .unwrap()
-                .get_type_from_ir_expression(&IrExpression::If(
-                    Box::new(IrExpression::Literal(LiteralValue::Boolean(true))),
-                    Box::new(consequence),
+            checker
+                .get_type_from_ir_expression(&ir(IrExpressionKind::If(
+                    condition(),
+                    Box::new(consequence.clone()),
                     Box::new(Some(alternative))
-                ))
+                )))
                 .unwrap(),
-            DataType::new(DataTypeKind::Number, POSITION)
+            DataType::new(DataTypeKind::Int, SPAN)
+        );
+        // without an `else` there is no value
+        assert_eq!(
+            checker
+                .get_type_from_ir_expression(&ir(IrExpressionKind::If(condition(), Box::new(consequence), Box::new(None))))
+                .unwrap(),
+            DataType::new(DataTypeKind::Void, SPAN)
         );
     }
 
     #[test]
     fn call_return_type_test() {
         let declarations = DeclaredTypes {
-            types: HashMap::from([(
+            types: Rc::new(HashMap::from([(
                 "a".to_string(),
                 DataType::new(
                     DataTypeKind::Fn(FunctionType {
                         generics: None,
                         parameters: vec![
-                            (DataType::new(DataTypeKind::Number, POSITION), false),
-                            (DataType::new(DataTypeKind::String, POSITION), true),
+                            (DataType::new(DataTypeKind::Int, SPAN), false),
+                            (DataType::new(DataTypeKind::String, SPAN), true),
                         ],
-                        return_type: Box::new(DataType::new(DataTypeKind::Number, POSITION)),
+                        return_type: Box::new(DataType::new(DataTypeKind::Int, SPAN)),
                     }),
-                    POSITION,
+                    SPAN,
                 ),
-            )]),
+            )])),
+            mutables: Rc::default(),
+            spans: Rc::default(),
             parent: None,
         };
 
         assert_eq!(
-            Checker::new(None, &declarations, &CustomTypes::new(None), POSITION)
+            Checker::new(None, &declarations, &CustomTypes::new(None))
                 .unwrap()
-                .get_type_from_ir_expression(&IrExpression::Call(
-                    Box::new(IrExpression::Identifier("a".to_string())),
+                .get_type_from_ir_expression(&ir(IrExpressionKind::Call(
+                    Box::new(ir(IrExpressionKind::Identifier("a".to_string()))),
                     vec![
-                        IrExpression::Literal(LiteralValue::Number(5.)),
-                        IrExpression::Literal(LiteralValue::Array(vec![
-                            IrExpression::Literal(LiteralValue::String("foo".to_string())),
-                            IrExpression::Literal(LiteralValue::String("bar".to_string())),
-                            IrExpression::Literal(LiteralValue::String("baz".to_string())),
-                        ]))
+                        ir(IrExpressionKind::Literal(LiteralValue::Int(5))),
+                        ir(IrExpressionKind::Literal(LiteralValue::Array(Rc::new(vec![
+                            ir(IrExpressionKind::Literal(LiteralValue::String("foo".to_string()))),
+                            ir(IrExpressionKind::Literal(LiteralValue::String("bar".to_string()))),
+                            ir(IrExpressionKind::Literal(LiteralValue::String("baz".to_string()))),
+                        ]))))
                     ]
-                ))
+                )))
                 .unwrap(),
-            DataType::new(DataTypeKind::Number, POSITION)
+            DataType::new(DataTypeKind::Int, SPAN)
         );
     }
 
     #[test]
     fn index_type() {
         assert_eq!(
-            Checker::new(None, &DeclaredTypes::new(None), &CustomTypes::new(None), POSITION)
+            Checker::new(None, &DeclaredTypes::new(None), &CustomTypes::new(None))
                 .unwrap()
-                .get_type_from_ir_expression(&IrExpression::Index(
-                    Box::new(IrExpression::Literal(LiteralValue::Array(vec![
-                        IrExpression::Literal(LiteralValue::Number(5.)),
-                        IrExpression::Literal(LiteralValue::Number(10.)),
-                        IrExpression::Literal(LiteralValue::Number(15.)),
-                    ]))),
-                    Box::new(IrExpression::Literal(LiteralValue::Number(1.)))
-                ))
+                .get_type_from_ir_expression(&ir(IrExpressionKind::Index(
+                    Box::new(ir(IrExpressionKind::Literal(LiteralValue::Array(Rc::new(vec![
+                        ir(IrExpressionKind::Literal(LiteralValue::Int(5))),
+                        ir(IrExpressionKind::Literal(LiteralValue::Int(10))),
+                        ir(IrExpressionKind::Literal(LiteralValue::Int(15))),
+                    ]))))),
+                    Box::new(ir(IrExpressionKind::Literal(LiteralValue::Int(1))))
+                )))
                 .unwrap(),
-            DataType::new(DataTypeKind::Number, POSITION)
+            DataType::new(DataTypeKind::Int, SPAN)
         );
     }
 
     #[test]
     fn prefix_type() {
         assert_eq!(
-            Checker::new(None, &DeclaredTypes::new(None), &CustomTypes::new(None), POSITION)
+            Checker::new(None, &DeclaredTypes::new(None), &CustomTypes::new(None))
                 .unwrap()
-                .get_type_from_ir_expression(&IrExpression::Prefix(
+                .get_type_from_ir_expression(&ir(IrExpressionKind::Prefix(
                     TokenKind::Minus,
-                    Box::new(IrExpression::Literal(LiteralValue::Number(5.)))
-                ))
+                    Box::new(ir(IrExpressionKind::Literal(LiteralValue::Int(5))))
+                )))
                 .unwrap(),
-            DataType::new(DataTypeKind::Number, POSITION)
+            DataType::new(DataTypeKind::Int, SPAN)
         );
     }
 
     #[test]
     fn infix_type() {
         assert_eq!(
-            Checker::new(None, &DeclaredTypes::new(None), &CustomTypes::new(None), POSITION)
+            Checker::new(None, &DeclaredTypes::new(None), &CustomTypes::new(None))
                 .unwrap()
-                .get_type_from_ir_expression(&IrExpression::Infix(
-                    Box::new(IrExpression::Literal(LiteralValue::Number(5.))),
+                .get_type_from_ir_expression(&ir(IrExpressionKind::Infix(
+                    Box::new(ir(IrExpressionKind::Literal(LiteralValue::Int(5)))),
                     TokenKind::EQ,
-                    Box::new(IrExpression::Literal(LiteralValue::Number(10.)))
-                ))
+                    Box::new(ir(IrExpressionKind::Literal(LiteralValue::Int(10))))
+                )))
                 .unwrap(),
-            DataType::new(DataTypeKind::Boolean, POSITION)
+            DataType::new(DataTypeKind::Boolean, SPAN)
+        );
+    }
+
+    #[test]
+    fn arithmetic_type_test() {
+        let checker = Checker::new(None, &DeclaredTypes::new(None), &CustomTypes::new(None)).unwrap();
+        let infix = |left: LiteralValue, operator: TokenKind, right: LiteralValue| {
+            checker.get_type_from_ir_expression(&ir(IrExpressionKind::Infix(
+                Box::new(ir(IrExpressionKind::Literal(left))),
+                operator,
+                Box::new(ir(IrExpressionKind::Literal(right))),
+            )))
+        };
+
+        assert_eq!(
+            infix(LiteralValue::Int(1), TokenKind::Percent, LiteralValue::Int(2)).unwrap(),
+            DataType::new(DataTypeKind::Int, SPAN)
         );
+        assert_eq!(
+            infix(LiteralValue::Int(1), TokenKind::Slash, LiteralValue::Int(2)).unwrap(),
+            DataType::new(DataTypeKind::Float, SPAN)
+        );
+        assert_eq!(
+            infix(LiteralValue::Float(1.), TokenKind::Asterisk, LiteralValue::Float(2.)).unwrap(),
+            DataType::new(DataTypeKind::Float, SPAN)
+        );
+
+        assert_eq!(
+            infix(LiteralValue::Int(2), TokenKind::DoubleAsterisk, LiteralValue::Int(3)).unwrap(),
+            DataType::new(DataTypeKind::Int, SPAN)
+        );
+        assert_eq!(
+            infix(LiteralValue::Int(6), TokenKind::LShift, LiteralValue::Int(1)).unwrap(),
+            DataType::new(DataTypeKind::Int, SPAN)
+        );
+
+        assert!(infix(LiteralValue::Int(1), TokenKind::Plus, LiteralValue::Float(2.)).is_err());
+        assert!(infix(LiteralValue::Boolean(true), TokenKind::Plus, LiteralValue::Boolean(false)).is_err());
+        assert!(infix(LiteralValue::Float(1.), TokenKind::Ampersand, LiteralValue::Float(2.)).is_err());
+        assert!(infix(LiteralValue::Boolean(true), TokenKind::Pipe, LiteralValue::Boolean(false)).is_err());
+    }
+
+    #[test]
+    fn logical_type_test() {
+        let checker = Checker::new(None, &DeclaredTypes::new(None), &CustomTypes::new(None)).unwrap();
+        let logical = |left: LiteralValue, right: LiteralValue| {
+            checker.get_type_from_ir_expression(&ir(IrExpressionKind::Logical(
+                Box::new(ir(IrExpressionKind::Literal(left))),
+                TokenKind::And,
+                Box::new(ir(IrExpressionKind::Literal(right))),
+            )))
+        };
+
+        assert_eq!(
+            logical(LiteralValue::Boolean(true), LiteralValue::Boolean(false)).unwrap(),
+            DataType::new(DataTypeKind::Boolean, SPAN)
+        );
+        assert!(logical(LiteralValue::Int(1), LiteralValue::Boolean(false)).is_err());
+        assert!(logical(LiteralValue::Boolean(true), LiteralValue::Int(1)).is_err());
+    }
+
+    #[test]
+    fn assignment_type_test() {
+        let mut declarations = DeclaredTypes::new(None);
+        declarations.set_mutable("a".to_string(), DataType::new(DataTypeKind::Int, SPAN));
+        declarations.set("b".to_string(), DataType::new(DataTypeKind::Int, SPAN));
+
+        let checker = Checker::new(None, &declarations, &CustomTypes::new(None)).unwrap();
+        let assign = |name: &str, operator: TokenKind, value: LiteralValue| {
+            checker.get_type_from_ir_expression(&ir(IrExpressionKind::Assign(
+                Box::new(ir(IrExpressionKind::Identifier(name.to_string()))),
+                operator,
+                Box::new(ir(IrExpressionKind::Literal(value))),
+            )))
+        };
+
+        assert_eq!(
+            assign("a", TokenKind::Assign, LiteralValue::Int(1)).unwrap(),
+            DataType::new(DataTypeKind::Int, SPAN)
+        );
+        assert_eq!(
+            assign("a", TokenKind::Plus, LiteralValue::Int(1)).unwrap(),
+            DataType::new(DataTypeKind::Int, SPAN)
+        );
+
+        assert!(assign("a", TokenKind::Assign, LiteralValue::Boolean(true)).is_err());
+        // `a /= 2` would turn `a` into a float
+        assert!(assign("a", TokenKind::Slash, LiteralValue::Int(2)).is_err());
+        assert!(matches!(
+            assign("b", TokenKind::Assign, LiteralValue::Int(1)),
+            Err(CompileError::TypeError(error)) if error.message.code() == "E0119"
+        ));
+    }
+
+    #[test]
+    fn loop_type_test() {
+        let checker = Checker::new(None, &DeclaredTypes::new(None), &CustomTypes::new(None)).unwrap();
+        let statement = |expression| Instruction::new(InstructionType::Expression(ir(expression)), SPAN);
+        let break_with = |label: Option<&str>, value: Option<LiteralValue>| {
+            statement(IrExpressionKind::Break(
+                label.map(str::to_string),
+                Box::new(value.map(|value| ir(IrExpressionKind::Literal(value)))),
+            ))
+        };
+        let repeat = |label: Option<&str>, body| ir(IrExpressionKind::Loop(label.map(str::to_string), Box::new(None), body));
+
+        // a `break` out of an inner loop does not give this one its value, unless it names it
+        let inner = |label| {
+            IrExpressionKind::Loop(
+                None,
+                Box::new(None),
+                vec![break_with(label, Some(LiteralValue::String("foo".to_string())))],
+            )
+        };
+
+        assert_eq!(
+            checker
+                .get_type_from_ir_expression(&repeat(None, vec![statement(inner(None)), break_with(None, Some(LiteralValue::Int(5)))]))
+                .unwrap(),
+            DataType::new(DataTypeKind::Int, SPAN)
+        );
+        assert!(checker
+            .get_type_from_ir_expression(&repeat(
+                Some("a"),
+                vec![statement(inner(Some("a"))), break_with(None, Some(LiteralValue::Int(5)))]
+            ))
+            .is_err());
+        assert_eq!(
+            checker.get_type_from_ir_expression(&repeat(None, vec![break_with(None, None)])).unwrap(),
+            DataType::new(DataTypeKind::Void, SPAN)
+        );
+    }
+
+    #[test]
+    fn function_return_type_test() {
+        let checker = Checker::new(None, &DeclaredTypes::new(None), &CustomTypes::new(None)).unwrap();
+        let literal = |value| ir(IrExpressionKind::Literal(value));
+        let instruction = |instruction| Instruction::new(instruction, SPAN);
+        let function = |body| literal(LiteralValue::Function(None, Vec::new(), Rc::new(body), DataTypeKind::Int, None));
+
+        // `if true { return value; };`
+        let early_return = |value| {
+            instruction(InstructionType::Expression(ir(IrExpressionKind::If(
+                Box::new(literal(LiteralValue::Boolean(true))),
+                Box::new(ir(IrExpressionKind::Block(vec![instruction(InstructionType::Return(literal(value)))]))),
+                Box::new(None),
+            ))))
+        };
+
+        // a body that always returns needs no value of its own
+        assert!(checker
+            .get_type_from_ir_expression(&function(vec![
+                early_return(LiteralValue::Int(1)),
+                instruction(InstructionType::Return(literal(LiteralValue::Int(2))))
+            ]))
+            .is_ok());
+        assert!(checker
+            .get_type_from_ir_expression(&function(vec![
+                early_return(LiteralValue::String("foo".to_string())),
+                instruction(InstructionType::Value(literal(LiteralValue::Int(2))))
+            ]))
+            .is_err());
+        assert!(checker
+            .get_type_from_ir_expression(&function(vec![early_return(LiteralValue::Int(1))]))
+            .is_err());
+    }
+
+    #[test]
+    fn scope_type_test() {
+        let declarations = DeclaredTypes {
+            types: Rc::new(HashMap::from([("a".to_string(), DataType::new(DataTypeKind::Int, SPAN))])),
+            mutables: Rc::default(),
+            spans: Rc::default(),
+            parent: None,
+        };
+        let checker = Checker::new(None, &declarations, &CustomTypes::new(None)).unwrap();
+        let identifier = |name: &str| ir(IrExpressionKind::Identifier(name.to_string()));
+        let instruction = |instruction| Instruction::new(instruction, SPAN);
+
+        // a name declared in a block shadows the outer one until the block ends
+        let shadowed = ir(IrExpressionKind::Block(vec![
+            instruction(InstructionType::StoreName(
+                "a".to_string(),
+                ir(IrExpressionKind::Literal(LiteralValue::String("foo".to_string()))),
+                DataType::new(DataTypeKind::String, SPAN),
+                false,
+            )),
+            instruction(InstructionType::Value(identifier("a"))),
+        ]));
+
+        assert_eq!(
+            checker.get_type_from_ir_expression(&shadowed).unwrap(),
+            DataType::new(DataTypeKind::String, SPAN)
+        );
+        assert_eq!(
+            checker.get_type_from_ir_expression(&identifier("a")).unwrap(),
+            DataType::new(DataTypeKind::Int, SPAN)
+        );
+
+        // parameters are in scope in the body, a spread one as an array
+        let function = |spread| {
+            ir(IrExpressionKind::Literal(LiteralValue::Function(
+                None,
+                vec![Parameter::new(
+                    Identifier::new("b".to_string(), SPAN),
+                    DataType::new(DataTypeKind::Int, SPAN),
+                    spread,
+                    SPAN,
+                )],
+                Rc::new(vec![instruction(InstructionType::Value(identifier("b")))]),
+                DataTypeKind::Int,
+                None,
+            )))
+        };
+
+        assert!(checker.get_type_from_ir_expression(&function(false)).is_ok());
+        assert!(checker.get_type_from_ir_expression(&function(true)).is_err());
     }
 
     #[test]
     fn literal_type_test() {
         assert_eq!(
-            Checker::new(None, &DeclaredTypes::new(None), &CustomTypes::new(None), POSITION)
+            Checker::new(None, &DeclaredTypes::new(None), &CustomTypes::new(None))
                 .unwrap()
-                .get_type_from_ir_expression(&IrExpression::Literal(LiteralValue::Number(10.0)))
+                .get_type_from_ir_expression(&ir(IrExpressionKind::Literal(LiteralValue::Int(10))))
+                .unwrap(),
+            DataType::new(DataTypeKind::Int, SPAN)
+        );
+    }
+
+    #[test]
+    fn struct_field_type_test() {
+        let point = DataType::new(
+            DataTypeKind::Struct(StructType::new(
+                "Point".to_string(),
+                Vec::new(),
+                vec![
+                    ("x".to_string(), DataType::new(DataTypeKind::Int, SPAN)),
+                    ("y".to_string(), DataType::new(DataTypeKind::Int, SPAN)),
+                ],
+            )),
+            SPAN,
+        );
+        let customs = CustomTypes {
+            types: Rc::new(HashMap::from([("Point".to_string(), point.clone())])),
+            generics: Rc::default(),
+            parent: None,
+        };
+        let checker = Checker::new(None, &DeclaredTypes::new(None), &customs).unwrap();
+
+        let literal = |fields: Vec<(&str, i64)>| {
+            ir(IrExpressionKind::Literal(LiteralValue::Struct(
+                "Point".to_string(),
+                fields
+                    .into_iter()
+                    .map(|(field, value)| (field.to_string(), ir(IrExpressionKind::Literal(LiteralValue::Int(value)))))
+                    .collect(),
+            )))
+        };
+
+        assert_eq!(checker.get_type_from_ir_expression(&literal(vec![("x", 1), ("y", 2)])).unwrap(), point);
+        assert_eq!(
+            checker
+                .get_type_from_ir_expression(&ir(IrExpressionKind::Field(Box::new(literal(vec![("y", 2), ("x", 1)])), "y".to_string())))
+                .unwrap(),
+            DataType::new(DataTypeKind::Int, SPAN)
+        );
+
+        assert!(checker.get_type_from_ir_expression(&literal(vec![("x", 1)])).is_err());
+        assert!(checker.get_type_from_ir_expression(&literal(vec![("x", 1), ("y", 2), ("z", 3)])).is_err());
+        assert!(checker
+            .get_type_from_ir_expression(&ir(IrExpressionKind::Field(Box::new(literal(vec![("x", 1), ("y", 2)])), "z".to_string())))
+            .is_err());
+    }
+
+    #[test]
+    fn enum_type_test() {
+        let type_parameter = DataType::new(DataTypeKind::TypeParameter("T".to_string()), SPAN);
+        let option = |generics| {
+            DataType::new(
+                DataTypeKind::Enum(EnumType::new(
+                    "Option".to_string(),
+                    generics,
+                    vec![("Some".to_string(), vec![type_parameter.clone()]), ("None".to_string(), Vec::new())],
+                )),
+                SPAN,
+            )
+        };
+        let customs = CustomTypes {
+            types: Rc::new(HashMap::from([("Option".to_string(), option(Vec::new()))])),
+            generics: Rc::new(HashMap::from([("Option".to_string(), vec!["T".to_string()])])),
+            parent: None,
+        };
+        let checker = Checker::new(None, &DeclaredTypes::new(None), &customs).unwrap();
+        let literal = |variant: &str, payload: Vec<LiteralValue>| {
+            ir(IrExpressionKind::Literal(LiteralValue::Enum(
+                "Option".to_string(),
+                variant.to_string(),
+                payload.into_iter().map(|value| ir(IrExpressionKind::Literal(value))).collect(),
+            )))
+        };
+        let int = DataType::new(DataTypeKind::Int, SPAN);
+        let option_int = DataType::new(
+            DataTypeKind::Enum(EnumType::new(
+                "Option".to_string(),
+                vec![int.clone()],
+                vec![("Some".to_string(), vec![int.clone()]), ("None".to_string(), Vec::new())],
+            )),
+            SPAN,
+        );
+
+        // the type arguments come from the payload, or from the expected type
+        assert_eq!(
+            checker.get_type_from_ir_expression(&literal("Some", vec![LiteralValue::Int(1)])).unwrap(),
+            option_int
+        );
+        assert!(checker.get_type_from_ir_expression(&literal("None", Vec::new())).is_err());
+        assert_eq!(
+            Checker::new(Some(&option_int), &DeclaredTypes::new(None), &customs)
+                .unwrap()
+                .get_type_from_ir_expression(&literal("None", Vec::new()))
+                .unwrap(),
+            option_int
+        );
+
+        assert!(checker.get_type_from_ir_expression(&literal("Some", Vec::new())).is_err());
+        assert!(checker.get_type_from_ir_expression(&literal("Other", Vec::new())).is_err());
+    }
+
+    #[test]
+    fn match_type_test() {
+        let int = DataType::new(DataTypeKind::Int, SPAN);
+        let option = DataType::new(
+            DataTypeKind::Enum(EnumType::new(
+                "Option".to_string(),
+                vec![int.clone()],
+                vec![("Some".to_string(), vec![int.clone()]), ("None".to_string(), Vec::new())],
+            )),
+            SPAN,
+        );
+        let declarations = DeclaredTypes {
+            types: Rc::new(HashMap::from([("a".to_string(), option.clone())])),
+            mutables: Rc::default(),
+            spans: Rc::default(),
+            parent: None,
+        };
+        let customs = CustomTypes::new(None);
+        let checker = Checker::new(None, &declarations, &customs).unwrap();
+        let pattern = |pattern| IrPattern::new(pattern, SPAN);
+        let some = |payload| pattern(IrPatternKind::Enum("Option".to_string(), "Some".to_string(), vec![payload]));
+        let none = || pattern(IrPatternKind::Enum("Option".to_string(), "None".to_string(), Vec::new()));
+        let binding = || pattern(IrPatternKind::Binding("x".to_string()));
+        let literal = |value| ir(IrExpressionKind::Literal(value));
+        let matching = |arms| ir(IrExpressionKind::Match(Box::new(ir(IrExpressionKind::Identifier("a".to_string()))), arms));
+
+        // `match a { Option::Some(x) => x, Option::None => 0 }`
+        assert_eq!(
+            checker
+                .get_type_from_ir_expression(&matching(vec![
+                    (some(binding()), None, ir(IrExpressionKind::Identifier("x".to_string()))),
+                    (none(), None, literal(LiteralValue::Int(0))),
+                ]))
                 .unwrap(),
-            DataType::new(DataTypeKind::Number, POSITION)
+            int
+        );
+        assert!(checker
+            .get_type_from_ir_expression(&matching(vec![
+                (some(binding()), None, ir(IrExpressionKind::Identifier("x".to_string()))),
+                (none(), None, literal(LiteralValue::String("foo".to_string()))),
+            ]))
+            .is_err());
+
+        assert_eq!(
+            pattern_bindings(&some(binding()), &option, &customs).unwrap(),
+            vec![("x".to_string(), int.clone())]
+        );
+        assert!(pattern_bindings(
+            &some(pattern(IrPatternKind::Literal(LiteralValue::String("foo".to_string())))),
+            &option,
+            &customs
+        )
+        .is_err());
+        assert!(pattern_bindings(&pattern(IrPatternKind::Array(Vec::new(), None)), &option, &customs).is_err());
+
+        assert_eq!(missing_cases(&[&some(binding())], &option, &customs).unwrap(), vec!["Option::None"]);
+        assert_eq!(
+            missing_cases(
+                &[&some(pattern(IrPatternKind::Literal(LiteralValue::Int(1)))), &none()],
+                &option,
+                &customs
+            )
+            .unwrap(),
+            vec!["Option::Some(_)"]
+        );
+        assert!(missing_cases(&[&some(pattern(IrPatternKind::Wildcard)), &none()], &option, &customs)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn try_type_test() {
+        let int = DataType::new(DataTypeKind::Int, SPAN);
+        let option = DataType::new(
+            DataTypeKind::Enum(EnumType::new(
+                "Option".to_string(),
+                vec![int.clone()],
+                vec![("Some".to_string(), vec![int.clone()]), ("None".to_string(), Vec::new())],
+            )),
+            SPAN,
         );
+        let declarations = DeclaredTypes {
+            types: Rc::new(HashMap::from([("a".to_string(), option), ("b".to_string(), int.clone())])),
+            mutables: Rc::default(),
+            spans: Rc::default(),
+            parent: None,
+        };
+        let checker = Checker::new(None, &declarations, &CustomTypes::new(None)).unwrap();
+        let attempt = |name: &str| ir(IrExpressionKind::Try(Box::new(ir(IrExpressionKind::Identifier(name.to_string())))));
+
+        // `a?` gives the payload of `Option<int>`
+        assert_eq!(checker.get_type_from_ir_expression(&attempt("a")).unwrap(), int);
+        assert!(checker.get_type_from_ir_expression(&attempt("b")).is_err());
+    }
+
+    #[test]
+    fn generic_call_return_type_test() {
+        let type_parameter = DataType::new(DataTypeKind::TypeParameter("T".to_string()), SPAN);
+        let declarations = DeclaredTypes {
+            types: Rc::new(HashMap::from([(
+                "first".to_string(),
+                DataType::new(
+                    DataTypeKind::Fn(FunctionType {
+                        generics: Some(vec![Identifier::new("T".to_string(), SPAN)]),
+                        parameters: vec![
+                            (DataType::new(DataTypeKind::Array(Box::new(type_parameter.clone())), SPAN), false),
+                            (type_parameter.clone(), false),
+                        ],
+                        return_type: Box::new(type_parameter),
+                    }),
+                    SPAN,
+                ),
+            )])),
+            mutables: Rc::default(),
+            spans: Rc::default(),
+            parent: None,
+        };
+        let checker = Checker::new(None, &declarations, &CustomTypes::new(None)).unwrap();
+
+        let call = |default: LiteralValue| {
+            ir(IrExpressionKind::Call(
+                Box::new(ir(IrExpressionKind::Identifier("first".to_string()))),
+                vec![
+                    ir(IrExpressionKind::Literal(LiteralValue::Array(Rc::new(vec![ir(
+                        IrExpressionKind::Literal(LiteralValue::String("foo".to_string())),
+                    )])))),
+                    ir(IrExpressionKind::Literal(default)),
+                ],
+            ))
+        };
+
+        assert_eq!(
+            checker
+                .get_type_from_ir_expression(&call(LiteralValue::String("bar".to_string())))
+                .unwrap(),
+            DataType::new(DataTypeKind::String, SPAN)
+        );
+        assert!(checker.get_type_from_ir_expression(&call(LiteralValue::Int(5))).is_err());
+    }
+
+    #[test]
+    fn error_span_test() {
+        let declarations = DeclaredTypes {
+            types: Rc::new(HashMap::from([(
+                "a".to_string(),
+                DataType::new(
+                    DataTypeKind::Fn(FunctionType {
+                        generics: None,
+                        parameters: vec![(DataType::new(DataTypeKind::Int, SPAN), false)],
+                        return_type: Box::new(DataType::new(DataTypeKind::Int, SPAN)),
+                    }),
+                    SPAN,
+                ),
+            )])),
+            mutables: Rc::default(),
+            spans: Rc::default(),
+            parent: None,
+        };
+        let argument = Span::new(FileId(0), 2, 7);
+
+        let error = Checker::new(None, &declarations, &CustomTypes::new(None))
+            .unwrap()
+            .get_type_from_ir_expression(&IrExpression::new(
+                IrExpressionKind::Call(
+                    Box::new(ir(IrExpressionKind::Identifier("a".to_string()))),
+                    vec![IrExpression::new(
+                        IrExpressionKind::Literal(LiteralValue::String("foo".to_string())),
+                        argument,
+                    )],
+                ),
+                Span::new(FileId(0), 0, 8),
+            ))
+            .unwrap_err();
+
+        match error {
+            CompileError::TypeError(error) => assert_eq!(error.span, argument),
+            error => panic!("expected a type error, got {error:?}"),
+        }
     }
 }
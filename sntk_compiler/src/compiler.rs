@@ -1,19 +1,40 @@
 use crate::{
-    checker::{custom_data_type, Checker, CustomTypes, DeclaredTypes},
+    checker::{custom_data_type, declare_type_parameters, pattern_bindings, propagated, Checker, CustomTypes, DeclaredTypes},
+    module::{type_names, Export, Module},
+    prelude::Prelude,
     CompileError, TypeError, TypeErrorKind,
 };
-use sntk_core::parser::ast::{
-    ArrayLiteral, AutoStatement, BlockExpression, BooleanLiteral, CallExpression, DataType, DataTypeKind, DeclareStatement, Expression,
-    ExpressionStatement, FunctionLiteral, Identifier, IfExpression, IndexExpression, InfixExpression, LetStatement, NumberLiteral, Parameter,
-    Position, PrefixExpression, Program, ReturnStatement, Statement, StringLiteral, TypeStatement, TypeofExpression,
+use sntk_core::{
+    diagnostic::Label,
+    parser::ast::{
+        ArrayLiteral, ArrayPattern, AssignExpression, AutoStatement, BindingPattern, BlockExpression, BooleanLiteral, BreakExpression,
+        CallExpression, ContinueExpression, DataType, DataTypeKind, DeclareStatement, EnumLiteral, EnumPattern, EnumStatement, EnumType,
+        ErrorStatement, Expression, ExpressionStatement, FieldExpression, FloatLiteral, ForExpression, FunctionLiteral, FunctionType, Identifier,
+        IfExpression, ImportStatement, IndexExpression, InfixExpression, IntLiteral, LetStatement, LiteralPattern, LoopExpression, MatchArm,
+        MatchExpression, Parameter, Pattern, PrefixExpression, Program, ReturnStatement, Span, Statement, StringLiteral, StructLiteral,
+        StructPattern, StructStatement, StructType, TryExpression, TypeStatement, TypeofExpression, WhileExpression,
+    },
+    tokenizer::token::TokenKind,
+};
+use sntk_ir::instruction::{Block, Instruction, InstructionType, IrExpression, IrExpressionKind, IrPattern, IrPatternKind, LiteralValue};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
 };
-use sntk_ir::instruction::{Instruction, InstructionType, IrExpression, LiteralValue};
 
 #[derive(Debug)]
 pub struct Compiler {
     pub program: Program,
     pub declares: DeclaredTypes,
     pub customs: CustomTypes,
+    loops: Vec<(Option<String>, bool)>, // the labels of the loops being compiled, innermost last, and whether they can break with a value
+    pub return_type: Option<DataType>,  // the return type of the function being compiled, if any
+    hoisted: HashSet<String>,           // top-level functions declared ahead of their definitions, which only functions can refer to until then
+    ended: HashSet<String>,             // names declared in scopes that have ended, to tell them apart from names never declared
+    referenced: Vec<(String, Span)>,    // the top-level names the top-level statement being compiled refers to, and where
+    uses: HashMap<String, Vec<String>>, // the top-level names each top-level function refers to, which calling it can reach
+    callable: HashSet<String>,          // top-level functions which reach only what is defined, and so can be called already
+    pub modules: HashMap<String, Rc<Module>>, // the modules the program imports, by the paths they are imported with
 }
 
 pub type CompileResult<T> = Result<T, CompileError>;
@@ -21,16 +42,38 @@ pub type CompileResult<T> = Result<T, CompileError>;
 impl Compiler {
     #[inline]
     pub fn new(program: Program) -> Self {
+        let Prelude { declares, customs, .. } = Prelude::default();
+
         Self {
             program,
-            declares: DeclaredTypes::new(None),
-            customs: CustomTypes::new(None),
+            declares,
+            customs,
+            loops: Vec::new(),
+            return_type: None,
+            hoisted: HashSet::new(),
+            ended: HashSet::new(),
+            referenced: Vec::new(),
+            uses: HashMap::new(),
+            callable: HashSet::new(),
+            modules: HashMap::new(),
         }
     }
 
     #[inline]
     pub fn new_with(program: Program, declares: DeclaredTypes, customs: CustomTypes) -> Self {
-        Self { program, declares, customs }
+        Self {
+            program,
+            declares,
+            customs,
+            loops: Vec::new(),
+            return_type: None,
+            hoisted: HashSet::new(),
+            ended: HashSet::new(),
+            referenced: Vec::new(),
+            uses: HashMap::new(),
+            callable: HashSet::new(),
+            modules: HashMap::new(),
+        }
     }
 
     pub fn compile_program(&mut self) -> CompileResult<Vec<Instruction>> {
@@ -40,225 +83,976 @@ impl Compiler {
             return Err(CompileError::ParsingError(self.program.errors.clone()));
         }
 
-        for statement in self.program.statements.clone().iter() {
+        let statements = self.program.statements.clone();
+        self.hoist(&statements);
+
+        for statement in statements.iter() {
             instructions.push(self.compile_statement(statement)?);
         }
 
         Ok(instructions)
     }
 
+    /// Declares the functions bound at the top level ahead of their definitions, so that they can call each other wherever they
+    /// are defined.
+    pub fn hoist(&mut self, statements: &[Statement]) {
+        for statement in statements.iter() {
+            if let Some((name, data_type, mutable)) = function_binding(statement) {
+                self.declare(name.value.clone(), name.span, data_type, mutable);
+                self.hoisted.insert(name.value.clone());
+            }
+        }
+    }
+
     pub fn compile_statement(&mut self, statement: &Statement) -> CompileResult<Instruction> {
+        if self.declares.parent.is_some() {
+            return self.compile_statement_kind(statement);
+        }
+
+        self.referenced.clear();
+        let instruction = self.compile_statement_kind(statement)?;
+        let referenced = std::mem::take(&mut self.referenced);
+
+        // defining a function runs none of it, while anything else runs once, in order, and can call the functions it uses
+        match function_binding(statement) {
+            Some((name, ..)) => {
+                self.uses
+                    .insert(name.value.clone(), referenced.into_iter().map(|(name, _)| name).collect());
+            }
+            None => self.check_callable(&referenced)?,
+        }
+
+        Ok(instruction)
+    }
+
+    /// Fails if the top-level names referred to, or the functions they call in turn, reach a function before its definition
+    /// has run.
+    fn check_callable(&mut self, referenced: &[(String, Span)]) -> CompileResult<()> {
+        for (name, span) in referenced.iter() {
+            let mut reached = HashSet::from([name]);
+            let mut pending = vec![name];
+
+            while let Some(name) = pending.pop() {
+                if self.hoisted.contains(name) {
+                    return Err(TypeError::new(TypeErrorKind::CalledBeforeDefinition(name.clone()), *span));
+                }
+
+                for used in self.uses.get(name).into_iter().flatten() {
+                    if !self.callable.contains(used) && reached.insert(used) {
+                        pending.push(used);
+                    }
+                }
+            }
+
+            self.callable.extend(reached.into_iter().cloned());
+        }
+
+        Ok(())
+    }
+
+    fn compile_statement_kind(&mut self, statement: &Statement) -> CompileResult<Instruction> {
+        if statement.is_exported() && self.declares.parent.is_some() {
+            return Err(TypeError::new(TypeErrorKind::NotAtTopLevel("export".to_string()), statement.span()));
+        }
+
         Ok(match statement {
             Statement::LetStatement(LetStatement {
                 name,
                 value,
-                position,
+                span,
                 data_type,
+                mutable,
+                ..
             }) => {
-                let value = self.compile_expression(value, *position)?;
-                let value_type = Checker::new(Some(data_type), &self.declares, &self.customs, *position)?.get_type_from_ir_expression(&value)?;
+                let data_type = &custom_data_type(data_type, &self.customs)?;
+                self.declare_function(statement);
+                let value = self.compile_expression(value)?;
+                let value_type = Checker::new(Some(data_type), &self.declares, &self.customs)?.get_type_from_ir_expression(&value)?;
 
                 if data_type != &value_type {
                     return Err(TypeError::new(
                         TypeErrorKind::ExpectedDataType(data_type.to_string(), value_type.to_string()),
-                        *position,
-                        1
+                        value.span,
                     ));
                 }
 
-                self.declares.set(name.value.clone(), data_type.clone());
+                self.declare(name.value.clone(), name.span, data_type.clone(), *mutable);
 
-                Instruction::new(InstructionType::StoreName(name.value.clone(), value), *position)
+                Instruction::new(InstructionType::StoreName(name.value.clone(), value, data_type.clone(), *mutable), *span)
             }
-            Statement::AutoStatement(AutoStatement { name, value, position }) => {
-                let value = self.compile_expression(value, *position)?;
+            Statement::AutoStatement(AutoStatement {
+                name, value, span, mutable, ..
+            }) => {
+                self.declare_function(statement);
+                let value = self.compile_expression(value)?;
+                let value_type = Checker::new(None, &self.declares, &self.customs)?.get_type_from_ir_expression(&value)?;
 
-                self.declares.set(
-                    name.value.clone(),
-                    Checker::new(None, &self.declares, &self.customs, *position)?.get_type_from_ir_expression(&value)?,
-                );
+                self.declare(name.value.clone(), name.span, value_type.clone(), *mutable);
 
-                Instruction::new(InstructionType::StoreName(name.value.clone(), value), *position)
+                Instruction::new(InstructionType::StoreName(name.value.clone(), value, value_type, *mutable), *span)
             }
-            Statement::ReturnStatement(ReturnStatement { value, position }) => {
-                Instruction::new(InstructionType::Return(self.compile_expression(value, *position)?), *position)
+            Statement::ReturnStatement(ReturnStatement { value, span }) => {
+                if self.return_type.is_none() {
+                    return Err(TypeError::new(TypeErrorKind::ReturnOutsideFunction, *span));
+                }
+
+                let value = match value {
+                    Some(value) => self.compile_expression(value)?,
+                    None => IrExpression::new(IrExpressionKind::Literal(LiteralValue::Void), *span),
+                };
+
+                Instruction::new(InstructionType::Return(value), *span)
             }
             Statement::TypeStatement(TypeStatement {
-                name, data_type, position, ..
+                name,
+                generics,
+                data_type,
+                span,
+                ..
             }) => {
-                self.customs.set(name.value.clone(), data_type.clone());
+                let generics = generics.iter().map(|generic| generic.value.clone()).collect::<Vec<_>>();
 
-                Instruction::new(InstructionType::None, *position)
+                self.declare_type(name.value.clone(), generics, data_type.clone(), *span)
             }
-            Statement::StructStatement(_) => unimplemented!(),
-            Statement::DeclareStatement(DeclareStatement { name, data_type, position }) => {
-                self.declares.set(name.value.clone(), data_type.clone());
+            Statement::StructStatement(StructStatement {
+                name,
+                generics,
+                fields,
+                span,
+                ..
+            }) => {
+                for (index, (field, _)) in fields.iter().enumerate() {
+                    if fields[..index].iter().any(|(previous, _)| previous.value == field.value) {
+                        return Err(TypeError::new(
+                            TypeErrorKind::DuplicateField(name.value.clone(), field.value.clone()),
+                            field.span,
+                        ));
+                    }
+                }
 
-                Instruction::new(InstructionType::None, *position)
+                let generics = generics.iter().map(|generic| generic.value.clone()).collect::<Vec<_>>();
+                let struct_type = DataType::new(
+                    DataTypeKind::Struct(StructType::new(
+                        name.value.clone(),
+                        Vec::new(),
+                        fields.iter().map(|(field, data_type)| (field.value.clone(), data_type.clone())).collect(),
+                    )),
+                    *span,
+                );
+
+                self.declare_type(name.value.clone(), generics, struct_type, *span)
             }
-            Statement::ExpressionStatement(ExpressionStatement { expression, position }) => {
-                Instruction::new(InstructionType::Expression(self.compile_expression(expression, *position)?), *position)
+            Statement::EnumStatement(EnumStatement {
+                name,
+                generics,
+                variants,
+                span,
+                ..
+            }) => {
+                for (index, (variant, _)) in variants.iter().enumerate() {
+                    if variants[..index].iter().any(|(previous, _)| previous.value == variant.value) {
+                        return Err(TypeError::new(
+                            TypeErrorKind::DuplicateVariant(name.value.clone(), variant.value.clone()),
+                            variant.span,
+                        ));
+                    }
+                }
+
+                let generics = generics.iter().map(|generic| generic.value.clone()).collect::<Vec<_>>();
+                let enum_type = DataType::new(
+                    DataTypeKind::Enum(EnumType::new(
+                        name.value.clone(),
+                        Vec::new(),
+                        variants
+                            .iter()
+                            .map(|(variant, payload)| (variant.value.clone(), payload.clone()))
+                            .collect(),
+                    )),
+                    *span,
+                );
+
+                self.declare_type(name.value.clone(), generics, enum_type, *span)
+            }
+            Statement::DeclareStatement(DeclareStatement { name, data_type, span, .. }) => {
+                self.declare(name.value.clone(), name.span, data_type.clone(), false);
+
+                Instruction::new(InstructionType::DeclareName(name.value.clone(), data_type.clone()), *span)
             }
+            Statement::ImportStatement(ImportStatement { names, path, span }) => {
+                if self.declares.parent.is_some() {
+                    return Err(TypeError::new(TypeErrorKind::NotAtTopLevel("import".to_string()), *span));
+                }
+
+                let module = match self.modules.get(&path.value) {
+                    Some(module) => module.clone(),
+                    None => {
+                        return Err(TypeError::new(
+                            TypeErrorKind::ModuleNotFound(path.value.clone(), "it is not loaded".to_string()),
+                            path.span,
+                        ))
+                    }
+                };
+                let mut values = Vec::new();
+
+                for name in names.iter() {
+                    match module.exports.get(&name.value) {
+                        Some(export @ (Export::Value | Export::Declaration)) => {
+                            let data_type = module.declares.get(name.value.clone()).expect("an exported name is declared");
+                            self.import_types(&module, &data_type);
+                            self.declare(name.value.clone(), name.span, data_type, false);
+
+                            if export == &Export::Value {
+                                values.push(name.value.clone());
+                            }
+                        }
+                        Some(Export::Type) => self.import_type(&module, &name.value),
+                        None => {
+                            return Err(TypeError::new(
+                                TypeErrorKind::NotExported(name.value.clone(), path.value.clone()),
+                                name.span,
+                            ))
+                        }
+                    }
+                }
+
+                Instruction::new(InstructionType::Import(module.path.clone(), values), *span)
+            }
+            Statement::ExpressionStatement(ExpressionStatement { expression, semicolon, span }) => {
+                let expression = self.compile_expression(expression)?;
+
+                // the value of a block is checked along with the block, where it is used, unless nothing uses it
+                if *semicolon || self.declares.parent.is_none() {
+                    Checker::new(None, &self.declares, &self.customs)?.get_type_from_ir_expression(&expression)?;
+                }
+
+                match semicolon {
+                    true => Instruction::new(InstructionType::Expression(expression), *span),
+                    false => Instruction::new(InstructionType::Value(expression), *span),
+                }
+            }
+            // programs with parsing errors are rejected by `compile_program` before reaching here
+            Statement::ErrorStatement(ErrorStatement { span }) => Instruction::new(InstructionType::None, *span),
         })
     }
 
-    pub fn compile_expression(&mut self, expression: &Expression, position: Position) -> CompileResult<IrExpression> {
+    pub fn compile_expression(&mut self, expression: &Expression) -> CompileResult<IrExpression> {
+        let span = expression.span();
         let expression = match expression {
-            Expression::Identifier(Identifier { value, .. }) => IrExpression::Identifier(value.clone()),
-            Expression::BlockExpression(BlockExpression { statements, .. }) => {
-                let mut instructions = Vec::new();
+            Expression::Identifier(Identifier { value, span }) => {
+                if self.return_type.is_none() && self.hoisted.contains(value) && !self.declares.is_local(value) {
+                    return Err(TypeError::new(TypeErrorKind::UndefinedIdentifier(value.clone()), *span));
+                }
 
-                for statement in statements.iter() {
-                    instructions.push(self.compile_statement(statement)?);
+                if self.ended.contains(value) && self.declares.get(value.clone()).is_none() {
+                    return Err(TypeError::new(TypeErrorKind::OutOfScope(value.clone()), *span));
+                }
 
-                    if let Statement::ReturnStatement(_) = statement {
-                        break;
-                    }
+                if !self.declares.is_local(value) {
+                    self.referenced.push((value.clone(), *span));
                 }
 
-                IrExpression::Block(instructions)
+                IrExpressionKind::Identifier(value.clone())
             }
+            Expression::BlockExpression(BlockExpression { statements, .. }) => self.scoped(|compiler| {
+                let mut instructions = Vec::new();
+
+                for statement in statements.iter() {
+                    instructions.push(compiler.compile_statement(statement)?);
+                }
+
+                Ok(IrExpressionKind::Block(instructions))
+            })?,
             Expression::PrefixExpression(PrefixExpression { operator, right, .. }) => {
-                IrExpression::Prefix(operator.clone(), Box::new(self.compile_expression(right, position)?))
+                IrExpressionKind::Prefix(operator.clone(), Box::new(self.compile_expression(right)?))
             }
-            Expression::InfixExpression(InfixExpression { operator, left, right, .. }) => IrExpression::Infix(
-                Box::new(self.compile_expression(left, position)?),
+            // `&&` and `||` only evaluate their right operand when the left one does not decide the result
+            Expression::InfixExpression(InfixExpression { operator, left, right, .. }) if matches!(operator, TokenKind::And | TokenKind::Or) => {
+                IrExpressionKind::Logical(
+                    Box::new(self.compile_expression(left)?),
+                    operator.clone(),
+                    Box::new(self.compile_expression(right)?),
+                )
+            }
+            Expression::InfixExpression(InfixExpression { operator, left, right, .. }) => IrExpressionKind::Infix(
+                Box::new(self.compile_expression(left)?),
                 operator.clone(),
-                Box::new(self.compile_expression(right, position)?),
+                Box::new(self.compile_expression(right)?),
+            ),
+            Expression::AssignExpression(AssignExpression { target, operator, value, .. }) => IrExpressionKind::Assign(
+                Box::new(self.compile_expression(target)?),
+                operator.compound_operator().unwrap_or(TokenKind::Assign),
+                Box::new(self.compile_expression(value)?),
             ),
+            Expression::WhileExpression(WhileExpression { label, condition, body, .. }) => IrExpressionKind::Loop(
+                label_name(label),
+                Box::new(Some(self.compile_expression(condition)?)),
+                self.compile_loop_body(label, false, body)?,
+            ),
+            Expression::ForExpression(ForExpression {
+                label, name, iterable, body, ..
+            }) => {
+                let iterable = self.compile_expression(iterable)?;
+                let element_type = match Checker::new(None, &self.declares, &self.customs)?
+                    .get_type_from_ir_expression(&iterable)?
+                    .data_type
+                {
+                    DataTypeKind::Array(element_type) => *element_type,
+                    data_type => return Err(TypeError::new(TypeErrorKind::NotIterable(data_type.to_string()), iterable.span)),
+                };
+
+                // the loop variable is only visible in the body
+                let body = self.scoped(|compiler| {
+                    compiler.declare(name.value.clone(), name.span, element_type, false);
+                    compiler.compile_loop_body(label, false, body)
+                })?;
+
+                IrExpressionKind::For(label_name(label), name.value.clone(), Box::new(iterable), body)
+            }
+            Expression::LoopExpression(LoopExpression { label, body, .. }) => {
+                IrExpressionKind::Loop(label_name(label), Box::new(None), self.compile_loop_body(label, true, body)?)
+            }
+            Expression::BreakExpression(BreakExpression { label, value, .. }) => {
+                if !self.loop_target(label, "break", span)? && value.is_some() {
+                    return Err(TypeError::new(TypeErrorKind::BreakWithValue, span));
+                }
+
+                IrExpressionKind::Break(
+                    label_name(label),
+                    Box::new(value.as_ref().map(|value| self.compile_expression(value)).transpose()?),
+                )
+            }
+            Expression::ContinueExpression(ContinueExpression { label, .. }) => {
+                self.loop_target(label, "continue", span)?;
+
+                IrExpressionKind::Continue(label_name(label))
+            }
             Expression::IfExpression(IfExpression {
                 condition,
                 consequence,
                 alternative,
-                position,
-            }) => IrExpression::If(
-                Box::new(self.compile_expression(condition, *position)?),
-                Box::new(self.compile_expression(&Expression::BlockExpression(*consequence.clone()), *position)?),
+                ..
+            }) => IrExpressionKind::If(
+                Box::new(self.compile_expression(condition)?),
+                Box::new(self.compile_expression(&Expression::BlockExpression(*consequence.clone()))?),
                 Box::new(
                     alternative
                         .clone()
-                        .map(|alternative| self.compile_expression(&Expression::BlockExpression(*alternative), *position))
+                        .map(|alternative| self.compile_expression(&Expression::BlockExpression(*alternative)))
                         .transpose()?,
                 ),
             ),
-            Expression::FunctionLiteral(FunctionLiteral {
-                parameters,
-                body,
-                return_type,
-                position,
-                ..
-            }) => {
-                let mut new_parameters = Vec::new();
-
-                for (
-                    index,
-                    parameter @ Parameter {
-                        name,
-                        data_type,
-                        spread,
-                        position,
-                    },
-                ) in parameters.iter().enumerate()
-                {
-                    let data_type = custom_data_type(data_type, &self.customs)?;
+            Expression::MatchExpression(MatchExpression { value, arms, .. }) => {
+                let value = self.compile_expression(value)?;
+                let value_type = Checker::new(None, &self.declares, &self.customs)?.get_type_from_ir_expression(&value)?;
+
+                let mut compiled_arms = Vec::new();
 
-                    if *spread {
-                        if index != parameters.len() - 1 {
-                            return Err(TypeError::new(TypeErrorKind::SpreadParameterMustBeLast, *position, 2));
+                for MatchArm { pattern, guard, body, .. } in arms.iter() {
+                    let pattern = compile_pattern(pattern);
+
+                    // the names a pattern binds are only visible in its guard and body
+                    let (guard, body) = self.scoped(|compiler| {
+                        for (name, data_type) in pattern_bindings(&pattern, &value_type, &compiler.customs)? {
+                            compiler.declare(name, pattern.span, data_type, false);
                         }
 
-                        self.declares.set(
-                            name.value.clone(),
-                            DataType::new(DataTypeKind::Array(Box::new(data_type.clone())), *position),
-                        );
+                        Ok((
+                            guard.as_ref().map(|guard| compiler.compile_expression(guard)).transpose()?,
+                            compiler.compile_expression(body)?,
+                        ))
+                    })?;
 
-                        new_parameters.push(Parameter {
-                            name: name.clone(),
-                            data_type,
-                            spread: *spread,
-                            position: *position,
-                        });
+                    compiled_arms.push((pattern, guard, body));
+                }
 
-                        break;
-                    } else {
-                        self.declares.set(name.value.clone(), data_type);
-                        new_parameters.push(parameter.clone());
+                IrExpressionKind::Match(Box::new(value), compiled_arms)
+            }
+            Expression::FunctionLiteral(function) => {
+                // `break` and `continue` cannot leave a function
+                let loops = std::mem::take(&mut self.loops);
+                // type parameters and parameters are only visible inside the function
+                let function = self.scoped(|compiler| {
+                    for generic in function.generics.iter().flatten() {
+                        compiler.customs.set(
+                            generic.value.clone(),
+                            DataType::new(DataTypeKind::TypeParameter(generic.value.clone()), generic.span),
+                        );
                     }
-                }
 
-                IrExpression::Literal(LiteralValue::Function(
-                    new_parameters,
-                    match self.compile_expression(&Expression::BlockExpression(body.clone()), *position)? {
-                        IrExpression::Block(instructions) => instructions,
-                        _ => unreachable!(),
-                    },
-                    custom_data_type(return_type, &self.customs).map(|data_type| data_type.data_type)?,
-                    None,
-                ))
+                    compiler.compile_function_literal(function)
+                });
+                self.loops = loops;
+
+                function?
             }
-            Expression::CallExpression(CallExpression {
-                function,
-                arguments,
-                position,
-            }) => {
+            Expression::CallExpression(CallExpression { function, arguments, .. }) => {
                 let mut compiled_arguments = Vec::new();
-                let function = self.compile_expression(function, *position)?;
-                let function_type = match Checker::new(None, &self.declares, &self.customs, *position)?
+                let function = self.compile_expression(function)?;
+                let function_type = match Checker::new(None, &self.declares, &self.customs)?
                     .get_type_from_ir_expression(&function)?
                     .data_type
                 {
                     DataTypeKind::Fn(function_type) => function_type,
-                    _ => unreachable!(),
+                    data_type => return Err(TypeError::new(TypeErrorKind::NotCallable(data_type.to_string()), function.span)),
                 };
 
-                for (index, (argument, (_, spread))) in arguments.iter().zip(function_type.parameters.iter()).enumerate() {
-                    if *spread {
-                        compiled_arguments.push(self.compile_expression(
-                            &Expression::ArrayLiteral(ArrayLiteral {
-                                elements: arguments[index..].to_vec(),
-                                position: *position,
-                            }),
-                            *position,
-                        )?);
+                // arguments beyond the parameters are compiled too, so that the checker finds how many were given
+                for (index, argument) in arguments.iter().enumerate() {
+                    if function_type.parameters.get(index).is_some_and(|(_, spread)| *spread) {
+                        let elements = arguments[index..].to_vec();
+                        let span = elements.iter().fold(argument.span(), |span, element| span.to(element.span()));
+
+                        let elements = self.compile_expression(&Expression::ArrayLiteral(ArrayLiteral::new(elements, span)))?;
+                        compiled_arguments.push(IrExpression::new(IrExpressionKind::Spread(Box::new(elements)), span));
 
                         break;
                     }
 
-                    compiled_arguments.push(self.compile_expression(argument, *position)?);
+                    compiled_arguments.push(self.compile_expression(argument)?);
                 }
 
-                IrExpression::Call(Box::new(function), compiled_arguments)
+                IrExpressionKind::Call(Box::new(function), compiled_arguments)
+            }
+            Expression::TryExpression(TryExpression { value, .. }) => {
+                let value = self.compile_expression(value)?;
+                let value_type = Checker::new(None, &self.declares, &self.customs)?.get_type_from_ir_expression(&value)?;
+
+                if propagated(&value_type).is_none() {
+                    return Err(TypeError::new(TypeErrorKind::NotPropagatable(value_type.to_string()), value.span));
+                }
+
+                match &self.return_type {
+                    Some(return_type) if propagates(&value_type, return_type) => IrExpressionKind::Try(Box::new(value)),
+                    Some(return_type) => {
+                        return Err(TypeError::new(
+                            TypeErrorKind::IncompatiblePropagation(value_type.to_string(), return_type.to_string()),
+                            span,
+                        ))
+                    }
+                    None => return Err(TypeError::new(TypeErrorKind::TryOutsideFunction, span)),
+                }
             }
-            Expression::TypeofExpression(TypeofExpression { expression, position }) => {
-                let expression = self.compile_expression(expression, *position)?;
+            Expression::TypeofExpression(TypeofExpression { expression, .. }) => {
+                let expression = self.compile_expression(expression)?;
 
-                IrExpression::Literal(LiteralValue::String(
-                    Checker::new(None, &self.declares, &self.customs, *position)?
+                IrExpressionKind::Literal(LiteralValue::String(
+                    Checker::new(None, &self.declares, &self.customs)?
                         .get_type_from_ir_expression(&expression)?
                         .to_string(),
                 ))
             }
-            Expression::IndexExpression(IndexExpression { left, index, position }) => IrExpression::Index(
-                Box::new(self.compile_expression(left, *position)?),
-                Box::new(self.compile_expression(index, *position)?),
-            ),
-            Expression::StringLiteral(StringLiteral { value, .. }) => IrExpression::Literal(LiteralValue::String(value.clone())),
-            Expression::NumberLiteral(NumberLiteral { value, .. }) => IrExpression::Literal(LiteralValue::Number(*value)),
-            Expression::BooleanLiteral(BooleanLiteral { value, .. }) => IrExpression::Literal(LiteralValue::Boolean(*value)),
+            Expression::IndexExpression(IndexExpression { left, index, .. }) => {
+                IrExpressionKind::Index(Box::new(self.compile_expression(left)?), Box::new(self.compile_expression(index)?))
+            }
+            Expression::StringLiteral(StringLiteral { value, .. }) => IrExpressionKind::Literal(LiteralValue::String(value.clone())),
+            Expression::IntLiteral(IntLiteral { value, .. }) => IrExpressionKind::Literal(LiteralValue::Int(*value)),
+            Expression::FloatLiteral(FloatLiteral { value, .. }) => IrExpressionKind::Literal(LiteralValue::Float(*value)),
+            Expression::BooleanLiteral(BooleanLiteral { value, .. }) => IrExpressionKind::Literal(LiteralValue::Boolean(*value)),
             Expression::ArrayLiteral(ArrayLiteral { elements, .. }) => {
                 let mut elements_compiled = Vec::new();
 
                 for element in elements.iter() {
-                    elements_compiled.push(self.compile_expression(element, position)?);
+                    elements_compiled.push(self.compile_expression(element)?);
+                }
+
+                IrExpressionKind::Literal(LiteralValue::Array(Rc::new(elements_compiled)))
+            }
+            Expression::FieldExpression(FieldExpression { left, field, .. }) => {
+                IrExpressionKind::Field(Box::new(self.compile_expression(left)?), field.value.clone())
+            }
+            Expression::StructLiteral(StructLiteral { name, fields, .. }) => {
+                let mut fields_compiled = Vec::new();
+
+                for (field, value) in fields.iter() {
+                    fields_compiled.push((field.value.clone(), self.compile_expression(value)?));
+                }
+
+                IrExpressionKind::Literal(LiteralValue::Struct(name.value.clone(), fields_compiled))
+            }
+            Expression::EnumLiteral(EnumLiteral {
+                name, variant, arguments, ..
+            }) => {
+                let mut payload = Vec::new();
+
+                for argument in arguments.iter() {
+                    payload.push(self.compile_expression(argument)?);
                 }
 
-                IrExpression::Literal(LiteralValue::Array(elements_compiled))
+                IrExpressionKind::Literal(LiteralValue::Enum(name.value.clone(), variant.value.clone(), payload))
             }
-            Expression::StructLiteral(_) => todo!(),
         };
 
-        // Checker::new(None, &self.declares, &self.customs, position)?.get_type_from_ir_expression(&expression)?;
+        Ok(IrExpression::new(expression, span))
+    }
+
+    fn compile_loop_body(&mut self, label: &Option<Identifier>, value: bool, body: &BlockExpression) -> CompileResult<Block> {
+        self.loops.push((label_name(label), value));
+        let body = self.compile_expression(&Expression::BlockExpression(body.clone()));
+        self.loops.pop();
+
+        match body?.expression {
+            IrExpressionKind::Block(block) => Ok(block),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Finds the loop a `break` or `continue` leaves, returning whether it can break with a value.
+    fn loop_target(&self, label: &Option<Identifier>, keyword: &str, span: Span) -> CompileResult<bool> {
+        let target = match label {
+            Some(label) => self
+                .loops
+                .iter()
+                .rev()
+                .find(|(name, _)| name.as_ref() == Some(&label.value))
+                .ok_or_else(|| TypeError::new(TypeErrorKind::UndefinedLabel(label.value.clone()), label.span))?,
+            None => self
+                .loops
+                .last()
+                .ok_or_else(|| TypeError::new(TypeErrorKind::BreakOutsideLoop(keyword.to_string()), span))?,
+        };
+
+        Ok(target.1)
+    }
+
+    /// Compiles in a child scope, so that the names and types declared while compiling are not visible after it.
+    fn scoped<T>(&mut self, compile: impl FnOnce(&mut Self) -> CompileResult<T>) -> CompileResult<T> {
+        self.declares = DeclaredTypes::new(Some(std::mem::take(&mut self.declares)));
+        self.customs = CustomTypes::new(Some(std::mem::take(&mut self.customs)));
+
+        let result = compile(self);
+
+        let declares = std::mem::take(&mut self.declares);
+        self.ended.extend(Rc::unwrap_or_clone(declares.types).into_keys());
+        self.declares = Rc::unwrap_or_clone(declares.parent.expect("a child scope has a parent"));
+        self.customs = Rc::unwrap_or_clone(std::mem::take(&mut self.customs).parent.expect("a child scope has a parent"));
+
+        result
+    }
+
+    /// Declares `name`, whose declaration is at `span`.
+    fn declare(&mut self, name: String, span: Span, data_type: DataType, mutable: bool) {
+        // only a definition in the same scope as a hoisted function is that function's
+        if self.declares.parent.is_none() {
+            self.hoisted.remove(&name);
+        }
+
+        if mutable {
+            self.declares.set_mutable(name.clone(), data_type);
+        } else {
+            self.declares.set(name.clone(), data_type);
+        }
+
+        self.declares.set_span(name, span);
+    }
 
-        Ok(expression)
+    /// Declares a type, or a generic type whose type parameters are given by `generics`.
+    fn declare_type(&mut self, name: String, generics: Vec<String>, data_type: DataType, span: Span) -> Instruction {
+        let data_type = match generics.is_empty() {
+            true => {
+                self.customs.set(name.clone(), data_type.clone());
+                data_type
+            }
+            false => {
+                let data_type = declare_type_parameters(&data_type, &generics);
+                self.customs.set_generic(name.clone(), generics.clone(), data_type.clone());
+                data_type
+            }
+        };
+
+        Instruction::new(InstructionType::DeclareType(name, generics, data_type), span)
+    }
+
+    /// Declares a type of a module as it is declared there.
+    fn import_type(&mut self, module: &Module, name: &str) {
+        let Some(data_type) = module.customs.get(name.to_string()) else {
+            return;
+        };
+
+        match module.customs.get_generics(name.to_string()) {
+            generics if generics.is_empty() => self.customs.set(name.to_string(), data_type.clone()),
+            generics => self.customs.set_generic(name.to_string(), generics, data_type.clone()),
+        }
+
+        self.import_types(module, &data_type);
+    }
+
+    /// Declares the types of a module that an imported type refers to and are not declared yet, which are needed to check values of
+    /// it even when they are not imported by name.
+    fn import_types(&mut self, module: &Module, data_type: &DataType) {
+        let mut names = Vec::new();
+        type_names(data_type, &mut names);
+
+        for name in names {
+            if self.customs.get(name.clone()).is_none() {
+                self.import_type(module, &name);
+            }
+        }
+    }
+
+    /// Declares the name a function literal is bound to before compiling it, so that the function can call itself.
+    fn declare_function(&mut self, statement: &Statement) {
+        if let Some((name, data_type, mutable)) = function_binding(statement) {
+            self.declare(name.value.clone(), name.span, data_type, mutable);
+        }
+    }
+
+    fn compile_function_literal(&mut self, function: &FunctionLiteral) -> CompileResult<IrExpressionKind> {
+        let FunctionLiteral {
+            generics,
+            parameters,
+            body,
+            return_type,
+            ..
+        } = function;
+
+        let mut new_parameters = Vec::<Parameter>::new();
+
+        for (
+            index,
+            Parameter {
+                name,
+                data_type,
+                spread,
+                span,
+            },
+        ) in parameters.iter().enumerate()
+        {
+            let data_type = custom_data_type(data_type, &self.customs)?;
+
+            if let Some(first) = new_parameters.iter().find(|parameter| parameter.name.value == name.value) {
+                return Err(TypeError::with_labels(
+                    TypeErrorKind::DuplicateParameter(name.value.clone()),
+                    name.span,
+                    vec![Label::secondary(
+                        first.name.span,
+                        Some(format!("`{}` is first declared here", name.value)),
+                    )],
+                ));
+            }
+
+            if *spread {
+                if index != parameters.len() - 1 {
+                    return Err(TypeError::new(TypeErrorKind::SpreadParameterMustBeLast, *span));
+                }
+
+                self.declare(
+                    name.value.clone(),
+                    name.span,
+                    DataType::new(DataTypeKind::Array(Box::new(data_type.clone())), *span),
+                    false,
+                );
+            } else {
+                self.declare(name.value.clone(), name.span, data_type.clone(), false);
+            }
+
+            new_parameters.push(Parameter::new(name.clone(), data_type, *spread, *span));
+        }
+
+        let return_type = custom_data_type(return_type, &self.customs)?;
+        let enclosing = self.return_type.replace(return_type.clone());
+        let body = self.compile_expression(&Expression::BlockExpression(body.clone()));
+        self.return_type = enclosing;
+
+        Ok(IrExpressionKind::Literal(LiteralValue::Function(
+            generics.clone(),
+            new_parameters,
+            match body?.expression {
+                IrExpressionKind::Block(instructions) => Rc::new(instructions),
+                _ => unreachable!(),
+            },
+            return_type.data_type,
+            None,
+        )))
+    }
+}
+
+/// The name, type and mutability of a binding to a function literal, whose type is known from its signature without compiling
+/// its body.
+fn function_binding(statement: &Statement) -> Option<(&Identifier, DataType, bool)> {
+    match statement {
+        Statement::LetStatement(LetStatement {
+            name,
+            data_type,
+            value: Expression::FunctionLiteral(_),
+            mutable,
+            ..
+        }) => Some((name, data_type.clone(), *mutable)),
+        Statement::AutoStatement(AutoStatement {
+            name,
+            value: Expression::FunctionLiteral(function),
+            mutable,
+            ..
+        }) => {
+            let generics = function
+                .generics
+                .iter()
+                .flatten()
+                .map(|generic| generic.value.clone())
+                .collect::<Vec<_>>();
+
+            Some((
+                name,
+                DataType::new(
+                    DataTypeKind::Fn(FunctionType::new(
+                        function.generics.clone(),
+                        function
+                            .parameters
+                            .iter()
+                            .map(|parameter| (declare_type_parameters(&parameter.data_type, &generics), parameter.spread))
+                            .collect(),
+                        declare_type_parameters(&function.return_type, &generics),
+                    )),
+                    function.span,
+                ),
+                *mutable,
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `?` on a value of `data_type` can return its `None` or `Err` from a function returning `return_type`: any `Option` takes
+/// a `None`, while an `Err` needs a `Result` with the same error type.
+fn propagates(data_type: &DataType, return_type: &DataType) -> bool {
+    match (&data_type.data_type, &return_type.data_type) {
+        (DataTypeKind::Enum(value), DataTypeKind::Enum(function)) if value.name == function.name => match value.name.as_str() {
+            "Option" => true,
+            "Result" => value.generics.get(1) == function.generics.get(1),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Compiles a pattern, which `pattern_bindings` checks against the type of the value it matches.
+pub fn compile_pattern(pattern: &Pattern) -> IrPattern {
+    let kind = match pattern {
+        Pattern::WildcardPattern(_) => IrPatternKind::Wildcard,
+        Pattern::LiteralPattern(LiteralPattern { value, .. }) => IrPatternKind::Literal(match value {
+            Expression::IntLiteral(IntLiteral { value, .. }) => LiteralValue::Int(*value),
+            Expression::FloatLiteral(FloatLiteral { value, .. }) => LiteralValue::Float(*value),
+            Expression::StringLiteral(StringLiteral { value, .. }) => LiteralValue::String(value.clone()),
+            Expression::BooleanLiteral(BooleanLiteral { value, .. }) => LiteralValue::Boolean(*value),
+            _ => unreachable!("only literals are parsed as literal patterns"),
+        }),
+        Pattern::BindingPattern(BindingPattern { name, .. }) => IrPatternKind::Binding(name.value.clone()),
+        Pattern::ArrayPattern(ArrayPattern { elements, rest, .. }) => IrPatternKind::Array(
+            elements.iter().map(compile_pattern).collect(),
+            rest.as_ref().map(|rest| Box::new(compile_pattern(rest))),
+        ),
+        Pattern::StructPattern(StructPattern { name, fields, .. }) => IrPatternKind::Struct(
+            name.value.clone(),
+            fields
+                .iter()
+                .map(|(field, pattern)| (field.value.clone(), compile_pattern(pattern)))
+                .collect(),
+        ),
+        Pattern::EnumPattern(EnumPattern { name, variant, payload, .. }) => {
+            IrPatternKind::Enum(name.value.clone(), variant.value.clone(), payload.iter().map(compile_pattern).collect())
+        }
+    };
+
+    IrPattern::new(kind, pattern.span())
+}
+
+fn label_name(label: &Option<Identifier>) -> Option<String> {
+    label.as_ref().map(|label| label.value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Compiler;
+    use crate::CompileError;
+    use sntk_core::parser::parser::Parser;
+
+    /// The code of the error compiling a program gives, if any.
+    fn error(source: &str) -> Option<&'static str> {
+        match Compiler::new(Parser::from(source.to_string()).parse_program()).compile_program() {
+            Err(CompileError::TypeError(error)) => Some(error.message.code()),
+            Err(CompileError::ParsingError(errors)) => panic!("{source}: {errors:?}"),
+            Ok(_) => None,
+        }
+    }
+
+    #[test]
+    fn expression_statement_test() {
+        // expression statements are checked even though their values are not used
+        assert_eq!(error("if 1 { 2; };"), Some("E0100"));
+        assert_eq!(error("declare println = fn(int) -> void;\nprintln(\"x\");"), Some("E0100"));
+        assert_eq!(error("1 + \"x\";"), Some("E0100"));
+        assert_eq!(error("auto a = [1];\na[1.0];"), Some("E0100"));
+    }
+
+    #[test]
+    fn arguments_test() {
+        // too few arguments and too many are both an error
+        assert_eq!(error("auto f = fn(x: int) -> int => x;\nf();"), Some("E0101"));
+        assert_eq!(error("auto f = fn(x: int) -> int => x;\nf(1, 2);"), Some("E0101"));
+        assert_eq!(error("auto f = fn(x: int) -> int => x;\nf(1);"), None);
+        assert_eq!(error("declare g = fn(int, string) -> void;\ng(1);"), Some("E0101"));
+        assert_eq!(error("auto id = fn<T>(x: T) -> T => x;\nid(1, 2);"), Some("E0101"));
+
+        // a spread parameter takes the arguments after the others, but not the ones before it
+        let sum = "auto sum = fn(first: int, spread rest: int) -> int => first;\n";
+        assert_eq!(error(&format!("{sum}sum(1, 2, 3);")), None);
+        assert_eq!(error(&format!("{sum}sum();")), Some("E0101"));
+
+        // as are the payloads of enum variants
+        let shape = "enum Shape { Circle(float), Point };\n";
+        assert_eq!(error(&format!("{shape}auto c = Shape::Circle(1.0, 2.0);")), Some("E0101"));
+        assert_eq!(error(&format!("{shape}auto c = Shape::Circle();")), Some("E0101"));
+    }
+
+    #[test]
+    fn parameter_test() {
+        assert_eq!(error("auto f = fn(a: int, a: string) -> int => 1;"), Some("E0141"));
+        assert_eq!(error("auto f = fn(a: int, b: int, spread a: int) -> int => 1;"), Some("E0141"));
+        assert_eq!(error("auto f = fn(a: int) -> int => { auto g = fn(a: int) -> int => a; g(a) };"), None);
+
+        let Err(CompileError::TypeError(error)) =
+            Compiler::new(Parser::from("auto f = fn(a: int, a: string) -> int => 1;".to_string()).parse_program()).compile_program()
+        else {
+            panic!("duplicate parameters are accepted");
+        };
+        assert_eq!((error.span.start, error.span.end), (20, 21));
+        assert_eq!(
+            error.labels.iter().map(|label| (label.span.start, label.span.end)).collect::<Vec<_>>(),
+            [(12, 13)]
+        );
+    }
+
+    #[test]
+    fn index_test() {
+        // a non-int index is blamed on the index expression, wherever the index appears
+        for source in [
+            "auto a = [1, 2, 3];\na[1.0];",
+            "auto a = [1, 2, 3];\nauto b = a[true];",
+            "auto mut a = [1, 2, 3];\na[\"0\"] = 1;",
+            "auto mut a = [1, 2, 3];\na[0.5] += 1;",
+            "auto a = [[1], [2]];\na[0][1.5];",
+        ] {
+            match Compiler::new(Parser::from(source.to_string()).parse_program()).compile_program() {
+                Err(CompileError::TypeError(error)) => {
+                    assert_eq!(error.message.code(), "E0100", "{source}");
+                    assert!(
+                        ["1.0", "true", "\"0\"", "0.5", "1.5"].contains(&&source[error.span.start..error.span.end]),
+                        "{source}"
+                    );
+                }
+                _ => panic!("{source}: expected a type error"),
+            }
+        }
+
+        assert_eq!(error("auto a = 1;\na[0];"), Some("E0108"));
+    }
+
+    #[test]
+    fn hoisting_test() {
+        let (a, b, c) = (
+            "auto a = fn() -> int => b();\n",
+            "auto b = fn() -> int => c();\n",
+            "auto c = fn() -> int => 1;\n",
+        );
+
+        // top-level functions call each other wherever they are defined, but are only called once all they reach is defined
+        assert_eq!(error(&format!("{a}{b}{c}auto x = a();")), None);
+        assert_eq!(error(&format!("{a}auto x = a();\n{b}{c}")), Some("E0140"));
+        assert_eq!(error(&format!("{a}{b}auto x = a();\n{c}")), Some("E0140"));
+        assert_eq!(error(&format!("{a}{b}auto x = [fn() -> int => a()];\n{c}")), Some("E0140"));
+
+        // recursion ends, and a parameter named like a function later on is not that function
+        assert_eq!(
+            error("auto f = fn(n: int) -> int => g(n);\nauto g = fn(n: int) -> int => f(n);\nauto x = f(1);"),
+            None
+        );
+        assert_eq!(
+            error("auto f = fn(c: int) -> int => c;\nauto x = f(1);\nauto c = fn() -> int => 1;"),
+            None
+        );
+    }
+
+    #[test]
+    fn return_test() {
+        // a bare `return` returns void, which only a void function may do
+        assert_eq!(error("auto f = fn(x: int) -> void {\n    if x > 1 {\n        return;\n    };\n};"), None);
+        assert_eq!(error("auto f = fn() -> int {\n    return;\n};"), Some("E0100"));
+        assert_eq!(error("return;"), Some("E0124"));
+    }
+
+    #[test]
+    fn statement_test() {
+        // assignments, loops and matches used as statements are checked like any other
+        assert_eq!(error("auto mut a = 1;\na = \"x\";"), Some("E0100"));
+        assert_eq!(
+            error("auto mut a = 1;\nloop {\n    if a > 1 {\n        break 1;\n    };\n    break \"x\";\n};"),
+            Some("E0100")
+        );
+        assert_eq!(error("while 1 {};"), Some("E0100"));
+        assert_eq!(error("match 1 {\n    n if n => 1,\n    _ => 2,\n};"), Some("E0100"));
+        assert_eq!(error("match true {\n    true => 1,\n};"), Some("E0130"));
+        assert_eq!(error("match true {\n    true => 1,\n    b if b => 2,\n};"), Some("E0130"));
+        assert_eq!(error("match true {\n    true => 1,\n    false => 2,\n};"), None);
+    }
+
+    #[test]
+    fn expected_type_test() {
+        // `Option::None` takes its type argument from the parameter, element or variable it is given to
+        assert_eq!(error("auto f = fn(x: Option<int>) -> int => unwrap_or(x, 0);\nf(Option::None);"), None);
+        assert_eq!(error("unwrap_or(Option::None, 5);"), None);
+        assert_eq!(error("let a: Option<int>[] = [Option::None, Option::Some(1)];"), None);
+        assert_eq!(error("auto a = [Option::Some(1), Option::None];"), None);
+        assert_eq!(error("let mut a: Option<int> = Option::Some(1);\na = Option::None;"), None);
+        assert_eq!(error("let a: int[][] = [[1], [2]];"), None);
+
+        assert_eq!(error("auto a = Option::None;"), Some("E0116"));
+        assert_eq!(error("auto a = [Option::None, Option::Some(1)];"), Some("E0116"));
+        assert_eq!(error("let a: int = unwrap_or(Option::None, \"x\");"), Some("E0100"));
+    }
+
+    #[test]
+    fn comparison_test() {
+        assert_eq!(error("1 == 2;\n\"a\" < \"b\";\ntrue != false;"), None);
+        assert_eq!(error("true < false;"), Some("E0139"));
+        assert_eq!(error("[1] == [1];"), Some("E0139"));
+        assert_eq!(error("Option::Some(1) != Option::None;"), Some("E0139"));
+        assert_eq!(
+            error("struct P { x: int }\nauto a = struct P { x: 1 } == struct P { x: 1 };"),
+            Some("E0139")
+        );
+        assert_eq!(error("auto f = fn() -> int => 1;\nf == f;"), Some("E0139"));
+    }
+
+    #[test]
+    fn secondary_label_test() {
+        // what an error is in conflict with is labelled, as the source it spans
+        for (source, code, labelled) in [
+            ("auto x = 1;\nx = 2;", "E0119", "x"),
+            ("auto f = fn(n: int) -> int { n = 1; n };", "E0119", "n"),
+            ("auto mut x = 1;\n{ auto x = 1; x = 2; };", "E0119", "x"),
+            ("auto f = fn<T>(a: T, b: T) -> T => a;\nauto s = \"s\";\nf(s, 1);", "E0117", "s"),
+            ("auto x = if true { 1 } else { \"a\" };", "E0100", "{ 1 }"),
+        ] {
+            match Compiler::new(Parser::from(source.to_string()).parse_program()).compile_program() {
+                Err(CompileError::TypeError(error)) => {
+                    assert_eq!(error.message.code(), code, "{source}");
+                    assert_eq!(
+                        error
+                            .labels
+                            .iter()
+                            .map(|label| &source[label.span.start..label.span.end])
+                            .collect::<Vec<_>>(),
+                        [labelled],
+                        "{source}"
+                    );
+                }
+                _ => panic!("{source}: expected a type error"),
+            }
+        }
+
+        // a shadowing declaration is the one pointed at
+        match Compiler::new(Parser::from("auto mut x = 1;\nauto x = 2;\nx = 3;".to_string()).parse_program()).compile_program() {
+            Err(CompileError::TypeError(error)) => assert_eq!(error.labels[0].span.start, 21),
+            _ => panic!("expected a type error"),
+        }
     }
 }
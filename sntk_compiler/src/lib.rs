@@ -1,7 +1,15 @@
 pub mod checker;
 pub mod compiler;
+pub mod module;
+pub mod prelude;
 
-use sntk_core::parser::{ast::Position, ParsingError};
+use sntk_core::{
+    diagnostic::{Diagnostic, Label},
+    parser::{
+        ast::{FileId, Span},
+        ParsingError,
+    },
+};
 use std::fmt;
 use thiserror::Error;
 
@@ -15,7 +23,24 @@ impl fmt::Display for CompileError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::ParsingError(errors) => write!(f, "{}", errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n")),
-            Self::TypeError(TypeError { message, position, .. }) => write!(f, "{}: {}", position, message),
+            Self::TypeError(TypeError { message, span, .. }) => write!(f, "{}: {}", span, message),
+        }
+    }
+}
+
+impl CompileError {
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            Self::ParsingError(errors) => errors.iter().map(Diagnostic::from).collect(),
+            Self::TypeError(error) => vec![Diagnostic::from(error)],
+        }
+    }
+
+    /// The file the error is in, which is that of an imported module when the error is in one.
+    pub fn file(&self) -> FileId {
+        match self {
+            Self::ParsingError(errors) => errors.first().map(|error| error.span.file).unwrap_or_default(),
+            Self::TypeError(error) => error.span.file,
         }
     }
 }
@@ -23,13 +48,39 @@ impl fmt::Display for CompileError {
 #[derive(Debug, Clone)]
 pub struct TypeError {
     pub message: TypeErrorKind,
-    pub position: Position,
-    pub debug: usize,
+    pub span: Span,
+    pub labels: Vec<Label>, // secondary labels, pointing at what the error is in conflict with
 }
 
 impl TypeError {
-    pub fn new(message: TypeErrorKind, position: Position, debug: usize) -> CompileError {
-        CompileError::TypeError(Self { message, position, debug })
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(message: TypeErrorKind, span: Span) -> CompileError {
+        Self::with_labels(message, span, Vec::new())
+    }
+
+    pub fn with_labels(message: TypeErrorKind, span: Span, labels: Vec<Label>) -> CompileError {
+        CompileError::TypeError(Self { message, span, labels })
+    }
+}
+
+impl From<&TypeError> for Diagnostic {
+    fn from(error: &TypeError) -> Self {
+        let label = match &error.message {
+            TypeErrorKind::ExpectedDataType(expected, _) => Some(format!("expected `{expected}`")),
+            TypeErrorKind::MismatchedTypeParameter(name, _, argument) => Some(format!("`{name}` is inferred as `{argument}` here")),
+            TypeErrorKind::UndefinedIdentifier(_) | TypeErrorKind::UndefinedType(_) | TypeErrorKind::UndefinedLabel(_) => {
+                Some("not found in this scope".to_string())
+            }
+            _ => None,
+        };
+
+        let diagnostic = Diagnostic::error(error.message.code(), error.message.to_string()).with_label(Label::primary(error.span, label));
+
+        error
+            .labels
+            .iter()
+            .fold(diagnostic, |diagnostic, label| diagnostic.with_label(label.clone()))
+            .with_help(error.message.help())
     }
 }
 
@@ -40,11 +91,132 @@ pub enum TypeErrorKind {
     #[error("Expected `{0}` arguments, got `{1}` instead")] ExpectedArguments(usize, usize),
     #[error("Undefined identifier: `{0}`")] UndefinedIdentifier(String),
     #[error("Undefined type: `{0}`")] UndefinedType(String),
+    #[error("Expected `{1}` type arguments for `{0}`, got `{2}` instead")] ExpectedTypeArguments(String, usize, usize),
+    #[error("Cannot infer type parameter `{0}`")] UnresolvedTypeParameter(String, Option<String>),
+    #[error("Type parameter `{0}` is inferred as both `{1}` and `{2}`")] MismatchedTypeParameter(String, String, String),
     #[error("Unknown type: `{0}`")] UnknownType(String),
     #[error("Unknown array type")] UnknownArrayType,
     #[error("Unexpected parameter length")] UnexpectedParameterLength,
     #[error("`{0}` is not a callable")] NotCallable(String),
-    #[error("`{0}` is not a indexable")] NotIndexable(String),
+    #[error("`{0}` is not indexable")] NotIndexable(String),
+    #[error("`{0}` is not a struct")] NotAStruct(String),
+    #[error("`{0}` is not a number")] NotANumber(String),
+    #[error("Missing field `{1}` in `{0}`")] MissingField(String, String),
+    #[error("Unknown field `{1}` in `{0}`")] UnknownField(String, String),
+    #[error("Duplicate field `{1}` in `{0}`")] DuplicateField(String, String),
+    #[error("`{0}` is not an enum")] NotAnEnum(String),
+    #[error("Unknown variant `{1}` in `{0}`")] UnknownVariant(String, String),
+    #[error("Duplicate variant `{1}` in `{0}`")] DuplicateVariant(String, String),
+    #[error("Pattern `{0}` cannot match a value of type `{1}`")] MismatchedPattern(String, String),
+    #[error("Non-exhaustive match, {0} not covered")] NonExhaustiveMatch(String),
+    #[error("`{0}` is bound more than once in the same pattern")] DuplicateBinding(String),
+    #[error("Cannot assign twice to immutable variable `{0}`")] ImmutableAssignment(String),
+    #[error("`{0}` outside of a loop")] BreakOutsideLoop(String),
+    #[error("`return` outside of a function")] ReturnOutsideFunction,
+    #[error("Undefined label `'{0}`")] UndefinedLabel(String),
+    #[error("`{0}` is out of scope")] OutOfScope(String),
+    #[error("`break` with a value in a `while` or `for` loop")] BreakWithValue,
+    #[error("`{0}` is not iterable")] NotIterable(String),
+    #[error("`?` cannot be applied to `{0}`")] NotPropagatable(String),
+    #[error("`?` on `{0}` cannot return from a function returning `{1}`")] IncompatiblePropagation(String, String),
+    #[error("`?` outside of a function")] TryOutsideFunction,
+    #[error("`{0}` outside of the top level of a module")] NotAtTopLevel(String),
+    #[error("Cannot load module `{0}`: {1}")] ModuleNotFound(String, String),
+    #[error("Import cycle: {0}")] ImportCycle(String),
+    #[error("`{1}` does not export `{0}`")] NotExported(String, String),
+    #[error("`{0}` values cannot be compared with `{1}`")] NotComparable(String, String),
+    #[error("`{0}` can be called here before it is defined")] CalledBeforeDefinition(String),
     #[error("Spread parameter must be last")] SpreadParameterMustBeLast,
-    #[error("`if` expression without alternative")] IfExpressionWithoutAlternative
+    #[error("Duplicate parameter `{0}`")] DuplicateParameter(String),
+}
+
+impl TypeErrorKind {
+    /// A stable code identifying the kind of error, never reused once assigned.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeErrorKind::ExpectedDataType(..) => "E0100",
+            TypeErrorKind::ExpectedArguments(..) => "E0101",
+            TypeErrorKind::UndefinedIdentifier(_) => "E0102",
+            TypeErrorKind::UndefinedType(_) => "E0103",
+            TypeErrorKind::UnknownType(_) => "E0104",
+            TypeErrorKind::UnknownArrayType => "E0105",
+            TypeErrorKind::UnexpectedParameterLength => "E0106",
+            TypeErrorKind::NotCallable(_) => "E0107",
+            TypeErrorKind::NotIndexable(_) => "E0108",
+            TypeErrorKind::SpreadParameterMustBeLast => "E0109",
+            TypeErrorKind::NotAStruct(_) => "E0111",
+            TypeErrorKind::MissingField(..) => "E0112",
+            TypeErrorKind::UnknownField(..) => "E0113",
+            TypeErrorKind::DuplicateField(..) => "E0114",
+            TypeErrorKind::ExpectedTypeArguments(..) => "E0115",
+            TypeErrorKind::UnresolvedTypeParameter(..) => "E0116",
+            TypeErrorKind::MismatchedTypeParameter(..) => "E0117",
+            TypeErrorKind::NotANumber(_) => "E0118",
+            TypeErrorKind::ImmutableAssignment(_) => "E0119",
+            TypeErrorKind::BreakOutsideLoop(_) => "E0120",
+            TypeErrorKind::UndefinedLabel(_) => "E0121",
+            TypeErrorKind::BreakWithValue => "E0122",
+            TypeErrorKind::NotIterable(_) => "E0123",
+            TypeErrorKind::ReturnOutsideFunction => "E0124",
+            TypeErrorKind::OutOfScope(_) => "E0125",
+            TypeErrorKind::NotAnEnum(_) => "E0126",
+            TypeErrorKind::UnknownVariant(..) => "E0127",
+            TypeErrorKind::DuplicateVariant(..) => "E0128",
+            TypeErrorKind::MismatchedPattern(..) => "E0129",
+            TypeErrorKind::NonExhaustiveMatch(_) => "E0130",
+            TypeErrorKind::DuplicateBinding(_) => "E0131",
+            TypeErrorKind::NotPropagatable(_) => "E0132",
+            TypeErrorKind::IncompatiblePropagation(..) => "E0133",
+            TypeErrorKind::TryOutsideFunction => "E0134",
+            TypeErrorKind::NotAtTopLevel(_) => "E0135",
+            TypeErrorKind::ModuleNotFound(..) => "E0136",
+            TypeErrorKind::ImportCycle(_) => "E0137",
+            TypeErrorKind::NotExported(..) => "E0138",
+            TypeErrorKind::NotComparable(..) => "E0139",
+            TypeErrorKind::CalledBeforeDefinition(_) => "E0140",
+            TypeErrorKind::DuplicateParameter(_) => "E0141",
+        }
+    }
+
+    pub fn help(&self) -> Option<String> {
+        match self {
+            TypeErrorKind::UnknownArrayType => Some("give the binding an explicit type, e.g. `let xs: int[] = [];`".to_string()),
+            TypeErrorKind::SpreadParameterMustBeLast => Some("move the `spread` parameter to the end of the parameter list".to_string()),
+            TypeErrorKind::MissingField(_, field) => Some(format!("add the field, e.g. `{field}: ...`")),
+            TypeErrorKind::ExpectedDataType(expected, actual)
+                if [expected, actual].iter().all(|data_type| ["Int", "Float"].contains(&data_type.as_str())) =>
+            {
+                Some("ints and floats are not converted implicitly, use `to_float(...)` or `to_int(...)`".to_string())
+            }
+            TypeErrorKind::NotANumber(_) => Some("arithmetic operators take two `int`s or two `float`s".to_string()),
+            TypeErrorKind::ImmutableAssignment(name) => Some(format!("declare it with `let mut {name}` or `auto mut {name}`")),
+            TypeErrorKind::UndefinedLabel(label) => Some(format!("label the loop, as in `'{label}: while ...`")),
+            TypeErrorKind::BreakWithValue => Some("only `loop` can give a value, since the others can end without a `break`".to_string()),
+            TypeErrorKind::NotIterable(_) => Some("`for` loops go over the elements of an array".to_string()),
+            TypeErrorKind::ReturnOutsideFunction => Some("a block gives the value of its last expression, written without a `;`".to_string()),
+            TypeErrorKind::OutOfScope(name) => Some(format!(
+                "`{name}` is declared in a block that has ended, declare it before the block instead"
+            )),
+            TypeErrorKind::NonExhaustiveMatch(_) => Some("add arms for the missing cases, or a `_` arm for everything else".to_string()),
+            TypeErrorKind::UnresolvedTypeParameter(name, None) => Some(format!("use `{name}` in the type of a parameter so that it can be inferred")),
+            TypeErrorKind::UnresolvedTypeParameter(_, Some(owner)) => Some(format!(
+                "annotate the type the value is expected to have, as in `let value: {owner}<...> = ...`"
+            )),
+            TypeErrorKind::NotPropagatable(_) => Some("`?` takes an `Option` or a `Result`".to_string()),
+            TypeErrorKind::IncompatiblePropagation(..) => {
+                Some("`?` on an `Option` needs the function to return an `Option`, and on a `Result` one with the same error type".to_string())
+            }
+            TypeErrorKind::TryOutsideFunction => Some("`match` on the value to handle its `None` or `Err` instead".to_string()),
+            TypeErrorKind::NotAtTopLevel(_) => Some("modules import and export names outside of any block or function".to_string()),
+            TypeErrorKind::ModuleNotFound(..) => Some("paths are relative to the importing file, as in `./util.sntk`".to_string()),
+            TypeErrorKind::ImportCycle(_) => Some("move what the modules share into a module that imports neither of them".to_string()),
+            TypeErrorKind::NotExported(name, _) => Some(format!("mark its definition with `export`, as in `export auto {name} = ...;`")),
+            TypeErrorKind::NotComparable(_, operator) if operator == "==" || operator == "!=" => {
+                Some("only ints, floats, strings and booleans can be compared for equality, `match` on other values instead".to_string())
+            }
+            TypeErrorKind::NotComparable(..) => Some("only ints, floats and strings can be ordered".to_string()),
+            TypeErrorKind::CalledBeforeDefinition(name) => Some(format!("move the definition of `{name}` above the code that uses it")),
+            _ => None,
+        }
+    }
 }
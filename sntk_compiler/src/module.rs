@@ -0,0 +1,366 @@
+use crate::{
+    checker::{CustomTypes, DeclaredTypes},
+    compiler::{CompileResult, Compiler},
+    prelude::Prelude,
+    CompileError, TypeError, TypeErrorKind,
+};
+use sntk_core::{
+    parser::{
+        ast::{
+            AutoStatement, DataType, DataTypeKind, DeclareStatement, EnumStatement, FileId, ImportStatement, LetStatement, Program, Statement,
+            StructStatement, TypeStatement,
+        },
+        parser::Parser,
+    },
+    tokenizer::lexer::Lexer,
+};
+use sntk_ir::instruction::{Instruction, InstructionType};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+/// What an exported name of a module is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Export {
+    Value,       // bound with `let` or `auto`
+    Declaration, // provided by the host, with `declare`
+    Type,        // declared with `type`, `struct` or `enum`
+}
+
+/// A module that has been type checked, with the names and types it declares at its top level.
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub path: String, // the path the module is run under, relative to the directory of the program loading it
+    pub declares: DeclaredTypes,
+    pub customs: CustomTypes,
+    pub exports: HashMap<String, Export>,
+}
+
+impl Module {
+    pub fn new(path: String, program: &Program, declares: DeclaredTypes, customs: CustomTypes) -> Self {
+        let exports = program
+            .statements
+            .iter()
+            .filter(|statement| statement.is_exported())
+            .filter_map(|statement| match statement {
+                Statement::LetStatement(LetStatement { name, .. }) | Statement::AutoStatement(AutoStatement { name, .. }) => {
+                    Some((name.value.clone(), Export::Value))
+                }
+                Statement::DeclareStatement(DeclareStatement { name, .. }) => Some((name.value.clone(), Export::Declaration)),
+                Statement::TypeStatement(TypeStatement { name, .. })
+                | Statement::StructStatement(StructStatement { name, .. })
+                | Statement::EnumStatement(EnumStatement { name, .. }) => Some((name.value.clone(), Export::Type)),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            path,
+            declares,
+            customs,
+            exports,
+        }
+    }
+
+    /// The exported names that are bound to values when the module runs, in order.
+    pub fn values(&self) -> Vec<String> {
+        let mut values = self
+            .exports
+            .iter()
+            .filter(|(_, export)| **export == Export::Value)
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+
+        values.sort();
+        values
+    }
+}
+
+/// Loads the modules a program imports, and the ones they import in turn, checking each of them once however many times it is
+/// imported.
+#[derive(Debug)]
+pub struct Loader {
+    root: PathBuf,
+    prelude: Prelude,
+    sources: Vec<(PathBuf, String)>, // the sources of the modules loaded, where `FileId(0)` is the importing program's own
+    modules: HashMap<PathBuf, Rc<Module>>, // the modules loaded, by their canonical paths
+    loading: Vec<PathBuf>,           // the modules being loaded, each imported by the one before it
+    linked: Vec<Instruction>,        // the modules loaded and not yet taken, each after the ones it imports
+}
+
+impl Loader {
+    /// A loader for programs in `root`, which their imports are relative to.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+
+        Self {
+            root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+            prelude: Prelude::default(),
+            sources: Vec::new(),
+            modules: HashMap::new(),
+            loading: Vec::new(),
+            linked: Vec::new(),
+        }
+    }
+
+    /// Compiles a program along with the modules it imports and the definitions it uses from the prelude, so that it can be run
+    /// or transpiled on its own.
+    pub fn load(&mut self, source: &str) -> CompileResult<Vec<Instruction>> {
+        let program = parse(source, FileId(0));
+
+        if !program.errors.is_empty() {
+            return Err(CompileError::ParsingError(program.errors));
+        }
+
+        let modules = self.imports(&program)?;
+        let mut compiler = Compiler::new_with(program, self.prelude.declares.clone(), self.prelude.customs.clone());
+        compiler.modules = modules;
+
+        let instructions = compiler.compile_program()?;
+        let mut linked = self.take_linked();
+        linked.extend(self.prelude.link(instructions));
+
+        Ok(linked)
+    }
+
+    /// Loads the modules imported at the top level of a program in `root`, by the paths they are imported with.
+    pub fn imports(&mut self, program: &Program) -> CompileResult<HashMap<String, Rc<Module>>> {
+        let root = self.root.clone();
+        self.resolve(program, &root)
+    }
+
+    /// Loads the module an import statement of a program in `root` imports.
+    pub fn import(&mut self, statement: &ImportStatement) -> CompileResult<Rc<Module>> {
+        let root = self.root.clone();
+        self.module(statement, &root)
+    }
+
+    /// Takes the modules loaded since the last call, to be run ahead of the programs importing them.
+    pub fn take_linked(&mut self) -> Vec<Instruction> {
+        std::mem::take(&mut self.linked)
+    }
+
+    /// The path and the text of a loaded module, `None` for the importing program itself.
+    pub fn source(&self, file: FileId) -> Option<(&Path, &str)> {
+        let (path, text) = self.sources.get(file.0.checked_sub(1)?)?;
+        Some((path, text))
+    }
+
+    fn resolve(&mut self, program: &Program, directory: &Path) -> CompileResult<HashMap<String, Rc<Module>>> {
+        let mut modules = HashMap::new();
+
+        for statement in program.statements.iter() {
+            if let Statement::ImportStatement(statement) = statement {
+                modules.insert(statement.path.value.clone(), self.module(statement, directory)?);
+            }
+        }
+
+        Ok(modules)
+    }
+
+    fn module(&mut self, statement: &ImportStatement, directory: &Path) -> CompileResult<Rc<Module>> {
+        let ImportStatement { path, span, .. } = statement;
+        let not_found = |error: std::io::Error| TypeError::new(TypeErrorKind::ModuleNotFound(path.value.clone(), error.to_string()), path.span);
+        let canonical = directory.join(&path.value).canonicalize().map_err(not_found)?;
+
+        if let Some(module) = self.modules.get(&canonical) {
+            return Ok(module.clone());
+        }
+
+        if let Some(index) = self.loading.iter().position(|loading| loading == &canonical) {
+            let cycle = self.loading[index..]
+                .iter()
+                .chain([&canonical])
+                .map(|path| self.relative(path))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+
+            return Err(TypeError::new(TypeErrorKind::ImportCycle(cycle), *span));
+        }
+
+        let source = fs::read_to_string(&canonical).map_err(not_found)?;
+        let program = parse(&source, FileId(self.sources.len() + 1));
+        self.sources.push((shown(&canonical), source));
+
+        if !program.errors.is_empty() {
+            return Err(CompileError::ParsingError(program.errors));
+        }
+
+        self.loading.push(canonical.clone());
+        let modules = self.resolve(&program, canonical.parent().expect("a file is in a directory"));
+        self.loading.pop();
+
+        let mut compiler = Compiler::new_with(program, self.prelude.declares.clone(), self.prelude.customs.clone());
+        compiler.modules = modules?;
+
+        let instructions = compiler.compile_program()?;
+        let module = Rc::new(Module::new(
+            self.relative(&canonical),
+            &compiler.program,
+            compiler.declares,
+            compiler.customs,
+        ));
+
+        self.linked.push(Instruction::new(
+            InstructionType::Module(module.path.clone(), self.prelude.link(instructions), module.values()),
+            *span,
+        ));
+        self.modules.insert(canonical, module.clone());
+
+        Ok(module)
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root).unwrap_or(path).display().to_string()
+    }
+}
+
+fn parse(source: &str, file: FileId) -> Program {
+    let mut lexer = Lexer::new(source.to_string());
+    lexer.file = file;
+
+    Parser::new(lexer).parse_program()
+}
+
+/// A path as it is shown in errors, relative to the working directory when it is inside it.
+fn shown(path: &Path) -> PathBuf {
+    let directory = env::current_dir().and_then(|directory| directory.canonicalize());
+
+    match directory.as_deref().map(|directory| path.strip_prefix(directory)) {
+        Ok(Ok(relative)) => relative.to_path_buf(),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Collects the names of the types a type refers to, which are resolved by name when checking and so have to be imported along
+/// with it.
+pub fn type_names(data_type: &DataType, names: &mut Vec<String>) {
+    match &data_type.data_type {
+        DataTypeKind::Array(element) => type_names(element, names),
+        DataTypeKind::Fn(function) => {
+            for (parameter, _) in function.parameters.iter() {
+                type_names(parameter, names);
+            }
+
+            type_names(&function.return_type, names);
+        }
+        DataTypeKind::Struct(struct_type) => {
+            names.push(struct_type.name.clone());
+            struct_type.generics.iter().for_each(|generic| type_names(generic, names));
+            struct_type.fields.iter().for_each(|(_, field)| type_names(field, names));
+        }
+        DataTypeKind::Enum(enum_type) => {
+            names.push(enum_type.name.clone());
+            enum_type.generics.iter().for_each(|generic| type_names(generic, names));
+            enum_type
+                .variants
+                .iter()
+                .flat_map(|(_, payload)| payload)
+                .for_each(|data_type| type_names(data_type, names));
+        }
+        DataTypeKind::Generic(generic) => {
+            type_names(&generic.0, names);
+            generic.1.iter().for_each(|argument| type_names(argument, names));
+        }
+        DataTypeKind::Custom(name) => names.push(name.clone()),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Loader;
+    use crate::{CompileError, TypeErrorKind};
+    use sntk_ir::instruction::InstructionType;
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+    };
+
+    /// Writes the modules to a directory of their own, named after the test.
+    fn modules(test: &str, modules: &[(&str, &str)]) -> PathBuf {
+        let directory = env::temp_dir().join(format!("sntk_{test}"));
+
+        for (path, source) in modules {
+            let path = directory.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+
+        directory
+    }
+
+    fn error(directory: &Path, source: &str) -> TypeErrorKind {
+        match Loader::new(directory).load(source) {
+            Err(CompileError::TypeError(error)) => error.message,
+            result => panic!("expected a type error, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn load_test() {
+        let directory = modules(
+            "load_test",
+            &[
+                ("lib/point.sntk", "export struct Point { x: int, y: int }\nexport auto origin = struct Point { x: 0, y: 0 };"),
+                (
+                    "lib/shift.sntk",
+                    "import { Point } from \"./point.sntk\";\nexport auto shift = fn(point: Point) -> Point => struct Point { x: point.x + 1, y: point.y };",
+                ),
+            ],
+        );
+
+        let instructions = Loader::new(&directory)
+            .load("import { origin } from \"./lib/point.sntk\";\nimport { shift } from \"./lib/shift.sntk\";\nauto x = shift(origin).x;")
+            .unwrap();
+
+        // `point.sntk` is imported twice but only run once, ahead of the module importing it
+        let modules = instructions
+            .iter()
+            .filter_map(|instruction| match &instruction.instruction {
+                InstructionType::Module(path, _, values) => Some((path.as_str(), values.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            modules,
+            vec![
+                ("lib/point.sntk", vec!["origin".to_string()]),
+                ("lib/shift.sntk", vec!["shift".to_string()])
+            ]
+        );
+    }
+
+    #[test]
+    fn import_error_test() {
+        let directory = modules(
+            "import_error_test",
+            &[
+                ("a.sntk", "import { b } from \"./b.sntk\";\nexport auto a = 1;"),
+                ("b.sntk", "import { a } from \"./a.sntk\";\nexport auto b = 2;"),
+                ("c.sntk", "auto c = 3;"),
+            ],
+        );
+
+        assert!(matches!(
+            error(&directory, "import { a } from \"./a.sntk\";"),
+            TypeErrorKind::ImportCycle(cycle) if cycle == "a.sntk -> b.sntk -> a.sntk"
+        ));
+        assert!(matches!(
+            error(&directory, "import { c } from \"./c.sntk\";"),
+            TypeErrorKind::NotExported(..)
+        ));
+        assert!(matches!(
+            error(&directory, "import { d } from \"./d.sntk\";"),
+            TypeErrorKind::ModuleNotFound(..)
+        ));
+        assert!(matches!(
+            error(&directory, "auto f = fn() -> void { export auto x = 1; };"),
+            TypeErrorKind::NotAtTopLevel(_)
+        ));
+    }
+}
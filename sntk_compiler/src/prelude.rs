@@ -0,0 +1,110 @@
+use crate::{
+    checker::{CustomTypes, DeclaredTypes},
+    compiler::Compiler,
+};
+use sntk_core::parser::parser::Parser;
+use sntk_ir::instruction::{Instruction, InstructionType, IrExpression, IrExpressionKind};
+use std::collections::HashSet;
+
+const SOURCE: &str = include_str!("prelude.sntk");
+
+/// The standard prelude every program is compiled against: `Option`, `Result` and the functions working on them.
+#[derive(Debug, Clone)]
+pub struct Prelude {
+    pub instructions: Vec<Instruction>,
+    pub declares: DeclaredTypes,
+    pub customs: CustomTypes,
+}
+
+impl Default for Prelude {
+    fn default() -> Self {
+        let mut compiler = Compiler::new_with(
+            Parser::from(SOURCE.to_string()).parse_program(),
+            DeclaredTypes::builtins(),
+            CustomTypes::new(None),
+        );
+        let instructions = compiler.compile_program().expect("the prelude compiles");
+
+        Self {
+            instructions,
+            declares: compiler.declares,
+            customs: compiler.customs,
+        }
+    }
+}
+
+impl Prelude {
+    /// Puts the definitions of the prelude that a program uses, and does not define itself, ahead of it, so that it can be run
+    /// or transpiled on its own.
+    pub fn link(&self, program: Vec<Instruction>) -> Vec<Instruction> {
+        let defined = program
+            .iter()
+            .flat_map(|instruction| match &instruction.instruction {
+                InstructionType::StoreName(name, ..) | InstructionType::DeclareName(name, _) => vec![name.clone()],
+                InstructionType::Import(_, names) => names.clone(),
+                _ => Vec::new(),
+            })
+            .collect::<HashSet<_>>();
+
+        let mut used = HashSet::new();
+        program
+            .iter()
+            .filter_map(Instruction::expression)
+            .for_each(|expression| references(expression, &mut used));
+
+        // a definition can only use the ones before it, so going backwards finds those it needs as well
+        let mut linked = Vec::new();
+
+        for instruction in self.instructions.iter().rev() {
+            if let InstructionType::StoreName(name, value, ..) = &instruction.instruction {
+                if used.contains(name) && !defined.contains(name) {
+                    references(value, &mut used);
+                    linked.push(instruction.clone());
+                }
+            }
+        }
+
+        linked.reverse();
+        linked.extend(program);
+        linked
+    }
+}
+
+/// Collects the names an expression refers to, wherever they are bound.
+fn references(expression: &IrExpression, names: &mut HashSet<String>) {
+    if let IrExpressionKind::Identifier(name) = &expression.expression {
+        names.insert(name.clone());
+    }
+
+    for child in expression.children() {
+        references(child, names);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Prelude;
+    use crate::compiler::Compiler;
+    use sntk_core::parser::parser::Parser;
+    use sntk_ir::instruction::InstructionType;
+
+    #[test]
+    fn link_test() {
+        let program =
+            Compiler::new(Parser::from("auto is_some = 1; auto x = map(Option::Some(1), fn(n: int) -> int => n);".to_string()).parse_program())
+                .compile_program()
+                .unwrap();
+
+        let names = Prelude::default()
+            .link(program)
+            .into_iter()
+            .filter_map(|instruction| match instruction.instruction {
+                InstructionType::StoreName(name, ..) => Some(name),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // only `map` is used, and `is_some` is the program's own
+        assert_eq!(names, vec!["map", "is_some", "x"]);
+    }
+}
@@ -0,0 +1,43 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sntk_core::tokenizer::{lexer::Lexer, token::TokenKind};
+
+/// The struct example with non-ASCII identifiers, strings and comments mixed in, repeated to the given size in bytes.
+fn source(size: usize) -> String {
+    let unit = format!(
+        "{}\n/* 좌표 */ auto café = \"日本語 🦀\"; // ünïcödé\nauto π = [1, 2.5, 3] ;\n",
+        include_str!("../../examples/struct.sntk")
+    );
+
+    unit.repeat(size / unit.len() + 1)
+}
+
+fn tokenize(source: &str) -> usize {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut count = 0;
+
+    while lexer.next_token().kind != TokenKind::EOF {
+        count += 1;
+    }
+
+    count
+}
+
+fn lexer(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("lexer");
+    group.sample_size(10);
+
+    // lexing is linear, so four times the input should take about four times as long
+    for size in [1 << 20, 4 << 20] {
+        let source = source(size);
+
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}MiB", size >> 20)), &source, |bencher, source| {
+            bencher.iter(|| tokenize(source))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, lexer);
+criterion_main!(benches);
@@ -0,0 +1,309 @@
+use crate::{
+    line_index::{LineIndex, Location},
+    parser::ast::Span,
+};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: Option<String>,
+    pub primary: bool,
+}
+
+impl Label {
+    #[inline]
+    pub fn primary(span: Span, message: Option<String>) -> Self {
+        Label {
+            span,
+            message,
+            primary: true,
+        }
+    }
+
+    #[inline]
+    pub fn secondary(span: Span, message: Option<String>) -> Self {
+        Label {
+            span,
+            message,
+            primary: false,
+        }
+    }
+}
+
+/// A compiler or runtime message that can be rendered against its source, shared by every stage of the pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    #[inline]
+    pub fn error(code: &'static str, message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            code,
+            message,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn with_label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn with_help(mut self, help: Option<String>) -> Self {
+        self.help = help;
+        self
+    }
+
+    pub fn primary_span(&self) -> Option<Span> {
+        self.labels.iter().find(|label| label.primary).map(|label| label.span)
+    }
+
+    /// Renders the diagnostic for a terminal, printing the offending source lines with carets under each label.
+    ///
+    /// ```text
+    /// error[E0100]: Expected `Int` type, got `String` instead
+    ///  --> main.sntk:1:14
+    ///   |
+    /// 1 | let x: int = "foo";
+    ///   |              ^^^^^ expected `Int`
+    ///   = help: ...
+    /// ```
+    pub fn render(&self, file: &str, source: &str) -> String {
+        let index = LineIndex::new(source);
+        let lines = source.lines().collect::<Vec<_>>();
+        let mut labels = self
+            .labels
+            .iter()
+            .map(|label| (index.location(label.span.start), label))
+            .collect::<Vec<_>>();
+        labels.sort_by_key(|(location, _)| *location);
+
+        let gutter = labels
+            .iter()
+            .map(|(location, _)| (location.line + 1).to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        let mut output = format!("{}[{}]: {}\n", self.severity, self.code, self.message);
+
+        if let Some(span) = self.primary_span() {
+            let Location { line, column, .. } = index.location(span.start);
+            output.push_str(&format!("{:gutter$}--> {}:{}:{}\n", "", file, line + 1, column));
+        }
+
+        if !labels.is_empty() {
+            output.push_str(&format!("{:gutter$} |\n", ""));
+        }
+
+        let mut previous_line = None;
+
+        for (Location { line, column, .. }, label) in labels {
+            if previous_line != Some(line) {
+                output.push_str(&format!(
+                    "{:>gutter$} | {}\n",
+                    line + 1,
+                    lines.get(line).copied().unwrap_or_default().trim_end()
+                ));
+                previous_line = Some(line);
+            }
+
+            let text = lines.get(line).copied().unwrap_or_default();
+
+            // the underline is indented with the tabs of the line, so that it lines up wherever the terminal puts the tab stops
+            let indent = text
+                .chars()
+                .take(column - 1)
+                .map(|char| if char == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+
+            // labels spanning several lines are only underlined up to the end of their first line
+            let rest = (text.chars().count() + 1).saturating_sub(column);
+            let width = source
+                .get(label.span.start..label.span.end)
+                .map_or(1, |text| text.chars().take_while(|char| *char != '\n').count())
+                .clamp(1, rest.max(1));
+
+            output.push_str(&format!(
+                "{:gutter$} | {}{}{}\n",
+                "",
+                indent,
+                (if label.primary { "^" } else { "-" }).repeat(width),
+                label.message.as_ref().map(|message| format!(" {message}")).unwrap_or_default()
+            ));
+        }
+
+        for note in self.notes.iter() {
+            output.push_str(&format!("{:gutter$} = note: {}\n", "", note));
+        }
+
+        if let Some(help) = &self.help {
+            output.push_str(&format!("{:gutter$} = help: {}\n", "", help));
+        }
+
+        output
+    }
+
+    /// Renders the diagnostic as a single line JSON object, for editors and other tools.
+    pub fn to_json(&self, file: &str, source: &str) -> String {
+        let index = LineIndex::new(source);
+        let labels = self
+            .labels
+            .iter()
+            .map(|label| {
+                let Location { line, column, utf16_column } = index.location(label.span.start);
+
+                format!(
+                    r#"{{"line":{},"column":{},"column_utf16":{},"start":{},"end":{},"primary":{},"message":{}}}"#,
+                    line + 1,
+                    column,
+                    utf16_column,
+                    label.span.start,
+                    label.span.end,
+                    label.primary,
+                    json_option(&label.message)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!(
+            r#"{{"severity":"{}","code":"{}","message":{},"file":{},"labels":[{}],"notes":[{}],"help":{}}}"#,
+            self.severity,
+            self.code,
+            json_string(&self.message),
+            json_string(file),
+            labels,
+            self.notes.iter().map(|note| json_string(note)).collect::<Vec<_>>().join(","),
+            json_option(&self.help)
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.primary_span() {
+            Some(span) => write!(f, "{}[{}]: {}: {}", self.severity, self.code, span, self.message),
+            None => write!(f, "{}[{}]: {}", self.severity, self.code, self.message),
+        }
+    }
+}
+
+pub fn json_string(string: &str) -> String {
+    let mut output = String::with_capacity(string.len() + 2);
+    output.push('"');
+
+    for char in string.chars() {
+        match char {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            char if (char as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", char as u32)),
+            char => output.push(char),
+        }
+    }
+
+    output.push('"');
+    output
+}
+
+fn json_option(string: &Option<String>) -> String {
+    match string {
+        Some(string) => json_string(string),
+        None => "null".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diagnostic, Label};
+    use crate::parser::ast::{FileId, Span};
+
+    const SOURCE: &str = "let café = 1;\n\tlet x = \"🦀\" + café\n\t\t+ 1;\n";
+
+    fn span(text: &str, nth: usize) -> Span {
+        let start = SOURCE.match_indices(text).nth(nth).unwrap().0;
+        Span::new(FileId::default(), start, start + text.len())
+    }
+
+    /// A diagnostic with labels on several lines and two on the same one, one of them spanning two lines, on lines with tabs and
+    /// non-ASCII chars.
+    fn diagnostic() -> Diagnostic {
+        Diagnostic::error("E0100", "Expected `Int` type, got `String` instead".to_string())
+            .with_label(Label::primary(span("\"🦀\" + café\n\t\t+ 1", 0), Some("expected `Int`".to_string())))
+            .with_label(Label::secondary(span("café", 1), None))
+            .with_label(Label::secondary(span("café", 0), Some("declared here".to_string())))
+            .with_note("a note".to_string())
+            .with_help(Some("a \"help\"".to_string()))
+    }
+
+    #[test]
+    fn render_test() {
+        let expected = [
+            "error[E0100]: Expected `Int` type, got `String` instead",
+            " --> main.sntk:2:10",
+            "  |",
+            "1 | let café = 1;",
+            "  |     ---- declared here",
+            "2 | \tlet x = \"🦀\" + café",
+            "  | \t        ^^^^^^^^^^ expected `Int`",
+            "  | \t              ----",
+            "  = note: a note",
+            "  = help: a \"help\"",
+        ];
+
+        assert_eq!(
+            diagnostic().render("main.sntk", SOURCE),
+            expected.map(|line| format!("{line}\n")).concat()
+        );
+    }
+
+    #[test]
+    fn json_test() {
+        let labels = [
+            r#"{"line":2,"column":10,"column_utf16":10,"start":24,"end":44,"primary":true,"message":"expected `Int`"}"#,
+            r#"{"line":2,"column":16,"column_utf16":17,"start":33,"end":38,"primary":false,"message":null}"#,
+            r#"{"line":1,"column":5,"column_utf16":5,"start":4,"end":9,"primary":false,"message":"declared here"}"#,
+        ];
+
+        assert_eq!(
+            diagnostic().to_json("main.sntk", SOURCE),
+            format!(
+                r#"{{"severity":"error","code":"E0100","message":"Expected `Int` type, got `String` instead","file":"main.sntk","labels":[{}],"notes":["a note"],"help":"a \"help\""}}"#,
+                labels.join(",")
+            )
+        );
+    }
+}
@@ -0,0 +1,1010 @@
+use crate::{
+    parser::ast::{
+        ArrayLiteral, ArrayPattern, AssignExpression, AutoStatement, BindingPattern, BlockExpression, BooleanLiteral, BreakExpression,
+        CallExpression, Comment, ContinueExpression, DataType, DataTypeKind, DeclareStatement, EnumLiteral, EnumPattern, EnumStatement, EnumType,
+        Expression, ExpressionStatement, FieldExpression, FloatLiteral, ForExpression, FunctionLiteral, FunctionType, Generic, Identifier,
+        IfExpression, ImportStatement, IndexExpression, InfixExpression, IntLiteral, LetStatement, LiteralPattern, LoopExpression, MatchExpression,
+        Parameter, Pattern, PrefixExpression, Priority, Program, ReturnStatement, Span, Statement, StringLiteral, StructLiteral, StructPattern,
+        StructStatement, StructType, TryExpression, TypeStatement, TypeofExpression, WhileExpression,
+    },
+    tokenizer::token::TokenKind,
+};
+
+/// Lists that would be wider than this on a single line are broken into one element per line.
+pub const MAX_WIDTH: usize = 100;
+
+const INDENT: &str = "    ";
+
+/// Prints a program back as canonical sntk source, keeping its comments.
+///
+/// The program should be parsed with `Parser::verbatim` set and without errors, otherwise constant expressions are printed
+/// evaluated and broken statements are left out.
+#[derive(Debug)]
+pub struct Formatter<'a> {
+    source: &'a str,
+    comments: Vec<Comment>,
+    indent: usize,
+}
+
+impl<'a> Formatter<'a> {
+    #[inline]
+    pub fn new(source: &'a str) -> Self {
+        Formatter {
+            source,
+            comments: Vec::new(),
+            indent: 0,
+        }
+    }
+
+    pub fn format_program(&mut self, program: &Program) -> String {
+        self.comments = program.comments.clone();
+        self.indent = 0;
+
+        let output = self.statements(&program.statements, 0, self.source.len());
+
+        match output.is_empty() {
+            true => output,
+            false => output + "\n",
+        }
+    }
+
+    /// Removes and returns the comments starting in `start..end`, which have not been printed yet.
+    fn take_comments(&mut self, start: usize, end: usize) -> Vec<Comment> {
+        let (taken, rest) = std::mem::take(&mut self.comments)
+            .into_iter()
+            .partition(|comment| start <= comment.span.start && comment.span.start < end);
+
+        self.comments = rest;
+        taken
+    }
+
+    /// The end of the line `offset` is on, or of the source.
+    fn line_end(&self, offset: usize) -> usize {
+        self.source
+            .get(offset..)
+            .and_then(|rest| rest.find('\n'))
+            .map_or(self.source.len(), |index| offset + index)
+    }
+
+    /// The source text of a number literal, if `span` covers one.
+    fn number(&self, span: &Span) -> Option<String> {
+        self.source
+            .get(span.start..span.end)
+            .filter(|text| text.starts_with(|c: char| c.is_ascii_digit()))
+            .map(str::to_string)
+    }
+
+    /// Comments written inside an item, or after it on the same line, which are kept at the end of its last line.
+    fn trailing_comments(&mut self, span: Span, next: usize) -> Vec<Comment> {
+        let end = self.line_end(span.end).min(next).max(span.end);
+
+        self.take_comments(span.start, end)
+    }
+
+    /// Takes the block comments written right before `span` or right after it, on the same line and with nothing else between, and
+    /// prints them next to `text` as they are written, as in `/* a */ 1 /* b */`.
+    fn attach(&mut self, span: Span, text: String) -> String {
+        let mut leading = Vec::new();
+        let mut trailing = Vec::new();
+        let (mut start, mut end) = (span.start, span.end);
+
+        while let Some(index) = self.comments.iter().position(|comment| {
+            comment.value.starts_with("/*") && comment.span.end <= start && self.source.get(comment.span.end..start).is_some_and(inline)
+        }) {
+            let comment = self.comments.remove(index);
+            start = comment.span.start;
+            leading.push(comment.value);
+        }
+
+        while let Some(index) = self.comments.iter().position(|comment| {
+            comment.value.starts_with("/*") && end <= comment.span.start && self.source.get(end..comment.span.start).is_some_and(inline)
+        }) {
+            let comment = self.comments.remove(index);
+            end = comment.span.end;
+            trailing.push(comment.value);
+        }
+
+        leading.reverse();
+        leading.into_iter().chain([text]).chain(trailing).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Where the token before `offset` ends, skipping the whitespace and the comments not printed yet in between.
+    fn token_before(&self, offset: usize) -> usize {
+        let mut offset = offset;
+
+        loop {
+            let end = self.source[..offset].trim_end().len();
+
+            match self.comments.iter().find(|comment| comment.span.end == end) {
+                Some(comment) => offset = comment.span.start,
+                None => return end,
+            }
+        }
+    }
+
+    /// Where the token after `offset` starts, skipping the whitespace, the comments not printed yet and a `,` in between.
+    fn token_after(&self, offset: usize) -> usize {
+        let mut offset = offset;
+        let mut comma = false;
+
+        loop {
+            let rest = &self.source[offset..];
+            let start = offset + rest.len() - rest.trim_start().len();
+
+            match self.comments.iter().find(|comment| comment.span.start == start) {
+                Some(comment) => offset = comment.span.end,
+                None if !comma && self.source[start..].starts_with(',') => {
+                    comma = true;
+                    offset = start + 1;
+                }
+                None => return start,
+            }
+        }
+    }
+
+    /// Keeps a single blank line where the source had at least one between two items.
+    fn separate(&self, lines: &mut Vec<String>, previous: Option<usize>, next: usize) {
+        if let Some(between) = previous.and_then(|previous| self.source.get(previous..next)) {
+            if between.matches('\n').count() > 1 {
+                lines.push(String::new());
+            }
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement], start: usize, end: usize) -> String {
+        let indent = INDENT.repeat(self.indent);
+        let mut lines = Vec::new();
+        let mut previous = None;
+
+        for (index, statement) in statements.iter().enumerate() {
+            if let Statement::ErrorStatement(_) = statement {
+                continue;
+            }
+
+            let span = statement.span();
+            let text = self.statement(statement);
+            let text = self.attach(span, text);
+
+            for comment in self.take_comments(start, span.start) {
+                self.separate(&mut lines, previous, comment.span.start);
+                lines.push(format!("{indent}{}", comment.value));
+                previous = Some(comment.span.end);
+            }
+
+            self.separate(&mut lines, previous, span.start);
+
+            let mut line = format!("{indent}{text}");
+            previous = Some(span.end);
+
+            let next = statements.get(index + 1).map_or(end, |next| next.span().start);
+
+            for comment in self.trailing_comments(span, next) {
+                line.push_str(&format!(" {}", comment.value));
+                previous = previous.max(Some(comment.span.end));
+            }
+
+            lines.push(line);
+        }
+
+        for comment in self.take_comments(start, end) {
+            self.separate(&mut lines, previous, comment.span.start);
+            lines.push(format!("{indent}{}", comment.value));
+            previous = Some(comment.span.end);
+        }
+
+        lines.join("\n")
+    }
+
+    fn statement(&mut self, statement: &Statement) -> String {
+        match statement {
+            Statement::LetStatement(LetStatement {
+                name,
+                data_type,
+                value,
+                mutable,
+                exported,
+                ..
+            }) => format!(
+                "{}let {}{}: {} = {};",
+                format_export(*exported),
+                format_mut(*mutable),
+                name.value,
+                format_data_type(data_type),
+                self.expression(value)
+            ),
+            Statement::AutoStatement(AutoStatement {
+                name,
+                value,
+                mutable,
+                exported,
+                ..
+            }) => format!(
+                "{}auto {}{} = {};",
+                format_export(*exported),
+                format_mut(*mutable),
+                name.value,
+                self.expression(value)
+            ),
+            Statement::ReturnStatement(ReturnStatement { value: Some(value), .. }) => format!("return {};", self.expression(value)),
+            Statement::ReturnStatement(ReturnStatement { value: None, .. }) => "return;".to_string(),
+            Statement::TypeStatement(TypeStatement {
+                name,
+                generics,
+                data_type,
+                exported,
+                ..
+            }) => format!(
+                "{}type {}{} = {};",
+                format_export(*exported),
+                name.value,
+                format_generics(generics),
+                format_data_type(data_type)
+            ),
+            Statement::DeclareStatement(DeclareStatement {
+                name, data_type, exported, ..
+            }) => format!("{}declare {} = {};", format_export(*exported), name.value, format_data_type(data_type)),
+            Statement::StructStatement(StructStatement {
+                name,
+                generics,
+                fields,
+                exported,
+                ..
+            }) => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, data_type)| (field.span.to(data_type.span), format!("{}: {}", field.value, format_data_type(data_type))))
+                    .collect();
+
+                format!(
+                    "{}struct {}{} {}",
+                    format_export(*exported),
+                    name.value,
+                    format_generics(generics),
+                    self.list("{", fields, "}", true)
+                )
+            }
+            Statement::EnumStatement(EnumStatement {
+                name,
+                generics,
+                variants,
+                exported,
+                ..
+            }) => {
+                let variants = variants
+                    .iter()
+                    .map(|(variant, payload)| match payload.last() {
+                        Some(last) => (
+                            variant.span.to(last.span),
+                            format!(
+                                "{}({})",
+                                variant.value,
+                                payload.iter().map(format_data_type).collect::<Vec<_>>().join(", ")
+                            ),
+                        ),
+                        None => (variant.span, variant.value.clone()),
+                    })
+                    .collect();
+
+                format!(
+                    "{}enum {}{} {}",
+                    format_export(*exported),
+                    name.value,
+                    format_generics(generics),
+                    self.list("{", variants, "}", true)
+                )
+            }
+            Statement::ImportStatement(ImportStatement { names, path, .. }) => {
+                let names = names.iter().map(|name| (name.span, name.value.clone())).collect();
+
+                format!(
+                    "import {} from {};",
+                    self.list("{", names, "}", true),
+                    self.expression(&Expression::StringLiteral(path.clone()))
+                )
+            }
+            Statement::ExpressionStatement(ExpressionStatement { expression, semicolon, .. }) => match semicolon {
+                true => format!("{};", self.expression(expression)),
+                false => self.expression(expression),
+            },
+            Statement::ErrorStatement(_) => String::new(),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) -> String {
+        let text = self.expression_kind(expression);
+
+        self.attach(expression.span(), text)
+    }
+
+    fn expression_kind(&mut self, expression: &Expression) -> String {
+        match expression {
+            Expression::Identifier(Identifier { value, .. }) => value.clone(),
+            // numbers are kept as written, with their radix, separators and exponent
+            Expression::IntLiteral(IntLiteral { value, span }) => self.number(span).unwrap_or_else(|| value.to_string()),
+            // `{:?}` keeps the `.0` of whole floats, which would otherwise read back as ints
+            Expression::FloatLiteral(FloatLiteral { value, span }) => self.number(span).unwrap_or_else(|| format!("{value:?}")),
+            // raw and multi-line strings are kept as written
+            Expression::StringLiteral(StringLiteral { value, span }) => match self.source.get(span.start..span.end) {
+                Some(text) if text.starts_with('"') || text.starts_with('r') => text.to_string(),
+                _ => format_string(value),
+            },
+            Expression::BooleanLiteral(BooleanLiteral { value, .. }) => value.to_string(),
+            Expression::BlockExpression(block) => self.block(block),
+            Expression::PrefixExpression(PrefixExpression { operator, right, .. }) => {
+                format!("{}{}", format_operator(operator), self.operand(right, &Priority::Prefix, false))
+            }
+            Expression::InfixExpression(InfixExpression { left, operator, right, .. }) => {
+                let priority = Priority::of(operator);
+
+                // `**` is right associative, and takes a prefix expression on its right as is
+                let (left, right) = match operator {
+                    TokenKind::DoubleAsterisk => (self.operand(left, &priority, true), self.operand(right, &Priority::Prefix, false)),
+                    _ => (self.operand(left, &priority, false), self.operand(right, &priority, true)),
+                };
+
+                format!("{} {} {}", left, format_operator(operator), right)
+            }
+            Expression::AssignExpression(AssignExpression { target, operator, value, .. }) => {
+                format!("{} {} {}", self.expression(target), format_operator(operator), self.expression(value))
+            }
+            Expression::IfExpression(if_expression) => self.if_expression(if_expression),
+            Expression::WhileExpression(WhileExpression { label, condition, body, .. }) => {
+                format!("{}while {} {}", format_label(label), self.expression(condition), self.block(body))
+            }
+            Expression::ForExpression(ForExpression {
+                label, name, iterable, body, ..
+            }) => format!(
+                "{}for {} in {} {}",
+                format_label(label),
+                name.value,
+                self.expression(iterable),
+                self.block(body)
+            ),
+            Expression::LoopExpression(LoopExpression { label, body, .. }) => format!("{}loop {}", format_label(label), self.block(body)),
+            Expression::MatchExpression(match_expression) => self.match_expression(match_expression),
+            Expression::BreakExpression(BreakExpression { label, value, .. }) => {
+                let mut output = "break".to_string();
+
+                if let Some(label) = label {
+                    output.push_str(&format!(" '{}", label.value));
+                }
+
+                if let Some(value) = value {
+                    output.push_str(&format!(" {}", self.expression(value)));
+                }
+
+                output
+            }
+            Expression::ContinueExpression(ContinueExpression { label, .. }) => match label {
+                Some(label) => format!("continue '{}", label.value),
+                None => "continue".to_string(),
+            },
+            Expression::FunctionLiteral(function) => self.function(function),
+            Expression::CallExpression(CallExpression { function, arguments, .. }) => {
+                let function = self.operand(function, &Priority::Call, false);
+                let arguments = self.items(arguments, |formatter, argument| (argument.span(), formatter.expression(argument)));
+
+                format!("{}{}", function, self.list("(", arguments, ")", false))
+            }
+            Expression::TypeofExpression(TypeofExpression { expression, .. }) => format!("typeof {}", self.expression(expression)),
+            Expression::IndexExpression(IndexExpression { left, index, .. }) => {
+                format!("{}[{}]", self.operand(left, &Priority::Call, false), self.expression(index))
+            }
+            Expression::FieldExpression(FieldExpression { left, field, .. }) => {
+                format!("{}.{}", self.operand(left, &Priority::Call, false), field.value)
+            }
+            Expression::TryExpression(TryExpression { value, .. }) => format!("{}?", self.operand(value, &Priority::Call, false)),
+            Expression::ArrayLiteral(ArrayLiteral { elements, .. }) => {
+                let elements = self.items(elements, |formatter, element| (element.span(), formatter.expression(element)));
+
+                self.list("[", elements, "]", false)
+            }
+            Expression::StructLiteral(StructLiteral { name, fields, .. }) => {
+                let fields = self.items(fields, |formatter, (field, value)| {
+                    (field.span.to(value.span()), format!("{}: {}", field.value, formatter.expression(value)))
+                });
+
+                format!("struct {} {}", name.value, self.list("{", fields, "}", true))
+            }
+            Expression::EnumLiteral(EnumLiteral {
+                name, variant, arguments, ..
+            }) if arguments.is_empty() => {
+                format!("{}::{}", name.value, variant.value)
+            }
+            Expression::EnumLiteral(EnumLiteral {
+                name, variant, arguments, ..
+            }) => {
+                let arguments = self.items(arguments, |formatter, argument| (argument.span(), formatter.expression(argument)));
+
+                format!("{}::{}{}", name.value, variant.value, self.list("(", arguments, ")", false))
+            }
+        }
+    }
+
+    /// Prints an operand of an operator binding with `priority`, parenthesized where it would otherwise bind differently.
+    fn operand(&mut self, expression: &Expression, priority: &Priority, right: bool) -> String {
+        let own = match expression {
+            Expression::InfixExpression(InfixExpression { operator, .. }) => Priority::of(operator),
+            Expression::PrefixExpression(_) => Priority::Prefix,
+            Expression::AssignExpression(_) => Priority::Assign,
+            Expression::TypeofExpression(_)
+            | Expression::IfExpression(_)
+            | Expression::WhileExpression(_)
+            | Expression::ForExpression(_)
+            | Expression::LoopExpression(_)
+            | Expression::MatchExpression(_)
+            | Expression::BreakExpression(_)
+            | Expression::FunctionLiteral(_) => Priority::Lowest,
+            _ => Priority::Dot,
+        };
+        let expression = self.expression(expression);
+
+        // operators are left associative, so an operand on the right of one with the same priority needs parentheses too
+        match own < *priority || (right && own == *priority) {
+            true => format!("({expression})"),
+            false => expression,
+        }
+    }
+
+    fn block(&mut self, block: &BlockExpression) -> String {
+        self.indent += 1;
+        let body = self.statements(&block.statements, block.span.start, block.span.end);
+        self.indent -= 1;
+
+        let text = match body.is_empty() {
+            true => "{}".to_string(),
+            false => format!("{{\n{}\n{}}}", body, INDENT.repeat(self.indent)),
+        };
+
+        self.attach(block.span, text)
+    }
+
+    fn if_expression(&mut self, if_expression: &IfExpression) -> String {
+        let IfExpression {
+            condition,
+            consequence,
+            alternative,
+            ..
+        } = if_expression;
+
+        let mut output = format!("if {} {}", self.expression(condition), self.block(consequence));
+
+        if let Some(alternative) = alternative {
+            // `else if` is parsed as an `else` block holding nothing but the `if` expression
+            output.push_str(&match alternative.statements.as_slice() {
+                [Statement::ExpressionStatement(ExpressionStatement {
+                    expression: Expression::IfExpression(if_expression),
+                    span,
+                    ..
+                })] if *span == alternative.span => format!(" else {}", self.if_expression(if_expression)),
+                _ => format!(" else {}", self.block(alternative)),
+            });
+        }
+
+        output
+    }
+
+    /// Prints a match with one arm per line, leaving out the `,` after arms whose body is a block.
+    fn match_expression(&mut self, match_expression: &MatchExpression) -> String {
+        let MatchExpression { value, arms, span } = match_expression;
+
+        let mut output = format!("match {} {{", self.expression(value));
+
+        self.indent += 1;
+        let indent = INDENT.repeat(self.indent);
+        let mut previous = value.span().end;
+
+        for (index, arm) in arms.iter().enumerate() {
+            for comment in self.take_comments(previous, arm.span.start) {
+                output.push_str(&format!("\n{indent}{}", comment.value));
+            }
+
+            let guard = match &arm.guard {
+                Some(guard) => format!(" if {}", self.expression(guard)),
+                None => String::new(),
+            };
+            let comma = match arm.body {
+                Expression::BlockExpression(_) => "",
+                _ => ",",
+            };
+            output.push_str(&format!(
+                "\n{indent}{}{guard} => {}{comma}",
+                format_pattern(&arm.pattern),
+                self.expression(&arm.body)
+            ));
+
+            let next = arms.get(index + 1).map_or(span.end, |next| next.span.start);
+
+            for comment in self.trailing_comments(arm.span, next) {
+                output.push_str(&format!(" {}", comment.value));
+            }
+
+            previous = arm.span.end;
+        }
+
+        for comment in self.take_comments(previous, span.end) {
+            output.push_str(&format!("\n{indent}{}", comment.value));
+        }
+
+        self.indent -= 1;
+        output.push_str(&format!("\n{}}}", INDENT.repeat(self.indent)));
+
+        output
+    }
+
+    fn function(&mut self, function: &FunctionLiteral) -> String {
+        let FunctionLiteral {
+            generics,
+            parameters,
+            return_type,
+            body,
+            ..
+        } = function;
+
+        let parameters = self.items(parameters, |_, parameter| {
+            let Parameter {
+                name,
+                data_type,
+                spread,
+                span,
+            } = parameter;
+
+            (
+                *span,
+                format!("{}{}: {}", if *spread { "spread " } else { "" }, name.value, format_data_type(data_type)),
+            )
+        });
+        let parameters = self.list("(", parameters, ")", false);
+
+        // `=> value` is parsed as a block holding nothing but the value
+        let body = match body.statements.as_slice() {
+            [Statement::ExpressionStatement(ExpressionStatement {
+                expression,
+                semicolon: false,
+                span,
+            })] if *span == body.span => format!("=> {}", self.expression(expression)),
+            _ => self.block(body),
+        };
+
+        format!(
+            "fn{}{} -> {} {}",
+            generics.as_deref().map(format_generics).unwrap_or_default(),
+            parameters,
+            format_data_type(return_type),
+            body
+        )
+    }
+
+    /// Formats the elements of a list one level deeper, as they end up when the list is broken over several lines.
+    fn items<T>(&mut self, items: &[T], format: impl Fn(&mut Self, &T) -> (Span, String)) -> Vec<(Span, String)> {
+        self.indent += 1;
+        let items = items.iter().map(|item| format(self, item)).collect();
+        self.indent -= 1;
+
+        items
+    }
+
+    /// Prints a comma separated list on a single line if it fits, or with one element per line and a trailing comma otherwise,
+    /// which it also is when an element spans several lines or comments are written between the brackets, other than the ones
+    /// printed next to an element.
+    fn list(&mut self, open: &str, items: Vec<(Span, String)>, close: &str, spaced: bool) -> String {
+        let items = items.into_iter().map(|(span, item)| (span, self.attach(span, item))).collect::<Vec<_>>();
+        let elements = items.iter().map(|(_, item)| item.as_str()).collect::<Vec<_>>().join(", ");
+        let single = match (items.is_empty(), spaced) {
+            (true, _) => format!("{open}{close}"),
+            (false, true) => format!("{open} {elements} {close}"),
+            (false, false) => format!("{open}{elements}{close}"),
+        };
+
+        // the comments between the brackets and the first and last element are in the list too
+        let (start, end) = match (items.first(), items.last()) {
+            (Some((first, _)), Some((last, _))) => (self.token_before(first.start), self.token_after(last.end)),
+            _ => (0, 0),
+        };
+        let commented = self
+            .comments
+            .iter()
+            .any(|comment| start <= comment.span.start && comment.span.start < end);
+
+        if !commented && !single.contains('\n') && INDENT.len() * self.indent + single.len() <= MAX_WIDTH {
+            return single;
+        }
+
+        let indent = INDENT.repeat(self.indent + 1);
+        let mut lines = vec![open.to_string()];
+
+        for (index, (span, item)) in items.iter().enumerate() {
+            for comment in self.take_comments(if index == 0 { start } else { items[index - 1].0.end }, span.start) {
+                lines.push(format!("{indent}{}", comment.value));
+            }
+
+            let mut line = format!("{indent}{item},");
+            let next = items.get(index + 1).map_or(end, |(next, _)| next.start);
+
+            for comment in self.trailing_comments(*span, next) {
+                line.push_str(&format!(" {}", comment.value));
+            }
+
+            lines.push(line);
+        }
+
+        if let Some((last, _)) = items.last() {
+            for comment in self.take_comments(last.end, end) {
+                lines.push(format!("{indent}{}", comment.value));
+            }
+        }
+
+        lines.push(format!("{}{close}", INDENT.repeat(self.indent)));
+        lines.join("\n")
+    }
+}
+
+/// Whether the text between a comment and what it is written by keeps them on the same line, with nothing else between.
+fn inline(text: &str) -> bool {
+    text.chars().all(|char| char == ' ' || char == '\t')
+}
+
+fn format_pattern(pattern: &Pattern) -> String {
+    let list = |patterns: &[Pattern]| patterns.iter().map(format_pattern).collect::<Vec<_>>().join(", ");
+
+    match pattern {
+        Pattern::WildcardPattern(_) => "_".to_string(),
+        Pattern::LiteralPattern(LiteralPattern { value, .. }) => match value {
+            Expression::IntLiteral(IntLiteral { value, .. }) => value.to_string(),
+            Expression::FloatLiteral(FloatLiteral { value, .. }) => format!("{value:?}"),
+            Expression::StringLiteral(StringLiteral { value, .. }) => format_string(value),
+            Expression::BooleanLiteral(BooleanLiteral { value, .. }) => value.to_string(),
+            _ => unreachable!("only literals are parsed as literal patterns"),
+        },
+        Pattern::BindingPattern(BindingPattern { name, .. }) => name.value.clone(),
+        Pattern::ArrayPattern(ArrayPattern { elements, rest, .. }) => {
+            let mut elements = elements.iter().map(format_pattern).collect::<Vec<_>>();
+
+            if let Some(rest) = rest {
+                elements.push(format!("...{}", format_pattern(rest)));
+            }
+
+            format!("[{}]", elements.join(", "))
+        }
+        Pattern::StructPattern(StructPattern { name, fields, .. }) => {
+            // a field bound to a name of its own is printed short, as `struct Point { x }`
+            let fields = fields
+                .iter()
+                .map(|(field, pattern)| match pattern {
+                    Pattern::BindingPattern(BindingPattern { name, .. }) if name.value == field.value => field.value.clone(),
+                    pattern => format!("{}: {}", field.value, format_pattern(pattern)),
+                })
+                .collect::<Vec<_>>();
+
+            match fields.is_empty() {
+                true => format!("struct {} {{}}", name.value),
+                false => format!("struct {} {{ {} }}", name.value, fields.join(", ")),
+            }
+        }
+        Pattern::EnumPattern(EnumPattern { name, variant, payload, .. }) => match payload.is_empty() {
+            true => format!("{}::{}", name.value, variant.value),
+            false => format!("{}::{}({})", name.value, variant.value, list(payload)),
+        },
+    }
+}
+
+fn format_label(label: &Option<Identifier>) -> String {
+    match label {
+        Some(label) => format!("'{}: ", label.value),
+        None => String::new(),
+    }
+}
+
+fn format_generics(generics: &[Identifier]) -> String {
+    match generics.is_empty() {
+        true => String::new(),
+        false => format!(
+            "<{}>",
+            generics.iter().map(|generic| generic.value.as_str()).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+fn format_data_type(data_type: &DataType) -> String {
+    match &data_type.data_type {
+        DataTypeKind::Int => "int".to_string(),
+        DataTypeKind::Float => "float".to_string(),
+        DataTypeKind::String => "string".to_string(),
+        DataTypeKind::Boolean => "boolean".to_string(),
+        DataTypeKind::Void => "void".to_string(),
+        DataTypeKind::Array(element) => format!("{}[]", format_data_type(element)),
+        DataTypeKind::Fn(FunctionType {
+            generics,
+            parameters,
+            return_type,
+        }) => format!(
+            "fn{}({}) -> {}",
+            generics.as_deref().map(format_generics).unwrap_or_default(),
+            parameters
+                .iter()
+                .map(|(data_type, spread)| format!("{}{}", if *spread { "spread " } else { "" }, format_data_type(data_type)))
+                .collect::<Vec<_>>()
+                .join(", "),
+            format_data_type(return_type)
+        ),
+        DataTypeKind::Generic(Generic(base, arguments)) => format!(
+            "{}<{}>",
+            format_data_type(base),
+            arguments.iter().map(format_data_type).collect::<Vec<_>>().join(", ")
+        ),
+        DataTypeKind::Struct(StructType { name, .. })
+        | DataTypeKind::Enum(EnumType { name, .. })
+        | DataTypeKind::TypeParameter(name)
+        | DataTypeKind::Custom(name) => name.clone(),
+        // only ever inferred, never written
+        DataTypeKind::Auto | DataTypeKind::Unknown => data_type.to_string(),
+    }
+}
+
+fn format_string(string: &str) -> String {
+    let mut output = String::from('"');
+
+    for char in string.chars() {
+        match char {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\0' => output.push_str("\\0"),
+            char if char.is_control() => output.push_str(&format!("\\u{{{:x}}}", char as u32)),
+            char => output.push(char),
+        }
+    }
+
+    output.push('"');
+    output
+}
+
+fn format_export(exported: bool) -> &'static str {
+    match exported {
+        true => "export ",
+        false => "",
+    }
+}
+
+fn format_mut(mutable: bool) -> &'static str {
+    match mutable {
+        true => "mut ",
+        false => "",
+    }
+}
+
+/// An operator as it is written in source.
+pub fn format_operator(operator: &TokenKind) -> String {
+    match operator {
+        TokenKind::Plus => "+",
+        TokenKind::Minus => "-",
+        TokenKind::Asterisk => "*",
+        TokenKind::Slash => "/",
+        TokenKind::Percent => "%",
+        TokenKind::DoubleAsterisk => "**",
+        TokenKind::And => "&&",
+        TokenKind::Or => "||",
+        TokenKind::Ampersand => "&",
+        TokenKind::Pipe => "|",
+        TokenKind::Caret => "^",
+        TokenKind::LShift => "<<",
+        TokenKind::RShift => ">>",
+        TokenKind::Bang => "!",
+        TokenKind::Tilde => "~",
+        TokenKind::Assign => "=",
+        TokenKind::PlusAssign => "+=",
+        TokenKind::MinusAssign => "-=",
+        TokenKind::AsteriskAssign => "*=",
+        TokenKind::SlashAssign => "/=",
+        TokenKind::PercentAssign => "%=",
+        TokenKind::DoubleAsteriskAssign => "**=",
+        TokenKind::AmpersandAssign => "&=",
+        TokenKind::PipeAssign => "|=",
+        TokenKind::CaretAssign => "^=",
+        TokenKind::LShiftAssign => "<<=",
+        TokenKind::RShiftAssign => ">>=",
+        TokenKind::EQ => "==",
+        TokenKind::NEQ => "!=",
+        TokenKind::LT => "<",
+        TokenKind::GT => ">",
+        TokenKind::LTE => "<=",
+        TokenKind::GTE => ">=",
+        operator => return operator.to_string(),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Formatter;
+    use crate::parser::parser::Parser;
+
+    fn format(source: &str) -> String {
+        let mut parser = Parser::from(source.to_string());
+        parser.verbatim = true;
+
+        let program = parser.parse_program();
+        assert!(program.errors.is_empty(), "{:?}", program.errors);
+
+        Formatter::new(source).format_program(&program)
+    }
+
+    #[test]
+    fn format_test() {
+        let source = r#"// points
+struct Point { x : int , y:float, }
+auto add=fn ( a:Point,b:Point )->Point{return struct Point{x:a.x+b.x,y:a.y+b.y};}; // adds
+
+
+auto x = (1 + 2) * -(3 - 4 - (5 - 6));
+auto half = 1.50 / 2.0;
+auto mask = 0xFF_FF + 0b1 * 1_000 + 0o7;
+auto tiny = 1e-9;
+auto mut total=0;
+total+=x*2;
+let mut grid: int[][] = [[0]];
+grid [0][0]=total = 3;
+auto bits = (-2) ** 2 ** (3) | ~x >> 1 && (a || b);
+auto f = fn<T>(spread values: T[]) -> T[] => values;
+auto g = if x > 1 { "a" } else if x < 1 { /* never */ } else { return "c"; };
+auto long = [1000000000, 2000000000, 3000000000, 4000000000, 5000000000, 6000000000, 7000000000, 8000000000, 9000000000];
+auto call = add(
+    struct Point { x: 1, y: 2 }, // first
+    struct Point { x: 3, y: 4 }
+);
+"#;
+
+        let expected = r#"// points
+struct Point { x: int, y: float }
+auto add = fn(a: Point, b: Point) -> Point {
+    return struct Point { x: a.x + b.x, y: a.y + b.y };
+}; // adds
+
+auto x = (1 + 2) * -(3 - 4 - (5 - 6));
+auto half = 1.50 / 2.0;
+auto mask = 0xFF_FF + 0b1 * 1_000 + 0o7;
+auto tiny = 1e-9;
+auto mut total = 0;
+total += x * 2;
+let mut grid: int[][] = [[0]];
+grid[0][0] = total = 3;
+auto bits = (-2) ** 2 ** 3 | ~x >> 1 && (a || b);
+auto f = fn<T>(spread values: T[]) -> T[] => values;
+auto g = if x > 1 {
+    "a"
+} else if x < 1 {
+    /* never */
+} else {
+    return "c";
+};
+auto long = [
+    1000000000,
+    2000000000,
+    3000000000,
+    4000000000,
+    5000000000,
+    6000000000,
+    7000000000,
+    8000000000,
+    9000000000,
+];
+auto call = add(
+    struct Point { x: 1, y: 2 }, // first
+    struct Point { x: 3, y: 4 },
+);
+"#;
+
+        assert_eq!(format(source), expected);
+        assert_eq!(format(expected), expected);
+    }
+
+    #[test]
+    fn comment_test() {
+        // comments stay by the token they are written next to, and only line comments break a list over several lines
+        let source = r#"struct P {
+    // c2
+    x: int,
+}
+auto x = /* c1 */ 1;
+if true /* c8 */ {
+    1;
+};
+f(1 /* a */, 2);
+auto y = [1, 2, // c3
+];
+/* lead */ auto a = 1;
+auto b = fn(/* p */ x: int, y: int /* q */) -> int /* r */ {
+    x + /* s */ y // t
+};
+auto c = [ // u
+    1, /* v */
+    2
+    // w
+];
+match a /* m */ {
+    1 => 2, /* n */
+    // o
+    _ => 3
+};
+auto d = struct P { /* f1 */ x: 1 /* f2 */ };
+auto e = b(1, /* k */
+    2);
+auto g = if a > 1 { 1 } /* e1 */ else /* e2 */ { 2 };
+import { a /* i1 */, b } from "./x.sntk";
+enum E { A, // e3
+  B }
+"#;
+
+        let expected = r#"struct P {
+    // c2
+    x: int,
+}
+auto x = /* c1 */ 1;
+if true /* c8 */ {
+    1;
+};
+f(1 /* a */, 2);
+auto y = [
+    1,
+    2, // c3
+];
+/* lead */ auto a = 1;
+auto b = fn(/* p */ x: int, y: int /* q */) -> int /* r */ {
+    x + /* s */ y // t
+};
+auto c = [
+    // u
+    1, /* v */
+    2,
+    // w
+];
+match a /* m */ {
+    1 => 2, /* n */
+    // o
+    _ => 3,
+};
+auto d = struct P { /* f1 */ x: 1 /* f2 */ };
+auto e = b(
+    1, /* k */
+    2,
+);
+auto g = if a > 1 {
+    1
+} /* e1 */ else /* e2 */ {
+    2
+};
+import { a /* i1 */, b } from "./x.sntk";
+enum E {
+    A, // e3
+    B,
+}
+"#;
+
+        assert_eq!(format(source), expected);
+        assert_eq!(format(expected), expected);
+    }
+
+    #[test]
+    fn format_examples_test() {
+        // the examples are kept formatted, so formatting them changes nothing
+        for source in [
+            include_str!("../../examples/assignment.sntk"),
+            include_str!("../../examples/control.sntk"),
+            include_str!("../../examples/enum.sntk"),
+            include_str!("../../examples/loops.sntk"),
+            include_str!("../../examples/match.sntk"),
+            include_str!("../../examples/modules.sntk"),
+            include_str!("../../examples/operators.sntk"),
+            include_str!("../../examples/option.sntk"),
+            include_str!("../../examples/recursion.sntk"),
+            include_str!("../../examples/spread.sntk"),
+            include_str!("../../examples/struct.sntk"),
+            include_str!("../../examples/lib/shapes.sntk"),
+            include_str!("../../examples/lib/scale.sntk"),
+        ] {
+            assert_eq!(format(source), source);
+        }
+    }
+}
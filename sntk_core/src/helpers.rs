@@ -6,7 +6,7 @@ macro_rules! identifier {
             _ => {
                 return Err(ParsingError::new(
                     ParsingErrorKind::UnexpectedToken($self.current_token.kind.to_string()),
-                    $self.position,
+                    $self.span,
                 ));
             }
         }
@@ -1,3 +1,6 @@
+pub mod diagnostic;
+pub mod formatter;
 pub mod helpers;
+pub mod line_index;
 pub mod parser;
 pub mod tokenizer;
@@ -0,0 +1,104 @@
+/// Where each line of a source starts, found once so that any number of offsets can be located in it.
+#[derive(Debug)]
+pub struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+/// A 0-based line and 1-based columns, counted in chars for people and in UTF-16 code units for editors.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    pub utf16_column: usize,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        LineIndex {
+            source,
+            line_starts: std::iter::once(0).chain(source.match_indices('\n').map(|(index, _)| index + 1)).collect(),
+        }
+    }
+
+    /// Where the byte `offset` is, or the char it is inside of.
+    pub fn location(&self, offset: usize) -> Location {
+        let mut offset = offset.min(self.source.len());
+
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let text = &self.source[self.line_starts[line]..offset];
+
+        Location {
+            line,
+            column: text.chars().count() + 1,
+            utf16_column: text.encode_utf16().count() + 1,
+        }
+    }
+
+    /// The byte offset of the 0-based UTF-16 `character` of `line`, clamped to the end of the line (or of the source, past
+    /// the last line).
+    pub fn offset(&self, line: usize, character: usize) -> usize {
+        let Some(&start) = self.line_starts.get(line) else {
+            return self.source.len();
+        };
+        let end = self.line_starts.get(line + 1).copied().unwrap_or(self.source.len());
+
+        let mut column = 0;
+
+        for (index, char) in self.source[start..end].char_indices() {
+            if column >= character || char == '\n' {
+                return start + index;
+            }
+
+            column += char.len_utf16();
+        }
+
+        end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineIndex, Location};
+
+    #[test]
+    fn location_test() {
+        let source = "a\n🦀é x\n";
+        let index = LineIndex::new(source);
+
+        // `🦀` is one char but two UTF-16 code units, and an offset inside it is where it starts
+        let x = source.find('x').unwrap();
+        assert_eq!(
+            index.location(x),
+            Location {
+                line: 1,
+                column: 4,
+                utf16_column: 5
+            }
+        );
+        assert_eq!(
+            index.location(3),
+            Location {
+                line: 1,
+                column: 1,
+                utf16_column: 1
+            }
+        );
+        assert_eq!(
+            index.location(source.len()),
+            Location {
+                line: 2,
+                column: 1,
+                utf16_column: 1
+            }
+        );
+
+        assert_eq!(index.offset(1, 4), x);
+        assert_eq!(index.offset(0, 10), 1);
+        assert_eq!(index.offset(5, 0), source.len());
+    }
+}
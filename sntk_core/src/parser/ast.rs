@@ -5,6 +5,7 @@ use std::fmt;
 pub struct Program {
     pub statements: Vec<Statement>,
     pub errors: Vec<ParsingError>,
+    pub comments: Vec<Comment>, // trivia, kept aside so that the source can be printed back
 }
 
 impl Program {
@@ -13,10 +14,17 @@ impl Program {
         Self {
             statements,
             errors: Vec::new(),
+            comments: Vec::new(),
         }
     }
 }
 
+impl SpanlessEq for Program {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        self.statements.spanless_eq(&other.statements)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Statement {
     LetStatement(LetStatement),
@@ -25,7 +33,25 @@ pub enum Statement {
     TypeStatement(TypeStatement),
     DeclareStatement(DeclareStatement),
     StructStatement(StructStatement),
+    EnumStatement(EnumStatement),
+    ImportStatement(ImportStatement),
     ExpressionStatement(ExpressionStatement),
+    ErrorStatement(ErrorStatement),
+}
+
+impl Statement {
+    /// Whether the statement is marked with `export`, so that other modules can import what it declares.
+    pub fn is_exported(&self) -> bool {
+        match self {
+            Statement::LetStatement(LetStatement { exported, .. })
+            | Statement::AutoStatement(AutoStatement { exported, .. })
+            | Statement::TypeStatement(TypeStatement { exported, .. })
+            | Statement::DeclareStatement(DeclareStatement { exported, .. })
+            | Statement::StructStatement(StructStatement { exported, .. })
+            | Statement::EnumStatement(EnumStatement { exported, .. }) => *exported,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -34,28 +60,91 @@ pub enum Expression {
     Identifier(Identifier),
     PrefixExpression(PrefixExpression),
     InfixExpression(InfixExpression),
+    AssignExpression(AssignExpression),
     IfExpression(IfExpression),
+    WhileExpression(WhileExpression),
+    ForExpression(ForExpression),
+    LoopExpression(LoopExpression),
+    MatchExpression(MatchExpression),
+    BreakExpression(BreakExpression),
+    ContinueExpression(ContinueExpression),
     FunctionLiteral(FunctionLiteral),
     CallExpression(CallExpression),
     TypeofExpression(TypeofExpression),
     IndexExpression(IndexExpression),
+    FieldExpression(FieldExpression),
+    TryExpression(TryExpression),
     StringLiteral(StringLiteral),
-    NumberLiteral(NumberLiteral),
+    IntLiteral(IntLiteral),
+    FloatLiteral(FloatLiteral),
     ArrayLiteral(ArrayLiteral),
     BooleanLiteral(BooleanLiteral),
     StructLiteral(StructLiteral),
+    EnumLiteral(EnumLiteral),
+}
+
+macro_rules! node_enum {
+    ($name:ident => $( $variant:ident ),*) => {
+        impl $name {
+            pub fn span(&self) -> Span {
+                match self {
+                    $( $name::$variant(node) => node.span, )*
+                }
+            }
+        }
+
+        impl SpanlessEq for $name {
+            fn spanless_eq(&self, other: &Self) -> bool {
+                match (self, other) {
+                    $( ($name::$variant(left), $name::$variant(right)) => left.spanless_eq(right), )*
+                    _ => false,
+                }
+            }
+        }
+    };
+}
+
+node_enum! {
+    Statement => LetStatement, AutoStatement, ReturnStatement, TypeStatement, DeclareStatement, StructStatement, EnumStatement, ImportStatement,
+    ExpressionStatement, ErrorStatement
+}
+
+node_enum! {
+    Expression => BlockExpression, Identifier, PrefixExpression, InfixExpression, AssignExpression, IfExpression, WhileExpression, ForExpression,
+    LoopExpression, MatchExpression, BreakExpression, ContinueExpression, FunctionLiteral, CallExpression, TypeofExpression, IndexExpression, FieldExpression, TryExpression, StringLiteral, IntLiteral, FloatLiteral, ArrayLiteral, BooleanLiteral, StructLiteral, EnumLiteral
 }
 
 #[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    WildcardPattern(WildcardPattern),
+    LiteralPattern(LiteralPattern),
+    BindingPattern(BindingPattern),
+    ArrayPattern(ArrayPattern),
+    StructPattern(StructPattern),
+    EnumPattern(EnumPattern),
+}
+
+node_enum! {
+    Pattern => WildcardPattern, LiteralPattern, BindingPattern, ArrayPattern, StructPattern, EnumPattern
+}
+
+#[derive(Debug, Clone)]
 pub struct DataType {
     pub data_type: DataTypeKind,
-    pub position: Position,
+    pub span: Span,
 }
 
 impl DataType {
     #[inline]
-    pub fn new(data_type: DataTypeKind, position: Position) -> Self {
-        Self { data_type, position }
+    pub fn new(data_type: DataTypeKind, span: Span) -> Self {
+        Self { data_type, span }
+    }
+}
+
+/// Types are equal when they have the same structure, wherever they were written.
+impl PartialEq for DataType {
+    fn eq(&self, other: &Self) -> bool {
+        self.data_type == other.data_type
     }
 }
 
@@ -67,12 +156,17 @@ impl fmt::Display for DataType {
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum DataTypeKind {
-    Number,
+    Int,
+    Float,
     String,
     Boolean,
+    Void,
     Array(Box<DataType>),
     Fn(FunctionType),
+    Struct(StructType),
+    Enum(EnumType),
     Generic(Generic),
+    TypeParameter(String),
     Custom(String),
     Auto,
     Unknown,
@@ -81,12 +175,17 @@ pub enum DataTypeKind {
 impl fmt::Display for DataTypeKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         match self {
-            DataTypeKind::Number => write!(f, "Number"),
+            DataTypeKind::Int => write!(f, "Int"),
+            DataTypeKind::Float => write!(f, "Float"),
             DataTypeKind::String => write!(f, "String"),
             DataTypeKind::Boolean => write!(f, "Boolean"),
+            DataTypeKind::Void => write!(f, "Void"),
             DataTypeKind::Array(data_type) => write!(f, "{}[]", data_type),
             DataTypeKind::Fn(function_type) => write!(f, "{}", function_type),
+            DataTypeKind::Struct(struct_type) => write!(f, "{}", struct_type),
+            DataTypeKind::Enum(enum_type) => write!(f, "{}", enum_type),
             DataTypeKind::Generic(generic) => write!(f, "{}", generic),
+            DataTypeKind::TypeParameter(name) => write!(f, "{}", name),
             DataTypeKind::Custom(name) => write!(f, "{}", name),
             DataTypeKind::Auto => write!(f, "Auto"),
             DataTypeKind::Unknown => write!(f, "Unknown"),
@@ -96,7 +195,7 @@ impl fmt::Display for DataTypeKind {
 
 pub type IdentifierGeneric = Vec<Identifier>;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, Clone)]
 pub struct FunctionType {
     pub generics: Option<IdentifierGeneric>,
     pub parameters: Vec<(DataType, bool)>, // Vec<(type, is_spread)>
@@ -114,6 +213,18 @@ impl FunctionType {
     }
 }
 
+impl PartialEq for FunctionType {
+    fn eq(&self, other: &Self) -> bool {
+        let names = |generics: &Option<IdentifierGeneric>| {
+            generics
+                .as_ref()
+                .map(|generics| generics.iter().map(|generic| generic.value.clone()).collect::<Vec<_>>())
+        };
+
+        names(&self.generics) == names(&other.generics) && self.parameters == other.parameters && self.return_type == other.return_type
+    }
+}
+
 impl std::fmt::Display for FunctionType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         let parameters = self
@@ -134,6 +245,64 @@ impl std::fmt::Display for FunctionType {
     }
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub struct StructType {
+    pub name: String,
+    pub generics: Vec<DataType>, // type arguments of an instantiated generic struct
+    pub fields: Vec<(String, DataType)>,
+}
+
+impl StructType {
+    #[inline]
+    pub fn new(name: String, generics: Vec<DataType>, fields: Vec<(String, DataType)>) -> Self {
+        StructType { name, generics, fields }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&DataType> {
+        self.fields.iter().find(|(field, _)| field == name).map(|(_, data_type)| data_type)
+    }
+}
+
+impl std::fmt::Display for StructType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        if self.generics.is_empty() {
+            return write!(f, "{}", self.name);
+        }
+
+        let generics = self.generics.iter().map(ToString::to_string).collect::<Vec<String>>().join(", ");
+        write!(f, "{}<{}>", self.name, generics)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct EnumType {
+    pub name: String,
+    pub generics: Vec<DataType>,                // type arguments of an instantiated generic enum
+    pub variants: Vec<(String, Vec<DataType>)>, // Vec<(variant, payload)>
+}
+
+impl EnumType {
+    #[inline]
+    pub fn new(name: String, generics: Vec<DataType>, variants: Vec<(String, Vec<DataType>)>) -> Self {
+        EnumType { name, generics, variants }
+    }
+
+    pub fn variant(&self, name: &str) -> Option<&Vec<DataType>> {
+        self.variants.iter().find(|(variant, _)| variant == name).map(|(_, payload)| payload)
+    }
+}
+
+impl std::fmt::Display for EnumType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        if self.generics.is_empty() {
+            return write!(f, "{}", self.name);
+        }
+
+        let generics = self.generics.iter().map(ToString::to_string).collect::<Vec<String>>().join(", ");
+        write!(f, "{}<{}>", self.name, generics)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Generic(pub Box<DataType>, pub Vec<DataType>);
 
@@ -156,25 +325,82 @@ impl std::fmt::Display for Generic {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy)]
-pub struct Position(pub usize, pub usize);
+/// Identifies the source file a `Span` belongs to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId(pub usize);
 
-impl Position {
+/// A range of bytes `start..end` in a source file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
     #[inline]
-    pub fn new(line: usize, column: usize) -> Self {
-        Position(line, column)
+    pub fn new(file: FileId, start: usize, end: usize) -> Self {
+        Span { file, start, end }
     }
-}
 
-impl PartialEq for Position {
-    fn eq(&self, _: &Self) -> bool {
-        true
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Self {
+        Span::new(self.file, self.start.min(other.start), self.end.max(other.end))
     }
 }
 
-impl fmt::Display for Position {
+impl fmt::Display for Span {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        write!(f, "[{}:{}]", self.0, self.1)
+        write!(f, "[{}..{}]", self.start, self.end)
+    }
+}
+
+/// Structural equality of syntax trees, ignoring where in the source each node was written.
+pub trait SpanlessEq {
+    fn spanless_eq(&self, other: &Self) -> bool;
+}
+
+macro_rules! spanless_eq_by_partial_eq {
+    ($( $type:ty ),*) => {
+        $(
+            impl SpanlessEq for $type {
+                #[inline]
+                fn spanless_eq(&self, other: &Self) -> bool {
+                    self == other
+                }
+            }
+        )*
+    };
+}
+
+// `DataType`s are already compared by structure, see its `PartialEq` implementation.
+spanless_eq_by_partial_eq! { String, i64, f64, bool, TokenKind, DataType }
+
+impl<T: SpanlessEq> SpanlessEq for Box<T> {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        (**self).spanless_eq(other)
+    }
+}
+
+impl<T: SpanlessEq> SpanlessEq for Option<T> {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(left), Some(right)) => left.spanless_eq(right),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T: SpanlessEq> SpanlessEq for Vec<T> {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(left, right)| left.spanless_eq(right))
+    }
+}
+
+impl<A: SpanlessEq, B: SpanlessEq> SpanlessEq for (A, B) {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        self.0.spanless_eq(&other.0) && self.1.spanless_eq(&other.1)
     }
 }
 
@@ -183,13 +409,19 @@ macro_rules! make_struct {
         #[derive(Debug, PartialEq, Clone)]
         pub struct $name {
             $( pub $field: $type, )*
-            pub position: Position
+            pub span: Span
         }
 
         impl $name {
             #[inline]
-            pub fn new($( $field: $type, )* position: Position) -> Self {
-                $name { $($field,)* position: position.clone() }
+            pub fn new($( $field: $type, )* span: Span) -> Self {
+                $name { $($field,)* span }
+            }
+        }
+
+        impl SpanlessEq for $name {
+            fn spanless_eq(&self, other: &Self) -> bool {
+                $( self.$field.spanless_eq(&other.$field) && )* true
             }
         }
     };
@@ -198,72 +430,177 @@ macro_rules! make_struct {
         pub struct $name {
             $( pub $field: $type, )*
             pub data_type: DataType,
-            pub position: Position
+            pub span: Span
         }
 
         impl $name {
             #[inline]
-            pub fn new(data_type: DataType, $( $field: $type, )* position: Position) -> Self {
-                $name { $($field,)* data_type, position: position.clone() }
+            pub fn new(data_type: DataType, $( $field: $type, )* span: Span) -> Self {
+                $name { $($field,)* data_type, span }
+            }
+        }
+
+        impl SpanlessEq for $name {
+            fn spanless_eq(&self, other: &Self) -> bool {
+                $( self.$field.spanless_eq(&other.$field) && )* self.data_type.spanless_eq(&other.data_type)
             }
         }
     }
 }
 
-make_struct! { @data_type LetStatement => name: Identifier, value: Expression }
-make_struct! { @data_type TypeStatement => name: Identifier, generics: IdentifierGeneric }
-make_struct! { @data_type DeclareStatement => name: Identifier }
+make_struct! { @data_type LetStatement => name: Identifier, value: Expression, mutable: bool, exported: bool }
+make_struct! { @data_type TypeStatement => name: Identifier, generics: IdentifierGeneric, exported: bool }
+make_struct! { @data_type DeclareStatement => name: Identifier, exported: bool }
 
-make_struct! { AutoStatement => name: Identifier, value: Expression }
-make_struct! { StructStatement => name: Identifier, generics: IdentifierGeneric, fields: Vec<(Identifier, DataType)> }
-make_struct! { ReturnStatement => value: Expression }
-make_struct! { ExpressionStatement => expression: Expression }
+make_struct! { AutoStatement => name: Identifier, value: Expression, mutable: bool, exported: bool }
+make_struct! { StructStatement => name: Identifier, generics: IdentifierGeneric, fields: Vec<(Identifier, DataType)>, exported: bool }
+make_struct! { EnumStatement => name: Identifier, generics: IdentifierGeneric, variants: Vec<(Identifier, Vec<DataType>)>, exported: bool }
+make_struct! { ImportStatement => names: Vec<Identifier>, path: StringLiteral }
+make_struct! { ReturnStatement => value: Option<Expression> }
+make_struct! { ExpressionStatement => expression: Expression, semicolon: bool }
 
 make_struct! { BlockExpression => statements: Vec<Statement> }
 make_struct! { IfExpression => condition: Box<Expression>, consequence: Box<BlockExpression>, alternative: Option<Box<BlockExpression>> }
+make_struct! { WhileExpression => label: Option<Identifier>, condition: Box<Expression>, body: BlockExpression }
+make_struct! { ForExpression => label: Option<Identifier>, name: Identifier, iterable: Box<Expression>, body: BlockExpression }
+make_struct! { LoopExpression => label: Option<Identifier>, body: BlockExpression }
+make_struct! { MatchExpression => value: Box<Expression>, arms: Vec<MatchArm> }
+make_struct! { MatchArm => pattern: Pattern, guard: Option<Expression>, body: Expression }
+make_struct! { BreakExpression => label: Option<Identifier>, value: Option<Box<Expression>> }
+make_struct! { ContinueExpression => label: Option<Identifier> }
 make_struct! { CallExpression => function: Box<Expression>, arguments: Vec<Expression> }
 make_struct! { TypeofExpression => expression: Box<Expression> }
 make_struct! { IndexExpression => left: Box<Expression>, index: Box<Expression> }
+make_struct! { FieldExpression => left: Box<Expression>, field: Identifier }
+make_struct! { TryExpression => value: Box<Expression> }
 make_struct! { PrefixExpression => operator: TokenKind, right: Box<Expression> }
 make_struct! { InfixExpression => left: Box<Expression>, operator: TokenKind, right: Box<Expression> }
+make_struct! { AssignExpression => target: Box<Expression>, operator: TokenKind, value: Box<Expression> }
 
 make_struct! { Identifier => value: String }
-make_struct! { NumberLiteral => value: f64 }
+make_struct! { IntLiteral => value: i64 }
+make_struct! { FloatLiteral => value: f64 }
 make_struct! { StringLiteral => value: String }
 make_struct! { BooleanLiteral => value: bool }
 make_struct! { FunctionLiteral => generics: Option<IdentifierGeneric>, parameters: Vec<Parameter>, return_type: DataType, body: BlockExpression }
 make_struct! { ArrayLiteral => elements: Vec<Expression> }
 make_struct! { StructLiteral => name: Identifier, fields: Vec<(Identifier, Expression)> }
+make_struct! { EnumLiteral => name: Identifier, variant: Identifier, arguments: Vec<Expression> }
+
+make_struct! { LiteralPattern => value: Expression }
+make_struct! { BindingPattern => name: Identifier }
+make_struct! { ArrayPattern => elements: Vec<Pattern>, rest: Option<Box<Pattern>> }
+make_struct! { StructPattern => name: Identifier, fields: Vec<(Identifier, Pattern)> }
+make_struct! { EnumPattern => name: Identifier, variant: Identifier, payload: Vec<Pattern> }
+
+make_struct! { Comment => value: String }
+
+/// Takes the place of a statement that failed to parse, so that the rest of the program can still be inspected.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ErrorStatement {
+    pub span: Span,
+}
+
+impl ErrorStatement {
+    #[inline]
+    pub fn new(span: Span) -> Self {
+        ErrorStatement { span }
+    }
+}
+
+impl SpanlessEq for ErrorStatement {
+    fn spanless_eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// `_`, matching any value without binding it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WildcardPattern {
+    pub span: Span,
+}
+
+impl WildcardPattern {
+    #[inline]
+    pub fn new(span: Span) -> Self {
+        WildcardPattern { span }
+    }
+}
+
+impl SpanlessEq for WildcardPattern {
+    fn spanless_eq(&self, _: &Self) -> bool {
+        true
+    }
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Parameter {
     pub name: Identifier,
     pub data_type: DataType,
     pub spread: bool,
-    pub position: Position,
+    pub span: Span,
 }
 
 impl Parameter {
     #[inline]
-    pub fn new(name: Identifier, data_type: DataType, spread: bool, position: Position) -> Self {
+    pub fn new(name: Identifier, data_type: DataType, spread: bool, span: Span) -> Self {
         Parameter {
             name,
             data_type,
             spread,
-            position,
+            span,
         }
     }
 }
 
+impl SpanlessEq for Parameter {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        self.name.spanless_eq(&other.name) && self.data_type.spanless_eq(&other.data_type) && self.spread == other.spread
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, PartialOrd)]
 pub enum Priority {
     Lowest,
-    Dot,
+    Assign,
+    Or,
+    And,
     Equals,
     LessGreater,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
     Sum,
     Product,
     Prefix,
+    Power,
     Call,
     Index,
+    Dot,
+}
+
+impl Priority {
+    /// The binding power of a token in infix position.
+    pub fn of(token_type: &TokenKind) -> Self {
+        match token_type {
+            TokenKind::EQ | TokenKind::NEQ => Priority::Equals,
+            TokenKind::Plus | TokenKind::Minus => Priority::Sum,
+            TokenKind::Slash | TokenKind::Asterisk | TokenKind::Percent => Priority::Product,
+            TokenKind::LT | TokenKind::GT | TokenKind::LTE | TokenKind::GTE => Priority::LessGreater,
+            TokenKind::Or => Priority::Or,
+            TokenKind::And => Priority::And,
+            TokenKind::Pipe => Priority::BitOr,
+            TokenKind::Caret => Priority::BitXor,
+            TokenKind::Ampersand => Priority::BitAnd,
+            TokenKind::LShift | TokenKind::RShift => Priority::Shift,
+            // binds tighter than a prefix operator on its left, so `-2 ** 2` is `-(2 ** 2)`
+            TokenKind::DoubleAsterisk => Priority::Power,
+            operator if *operator == TokenKind::Assign || operator.compound_operator().is_some() => Priority::Assign,
+            TokenKind::LParen | TokenKind::Question => Priority::Call,
+            TokenKind::LBracket => Priority::Index,
+            TokenKind::Dot => Priority::Dot,
+            _ => Priority::Lowest,
+        }
+    }
 }
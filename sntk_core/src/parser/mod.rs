@@ -1,25 +1,27 @@
-use self::ast::Position;
+use self::ast::Span;
+use crate::diagnostic::{Diagnostic, Label};
 use std::fmt;
 use thiserror::Error;
 
 pub mod ast;
+#[allow(clippy::module_inception)]
 pub mod parser;
 
 #[derive(Debug, Clone)]
 pub struct ParsingError {
     pub message: ParsingErrorKind,
-    pub position: Position,
+    pub span: Span,
 }
 
 impl fmt::Display for ParsingError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}: {}", self.position, self.message)
+        write!(f, "{}: {}", self.span, self.message)
     }
 }
 
 impl ParsingError {
-    pub fn new(message: ParsingErrorKind, position: Position) -> Self {
-        Self { message, position }
+    pub fn new(message: ParsingErrorKind, span: Span) -> Self {
+        Self { message, span }
     }
 }
 
@@ -29,5 +31,77 @@ pub enum ParsingErrorKind {
     #[error("Expected next token to be `{0}`, got `{1}` instead")] ExpectedNextToken(String, String),
     #[error("Expected next token to be a data type, got `{0}` instead")] ExpectedDataType(String),
     #[error("Expected next token to be an expression, got `{0}` instead")] ExpectedExpression(String),
+    #[error("Expected next token to be a pattern, got `{0}` instead")] ExpectedPattern(String),
     #[error("Unexpected token `{0}`")] UnexpectedToken(String),
+    #[error("Unterminated string literal")] UnterminatedString,
+    #[error("Unknown escape sequence `\\{0}`")] UnknownEscape(String),
+    #[error("Invalid unicode escape `{0}`")] InvalidUnicodeEscape(String),
+    #[error("Integer literal `{0}` is too large for `int`")] IntegerTooLarge(String),
+    #[error("Malformed number literal `{0}`")] MalformedNumber(String),
+    #[error("Float literal `{0}` is too large for `float`")] FloatTooLarge(String),
+    #[error("Invalid assignment target")] InvalidAssignmentTarget,
+    #[error("Nested more than {0} deep")] NestedTooDeeply(usize),
+}
+
+impl ParsingErrorKind {
+    /// A stable code identifying the kind of error, never reused once assigned.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParsingErrorKind::ExpectedNextToken(..) => "E0001",
+            ParsingErrorKind::ExpectedDataType(_) => "E0002",
+            ParsingErrorKind::ExpectedExpression(_) => "E0003",
+            ParsingErrorKind::UnexpectedToken(_) => "E0004",
+            ParsingErrorKind::UnterminatedString => "E0005",
+            ParsingErrorKind::UnknownEscape(_) => "E0006",
+            ParsingErrorKind::InvalidUnicodeEscape(_) => "E0007",
+            ParsingErrorKind::IntegerTooLarge(_) => "E0008",
+            ParsingErrorKind::MalformedNumber(_) => "E0009",
+            ParsingErrorKind::InvalidAssignmentTarget => "E0010",
+            ParsingErrorKind::ExpectedPattern(_) => "E0011",
+            ParsingErrorKind::NestedTooDeeply(_) => "E0012",
+            ParsingErrorKind::FloatTooLarge(_) => "E0013",
+        }
+    }
+
+    pub fn help(&self) -> Option<String> {
+        match self {
+            ParsingErrorKind::ExpectedNextToken(expected, _) if expected == "Semicolon" => {
+                Some("statements and expression statements end with `;`".to_string())
+            }
+            ParsingErrorKind::ExpectedDataType(_) => {
+                Some("data types are `int`, `float`, `string`, `boolean`, `fn(...) -> T`, `T[]` or a declared type".to_string())
+            }
+            ParsingErrorKind::ExpectedPattern(_) => Some(
+                "patterns are `_`, a literal, a name, `[first, ...rest]`, `struct Name { field: pattern }` or `Enum::Variant(pattern)`".to_string(),
+            ),
+            ParsingErrorKind::UnknownEscape(_) => Some(
+                "escape sequences are `\\n`, `\\r`, `\\t`, `\\0`, `\\\\`, `\\\"` and `\\u{...}`, or write a raw string such as `r\"C:\\path\"`"
+                    .to_string(),
+            ),
+            ParsingErrorKind::InvalidUnicodeEscape(_) => {
+                Some("unicode escapes are one to six hexadecimal digits in braces, such as `\\u{1F600}`".to_string())
+            }
+            ParsingErrorKind::IntegerTooLarge(_) => Some(format!(
+                "`int` holds values up to {}, write a `float` such as `1.5` for larger ones",
+                i64::MAX
+            )),
+            ParsingErrorKind::FloatTooLarge(_) => Some(format!("`float` holds values up to {:e}", f64::MAX)),
+            ParsingErrorKind::MalformedNumber(_) => {
+                Some("numbers are written as `42`, `1_000_000`, `0xFF`, `0o17`, `0b1010`, `2.5` or `1e-9`, and `_` only separates digits".to_string())
+            }
+            ParsingErrorKind::InvalidAssignmentTarget => {
+                Some("only variables, array elements and struct fields can be assigned to, as in `x = 1`, `xs[0] = 1` or `p.x = 1`".to_string())
+            }
+            ParsingErrorKind::NestedTooDeeply(_) => Some("name the inner parts with `auto` first, as in `auto inner = ...;`".to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl From<&ParsingError> for Diagnostic {
+    fn from(error: &ParsingError) -> Self {
+        Diagnostic::error(error.message.code(), error.message.to_string())
+            .with_label(Label::primary(error.span, None))
+            .with_help(error.message.help())
+    }
 }
@@ -2,10 +2,13 @@ use crate::{
     identifier,
     parser::{
         ast::{
-            ArrayLiteral, AutoStatement, BlockExpression, BooleanLiteral, CallExpression, DataType, DataTypeKind, DeclareStatement, Expression,
-            ExpressionStatement, FunctionLiteral, FunctionType, Generic, Identifier, IdentifierGeneric, IfExpression, IndexExpression,
-            InfixExpression, LetStatement, NumberLiteral, Parameter, Position, PrefixExpression, Priority, Program, ReturnStatement, Statement,
-            StringLiteral, StructLiteral, StructStatement, TypeStatement, TypeofExpression,
+            ArrayLiteral, ArrayPattern, AssignExpression, AutoStatement, BindingPattern, BlockExpression, BooleanLiteral, BreakExpression,
+            CallExpression, Comment, ContinueExpression, DataType, DataTypeKind, DeclareStatement, EnumLiteral, EnumPattern, EnumStatement,
+            ErrorStatement, Expression, ExpressionStatement, FieldExpression, FloatLiteral, ForExpression, FunctionLiteral, FunctionType, Generic,
+            Identifier, IdentifierGeneric, IfExpression, ImportStatement, IndexExpression, InfixExpression, IntLiteral, LetStatement, LiteralPattern,
+            LoopExpression, MatchArm, MatchExpression, Parameter, Pattern, PrefixExpression, Priority, Program, ReturnStatement, Span, Statement,
+            StringLiteral, StructLiteral, StructPattern, StructStatement, TryExpression, TypeStatement, TypeofExpression, WhileExpression,
+            WildcardPattern,
         },
         ParsingError, ParsingErrorKind,
     },
@@ -17,13 +20,21 @@ use crate::{
 
 pub type ParseResult<T> = Result<T, ParsingError>;
 
+/// How deeply expressions, patterns and data types may nest, so that source nested deeply enough to overflow the stack of the
+/// parser, or of the compiler after it, is an error instead of a crash.
+pub const MAX_NESTING_DEPTH: usize = 128;
+
 #[derive(Debug, Default)]
 pub struct Parser {
     pub lexer: Lexer,
     pub current_token: Token,
     pub peek_token: Token,
-    pub position: Position,
+    pending_token: Option<Token>, // the token after `peek_token`, once a `>>` closing two generics is split
+    pub span: Span,
     pub errors: Vec<ParsingError>,
+    pub comments: Vec<Comment>,
+    pub verbatim: bool, // keep expressions as written instead of evaluating constant ones (EEE), e.g. for formatting
+    depth: usize,       // expressions, patterns and data types being parsed, one inside the other
 }
 
 impl From<String> for Parser {
@@ -40,9 +51,18 @@ impl Parser {
 
     fn next_token(&mut self) {
         self.current_token = self.peek_token.clone();
-        self.peek_token = self.lexer.next_token();
+        self.peek_token = match self.pending_token.take() {
+            Some(token) => token,
+            None => self.lexer.next_token(),
+        };
+
+        while let TokenKind::Comment(comment) = &self.peek_token.kind {
+            self.comments.push(Comment::new(comment.clone(), self.peek_token.span));
+            self.peek_token = self.lexer.next_token();
+        }
 
-        self.position = Position::new(self.current_token.position.0, self.current_token.position.1);
+        self.errors.append(&mut self.lexer.errors);
+        self.span = self.current_token.span;
     }
 
     fn expect_token(&mut self, token_type: &TokenKind) -> ParseResult<()> {
@@ -52,36 +72,35 @@ impl Parser {
             Ok(())
         } else {
             Err(ParsingError::new(
-                ParsingErrorKind::ExpectedExpression(self.current_token.kind.to_string()),
-                self.position,
+                ParsingErrorKind::ExpectedNextToken(token_type.to_string(), self.current_token.kind.to_string()),
+                self.span,
             ))
         }
     }
 
+    /// Splits a current `>>` into two `>`, when it closes two generics as in `Box<Box<int>>`.
+    fn split_right_shift(&mut self) {
+        if self.current_token.kind == TokenKind::RShift {
+            let Span { file, start, end } = self.span;
+            let second = Token::new(TokenKind::GT, Span::new(file, start + 1, end));
+
+            self.pending_token = Some(std::mem::replace(&mut self.peek_token, second));
+            self.current_token = Token::new(TokenKind::GT, Span::new(file, start, start + 1));
+            self.span = self.current_token.span;
+        }
+    }
+
     #[inline]
     fn peek_token(&self, token_type: &TokenKind) -> bool {
         self.peek_token.kind == *token_type
     }
 
-    fn get_priority(&self, token_type: &TokenKind) -> Priority {
-        match token_type {
-            TokenKind::Dot | TokenKind::Arrow => Priority::Dot,
-            TokenKind::Assign | TokenKind::EQ | TokenKind::NEQ => Priority::Equals,
-            TokenKind::Plus | TokenKind::Minus => Priority::Sum,
-            TokenKind::Slash | TokenKind::Asterisk => Priority::Product,
-            TokenKind::LT | TokenKind::GT | TokenKind::LTE | TokenKind::GTE => Priority::LessGreater,
-            TokenKind::LParen => Priority::Call,
-            TokenKind::LBracket => Priority::Index,
-            _ => Priority::Lowest,
-        }
-    }
-
     fn peek_priority(&mut self) -> Priority {
-        self.get_priority(&self.peek_token.kind)
+        Priority::of(&self.peek_token.kind)
     }
 
     fn current_priority(&self) -> Priority {
-        self.get_priority(&self.current_token.kind)
+        Priority::of(&self.current_token.kind)
     }
 
     pub fn parse_program(&mut self) -> Program {
@@ -91,21 +110,84 @@ impl Parser {
         let mut program = Program::default();
 
         while self.current_token.kind != TokenKind::EOF {
+            let start = self.span;
+
             match self.parse_statement() {
-                Ok(statement) => program.statements.push(statement),
-                Err(error) => self.errors.push(error),
+                Ok(statement) => {
+                    program.statements.push(statement);
+                    self.next_token();
+                }
+                Err(error) => {
+                    let statement = self.recover(error, start);
+                    program.statements.push(statement);
+                }
             }
-
-            self.next_token();
         }
 
         program.errors = self.errors.clone();
+        program.comments = self.comments.clone();
+
+        program
+    }
 
-        if !self.errors.is_empty() {
-            program.statements = Vec::new();
+    /// Records `error` and skips the rest of the statement starting at `start`, returning an error node in its place. The error
+    /// is left out when the statement has an unterminated string, which took the rest of its line, and the statement's end, with
+    /// it.
+    fn recover(&mut self, error: ParsingError, start: Span) -> Statement {
+        let span = start.to(error.span);
+        let unterminated = self
+            .errors
+            .iter()
+            .rev()
+            .take_while(|error| error.span.start >= start.start)
+            .any(|error| matches!(error.message, ParsingErrorKind::UnterminatedString));
+
+        if !unterminated {
+            self.errors.push(error);
         }
+        let end = self.synchronize(start);
 
-        program
+        Statement::ErrorStatement(ErrorStatement::new(span.to(end)))
+    }
+
+    /// Skips tokens up to the next statement, which starts after a `;` outside of any bracket, at a statement keyword
+    /// or at the `}` closing the enclosing block, and returns the span of the last token skipped.
+    fn synchronize(&mut self, start: Span) -> Span {
+        let mut depth = 0usize;
+        let mut end = start;
+
+        loop {
+            // the token the broken statement started with is never the start of the next one
+            let boundary = depth == 0 && self.span != start;
+
+            match self.current_token.kind {
+                TokenKind::EOF => return end,
+                TokenKind::RBrace
+                | TokenKind::Let
+                | TokenKind::Auto
+                | TokenKind::Type
+                | TokenKind::Declare
+                | TokenKind::Return
+                | TokenKind::Import
+                | TokenKind::Export
+                    if boundary =>
+                {
+                    return end
+                }
+                TokenKind::Semicolon if depth == 0 => {
+                    end = self.span;
+                    self.next_token();
+
+                    return end;
+                }
+                TokenKind::LBrace | TokenKind::LParen | TokenKind::LBracket => depth += 1,
+                TokenKind::RBrace | TokenKind::RParen | TokenKind::RBracket => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+
+            end = self.span;
+            self.next_token();
+        }
     }
 
     fn parse_statement(&mut self) -> ParseResult<Statement> {
@@ -116,14 +198,105 @@ impl Parser {
             TokenKind::Type => Statement::TypeStatement(self.parse_type_statement()?),
             TokenKind::Declare => Statement::DeclareStatement(self.parse_declare_statement()?),
             TokenKind::Struct => Statement::StructStatement(self.parse_struct_statement()?),
+            TokenKind::Enum => Statement::EnumStatement(self.parse_enum_statement()?),
+            TokenKind::Import => Statement::ImportStatement(self.parse_import_statement()?),
+            TokenKind::Export => self.parse_export_statement()?,
             _ => Statement::ExpressionStatement(self.parse_expression_statement()?),
         })
     }
 
+    /// `export` before a `let`, `auto`, `type`, `declare`, `struct` or `enum` statement, whose names other modules can import.
+    fn parse_export_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.span;
+        self.next_token();
+
+        let mut statement = match self.current_token.kind {
+            TokenKind::Let | TokenKind::Auto | TokenKind::Type | TokenKind::Declare | TokenKind::Struct | TokenKind::Enum => {
+                self.parse_statement()?
+            }
+            _ => {
+                return Err(ParsingError::new(
+                    ParsingErrorKind::UnexpectedToken(self.current_token.kind.to_string()),
+                    self.span,
+                ))
+            }
+        };
+
+        match &mut statement {
+            Statement::LetStatement(LetStatement { exported, span, .. })
+            | Statement::AutoStatement(AutoStatement { exported, span, .. })
+            | Statement::TypeStatement(TypeStatement { exported, span, .. })
+            | Statement::DeclareStatement(DeclareStatement { exported, span, .. })
+            | Statement::StructStatement(StructStatement { exported, span, .. })
+            | Statement::EnumStatement(EnumStatement { exported, span, .. }) => {
+                *exported = true;
+                *span = start.to(*span);
+            }
+            _ => unreachable!(),
+        }
+
+        Ok(statement)
+    }
+
+    /// `import { a, b } from "./path.sntk";`, where `from` is only a keyword here, so that it can still name a field.
+    fn parse_import_statement(&mut self) -> ParseResult<ImportStatement> {
+        let start = self.span;
+        self.next_token();
+
+        self.expect_token(&TokenKind::LBrace)?;
+
+        let mut names = Vec::new();
+
+        while self.current_token.kind != TokenKind::RBrace {
+            names.push(Identifier::new(identifier! { self }, self.span));
+            self.next_token();
+
+            if self.current_token.kind == TokenKind::RBrace {
+                break;
+            }
+
+            self.expect_token(&TokenKind::Comma)?;
+        }
+
+        self.expect_token(&TokenKind::RBrace)?;
+
+        if self.current_token.kind != TokenKind::IDENT("from".to_string()) {
+            return Err(ParsingError::new(
+                ParsingErrorKind::ExpectedNextToken("from".to_string(), self.current_token.kind.to_string()),
+                self.span,
+            ));
+        }
+
+        self.next_token();
+
+        let path = match &self.current_token.kind {
+            TokenKind::String(path) => StringLiteral::new(path.clone(), self.span),
+            kind => {
+                return Err(ParsingError::new(
+                    ParsingErrorKind::ExpectedNextToken("String".to_string(), kind.to_string()),
+                    self.span,
+                ))
+            }
+        };
+
+        if self.peek_token(&TokenKind::Semicolon) {
+            self.next_token();
+
+            Ok(ImportStatement::new(names, path, start.to(self.span)))
+        } else {
+            Err(ParsingError::new(
+                ParsingErrorKind::ExpectedNextToken(TokenKind::Semicolon.to_string(), self.current_token.kind.to_string()),
+                self.span,
+            ))
+        }
+    }
+
     fn parse_let_statement(&mut self) -> ParseResult<LetStatement> {
+        let start = self.span;
         self.next_token();
 
-        let ident = Identifier::new(identifier! { self }, self.position);
+        let mutable = self.parse_mut();
+        let ident = Identifier::new(identifier! { self }, self.span);
         self.next_token();
 
         self.expect_token(&TokenKind::Colon)?;
@@ -132,78 +305,83 @@ impl Parser {
 
         self.expect_token(&TokenKind::Assign)?;
 
-        if let Ok(expression) = self.parse_expression(&Priority::Lowest) {
-            return if self.peek_token(&TokenKind::Semicolon) {
-                self.next_token();
+        let expression = self.parse_expression(&Priority::Lowest)?;
 
-                Ok(LetStatement::new(data_type, ident, expression, self.position))
-            } else {
-                Err(ParsingError::new(
-                    ParsingErrorKind::ExpectedNextToken(TokenKind::Semicolon.to_string(), self.current_token.kind.to_string()),
-                    self.position,
-                ))
-            };
-        }
+        if self.peek_token(&TokenKind::Semicolon) {
+            self.next_token();
 
-        Err(ParsingError::new(
-            ParsingErrorKind::UnexpectedToken(self.current_token.kind.to_string()),
-            self.position,
-        ))
+            Ok(LetStatement::new(data_type, ident, expression, mutable, false, start.to(self.span)))
+        } else {
+            Err(ParsingError::new(
+                ParsingErrorKind::ExpectedNextToken(TokenKind::Semicolon.to_string(), self.current_token.kind.to_string()),
+                self.span,
+            ))
+        }
     }
 
     fn parse_auto_statement(&mut self) -> ParseResult<AutoStatement> {
+        let start = self.span;
         self.next_token();
 
-        let ident = Identifier::new(identifier! { self }, self.position);
+        let mutable = self.parse_mut();
+        let ident = Identifier::new(identifier! { self }, self.span);
         self.next_token();
 
         self.expect_token(&TokenKind::Assign)?;
 
-        if let Ok(expression) = self.parse_expression(&Priority::Lowest) {
-            return if self.peek_token(&TokenKind::Semicolon) {
-                self.next_token();
+        let expression = self.parse_expression(&Priority::Lowest)?;
 
-                Ok(AutoStatement::new(ident, expression, self.position))
-            } else {
-                Err(ParsingError::new(
-                    ParsingErrorKind::ExpectedNextToken(TokenKind::Semicolon.to_string(), self.current_token.kind.to_string()),
-                    self.position,
-                ))
-            };
+        if self.peek_token(&TokenKind::Semicolon) {
+            self.next_token();
+
+            Ok(AutoStatement::new(ident, expression, mutable, false, start.to(self.span)))
+        } else {
+            Err(ParsingError::new(
+                ParsingErrorKind::ExpectedNextToken(TokenKind::Semicolon.to_string(), self.current_token.kind.to_string()),
+                self.span,
+            ))
         }
+    }
 
-        Err(ParsingError::new(
-            ParsingErrorKind::UnexpectedToken(self.current_token.kind.to_string()),
-            self.position,
-        ))
+    /// Skips a `mut` after `let` or `auto`, returning whether there was one.
+    fn parse_mut(&mut self) -> bool {
+        let mutable = self.current_token.kind == TokenKind::Mut;
+
+        if mutable {
+            self.next_token();
+        }
+
+        mutable
     }
 
     fn parse_return_statement(&mut self) -> ParseResult<ReturnStatement> {
-        self.next_token();
+        let start = self.span;
 
-        if let Ok(expression) = self.parse_expression(&Priority::Lowest) {
-            return if self.peek_token(&TokenKind::Semicolon) {
-                self.next_token();
+        // a bare `return;` returns void
+        let value = if self.peek_token(&TokenKind::Semicolon) {
+            None
+        } else {
+            self.next_token();
+            Some(self.parse_expression(&Priority::Lowest)?)
+        };
 
-                Ok(ReturnStatement::new(expression, self.position))
-            } else {
-                Err(ParsingError::new(
-                    ParsingErrorKind::ExpectedNextToken(TokenKind::Semicolon.to_string(), self.current_token.kind.to_string()),
-                    self.position,
-                ))
-            };
-        }
+        if self.peek_token(&TokenKind::Semicolon) {
+            self.next_token();
 
-        Err(ParsingError::new(
-            ParsingErrorKind::UnexpectedToken(self.current_token.kind.to_string()),
-            self.position,
-        ))
+            Ok(ReturnStatement::new(value, start.to(self.span)))
+        } else {
+            Err(ParsingError::new(
+                ParsingErrorKind::ExpectedNextToken(TokenKind::Semicolon.to_string(), self.current_token.kind.to_string()),
+                self.span,
+            ))
+        }
     }
 
     fn parse_type_statement(&mut self) -> ParseResult<TypeStatement> {
+        let start = self.span;
         self.next_token();
 
-        let ident = identifier! { self };
+        let ident = Identifier::new(identifier! { self }, self.span);
         self.next_token();
 
         let generics = if self.current_token.kind == TokenKind::LT {
@@ -219,24 +397,20 @@ impl Parser {
         let data_type = self.parse_data_type()?;
 
         if self.current_token.kind == TokenKind::Semicolon {
-            Ok(TypeStatement::new(
-                data_type,
-                Identifier::new(ident, self.position),
-                generics,
-                self.position,
-            ))
+            Ok(TypeStatement::new(data_type, ident, generics, false, start.to(self.span)))
         } else {
             Err(ParsingError::new(
                 ParsingErrorKind::ExpectedNextToken(TokenKind::Semicolon.to_string(), self.current_token.kind.to_string()),
-                self.position,
+                self.span,
             ))
         }
     }
 
     fn parse_declare_statement(&mut self) -> ParseResult<DeclareStatement> {
+        let start = self.span;
         self.next_token();
 
-        let ident = identifier! { self };
+        let ident = Identifier::new(identifier! { self }, self.span);
         self.next_token();
 
         self.expect_token(&TokenKind::Assign)?;
@@ -244,19 +418,20 @@ impl Parser {
         let data_type = self.parse_data_type()?;
 
         if self.current_token.kind == TokenKind::Semicolon {
-            Ok(DeclareStatement::new(data_type, Identifier::new(ident, self.position), self.position))
+            Ok(DeclareStatement::new(data_type, ident, false, start.to(self.span)))
         } else {
             Err(ParsingError::new(
                 ParsingErrorKind::ExpectedNextToken(TokenKind::Semicolon.to_string(), self.current_token.kind.to_string()),
-                self.position,
+                self.span,
             ))
         }
     }
 
     fn parse_struct_statement(&mut self) -> ParseResult<StructStatement> {
+        let start = self.span;
         self.next_token();
 
-        let ident = identifier! { self };
+        let ident = Identifier::new(identifier! { self }, self.span);
         self.next_token();
 
         let generics = if self.current_token.kind == TokenKind::LT {
@@ -274,7 +449,7 @@ impl Parser {
         let mut fields = Vec::new();
 
         while self.current_token.kind != TokenKind::RBrace {
-            let key = Identifier::new(identifier! { self }, self.position);
+            let key = Identifier::new(identifier! { self }, self.span);
             self.next_token();
 
             self.expect_token(&TokenKind::Colon)?;
@@ -290,221 +465,421 @@ impl Parser {
             self.expect_token(&TokenKind::Comma)?;
         }
 
-        self.expect_token(&TokenKind::RBrace)?;
+        if self.current_token.kind != TokenKind::RBrace {
+            return Err(ParsingError::new(
+                ParsingErrorKind::ExpectedNextToken(TokenKind::RBrace.to_string(), self.current_token.kind.to_string()),
+                self.span,
+            ));
+        }
 
-        Ok(StructStatement::new(
-            Identifier::new(ident, self.position),
-            generics,
-            fields,
-            self.position,
-        ))
+        if self.peek_token(&TokenKind::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(StructStatement::new(ident, generics, fields, false, start.to(self.span)))
     }
 
-    fn parse_expression_statement(&mut self) -> ParseResult<ExpressionStatement> {
-        let expression = self.parse_expression(&Priority::Lowest)?;
+    fn parse_enum_statement(&mut self) -> ParseResult<EnumStatement> {
+        let start = self.span;
+        self.next_token();
+
+        let ident = Identifier::new(identifier! { self }, self.span);
+        self.next_token();
+
+        let generics = if self.current_token.kind == TokenKind::LT {
+            let generic = self.parse_generic_identifier()?;
 
-        if self.peek_token(&TokenKind::Semicolon) {
             self.next_token();
 
-            Ok(ExpressionStatement::new(expression, self.position))
+            generic
         } else {
-            Err(ParsingError::new(
-                ParsingErrorKind::ExpectedNextToken(TokenKind::Semicolon.to_string(), self.current_token.kind.to_string()),
-                self.position,
-            ))
-        }
-    }
+            Vec::new()
+        };
 
-    fn parse_expression(&mut self, priority: &Priority) -> ParseResult<Expression> {
-        let left_expression = match self.current_token.kind.clone() {
-            TokenKind::IDENT(ident) => Some(Ok(Expression::Identifier(Identifier::new(ident, self.position)))),
-            TokenKind::Number(number) => Some(Ok(Expression::NumberLiteral(NumberLiteral::new(number, self.position)))),
-            TokenKind::String(string) => Some(Ok(Expression::StringLiteral(StringLiteral::new(string, self.position)))),
-            TokenKind::Boolean(boolean) => Some(Ok(Expression::BooleanLiteral(BooleanLiteral::new(boolean, self.position)))),
-            TokenKind::Bang | TokenKind::Minus => {
-                let operator = self.current_token.kind.clone();
+        self.expect_token(&TokenKind::LBrace)?;
 
-                self.next_token();
+        let mut variants = Vec::new();
 
-                Some(Ok(Expression::PrefixExpression(PrefixExpression::new(
-                    operator,
-                    Box::new(self.parse_expression(&Priority::Prefix)?),
-                    self.position,
-                ))))
-            }
-            TokenKind::LParen => {
-                self.next_token();
+        while self.current_token.kind != TokenKind::RBrace {
+            let variant = Identifier::new(identifier! { self }, self.span);
+            self.next_token();
 
-                let expression = self.parse_expression(&Priority::Lowest);
+            let mut payload = Vec::new();
+
+            if self.current_token.kind == TokenKind::LParen {
                 self.next_token();
 
-                if self.current_token.kind != TokenKind::RParen {
-                    return Err(ParsingError::new(
-                        ParsingErrorKind::ExpectedNextToken(TokenKind::RParen.to_string(), self.current_token.kind.to_string()),
-                        self.position,
-                    ));
+                while self.current_token.kind != TokenKind::RParen {
+                    payload.push(self.parse_data_type()?);
+
+                    if self.current_token.kind == TokenKind::RParen {
+                        break;
+                    }
+
+                    self.expect_token(&TokenKind::Comma)?;
                 }
 
-                Some(expression)
+                self.expect_token(&TokenKind::RParen)?;
             }
-            TokenKind::LBrace => Some(Ok(Expression::BlockExpression(self.parse_block_expression()?))),
-            TokenKind::LBracket => Some(Ok(Expression::ArrayLiteral(self.parse_array_literal()?))),
-            TokenKind::Function => Some(Ok(Expression::FunctionLiteral(self.parse_function_literal()?))),
-            TokenKind::Struct => Some(Ok(Expression::StructLiteral(self.parse_struct_literal()?))),
-            TokenKind::If => Some(Ok(Expression::IfExpression(self.parse_if_expression()?))),
-            TokenKind::Typeof => {
-                self.next_token();
 
-                Some(Ok(Expression::TypeofExpression(TypeofExpression::new(
-                    Box::new(self.parse_expression(&Priority::Lowest)?),
-                    self.position,
-                ))))
+            variants.push((variant, payload));
+
+            if self.current_token.kind == TokenKind::RBrace {
+                break;
             }
-            _ => None,
-        };
 
-        if left_expression.is_none() && self.current_token.kind != TokenKind::Semicolon {
+            self.expect_token(&TokenKind::Comma)?;
+        }
+
+        if self.current_token.kind != TokenKind::RBrace {
             return Err(ParsingError::new(
-                ParsingErrorKind::UnexpectedToken(self.current_token.kind.to_string()),
-                self.position,
+                ParsingErrorKind::ExpectedNextToken(TokenKind::RBrace.to_string(), self.current_token.kind.to_string()),
+                self.span,
             ));
         }
 
-        let mut left_expression = left_expression.ok_or_else(|| {
-            ParsingError::new(
-                ParsingErrorKind::UnexpectedToken(self.current_token.kind.to_string()),
-                self.position,
-            )
-        })?;
-
-        while !self.peek_token(&TokenKind::Semicolon) && priority < &self.peek_priority() {
+        if self.peek_token(&TokenKind::Semicolon) {
             self.next_token();
+        }
 
-            left_expression = match self.current_token.kind {
-                TokenKind::Plus
-                | TokenKind::Minus
-                | TokenKind::Dot
-                | TokenKind::Slash
-                | TokenKind::Asterisk
-                | TokenKind::Percent
-                | TokenKind::EQ
-                | TokenKind::NEQ
-                | TokenKind::LT
-                | TokenKind::GT
-                | TokenKind::LTE
-                | TokenKind::GTE => Ok(Expression::InfixExpression(InfixExpression::new(
-                    Box::new(left_expression?),
-                    self.current_token.kind.clone(),
-                    {
-                        self.next_token();
-                        Box::new(self.parse_expression(&self.current_priority())?)
-                    },
-                    self.position,
-                ))),
-                TokenKind::LParen => {
-                    self.next_token();
-
-                    let mut arguments = Vec::new();
-
-                    if self.current_token.kind != TokenKind::RParen {
-                        arguments.push(self.parse_expression(&Priority::Lowest)?);
-                        self.next_token();
-
-                        if self.current_token.kind == TokenKind::Comma {
-                            self.next_token();
-                        }
-
-                        while self.current_token.kind != TokenKind::RParen {
-                            arguments.push(self.parse_expression(&Priority::Lowest)?);
-                            self.next_token();
+        Ok(EnumStatement::new(ident, generics, variants, false, start.to(self.span)))
+    }
 
-                            if self.current_token.kind == TokenKind::RParen {
-                                break;
-                            }
+    fn parse_expression_statement(&mut self) -> ParseResult<ExpressionStatement> {
+        let expression = self.parse_expression(&Priority::Lowest)?;
 
-                            self.expect_token(&TokenKind::Comma)?;
-                        }
+        if self.peek_token(&TokenKind::Semicolon) {
+            self.next_token();
 
-                        if self.current_token.kind != TokenKind::RParen {
-                            return Err(ParsingError::new(
-                                ParsingErrorKind::ExpectedNextToken(TokenKind::RParen.to_string(), self.current_token.kind.to_string()),
-                                self.position,
-                            ));
-                        }
-                    }
+            Ok(ExpressionStatement::new(expression.clone(), true, expression.span().to(self.span)))
+        } else if self.peek_token.kind == TokenKind::RBrace {
+            // the last expression of a block, without a `;`, is its value
+            Ok(ExpressionStatement::new(expression.clone(), false, expression.span()))
+        } else {
+            Err(ParsingError::new(
+                ParsingErrorKind::ExpectedNextToken(TokenKind::Semicolon.to_string(), self.current_token.kind.to_string()),
+                self.span,
+            ))
+        }
+    }
 
-                    Ok(Expression::CallExpression(CallExpression::new(
-                        Box::new(left_expression?),
-                        arguments,
-                        self.position,
-                    )))
-                }
-                TokenKind::LBracket => {
-                    self.next_token();
+    /// Runs `parse` one level deeper, failing once the source nests more than `MAX_NESTING_DEPTH` deep. `parse` may go
+    /// deeper still with `deeper`, and is back at this depth when it returns.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        let depth = self.depth;
 
-                    let index = self.parse_expression(&Priority::Lowest)?;
-                    self.next_token();
+        self.deeper()?;
+        let result = parse(self);
+        self.depth = depth;
 
-                    if self.current_token.kind != TokenKind::RBracket {
-                        return Err(ParsingError::new(
-                            ParsingErrorKind::ExpectedNextToken(TokenKind::RBracket.to_string(), self.current_token.kind.to_string()),
-                            self.position,
-                        ));
-                    }
+        result
+    }
 
-                    Ok(Expression::IndexExpression(IndexExpression::new(
-                        Box::new(left_expression?),
-                        Box::new(index),
-                        self.position,
-                    )))
-                }
-                _ => Err(ParsingError::new(
-                    ParsingErrorKind::UnexpectedToken(self.current_token.kind.to_string()),
-                    self.position,
-                )),
-            };
+    /// Goes one level deeper, for what nests without the parser recursing, such as each operator of a chain.
+    fn deeper(&mut self) -> ParseResult<()> {
+        if self.depth == MAX_NESTING_DEPTH {
+            return Err(ParsingError::new(ParsingErrorKind::NestedTooDeeply(MAX_NESTING_DEPTH), self.span));
         }
 
-        match left_expression.clone()? {
-            Expression::InfixExpression(infix) => {
-                if let Some(e) = self.eval_infix_expression(&infix) {
-                    return e;
-                };
-            }
-            Expression::PrefixExpression(prefix) => {
-                if let Some(e) = self.eval_prefix_expression(&prefix) {
-                    return e;
-                };
-            }
-            _ => {}
-        };
+        self.depth += 1;
 
-        left_expression
+        Ok(())
     }
 
-    fn parse_block_expression(&mut self) -> ParseResult<BlockExpression> {
-        self.next_token();
+    fn parse_expression(&mut self, priority: &Priority) -> ParseResult<Expression> {
+        self.nested(|parser| parser.parse_expression_inner(priority))
+    }
 
-        let mut statements = Vec::new();
+    fn parse_expression_inner(&mut self, priority: &Priority) -> ParseResult<Expression> {
+        let prefix = self.parse_prefix_expression()?;
+        let mut expression = self.fold(prefix)?;
 
-        while self.current_token.kind != TokenKind::RBrace {
-            statements.push(self.parse_statement()?);
+        while !self.peek_token(&TokenKind::Semicolon) && priority < &self.peek_priority() {
             self.next_token();
+
+            let operator = self.parse_operator_expression(expression)?;
+            expression = self.fold(operator)?;
+
+            // the next operator puts this expression one level deeper, even though the chain is parsed in a loop
+            self.deeper()?;
         }
 
-        Ok(BlockExpression::new(statements, self.position))
+        Ok(expression)
     }
 
-    fn parse_array_literal(&mut self) -> ParseResult<ArrayLiteral> {
-        self.next_token();
+    /// `expression` with its operator evaluated when its operands are constants. Each expression is folded as soon as it is
+    /// parsed, so its operands already are.
+    fn fold(&mut self, expression: Expression) -> ParseResult<Expression> {
+        if self.verbatim {
+            return Ok(expression);
+        }
 
-        let mut elements = Vec::new();
+        let folded = match &expression {
+            Expression::InfixExpression(infix) => self.eval_infix_expression(infix),
+            Expression::PrefixExpression(prefix) => self.eval_prefix_expression(prefix),
+            _ => None,
+        };
 
-        if self.current_token.kind == TokenKind::RBracket {
-            return Ok(ArrayLiteral::new(elements, self.position));
-        }
+        folded.unwrap_or(Ok(expression))
+    }
+
+    /// An expression starting at the current token, before any operator following it.
+    fn parse_prefix_expression(&mut self) -> ParseResult<Expression> {
+        // each arm only calls another function, so that the frame of this one, entered at every level of nesting, stays small
+        match self.current_token.kind.clone() {
+            TokenKind::IDENT(_) if self.peek_token(&TokenKind::DoubleColon) => self.parse_enum_literal().map(Expression::EnumLiteral),
+            TokenKind::IDENT(ident) => Ok(Expression::Identifier(Identifier::new(ident, self.span))),
+            TokenKind::Int(int) => Ok(Expression::IntLiteral(IntLiteral::new(int, self.span))),
+            TokenKind::Float(float) => Ok(Expression::FloatLiteral(FloatLiteral::new(float, self.span))),
+            TokenKind::String(string) => Ok(Expression::StringLiteral(StringLiteral::new(string, self.span))),
+            TokenKind::Boolean(boolean) => Ok(Expression::BooleanLiteral(BooleanLiteral::new(boolean, self.span))),
+            TokenKind::Bang | TokenKind::Minus | TokenKind::Tilde => self.parse_prefix_operator_expression().map(Expression::PrefixExpression),
+            TokenKind::LParen => self.parse_grouped_expression(),
+            TokenKind::LBrace => self.parse_block_expression().map(Expression::BlockExpression),
+            TokenKind::LBracket => self.parse_array_literal().map(Expression::ArrayLiteral),
+            TokenKind::Function => self.parse_function_literal().map(Expression::FunctionLiteral),
+            TokenKind::Struct => self.parse_struct_literal().map(Expression::StructLiteral),
+            TokenKind::If => self.parse_if_expression().map(Expression::IfExpression),
+            TokenKind::Match => self.parse_match_expression().map(Expression::MatchExpression),
+            TokenKind::While | TokenKind::For | TokenKind::Loop => self.parse_loop_expression(None, self.span),
+            TokenKind::Label(label) => self.parse_labeled_expression(label),
+            TokenKind::Break => self.parse_break_expression().map(Expression::BreakExpression),
+            TokenKind::Continue => self.parse_continue_expression().map(Expression::ContinueExpression),
+            TokenKind::Typeof => self.parse_typeof_expression().map(Expression::TypeofExpression),
+            _ => Err(ParsingError::new(
+                ParsingErrorKind::UnexpectedToken(self.current_token.kind.to_string()),
+                self.span,
+            )),
+        }
+    }
+
+    fn parse_prefix_operator_expression(&mut self) -> ParseResult<PrefixExpression> {
+        let (operator, start) = (self.current_token.kind.clone(), self.span);
+
+        self.next_token();
+
+        Ok(PrefixExpression::new(
+            operator,
+            Box::new(self.parse_expression(&Priority::Prefix)?),
+            start.to(self.span),
+        ))
+    }
+
+    fn parse_grouped_expression(&mut self) -> ParseResult<Expression> {
+        self.next_token();
+
+        let expression = self.parse_expression(&Priority::Lowest)?;
+        self.next_token();
+
+        if self.current_token.kind != TokenKind::RParen {
+            return Err(ParsingError::new(
+                ParsingErrorKind::ExpectedNextToken(TokenKind::RParen.to_string(), self.current_token.kind.to_string()),
+                self.span,
+            ));
+        }
+
+        Ok(expression)
+    }
+
+    fn parse_labeled_expression(&mut self, label: String) -> ParseResult<Expression> {
+        let (label, start) = (Identifier::new(label, self.span), self.span);
+
+        self.next_token();
+        self.expect_token(&TokenKind::Colon)?;
+
+        self.parse_loop_expression(Some(label), start)
+    }
+
+    fn parse_continue_expression(&mut self) -> ParseResult<ContinueExpression> {
+        let start = self.span;
+        let label = self.parse_label();
+
+        Ok(ContinueExpression::new(label, start.to(self.span)))
+    }
+
+    fn parse_typeof_expression(&mut self) -> ParseResult<TypeofExpression> {
+        let start = self.span;
+        self.next_token();
+
+        Ok(TypeofExpression::new(
+            Box::new(self.parse_expression(&Priority::Lowest)?),
+            start.to(self.span),
+        ))
+    }
+
+    /// The operator at the current token applied to `left`, with its right operand or arguments.
+    fn parse_operator_expression(&mut self, left: Expression) -> ParseResult<Expression> {
+        let start = left.span();
+
+        match self.current_token.kind {
+            TokenKind::Plus
+            | TokenKind::Minus
+            | TokenKind::Slash
+            | TokenKind::Asterisk
+            | TokenKind::Percent
+            | TokenKind::DoubleAsterisk
+            | TokenKind::And
+            | TokenKind::Or
+            | TokenKind::Ampersand
+            | TokenKind::Pipe
+            | TokenKind::Caret
+            | TokenKind::LShift
+            | TokenKind::RShift
+            | TokenKind::EQ
+            | TokenKind::NEQ
+            | TokenKind::LT
+            | TokenKind::GT
+            | TokenKind::LTE
+            | TokenKind::GTE => Ok(Expression::InfixExpression(InfixExpression::new(
+                Box::new(left),
+                self.current_token.kind.clone(),
+                {
+                    let priority = match self.current_token.kind {
+                        // right associative, so `2 ** 3 ** 2` is `2 ** (3 ** 2)`
+                        TokenKind::DoubleAsterisk => Priority::Prefix,
+                        _ => self.current_priority(),
+                    };
+                    self.next_token();
+                    Box::new(self.parse_expression(&priority)?)
+                },
+                start.to(self.span),
+            ))),
+            ref operator if *operator == TokenKind::Assign || operator.compound_operator().is_some() => {
+                let operator = operator.clone();
+
+                if !is_place(&left) {
+                    return Err(ParsingError::new(ParsingErrorKind::InvalidAssignmentTarget, left.span()));
+                }
+
+                self.next_token();
+
+                // right associative, so `a = b = c` is `a = (b = c)`
+                let value = self.parse_expression(&Priority::Lowest)?;
+
+                Ok(Expression::AssignExpression(AssignExpression::new(
+                    Box::new(left),
+                    operator,
+                    Box::new(value),
+                    start.to(self.span),
+                )))
+            }
+            TokenKind::LParen => {
+                self.next_token();
+
+                let mut arguments = Vec::new();
+
+                if self.current_token.kind != TokenKind::RParen {
+                    arguments.push(self.parse_expression(&Priority::Lowest)?);
+                    self.next_token();
+
+                    if self.current_token.kind == TokenKind::Comma {
+                        self.next_token();
+                    }
+
+                    while self.current_token.kind != TokenKind::RParen {
+                        arguments.push(self.parse_expression(&Priority::Lowest)?);
+                        self.next_token();
+
+                        if self.current_token.kind == TokenKind::RParen {
+                            break;
+                        }
+
+                        self.expect_token(&TokenKind::Comma)?;
+                    }
+
+                    if self.current_token.kind != TokenKind::RParen {
+                        return Err(ParsingError::new(
+                            ParsingErrorKind::ExpectedNextToken(TokenKind::RParen.to_string(), self.current_token.kind.to_string()),
+                            self.span,
+                        ));
+                    }
+                }
+
+                Ok(Expression::CallExpression(CallExpression::new(
+                    Box::new(left),
+                    arguments,
+                    start.to(self.span),
+                )))
+            }
+            TokenKind::LBracket => {
+                self.next_token();
+
+                let index = self.parse_expression(&Priority::Lowest)?;
+                self.next_token();
+
+                if self.current_token.kind != TokenKind::RBracket {
+                    return Err(ParsingError::new(
+                        ParsingErrorKind::ExpectedNextToken(TokenKind::RBracket.to_string(), self.current_token.kind.to_string()),
+                        self.span,
+                    ));
+                }
+
+                Ok(Expression::IndexExpression(IndexExpression::new(
+                    Box::new(left),
+                    Box::new(index),
+                    start.to(self.span),
+                )))
+            }
+            TokenKind::Dot => {
+                self.next_token();
+
+                Ok(Expression::FieldExpression(FieldExpression::new(
+                    Box::new(left),
+                    Identifier::new(identifier! { self }, self.span),
+                    start.to(self.span),
+                )))
+            }
+            // `value?` gives the payload of a `Some` or `Ok`, returning any other value from the function
+            TokenKind::Question => Ok(Expression::TryExpression(TryExpression::new(Box::new(left), start.to(self.span)))),
+            _ => Err(ParsingError::new(
+                ParsingErrorKind::UnexpectedToken(self.current_token.kind.to_string()),
+                self.span,
+            )),
+        }
+    }
+
+    fn parse_block_expression(&mut self) -> ParseResult<BlockExpression> {
+        let start = self.span;
+        self.next_token();
+
+        let mut statements = Vec::new();
 
         while self.current_token.kind != TokenKind::RBrace {
+            if self.current_token.kind == TokenKind::EOF {
+                return Err(ParsingError::new(
+                    ParsingErrorKind::ExpectedNextToken(TokenKind::RBrace.to_string(), self.current_token.kind.to_string()),
+                    self.span,
+                ));
+            }
+
+            let start = self.span;
+
+            match self.parse_statement() {
+                Ok(statement) => {
+                    statements.push(statement);
+                    self.next_token();
+                }
+                Err(error) => {
+                    let statement = self.recover(error, start);
+                    statements.push(statement);
+                }
+            }
+        }
+
+        Ok(BlockExpression::new(statements, start.to(self.span)))
+    }
+
+    fn parse_array_literal(&mut self) -> ParseResult<ArrayLiteral> {
+        let start = self.span;
+        self.next_token();
+
+        let mut elements = Vec::new();
+
+        if self.current_token.kind == TokenKind::RBracket {
+            return Ok(ArrayLiteral::new(elements, start.to(self.span)));
+        }
+
+        while self.current_token.kind != TokenKind::RBracket {
             elements.push(self.parse_expression(&Priority::Lowest)?);
             self.next_token();
 
@@ -518,24 +893,25 @@ impl Parser {
         if self.current_token.kind != TokenKind::RBracket {
             return Err(ParsingError::new(
                 ParsingErrorKind::ExpectedNextToken(TokenKind::RBracket.to_string(), self.current_token.kind.to_string()),
-                self.position,
+                self.span,
             ));
         }
 
-        Ok(ArrayLiteral::new(elements, self.position))
+        Ok(ArrayLiteral::new(elements, start.to(self.span)))
     }
 
     fn parse_struct_literal(&mut self) -> ParseResult<StructLiteral> {
+        let start = self.span;
         self.next_token();
-        let identifier = identifier! { self };
+        let identifier = Identifier::new(identifier! { self }, self.span);
 
         self.next_token();
-        self.next_token();
+        self.expect_token(&TokenKind::LBrace)?;
 
         let mut fields = Vec::new();
 
         while self.current_token.kind != TokenKind::RBrace {
-            let key = Identifier::new(identifier! { self }, self.position);
+            let key = Identifier::new(identifier! { self }, self.span);
             self.next_token();
 
             self.expect_token(&TokenKind::Colon)?;
@@ -555,14 +931,44 @@ impl Parser {
         if self.current_token.kind != TokenKind::RBrace {
             return Err(ParsingError::new(
                 ParsingErrorKind::ExpectedNextToken(TokenKind::RBrace.to_string(), self.current_token.kind.to_string()),
-                self.position,
+                self.span,
             ));
         }
 
-        Ok(StructLiteral::new(Identifier::new(identifier, self.position), fields, self.position))
+        Ok(StructLiteral::new(identifier, fields, start.to(self.span)))
+    }
+
+    fn parse_enum_literal(&mut self) -> ParseResult<EnumLiteral> {
+        let start = self.span;
+        let name = Identifier::new(identifier! { self }, self.span);
+
+        self.next_token();
+        self.next_token();
+        let variant = Identifier::new(identifier! { self }, self.span);
+
+        let mut arguments = Vec::new();
+
+        if self.peek_token(&TokenKind::LParen) {
+            self.next_token();
+            self.next_token();
+
+            while self.current_token.kind != TokenKind::RParen {
+                arguments.push(self.parse_expression(&Priority::Lowest)?);
+                self.next_token();
+
+                if self.current_token.kind == TokenKind::RParen {
+                    break;
+                }
+
+                self.expect_token(&TokenKind::Comma)?;
+            }
+        }
+
+        Ok(EnumLiteral::new(name, variant, arguments, start.to(self.span)))
     }
 
     fn parse_function_literal(&mut self) -> ParseResult<FunctionLiteral> {
+        let start = self.span;
         self.next_token();
 
         let generics = if self.current_token.kind == TokenKind::LT {
@@ -579,6 +985,7 @@ impl Parser {
         let mut parameters = Vec::new();
 
         while self.current_token.kind != TokenKind::RParen {
+            let parameter_start = self.span;
             let is_spread = if self.current_token.kind == TokenKind::Spread {
                 self.next_token();
                 true
@@ -587,21 +994,18 @@ impl Parser {
             };
 
             if let TokenKind::IDENT(identifier) = self.current_token.kind.clone() {
+                let identifier = Identifier::new(identifier, self.span);
                 self.next_token();
                 self.expect_token(&TokenKind::Colon)?;
 
                 let data_type = self.parse_data_type()?;
+                let span = parameter_start.to(data_type.span);
 
-                parameters.push(Parameter::new(
-                    Identifier::new(identifier.clone(), self.position),
-                    data_type,
-                    is_spread,
-                    self.position,
-                ));
+                parameters.push(Parameter::new(identifier, data_type, is_spread, span));
             } else {
                 return Err(ParsingError::new(
                     ParsingErrorKind::ExpectedNextToken(TokenKind::IDENT("".to_string()).to_string(), self.current_token.kind.to_string()),
-                    self.position,
+                    self.span,
                 ));
             }
 
@@ -622,26 +1026,27 @@ impl Parser {
             TokenKind::DoubleArrow => {
                 self.next_token();
 
+                let expression = self.parse_expression(&Priority::Lowest)?;
+                let span = expression.span();
+
                 BlockExpression::new(
-                    vec![Statement::ReturnStatement(ReturnStatement::new(
-                        self.parse_expression(&Priority::Lowest)?,
-                        self.position,
-                    ))],
-                    self.position,
+                    vec![Statement::ExpressionStatement(ExpressionStatement::new(expression, false, span))],
+                    span,
                 )
             }
             _ => {
                 return Err(ParsingError::new(
                     ParsingErrorKind::ExpectedNextToken(TokenKind::LBrace.to_string(), self.current_token.kind.to_string()),
-                    self.position,
+                    self.span,
                 ))
             }
         };
 
-        Ok(FunctionLiteral::new(generics, parameters, return_type, body, self.position))
+        Ok(FunctionLiteral::new(generics, parameters, return_type, body, start.to(self.span)))
     }
 
     fn parse_if_expression(&mut self) -> ParseResult<IfExpression> {
+        let start = self.span;
         self.next_token();
 
         let condition = self.parse_expression(&Priority::Lowest)?;
@@ -654,13 +1059,17 @@ impl Parser {
             self.next_token();
 
             if self.current_token.kind == TokenKind::If {
-                Some(Box::new(BlockExpression {
-                    statements: vec![Statement::ExpressionStatement(ExpressionStatement::new(
-                        Expression::IfExpression(self.parse_if_expression()?),
-                        self.position,
+                let expression = self.nested(Self::parse_if_expression)?;
+                let span = expression.span;
+
+                Some(Box::new(BlockExpression::new(
+                    vec![Statement::ExpressionStatement(ExpressionStatement::new(
+                        Expression::IfExpression(expression),
+                        false,
+                        span,
                     ))],
-                    position: self.position,
-                }))
+                    span,
+                )))
             } else {
                 Some(Box::new(self.parse_block_expression()?))
             }
@@ -668,28 +1077,316 @@ impl Parser {
             None
         };
 
-        Ok(IfExpression::new(Box::new(condition), Box::new(consequence), alternative, self.position))
+        Ok(IfExpression::new(
+            Box::new(condition),
+            Box::new(consequence),
+            alternative,
+            start.to(self.span),
+        ))
+    }
+
+    fn parse_match_expression(&mut self) -> ParseResult<MatchExpression> {
+        let start = self.span;
+        self.next_token();
+
+        let value = self.parse_expression(&Priority::Lowest)?;
+        self.next_token();
+
+        self.expect_token(&TokenKind::LBrace)?;
+
+        let mut arms = Vec::new();
+
+        while self.current_token.kind != TokenKind::RBrace {
+            let arm_start = self.span;
+            let pattern = self.parse_pattern()?;
+            self.next_token();
+
+            let guard = if self.current_token.kind == TokenKind::If {
+                self.next_token();
+
+                let guard = self.parse_expression(&Priority::Lowest)?;
+                self.next_token();
+
+                Some(guard)
+            } else {
+                None
+            };
+
+            self.expect_token(&TokenKind::DoubleArrow)?;
+
+            let body = self.parse_expression(&Priority::Lowest)?;
+            self.next_token();
+
+            // the `,` after an arm whose body is a block can be left out
+            let is_block = matches!(body, Expression::BlockExpression(_));
+            arms.push(MatchArm::new(pattern, guard, body, arm_start.to(self.span)));
+
+            if self.current_token.kind == TokenKind::RBrace {
+                break;
+            }
+
+            if !is_block || self.current_token.kind == TokenKind::Comma {
+                self.expect_token(&TokenKind::Comma)?;
+            }
+        }
+
+        if self.current_token.kind != TokenKind::RBrace {
+            return Err(ParsingError::new(
+                ParsingErrorKind::ExpectedNextToken(TokenKind::RBrace.to_string(), self.current_token.kind.to_string()),
+                self.span,
+            ));
+        }
+
+        Ok(MatchExpression::new(Box::new(value), arms, start.to(self.span)))
+    }
+
+    /// Parses the pattern starting at the current token, ending on its last token.
+    fn parse_pattern(&mut self) -> ParseResult<Pattern> {
+        self.nested(Self::parse_pattern_inner)
+    }
+
+    fn parse_pattern_inner(&mut self) -> ParseResult<Pattern> {
+        let start = self.span;
+
+        Ok(match self.current_token.kind.clone() {
+            TokenKind::IDENT(ident) if ident == "_" => Pattern::WildcardPattern(WildcardPattern::new(start)),
+            TokenKind::IDENT(_) if self.peek_token(&TokenKind::DoubleColon) => {
+                let name = Identifier::new(identifier! { self }, self.span);
+
+                self.next_token();
+                self.next_token();
+                let variant = Identifier::new(identifier! { self }, self.span);
+
+                let mut payload = Vec::new();
+
+                if self.peek_token(&TokenKind::LParen) {
+                    self.next_token();
+                    self.next_token();
+
+                    while self.current_token.kind != TokenKind::RParen {
+                        payload.push(self.parse_pattern()?);
+                        self.next_token();
+
+                        if self.current_token.kind == TokenKind::RParen {
+                            break;
+                        }
+
+                        self.expect_token(&TokenKind::Comma)?;
+                    }
+                }
+
+                Pattern::EnumPattern(EnumPattern::new(name, variant, payload, start.to(self.span)))
+            }
+            TokenKind::IDENT(ident) => Pattern::BindingPattern(BindingPattern::new(Identifier::new(ident, start), start)),
+            TokenKind::Int(int) => Pattern::LiteralPattern(LiteralPattern::new(Expression::IntLiteral(IntLiteral::new(int, start)), start)),
+            TokenKind::Float(float) => Pattern::LiteralPattern(LiteralPattern::new(Expression::FloatLiteral(FloatLiteral::new(float, start)), start)),
+            TokenKind::String(string) => {
+                Pattern::LiteralPattern(LiteralPattern::new(Expression::StringLiteral(StringLiteral::new(string, start)), start))
+            }
+            TokenKind::Boolean(boolean) => Pattern::LiteralPattern(LiteralPattern::new(
+                Expression::BooleanLiteral(BooleanLiteral::new(boolean, start)),
+                start,
+            )),
+            TokenKind::Minus => {
+                self.next_token();
+                let span = start.to(self.span);
+
+                let value = match self.current_token.kind {
+                    TokenKind::Int(int) => Expression::IntLiteral(IntLiteral::new(-int, span)),
+                    TokenKind::Float(float) => Expression::FloatLiteral(FloatLiteral::new(-float, span)),
+                    _ => {
+                        return Err(ParsingError::new(
+                            ParsingErrorKind::ExpectedPattern(self.current_token.kind.to_string()),
+                            self.span,
+                        ))
+                    }
+                };
+
+                Pattern::LiteralPattern(LiteralPattern::new(value, span))
+            }
+            TokenKind::LBracket => {
+                self.next_token();
+
+                let (mut elements, mut rest) = (Vec::new(), None);
+
+                while self.current_token.kind != TokenKind::RBracket {
+                    // `...rest` takes the remaining elements, so it comes last
+                    if self.current_token.kind == TokenKind::Dot {
+                        self.expect_token(&TokenKind::Dot)?;
+                        self.expect_token(&TokenKind::Dot)?;
+                        self.expect_token(&TokenKind::Dot)?;
+
+                        let name = Identifier::new(identifier! { self }, self.span);
+                        self.next_token();
+
+                        rest = Some(Box::new(match name.value.as_str() {
+                            "_" => Pattern::WildcardPattern(WildcardPattern::new(name.span)),
+                            _ => Pattern::BindingPattern(BindingPattern::new(name.clone(), name.span)),
+                        }));
+
+                        break;
+                    }
+
+                    elements.push(self.parse_pattern()?);
+                    self.next_token();
+
+                    if self.current_token.kind == TokenKind::RBracket {
+                        break;
+                    }
+
+                    self.expect_token(&TokenKind::Comma)?;
+                }
+
+                if self.current_token.kind != TokenKind::RBracket {
+                    return Err(ParsingError::new(
+                        ParsingErrorKind::ExpectedNextToken(TokenKind::RBracket.to_string(), self.current_token.kind.to_string()),
+                        self.span,
+                    ));
+                }
+
+                Pattern::ArrayPattern(ArrayPattern::new(elements, rest, start.to(self.span)))
+            }
+            TokenKind::Struct => {
+                self.next_token();
+                let name = Identifier::new(identifier! { self }, self.span);
+
+                self.next_token();
+                self.expect_token(&TokenKind::LBrace)?;
+
+                let mut fields = Vec::new();
+
+                while self.current_token.kind != TokenKind::RBrace {
+                    let key = Identifier::new(identifier! { self }, self.span);
+                    self.next_token();
+
+                    // `struct Point { x }` is short for `struct Point { x: x }`
+                    let pattern = if self.current_token.kind == TokenKind::Colon {
+                        self.next_token();
+
+                        let pattern = self.parse_pattern()?;
+                        self.next_token();
+
+                        pattern
+                    } else {
+                        Pattern::BindingPattern(BindingPattern::new(key.clone(), key.span))
+                    };
+
+                    fields.push((key, pattern));
+
+                    if self.current_token.kind == TokenKind::RBrace {
+                        break;
+                    }
+
+                    self.expect_token(&TokenKind::Comma)?;
+                }
+
+                if self.current_token.kind != TokenKind::RBrace {
+                    return Err(ParsingError::new(
+                        ParsingErrorKind::ExpectedNextToken(TokenKind::RBrace.to_string(), self.current_token.kind.to_string()),
+                        self.span,
+                    ));
+                }
+
+                Pattern::StructPattern(StructPattern::new(name, fields, start.to(self.span)))
+            }
+            kind => return Err(ParsingError::new(ParsingErrorKind::ExpectedPattern(kind.to_string()), self.span)),
+        })
+    }
+
+    /// Parses a `while`, `for` or `loop` expression starting at the current token, after its label if it has one.
+    fn parse_loop_expression(&mut self, label: Option<Identifier>, start: Span) -> ParseResult<Expression> {
+        let keyword = self.current_token.kind.clone();
+        self.next_token();
+
+        Ok(match keyword {
+            TokenKind::While => {
+                let condition = self.parse_expression(&Priority::Lowest)?;
+                self.next_token();
+
+                let body = self.parse_block_expression()?;
+
+                Expression::WhileExpression(WhileExpression::new(label, Box::new(condition), body, start.to(self.span)))
+            }
+            TokenKind::For => {
+                let name = Identifier::new(identifier! { self }, self.span);
+                self.next_token();
+
+                self.expect_token(&TokenKind::In)?;
+
+                let iterable = self.parse_expression(&Priority::Lowest)?;
+                self.next_token();
+
+                let body = self.parse_block_expression()?;
+
+                Expression::ForExpression(ForExpression::new(label, name, Box::new(iterable), body, start.to(self.span)))
+            }
+            TokenKind::Loop => {
+                let body = self.parse_block_expression()?;
+
+                Expression::LoopExpression(LoopExpression::new(label, body, start.to(self.span)))
+            }
+            _ => {
+                return Err(ParsingError::new(
+                    ParsingErrorKind::ExpectedNextToken("loop".to_string(), keyword.to_string()),
+                    self.span,
+                ))
+            }
+        })
+    }
+
+    fn parse_break_expression(&mut self) -> ParseResult<BreakExpression> {
+        let start = self.span;
+        let label = self.parse_label();
+
+        let value = match self.peek_token.kind {
+            TokenKind::Semicolon | TokenKind::RBrace | TokenKind::RParen | TokenKind::RBracket | TokenKind::Comma | TokenKind::EOF => None,
+            _ => {
+                self.next_token();
+                Some(Box::new(self.parse_expression(&Priority::Lowest)?))
+            }
+        };
+
+        Ok(BreakExpression::new(label, value, start.to(self.span)))
+    }
+
+    /// Skips the label after a `break` or `continue`, if there is one.
+    fn parse_label(&mut self) -> Option<Identifier> {
+        match self.peek_token.kind.clone() {
+            TokenKind::Label(label) => {
+                self.next_token();
+                Some(Identifier::new(label, self.span))
+            }
+            _ => None,
+        }
     }
 
     fn parse_data_type(&mut self) -> ParseResult<DataType> {
-        let position = self.position;
+        let start = self.span;
 
         let result = self.parse_data_type_without_next();
+        let span = start.to(self.span);
         self.next_token();
 
-        result.map(|data_type| DataType::new(data_type, position))
+        result.map(|data_type| DataType::new(data_type, span))
     }
 
     fn parse_data_type_without_next(&mut self) -> ParseResult<DataTypeKind> {
+        self.nested(Self::parse_data_type_inner)
+    }
+
+    fn parse_data_type_inner(&mut self) -> ParseResult<DataTypeKind> {
+        let start = self.span;
         let mut data_type = match self.current_token.kind {
-            TokenKind::NumberType => Ok(DataTypeKind::Number),
+            TokenKind::IntType => Ok(DataTypeKind::Int),
+            TokenKind::FloatType => Ok(DataTypeKind::Float),
             TokenKind::StringType => Ok(DataTypeKind::String),
             TokenKind::BooleanType => Ok(DataTypeKind::Boolean),
+            TokenKind::VoidType => Ok(DataTypeKind::Void),
             TokenKind::Function => Ok(DataTypeKind::Fn(self.parse_function_type()?)),
             TokenKind::IDENT(ref ident) => Ok(DataTypeKind::Custom(ident.clone())),
             _ => Err(ParsingError::new(
-                ParsingErrorKind::ExpectedNextToken(TokenKind::NumberType.to_string(), self.current_token.kind.to_string()),
-                self.position,
+                ParsingErrorKind::ExpectedDataType(self.current_token.kind.to_string()),
+                self.span,
             )),
         };
 
@@ -700,17 +1397,20 @@ impl Parser {
         }
 
         while self.peek_token(&TokenKind::LBracket) {
+            self.deeper()?;
+            let element = start.to(self.span);
+
             self.next_token();
             self.next_token();
 
             if self.current_token.kind != TokenKind::RBracket {
                 return Err(ParsingError::new(
                     ParsingErrorKind::ExpectedNextToken(TokenKind::RBracket.to_string(), self.current_token.kind.to_string()),
-                    self.position,
+                    self.span,
                 ));
             }
 
-            data_type = data_type.map(|t| DataTypeKind::Array(Box::new(DataType::new(t, self.position))));
+            data_type = data_type.map(|t| DataTypeKind::Array(Box::new(DataType::new(t, element))));
         }
 
         data_type
@@ -752,13 +1452,14 @@ impl Parser {
         self.expect_token(&TokenKind::RParen)?;
         self.expect_token(&TokenKind::Arrow)?;
 
+        let start = self.span;
         let return_type = self.parse_data_type_without_next()?;
 
-        Ok(FunctionType::new(generics, parameters, DataType::new(return_type, self.position)))
+        Ok(FunctionType::new(generics, parameters, DataType::new(return_type, start.to(self.span))))
     }
 
     fn parse_generic(&mut self) -> ParseResult<Generic> {
-        let ident = identifier! { self };
+        let (ident, span) = (identifier! { self }, self.span);
         self.next_token();
 
         let mut generics = Vec::new();
@@ -767,6 +1468,7 @@ impl Parser {
 
         while self.current_token.kind != TokenKind::GT {
             let data_type = self.parse_data_type()?;
+            self.split_right_shift();
 
             generics.push(data_type);
 
@@ -777,7 +1479,7 @@ impl Parser {
             self.expect_token(&TokenKind::Comma)?;
         }
 
-        Ok(Generic::new(DataType::new(DataTypeKind::Custom(ident), self.position), generics))
+        Ok(Generic::new(DataType::new(DataTypeKind::Custom(ident), span), generics))
     }
 
     fn parse_generic_identifier(&mut self) -> ParseResult<IdentifierGeneric> {
@@ -786,11 +1488,9 @@ impl Parser {
         self.expect_token(&TokenKind::LT)?;
 
         while self.current_token.kind != TokenKind::GT {
-            let ident = identifier! { self };
+            generics.push(Identifier::new(identifier! { self }, self.span));
             self.next_token();
 
-            generics.push(Identifier::new(ident, self.position));
-
             if self.current_token.kind == TokenKind::GT {
                 break;
             }
@@ -803,9 +1503,9 @@ impl Parser {
 
     fn eval_expression(&mut self, expression: &Expression) -> Option<ParseResult<Expression>> {
         match expression {
-            Expression::InfixExpression(infix) => self.eval_infix_expression(infix),
-            Expression::PrefixExpression(prefix) => self.eval_prefix_expression(prefix),
-            e => Some(Ok(e.clone())),
+            // an operand was folded when it was parsed, so an operator left in it has no constant value
+            Expression::IntLiteral(_) | Expression::FloatLiteral(_) | Expression::BooleanLiteral(_) => Some(Ok(expression.clone())),
+            _ => None,
         }
     }
 
@@ -814,49 +1514,105 @@ impl Parser {
     }
 
     fn eval_infix_expression_opt_1(&mut self, infix: &InfixExpression) -> Option<ParseResult<Expression>> {
-        let InfixExpression { left, operator, right, .. } = infix;
+        let InfixExpression { left, operator, right, span } = infix;
 
-        macro_rules! f64_ops {
-            ($op:tt) => {{
-                if let (Some(Ok(left)), Some(Ok(right))) = (self.eval_expression(left), self.eval_expression(right)) {
-                    if let (Expression::NumberLiteral(left), Expression::NumberLiteral(right)) = (left, right) {
-                        return Some(Ok(Expression::NumberLiteral(NumberLiteral::new(
-                            left.value $op right.value,
-                            self.position,
-                        ))));
-                    }
+        let (Some(Ok(left)), Some(Ok(right))) = (self.eval_expression(left), self.eval_expression(right)) else {
+            return None;
+        };
 
-                    return None;
+        macro_rules! i64_ops {
+            ($op:ident) => {
+                // overflows are left for the interpreter to report
+                match (&left, &right) {
+                    (Expression::IntLiteral(left), Expression::IntLiteral(right)) => left
+                        .value
+                        .$op(right.value)
+                        .map(|value| Ok(Expression::IntLiteral(IntLiteral::new(value, *span)))),
+                    _ => f64_ops! { $op },
                 }
+            };
+        }
 
-                return None;
-            }}
+        macro_rules! f64_ops {
+            ($op:ident) => {
+                match (&left, &right) {
+                    (Expression::FloatLiteral(left), Expression::FloatLiteral(right)) => Some(Ok(Expression::FloatLiteral(FloatLiteral::new(
+                        f64_ops!(@$op left.value, right.value),
+                        *span,
+                    )))),
+                    _ => None,
+                }
+            };
+            (@checked_add $left:expr, $right:expr) => { $left + $right };
+            (@checked_sub $left:expr, $right:expr) => { $left - $right };
+            (@checked_mul $left:expr, $right:expr) => { $left * $right };
+            (@checked_rem $left:expr, $right:expr) => { $left % $right };
         }
 
         match operator {
-            TokenKind::Plus => f64_ops! { + },
-            TokenKind::Minus => f64_ops! { - },
-            TokenKind::Asterisk => f64_ops! { * },
-            TokenKind::Slash => f64_ops! { / },
-            TokenKind::Percent => f64_ops! { % },
+            TokenKind::Plus => i64_ops! { checked_add },
+            TokenKind::Minus => i64_ops! { checked_sub },
+            TokenKind::Asterisk => i64_ops! { checked_mul },
+            TokenKind::Percent => i64_ops! { checked_rem },
+            TokenKind::DoubleAsterisk => match (&left, &right) {
+                (Expression::IntLiteral(left), Expression::IntLiteral(right)) => u32::try_from(right.value)
+                    .ok()
+                    .and_then(|right| left.value.checked_pow(right))
+                    .map(|value| Ok(Expression::IntLiteral(IntLiteral::new(value, *span)))),
+                (Expression::FloatLiteral(left), Expression::FloatLiteral(right)) => {
+                    Some(Ok(Expression::FloatLiteral(FloatLiteral::new(left.value.powf(right.value), *span))))
+                }
+                _ => None,
+            },
+            TokenKind::Ampersand | TokenKind::Pipe | TokenKind::Caret | TokenKind::LShift | TokenKind::RShift => match (&left, &right) {
+                (Expression::IntLiteral(IntLiteral { value: left, .. }), Expression::IntLiteral(IntLiteral { value: right, .. })) => match operator {
+                    TokenKind::Ampersand => Some(left & right),
+                    TokenKind::Pipe => Some(left | right),
+                    TokenKind::Caret => Some(left ^ right),
+                    TokenKind::LShift => u32::try_from(*right).ok().and_then(|right| left.checked_shl(right)),
+                    _ => u32::try_from(*right).ok().and_then(|right| left.checked_shr(right)),
+                }
+                .map(|value| Ok(Expression::IntLiteral(IntLiteral::new(value, *span)))),
+                _ => None,
+            },
+            TokenKind::And | TokenKind::Or => match (&left, &right) {
+                (Expression::BooleanLiteral(left), Expression::BooleanLiteral(right)) => Some(Ok(Expression::BooleanLiteral(BooleanLiteral::new(
+                    match operator {
+                        TokenKind::And => left.value && right.value,
+                        _ => left.value || right.value,
+                    },
+                    *span,
+                )))),
+                _ => None,
+            },
+            // dividing ints gives a float
+            TokenKind::Slash => match (&left, &right) {
+                (Expression::IntLiteral(left), Expression::IntLiteral(right)) => Some(Ok(Expression::FloatLiteral(FloatLiteral::new(
+                    left.value as f64 / right.value as f64,
+                    *span,
+                )))),
+                (Expression::FloatLiteral(left), Expression::FloatLiteral(right)) => {
+                    Some(Ok(Expression::FloatLiteral(FloatLiteral::new(left.value / right.value, *span))))
+                }
+                _ => None,
+            },
             _ => None,
         }
     }
 
     fn eval_infix_expression_opt_2(&mut self, infix: &InfixExpression) -> Option<ParseResult<Expression>> {
-        let InfixExpression { left, operator, right, .. } = infix;
+        let InfixExpression { left, operator, right, span } = infix;
 
-        macro_rules! f64_ops {
+        macro_rules! compare_ops {
             ($op:tt) => {{
                 if let (Some(Ok(left)), Some(Ok(right))) = (self.eval_expression(left), self.eval_expression(right)) {
-                    if let (Expression::NumberLiteral(left), Expression::NumberLiteral(right)) = (left, right) {
-                        return Some(Ok(Expression::BooleanLiteral(BooleanLiteral::new(
-                            left.value $op right.value,
-                            self.position,
-                        ))));
-                    }
+                    let value = match (left, right) {
+                        (Expression::IntLiteral(left), Expression::IntLiteral(right)) => left.value $op right.value,
+                        (Expression::FloatLiteral(left), Expression::FloatLiteral(right)) => left.value $op right.value,
+                        _ => return None,
+                    };
 
-                    return None;
+                    return Some(Ok(Expression::BooleanLiteral(BooleanLiteral::new(value, *span))));
                 }
 
                 return None;
@@ -864,30 +1620,35 @@ impl Parser {
         }
 
         match operator {
-            TokenKind::EQ => Some(Ok(Expression::BooleanLiteral(BooleanLiteral::new(left == right, self.position)))),
-            TokenKind::NEQ => f64_ops! { != },
-            TokenKind::GT => f64_ops! { > },
-            TokenKind::LT => f64_ops! { < },
-            TokenKind::GTE => f64_ops! { >= },
-            TokenKind::LTE => f64_ops! { <= },
+            TokenKind::EQ => compare_ops! { == },
+            TokenKind::NEQ => compare_ops! { != },
+            TokenKind::GT => compare_ops! { > },
+            TokenKind::LT => compare_ops! { < },
+            TokenKind::GTE => compare_ops! { >= },
+            TokenKind::LTE => compare_ops! { <= },
             _ => self.eval_infix_expression_opt_1(infix),
         }
     }
 
     fn eval_prefix_expression(&mut self, prefix: &PrefixExpression) -> Option<ParseResult<Expression>> {
-        let PrefixExpression { operator, right, position } = prefix;
+        let PrefixExpression { operator, right, span } = prefix;
 
         match operator {
-            TokenKind::Minus => {
-                if let Expression::NumberLiteral(right) = *right.clone() {
-                    return Some(Ok(Expression::NumberLiteral(NumberLiteral::new(-right.value, *position))));
-                }
-
-                None
-            }
+            TokenKind::Minus => match *right.clone() {
+                Expression::IntLiteral(right) => right
+                    .value
+                    .checked_neg()
+                    .map(|value| Ok(Expression::IntLiteral(IntLiteral::new(value, *span)))),
+                Expression::FloatLiteral(right) => Some(Ok(Expression::FloatLiteral(FloatLiteral::new(-right.value, *span)))),
+                _ => None,
+            },
+            TokenKind::Tilde => match *right.clone() {
+                Expression::IntLiteral(right) => Some(Ok(Expression::IntLiteral(IntLiteral::new(!right.value, *span)))),
+                _ => None,
+            },
             TokenKind::Bang => {
                 if let Expression::BooleanLiteral(right) = *right.clone() {
-                    return Some(Ok(Expression::BooleanLiteral(BooleanLiteral::new(!right.value, *position))));
+                    return Some(Ok(Expression::BooleanLiteral(BooleanLiteral::new(!right.value, *span))));
                 }
 
                 None
@@ -896,3 +1657,368 @@ impl Parser {
         }
     }
 }
+
+/// Whether `expression` can be assigned to: a variable, or an element or field of one.
+fn is_place(expression: &Expression) -> bool {
+    match expression {
+        Expression::Identifier(_) => true,
+        Expression::IndexExpression(IndexExpression { left, .. }) | Expression::FieldExpression(FieldExpression { left, .. }) => is_place(left),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Parser, MAX_NESTING_DEPTH};
+    use crate::parser::ast::{
+        ArrayPattern, AutoStatement, BlockExpression, ContinueExpression, EnumLiteral, EnumStatement, Expression, ExpressionStatement, ForExpression,
+        ImportStatement, IntLiteral, LetStatement, LiteralPattern, MatchArm, MatchExpression, Pattern, ReturnStatement, SpanlessEq, Statement,
+        StructPattern, TryExpression,
+    };
+
+    fn parse(source: &str) -> Vec<Statement> {
+        let program = Parser::from(source.to_string()).parse_program();
+        assert!(program.errors.is_empty(), "{:?}", program.errors);
+
+        program.statements
+    }
+
+    #[test]
+    fn expression_span_test() {
+        let source = "let x: int = foo(1, bar[2]) + baz.qux;";
+
+        let value = match &parse(source)[0] {
+            Statement::LetStatement(statement) => {
+                assert_eq!(&source[statement.span.start..statement.span.end], source);
+                statement.value.clone()
+            }
+            statement => panic!("expected a let statement, got {statement:?}"),
+        };
+
+        let (left, right) = match &value {
+            Expression::InfixExpression(infix) => (infix.left.span(), infix.right.span()),
+            expression => panic!("expected an infix expression, got {expression:?}"),
+        };
+
+        assert_eq!(&source[value.span().start..value.span().end], "foo(1, bar[2]) + baz.qux");
+        assert_eq!(&source[left.start..left.end], "foo(1, bar[2])");
+        assert_eq!(&source[right.start..right.end], "baz.qux");
+    }
+
+    #[test]
+    fn spanless_eq_test() {
+        let left = parse("auto x = a + b * c(d);");
+        let right = parse("auto   x=a+(b*c( d ));");
+
+        assert_ne!(left, right);
+        assert!(left.spanless_eq(&right));
+        assert!(!left.spanless_eq(&parse("auto x = (a + b) * c(d);")));
+    }
+
+    #[test]
+    fn precedence_test() {
+        let same = |left: &str, right: &str| assert!(parse(left).spanless_eq(&parse(right)), "{left} != {right}");
+
+        same("a || b && c == d;", "a || (b && (c == d));");
+        same("a < b | c ^ d & e << f + g;", "a < (b | (c ^ (d & (e << (f + g)))));");
+        same("a % b * c;", "(a % b) * c;");
+        same("-a ** b ** c * d;", "(-(a ** (b ** c))) * d;");
+        same("~a >> b;", "(~a) >> b;");
+        same("auto x = 2 ** 3 ** 2 + (6 & 3 | 1 << 4) ^ 1;", "auto x = 531;");
+        same("auto x = true && false || !false;", "auto x = true;");
+
+        parse("type Nested = Box<Box<int>>; struct Pair<T, U> { left: Box<T>, right: Box<Box<U>> }");
+    }
+
+    #[test]
+    fn assignment_test() {
+        let same = |left: &str, right: &str| assert!(parse(left).spanless_eq(&parse(right)), "{left} != {right}");
+
+        same("x = y += a || b;", "x = (y += (a || b));");
+        same("xs[i].y **= 2;", "(xs[i]).y **= 2;");
+
+        match &parse("let mut x: int = 1; auto y = 2;")[..] {
+            [Statement::LetStatement(LetStatement { mutable: true, .. }), Statement::AutoStatement(AutoStatement { mutable: false, .. })] => {}
+            statements => panic!("expected a mutable let and an immutable auto statement, got {statements:?}"),
+        }
+
+        for source in ["1 = 2;", "f() = 1;", "a + b = c;", "f()[0] = 1;"] {
+            let program = Parser::from(source.to_string()).parse_program();
+            assert!(program.errors.iter().any(|error| error.message.code() == "E0010"), "{source}");
+        }
+    }
+
+    #[test]
+    fn loop_test() {
+        let same = |left: &str, right: &str| assert!(parse(left).spanless_eq(&parse(right)), "{left} != {right}");
+
+        same("while i < n && go { i += 1; };", "while (i < n) && go { i += 1; };");
+        same("auto x = loop { break a + 1; };", "auto x = loop { break (a + 1); };");
+
+        match &parse("'outer: for x in xs { continue 'outer; };")[..] {
+            [Statement::ExpressionStatement(ExpressionStatement {
+                expression:
+                    Expression::ForExpression(ForExpression {
+                        label: Some(label),
+                        name,
+                        body,
+                        ..
+                    }),
+                ..
+            })] => {
+                assert_eq!((label.value.as_str(), name.value.as_str()), ("outer", "x"));
+                assert!(matches!(
+                    &body.statements[..],
+                    [Statement::ExpressionStatement(ExpressionStatement {
+                        expression: Expression::ContinueExpression(ContinueExpression { label: Some(_), .. }),
+                        ..
+                    })]
+                ));
+            }
+            statements => panic!("expected a labeled `for` loop, got {statements:?}"),
+        }
+
+        for source in ["'outer: if x {};", "for 1 in xs {};", "for x xs {};"] {
+            assert!(!Parser::from(source.to_string()).parse_program().errors.is_empty(), "{source}");
+        }
+    }
+
+    #[test]
+    fn return_test() {
+        let body = |source: &str| match &parse(source)[..] {
+            [Statement::AutoStatement(AutoStatement {
+                value: Expression::FunctionLiteral(function),
+                ..
+            })] => function.body.statements.clone(),
+            statements => panic!("expected a function, got {statements:?}"),
+        };
+
+        assert!(matches!(
+            &body("auto f = fn() -> void { return; };")[..],
+            [Statement::ReturnStatement(ReturnStatement { value: None, .. })]
+        ));
+        assert!(matches!(
+            &body("auto f = fn() -> int { return 1; };")[..],
+            [Statement::ReturnStatement(ReturnStatement { value: Some(_), .. })]
+        ));
+
+        assert!(!Parser::from("auto f = fn() -> int { return 1 };".to_string())
+            .parse_program()
+            .errors
+            .is_empty());
+    }
+
+    #[test]
+    fn block_value_test() {
+        match &parse("auto x = { f(); y };")[..] {
+            [Statement::AutoStatement(AutoStatement {
+                value: Expression::BlockExpression(BlockExpression { statements, .. }),
+                ..
+            })] => assert!(matches!(
+                &statements[..],
+                [
+                    Statement::ExpressionStatement(ExpressionStatement { semicolon: true, .. }),
+                    Statement::ExpressionStatement(ExpressionStatement { semicolon: false, .. })
+                ]
+            )),
+            statements => panic!("expected a block, got {statements:?}"),
+        }
+
+        // only the last expression of a block can leave out its `;`
+        for source in ["auto x = { y z };", "f()"] {
+            assert!(!Parser::from(source.to_string()).parse_program().errors.is_empty(), "{source}");
+        }
+    }
+
+    #[test]
+    fn enum_test() {
+        let same = |left: &str, right: &str| assert!(parse(left).spanless_eq(&parse(right)), "{left} != {right}");
+
+        same("auto x = Shape::Rect(1.0, a + b).width;", "auto x = (Shape::Rect(1.0, (a + b))).width;");
+        same("auto x = Shape::Empty == y;", "auto x = (Shape::Empty) == y;");
+
+        match &parse("enum Option<T> { Some(T), None }; auto x = Option::Some(1);")[..] {
+            [Statement::EnumStatement(EnumStatement {
+                name, generics, variants, ..
+            }), Statement::AutoStatement(AutoStatement {
+                value: Expression::EnumLiteral(EnumLiteral { variant, arguments, .. }),
+                ..
+            })] => {
+                assert_eq!((name.value.as_str(), generics.len()), ("Option", 1));
+                assert_eq!(
+                    variants
+                        .iter()
+                        .map(|(variant, payload)| (variant.value.as_str(), payload.len()))
+                        .collect::<Vec<_>>(),
+                    vec![("Some", 1), ("None", 0)]
+                );
+                assert_eq!((variant.value.as_str(), arguments.len()), ("Some", 1));
+            }
+            statements => panic!("expected an enum and a constructor, got {statements:?}"),
+        }
+
+        for source in ["enum E { A(int };", "enum E { A B };", "auto x = E::;"] {
+            assert!(!Parser::from(source.to_string()).parse_program().errors.is_empty(), "{source}");
+        }
+    }
+
+    #[test]
+    fn match_test() {
+        let source = "auto x = match y { Shape::Rect(w, _) if w > 0 => w, [first, ...rest] => { first }\n struct P { x, y: -1 } => x, _ => 0 };";
+
+        match &parse(source)[..] {
+            [Statement::AutoStatement(AutoStatement {
+                value: Expression::MatchExpression(MatchExpression { arms, .. }),
+                ..
+            })] => {
+                assert!(matches!(
+                    &arms[..],
+                    [
+                        MatchArm {
+                            pattern: Pattern::EnumPattern(_),
+                            guard: Some(_),
+                            ..
+                        },
+                        MatchArm {
+                            pattern: Pattern::ArrayPattern(ArrayPattern { rest: Some(_), .. }),
+                            body: Expression::BlockExpression(_),
+                            ..
+                        },
+                        MatchArm {
+                            pattern: Pattern::StructPattern(_),
+                            guard: None,
+                            ..
+                        },
+                        MatchArm {
+                            pattern: Pattern::WildcardPattern(_),
+                            ..
+                        }
+                    ]
+                ));
+
+                match &arms[2].pattern {
+                    Pattern::StructPattern(StructPattern { fields, .. }) => assert!(matches!(
+                        &fields[..],
+                        [
+                            (_, Pattern::BindingPattern(_)),
+                            (
+                                _,
+                                Pattern::LiteralPattern(LiteralPattern {
+                                    value: Expression::IntLiteral(IntLiteral { value: -1, .. }),
+                                    ..
+                                })
+                            )
+                        ]
+                    )),
+                    pattern => panic!("expected a struct pattern, got {pattern:?}"),
+                }
+            }
+            statements => panic!("expected a match, got {statements:?}"),
+        }
+
+        for source in [
+            "match x { 1 => a b => c };",
+            "match x { [...rest, y] => y };",
+            "match x { a + 1 => a };",
+            "match x { _ -> a };",
+        ] {
+            assert!(!Parser::from(source.to_string()).parse_program().errors.is_empty(), "{source}");
+        }
+    }
+
+    #[test]
+    fn try_test() {
+        let same = |left: &str, right: &str| assert!(parse(left).spanless_eq(&parse(right)), "{left} != {right}");
+
+        same("auto x = -f(a)?.b? + c[0]?;", "auto x = (-(((f(a))?).b)?) + ((c[0])?);");
+
+        match &parse("auto x = parse(s)?;")[..] {
+            [Statement::AutoStatement(AutoStatement {
+                value: Expression::TryExpression(TryExpression { value, .. }),
+                ..
+            })] => assert!(matches!(**value, Expression::CallExpression(_))),
+            statements => panic!("expected a `?` expression, got {statements:?}"),
+        }
+
+        assert!(!Parser::from("auto x = ?a;".to_string()).parse_program().errors.is_empty());
+    }
+
+    #[test]
+    fn module_test() {
+        let source = "import { a, Point } from \"./util.sntk\";\nexport struct Line { from: Point, to: Point }\nexport auto from = 1;";
+
+        match &parse(source)[..] {
+            [Statement::ImportStatement(ImportStatement { names, path, .. }), Statement::StructStatement(line), Statement::AutoStatement(from)] => {
+                assert_eq!(names.iter().map(|name| name.value.as_str()).collect::<Vec<_>>(), vec!["a", "Point"]);
+                assert_eq!(path.value, "./util.sntk");
+                assert!(line.exported && from.exported);
+                assert_eq!(&source[line.span.start..line.span.start + 6], "export");
+            }
+            statements => panic!("expected an import and two exports, got {statements:?}"),
+        }
+
+        assert!(!Parser::from("export 1;".to_string()).parse_program().errors.is_empty());
+        assert!(!Parser::from("import { a } \"./util.sntk\";".to_string())
+            .parse_program()
+            .errors
+            .is_empty());
+    }
+
+    #[test]
+    fn nesting_test() {
+        let nested = |depth: usize| format!("auto x = {}1{};\nauto y = 2;", "(".repeat(depth), ")".repeat(depth));
+        let chain = |length: usize| format!("auto x = 1{};\nauto y = 2;", " + 1".repeat(length));
+
+        // the value of `auto` is one level deep, and each parenthesis or operator one more
+        assert!(Parser::from(nested(MAX_NESTING_DEPTH - 1)).parse_program().errors.is_empty());
+        assert!(Parser::from(chain(MAX_NESTING_DEPTH - 1)).parse_program().errors.is_empty());
+
+        for source in [nested(MAX_NESTING_DEPTH), nested(100_000), chain(MAX_NESTING_DEPTH), chain(1_000_000)] {
+            let program = Parser::from(source).parse_program();
+
+            assert_eq!(program.errors[0].message.code(), "E0012");
+            assert!(matches!(program.statements.last(), Some(Statement::AutoStatement(_))));
+        }
+
+        let pattern = format!("match x {{\n    {}_{} => 1,\n}};", "[".repeat(1000), "]".repeat(1000));
+        let data_type = format!("let x: {}int{} = 1;", "Option<".repeat(1000), ">".repeat(1000));
+        let array_type = format!("let x: int{} = 1;", "[]".repeat(1000));
+        let calls = format!("auto x = f{};", "()".repeat(1000));
+        let else_if = format!("auto x = if a {{ 1 }}{} else {{ 2 }};", " else if a { 1 }".repeat(1000));
+
+        for source in [pattern, data_type, array_type, calls, else_if] {
+            assert_eq!(Parser::from(source).parse_program().errors[0].message.code(), "E0012");
+        }
+    }
+
+    #[test]
+    fn error_recovery_test() {
+        let source = "let x = 1;\nauto y = fn() -> int {\n    let z: int = ;\n    return 1;\n};\nauto w = (1 + ;\nauto v = 2;";
+        let program = Parser::from(source.to_string()).parse_program();
+
+        // one error for each broken statement, none for the statements following them
+        assert_eq!(program.errors.len(), 3, "{:?}", program.errors);
+        assert_eq!(program.statements.len(), 4);
+
+        assert!(matches!(program.statements[0], Statement::ErrorStatement(_)));
+        assert!(matches!(program.statements[2], Statement::ErrorStatement(_)));
+        assert!(parse("auto v = 2;")[0].spanless_eq(&program.statements[3]));
+
+        match &program.statements[1] {
+            Statement::AutoStatement(AutoStatement {
+                value: Expression::FunctionLiteral(function),
+                ..
+            }) => {
+                assert!(matches!(function.body.statements[0], Statement::ErrorStatement(_)));
+                assert!(matches!(function.body.statements[1], Statement::ReturnStatement(_)));
+            }
+            statement => panic!("expected an auto statement, got {statement:?}"),
+        }
+
+        // a string left open is the only error of its statement, and the next line is parsed as it is
+        let program = Parser::from("auto x = \"abc;\nauto y = 1;".to_string()).parse_program();
+
+        assert_eq!(program.errors.iter().map(|error| error.message.code()).collect::<Vec<_>>(), ["E0005"]);
+        assert!(parse("auto y = 1;")[0].spanless_eq(&program.statements[1]));
+    }
+}
@@ -1,15 +1,21 @@
 use crate::{
-    parser::ast::Position,
+    parser::{
+        ast::{FileId, Span},
+        ParsingError, ParsingErrorKind,
+    },
     tokenizer::token::{Token, TokenKind},
 };
+use unicode_ident::{is_xid_continue, is_xid_start};
 
+/// Scans tokens in a single pass over the UTF-8 input; positions are byte offsets.
 #[derive(Debug, Default)]
 pub struct Lexer {
     pub input: String,
-    pub position: usize,
-    pub read_position: usize,
+    pub file: FileId,
+    pub position: usize,      // byte offset of `current_char`
+    pub read_position: usize, // byte offset of the char after `current_char`
     pub current_char: char,
-    pub current_position: Position,
+    pub errors: Vec<ParsingError>,
 }
 
 impl Lexer {
@@ -22,116 +28,392 @@ impl Lexer {
     }
 
     pub fn read_char(&mut self) {
-        if self.read_position >= self.input.len() {
-            self.current_char = '\0';
-        } else {
-            self.current_char = self.input.chars().nth(self.read_position).unwrap();
-        }
-
         self.position = self.read_position;
-        self.read_position += 1;
 
-        self.current_position.1 += 1;
+        match self.input[self.read_position..].chars().next() {
+            Some(char) => {
+                self.current_char = char;
+                self.read_position += char.len_utf8();
+            }
+            None => self.current_char = '\0',
+        }
     }
 
+    #[inline]
     pub fn peek_char(&self) -> char {
-        if self.read_position >= self.input.len() {
-            '\0'
-        } else {
-            self.input.chars().nth(self.read_position).unwrap()
-        }
+        self.peek_nth_char(0)
+    }
+
+    pub fn peek_nth_char(&self, n: usize) -> char {
+        self.input[self.read_position..].chars().nth(n).unwrap_or('\0')
+    }
+
+    /// Goes back or ahead to the char at `position`, to read on from there.
+    fn seek(&mut self, position: usize) {
+        self.read_position = position;
+        self.read_char();
+    }
+
+    #[inline]
+    fn is_eof(&self) -> bool {
+        self.position >= self.input.len()
+    }
+
+    fn error(&mut self, message: ParsingErrorKind, start: usize, end: usize) {
+        self.errors.push(ParsingError::new(message, Span::new(self.file, start, end)));
     }
 
     pub fn skip_whitespace(&mut self) {
         while self.current_char.is_whitespace() {
-            if self.current_char == '\n' {
-                self.current_position.0 += 1;
-                self.current_position.1 = 0;
-            }
-
             self.read_char();
         }
     }
 
+    /// Identifiers follow UAX #31, with `_` allowed to start them.
     pub fn read_identifier(&mut self) -> String {
         let position = self.position;
-        while self.current_char.is_alphanumeric() || self.current_char == '_' {
+        while is_xid_continue(self.current_char) {
             self.read_char();
         }
 
-        let result = self.input[position..self.position].to_string();
+        self.input[position..self.position].to_string()
+    }
+
+    /// Reads an `int`, in decimal or with a `0x`, `0o` or `0b` prefix, or a decimal `float` with a fraction or an exponent;
+    /// `_` separates two digits.
+    pub fn read_number(&mut self) -> TokenKind {
+        let start = self.position;
+
+        let radix = match (self.current_char, self.peek_char()) {
+            ('0', 'x') => 16,
+            ('0', 'o') => 8,
+            ('0', 'b') => 2,
+            _ => 10,
+        };
+
+        if radix != 10 {
+            self.read_char();
+            self.read_char();
+            self.read_digits(radix);
+        } else {
+            self.read_digits(10);
+
+            if self.current_char == '.' && self.peek_char().is_ascii_digit() {
+                self.read_char();
+                self.read_digits(10);
+            }
+
+            if matches!(self.current_char, 'e' | 'E')
+                && (self.peek_char().is_ascii_digit() || matches!(self.peek_char(), '+' | '-') && self.peek_nth_char(1).is_ascii_digit())
+            {
+                self.read_char();
+                self.read_char();
+                self.read_digits(10);
+            }
+        }
 
-        macro_rules! replace_all {
-            ($s:expr, $($t:expr => $r:expr),*) => {{
-                let mut s = String::from($s);
-                $( s = s.replace($t, $r); )*
-                s
-            }};
+        // letters, digits or another fraction right after the literal, as in `0xZZ`, `12ab` or `1.2.3`, belong to it, and so
+        // does the sign of an exponent without digits, as in `1e+`
+        let end = self.position;
+        while is_xid_continue(self.current_char)
+            || self.current_char == '.' && self.peek_char().is_ascii_digit()
+            || radix == 10 && matches!(self.current_char, '+' | '-') && self.position > end && self.input[..self.position].ends_with(['e', 'E'])
+        {
+            self.read_char();
         }
 
-        replace_all! {
-            result,
-            "\\r" => "\r",
-            "\\t" => "\t",
-            "\\n" => "\n",
-            "\\\"" => "\"",
-            "\\\\" => "\\"
+        let literal = self.input[start..self.position].to_string();
+        let digits = self.input[start..end].replace('_', "");
+
+        if self.position != end || radix != 10 && digits.len() == 2 {
+            self.error(ParsingErrorKind::MalformedNumber(literal), start, self.position);
+            TokenKind::Int(0)
+        } else if radix == 10 && digits.contains(['.', 'e', 'E']) {
+            match digits.parse::<f64>() {
+                Ok(float) if float.is_finite() => TokenKind::Float(float),
+                _ => {
+                    self.error(ParsingErrorKind::FloatTooLarge(literal), start, self.position);
+                    TokenKind::Float(0.0)
+                }
+            }
+        } else {
+            match i64::from_str_radix(&digits[if radix == 10 { 0 } else { 2 }..], radix) {
+                Ok(int) => TokenKind::Int(int),
+                Err(_) => {
+                    self.error(ParsingErrorKind::IntegerTooLarge(literal), start, self.position);
+                    TokenKind::Int(0)
+                }
+            }
         }
     }
 
-    pub fn read_number(&mut self) -> f64 {
-        let position = self.position;
-        let mut has_dot = false;
+    /// Reads the digits in `radix`, and each `_` between two of them; any other `_` is left to make the literal malformed.
+    fn read_digits(&mut self, radix: u32) {
+        let mut digits = false;
 
-        while self.current_char.is_numeric() || self.current_char == '.' {
-            if self.current_char == '.' {
-                if has_dot {
-                    break;
+        while self.current_char.is_digit(radix) || digits && self.current_char == '_' && self.peek_char().is_digit(radix) {
+            digits = true;
+            self.read_char();
+        }
+    }
+
+    /// Reads a string literal from its opening quote, decoding escape sequences. One that is never closed only runs to the end of
+    /// its line, so that the lines after it are read as they would be without it.
+    pub fn read_string(&mut self) -> String {
+        let start = self.position;
+
+        if self.peek_char() == '"' && self.peek_nth_char(1) == '"' {
+            return self.read_multiline_string();
+        }
+
+        self.read_char();
+
+        let mut value = String::new();
+        let mut line_end = None; // where the first line of the string ends, and its value there
+
+        loop {
+            match self.current_char {
+                '"' => {
+                    self.read_char();
+
+                    return value;
                 }
+                _ if self.is_eof() => {
+                    self.error(ParsingErrorKind::UnterminatedString, start, start + 1);
+
+                    if let Some((position, length)) = line_end {
+                        value.truncate(length);
+                        self.seek(position);
+                    }
 
-                has_dot = true;
+                    return value;
+                }
+                '\n' if line_end.is_none() => {
+                    line_end = Some((self.position, value.len()));
+                    value.push('\n');
+                    self.read_char();
+                }
+                '\\' => value.extend(self.read_escape()),
+                char => {
+                    value.push(char);
+                    self.read_char();
+                }
             }
+        }
+    }
 
+    /// Reads a `"""` string, dropping its first and last lines if they are blank and the indentation common to the others, which
+    /// is what their indentations start with, so that a tab is never taken for some number of spaces.
+    pub fn read_multiline_string(&mut self) -> String {
+        let start = self.position;
+
+        for _ in 0..3 {
             self.read_char();
         }
 
-        self.input[position..self.position].parse().unwrap_or(0.)
+        let mut lines = Vec::new();
+        let (mut indentation, mut content) = (String::new(), String::new());
+
+        loop {
+            match self.current_char {
+                '"' if self.peek_char() == '"' && self.peek_nth_char(1) == '"' => {
+                    for _ in 0..3 {
+                        self.read_char();
+                    }
+
+                    break;
+                }
+                _ if self.is_eof() => {
+                    self.error(ParsingErrorKind::UnterminatedString, start, start + 3);
+
+                    break;
+                }
+                '\n' => {
+                    lines.push((std::mem::take(&mut indentation), std::mem::take(&mut content)));
+                    self.read_char();
+                }
+                '\r' if self.peek_char() == '\n' => self.read_char(),
+                char if content.is_empty() && char.is_whitespace() => {
+                    indentation.push(char);
+                    self.read_char();
+                }
+                '\\' => content.extend(self.read_escape()),
+                char => {
+                    content.push(char);
+                    self.read_char();
+                }
+            }
+        }
+
+        lines.push((indentation, content));
+
+        if lines.first().is_some_and(|(_, content)| content.is_empty()) {
+            lines.remove(0);
+        }
+
+        if lines.last().is_some_and(|(_, content)| content.is_empty()) {
+            lines.pop();
+        }
+
+        let common = lines
+            .iter()
+            .filter(|(_, content)| !content.is_empty())
+            .map(|(indentation, _)| indentation.as_str())
+            .reduce(|common, indentation| {
+                let length = common
+                    .chars()
+                    .zip(indentation.chars())
+                    .take_while(|(common, char)| common == char)
+                    .map(|(char, _)| char.len_utf8())
+                    .sum();
+
+                &common[..length]
+            })
+            .unwrap_or_default();
+
+        lines
+            .iter()
+            .map(|(indentation, content)| match content.is_empty() {
+                true => String::new(),
+                false => format!("{}{content}", &indentation[common.len()..]),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 
-    pub fn read_string(&mut self) -> String {
-        let position = self.position + 1;
-        while self.peek_char() != '"' && self.current_char != '\0' {
+    /// Reads a `r"..."` string, kept as written; any number of `#` around the quotes lets it contain `"`, as in `r#"say "hi""#`.
+    /// Returns `None` if the hashes are not followed by a quote.
+    pub fn read_raw_string(&mut self) -> Option<String> {
+        let start = self.position;
+        let mut hashes = 0;
+
+        self.read_char();
+
+        while self.current_char == '#' {
+            hashes += 1;
             self.read_char();
         }
 
+        if self.current_char != '"' {
+            return None;
+        }
+
         self.read_char();
-        self.input[position..self.position].to_string()
-    }
 
-    pub fn read_comment(&mut self) {
-        if self.current_char == '/' && self.peek_char() == '*' {
+        let content = self.position;
+
+        loop {
+            if self.current_char == '"' && (0..hashes).all(|n| self.peek_nth_char(n) == '#') {
+                let value = self.input[content..self.position].to_string();
+
+                for _ in 0..=hashes {
+                    self.read_char();
+                }
+
+                return Some(value);
+            }
+
+            if self.is_eof() {
+                self.error(ParsingErrorKind::UnterminatedString, start, content);
+
+                // like any other string, it only runs to the end of its line
+                let end = self.input[content..].find('\n').map_or(self.input.len(), |index| content + index);
+                self.seek(end);
+
+                return Some(self.input[content..end].to_string());
+            }
+
             self.read_char();
+        }
+    }
+
+    /// Reads an escape sequence from its backslash, reporting unknown and malformed ones.
+    fn read_escape(&mut self) -> Option<char> {
+        let start = self.position;
+
+        self.read_char();
+
+        let char = match self.current_char {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            'u' => return self.read_unicode_escape(start),
+            // left for the string to report as unterminated
+            _ if self.is_eof() => return None,
+            char => {
+                self.read_char();
+                self.error(ParsingErrorKind::UnknownEscape(char.to_string()), start, self.position);
+
+                return None;
+            }
+        };
+
+        self.read_char();
+        Some(char)
+    }
+
+    /// Reads `\u{...}` from its `u`, with one to six hexadecimal digits naming a Unicode scalar value.
+    fn read_unicode_escape(&mut self, start: usize) -> Option<char> {
+        self.read_char();
+
+        let mut digits = String::new();
+        let opened = self.current_char == '{';
+
+        if opened {
             self.read_char();
 
-            while self.current_char != '\0' && (self.current_char != '*' || self.peek_char() != '/') {
+            while self.current_char.is_ascii_hexdigit() {
+                digits.push(self.current_char);
                 self.read_char();
             }
+        }
+
+        let closed = opened && self.current_char == '}';
 
+        if closed {
             self.read_char();
         }
+
+        let char = match closed && (1..=6).contains(&digits.len()) {
+            true => u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32),
+            false => None,
+        };
+
+        if char.is_none() {
+            let escape = self.input[start..self.position].to_string();
+            self.error(ParsingErrorKind::InvalidUnicodeEscape(escape), start, self.position);
+        }
+
+        char
     }
 
-    pub fn read_inline_comment(&mut self) {
-        if self.current_char == '/' && self.peek_char() == '/' {
-            self.read_char();
+    pub fn read_comment(&mut self) -> String {
+        let start = self.position;
+
+        self.read_char();
+        self.read_char();
+
+        while self.current_char != '\0' && (self.current_char != '*' || self.peek_char() != '/') {
             self.read_char();
+        }
 
-            while self.current_char != '\0' && self.current_char != '\n' {
-                self.read_char();
-            }
+        self.read_char();
+        self.read_char();
+
+        self.input[start..self.position].to_string()
+    }
 
-            self.skip_whitespace();
+    pub fn read_inline_comment(&mut self) -> String {
+        let start = self.position;
+
+        while self.current_char != '\0' && self.current_char != '\n' {
+            self.read_char();
         }
+
+        self.input[start..self.position].trim_end().to_string()
     }
 
     pub fn next_token(&mut self) -> Token {
@@ -139,13 +421,13 @@ impl Lexer {
 
         self.skip_whitespace();
 
+        let start = self.position;
+
         macro_rules! match_token {
             ($($token:expr => $token_type:expr),*) => {{
-                let position = self.current_position;
-
                 match self.current_char {
-                    $( $token => Token::new($token_type, position), )*
-                    token => Token::new(TokenKind::ILLEGAL(token.to_string()), position)
+                    $( $token => $token_type, )*
+                    token => TokenKind::ILLEGAL(token.to_string())
                 }
             }}
         }
@@ -169,14 +451,15 @@ impl Lexer {
             };
         }
 
-        let token = match_token! {
-            '+' => Plus,
-            '*' => Asterisk,
-            '%' => Percent,
+        let kind = match_token! {
+            '+' => next!('=' => PlusAssign; Plus),
+            '%' => next!('=' => PercentAssign; Percent),
+            '^' => next!('=' => CaretAssign; Caret),
+            '~' => Tilde,
             '.' => Dot,
             ',' => Comma,
             ';' => Semicolon,
-            ':' => Colon,
+            '?' => Question,
             '(' => LParen,
             ')' => RParen,
             '{' => LBrace,
@@ -184,42 +467,223 @@ impl Lexer {
             '[' => LBracket,
             ']' => RBracket,
 
-            '-' => next!('>' => Arrow; Minus),
+            ':' => next!(':' => DoubleColon; Colon),
+            '-' => next!('>' => Arrow; next!('=' => MinusAssign; Minus)),
+            '*' => next!('*' => next!('=' => DoubleAsteriskAssign; DoubleAsterisk); next!('=' => AsteriskAssign; Asterisk)),
+            '&' => next!('&' => And; next!('=' => AmpersandAssign; Ampersand)),
+            '|' => next!('|' => Or; next!('=' => PipeAssign; Pipe)),
 
             '=' => next!('=' => EQ; next!('>' => DoubleArrow; Assign)),
             '!' => next!('=' => NEQ; Bang),
-            '<' => next!('=' => LTE; LT),
-            '>' => next!('=' => GTE; GT),
-
-            '"' => String(self.read_string()),
+            '<' => next!('=' => LTE; next!('<' => next!('=' => LShiftAssign; LShift); LT)),
+            '>' => next!('=' => GTE; next!('>' => next!('=' => RShiftAssign; RShift); GT)),
 
             '/' => next!(@no_read '*' => {
-                self.read_comment();
-                self.next_token();
+                let comment = self.read_comment();
 
-                return self.next_token();
+                return Token::new(Comment(comment), Span::new(self.file, start, self.position));
             }; next!(@no_read '/' => {
-                self.read_inline_comment();
+                let comment = self.read_inline_comment();
+                let span = Span::new(self.file, start, start + comment.len());
 
-                return self.next_token();
-            }; Slash)),
+                return Token::new(Comment(comment), span);
+            }; next!('=' => SlashAssign; Slash))),
 
             '\0' => EOF
         };
 
-        match self.current_char {
-            c if c.is_alphabetic() => {
-                let position = self.current_position;
-                Token::new(TokenKind::from(self.read_identifier()), position)
-            }
-            c if c.is_numeric() => {
-                let position = self.current_position;
-                Token::new(TokenKind::Number(self.read_number()), position)
+        let kind = match self.current_char {
+            '"' => TokenKind::String(self.read_string()),
+            'r' if matches!(self.peek_char(), '"' | '#') => match self.read_raw_string() {
+                Some(string) => TokenKind::String(string),
+                None => TokenKind::ILLEGAL(self.input[start..self.position].to_string()),
+            },
+            c if is_xid_start(c) || c == '_' => TokenKind::from(self.read_identifier()),
+            '\'' if is_xid_start(self.peek_char()) || self.peek_char() == '_' => {
+                self.read_char();
+                TokenKind::Label(self.read_identifier())
             }
+            c if c.is_ascii_digit() => self.read_number(),
             _ => {
                 self.read_char();
-                token
+                kind
             }
+        };
+
+        Token::new(kind, Span::new(self.file, start, self.position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lexer;
+    use crate::{
+        line_index::{LineIndex, Location},
+        parser::ParsingErrorKind,
+        tokenizer::token::TokenKind,
+    };
+
+    fn string(source: &str) -> (String, Vec<ParsingErrorKind>) {
+        let mut lexer = Lexer::new(source.to_string());
+
+        match lexer.next_token().kind {
+            TokenKind::String(string) => (string, lexer.errors.into_iter().map(|error| error.message).collect()),
+            kind => panic!("expected a string, got {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn unicode_test() {
+        let source = "auto café = \"日本\";\nauto _π2 = café; 🦀";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut tokens = Vec::new();
+
+        loop {
+            let token = lexer.next_token();
+
+            if token.kind == TokenKind::EOF {
+                break;
+            }
+
+            tokens.push(token);
+        }
+
+        let kinds = tokens.iter().map(|token| token.kind.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Auto,
+                TokenKind::IDENT("café".to_string()),
+                TokenKind::Assign,
+                TokenKind::String("日本".to_string()),
+                TokenKind::Semicolon,
+                TokenKind::Auto,
+                TokenKind::IDENT("_π2".to_string()),
+                TokenKind::Assign,
+                TokenKind::IDENT("café".to_string()),
+                TokenKind::Semicolon,
+                TokenKind::ILLEGAL("🦀".to_string()),
+            ]
+        );
+
+        let texts = tokens.iter().map(|token| &source[token.span.start..token.span.end]).collect::<Vec<_>>();
+        assert_eq!(texts[3], "\"日本\"");
+        assert_eq!(texts[10], "🦀");
+
+        // `🦀` is one char but two UTF-16 code units, `π` and `é` are one of each
+        assert_eq!(
+            LineIndex::new(source).location(tokens[8].span.start),
+            Location {
+                line: 1,
+                column: 12,
+                utf16_column: 12
+            }
+        );
+
+        let mut lexer = Lexer::new("\"🦀\" x".to_string());
+        lexer.next_token();
+        let x = lexer.next_token();
+
+        assert_eq!(
+            LineIndex::new(&lexer.input).location(x.span.start),
+            Location {
+                line: 0,
+                column: 5,
+                utf16_column: 6
+            }
+        );
+    }
+
+    #[test]
+    fn number_test() {
+        let mut lexer = Lexer::new("1 2.5 3.x 1_000_000 0xFF 0o17 0b1010_1010 1e3 2.5E-3 0x7FFF_FFFF_FFFF_FFFF".to_string());
+        let kinds = std::iter::from_fn(|| Some(lexer.next_token().kind).filter(|kind| *kind != TokenKind::EOF)).collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Int(1),
+                TokenKind::Float(2.5),
+                TokenKind::Int(3),
+                TokenKind::Dot,
+                TokenKind::IDENT("x".to_string()),
+                TokenKind::Int(1_000_000),
+                TokenKind::Int(0xFF),
+                TokenKind::Int(0o17),
+                TokenKind::Int(0b1010_1010),
+                TokenKind::Float(1e3),
+                TokenKind::Float(2.5E-3),
+                TokenKind::Int(i64::MAX),
+            ]
+        );
+        assert!(lexer.errors.is_empty());
+    }
+
+    #[test]
+    fn number_error_test() {
+        let errors = |source: &str| {
+            let mut lexer = Lexer::new(source.to_string());
+            while lexer.next_token().kind != TokenKind::EOF {}
+
+            lexer
+                .errors
+                .iter()
+                .map(|error| (error.message.code(), error.span.start, error.span.end))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(errors("1.2.3;"), [("E0009", 0, 5)]);
+        assert_eq!(errors("0xZZ + 12ab"), [("E0009", 0, 4), ("E0009", 7, 11)]);
+        assert_eq!(errors("0b102 0x 1e"), [("E0009", 0, 5), ("E0009", 6, 8), ("E0009", 9, 11)]);
+        assert_eq!(errors("99999999999999999999"), [("E0008", 0, 20)]);
+        assert_eq!(errors("1e400 -1.5e309 1e-400"), [("E0013", 0, 5), ("E0013", 7, 14)]);
+
+        // the sign of an exponent without digits is part of the literal
+        assert_eq!(errors("1e+ 2E-;"), [("E0009", 0, 3), ("E0009", 4, 7)]);
+        assert_eq!(errors("1e+x"), [("E0009", 0, 4)]);
+        assert_eq!(errors("0x1e+1 1e-3"), []);
+
+        // `_` only separates two digits
+        assert_eq!(errors("1_ + 2"), [("E0009", 0, 2)]);
+        assert_eq!(errors("1__0"), [("E0009", 0, 4)]);
+        assert_eq!(errors("0x_FF 1_.5 1e_3"), [("E0009", 0, 5), ("E0009", 6, 10), ("E0009", 11, 15)]);
+    }
+
+    #[test]
+    fn string_test() {
+        assert_eq!(string(r#""say \"hi\"\n\t\\ \u{1F600}""#).0, "say \"hi\"\n\t\\ \u{1F600}");
+        assert_eq!(string(r#"r"C:\path\to""#).0, r"C:\path\to");
+        assert_eq!(string(r###"r#"{"key": "value"}"#"###).0, r#"{"key": "value"}"#);
+        assert_eq!(
+            string("\"\"\"\n        {\n            \"key\": \"a\\tb\"\n\n        }\n        \"\"\"").0,
+            "{\n    \"key\": \"a\tb\"\n\n}"
+        );
+
+        // only the indentation the lines start with alike is dropped, whatever a tab is wide
+        assert_eq!(string("\"\"\"\n\t\ta\n\t  b\n\t\"\"\"").0, "\ta\n  b");
+        assert_eq!(string("\"\"\"\n\ta\n    b\n\"\"\"").0, "\ta\n    b");
+    }
+
+    #[test]
+    fn string_error_test() {
+        let errors = |source: &str| string(source).1.iter().map(ParsingErrorKind::code).collect::<Vec<_>>();
+
+        assert_eq!(errors(r#""\q""#), ["E0006"]);
+        assert_eq!(errors(r#""\u{110000}" "#), ["E0007"]);
+        assert_eq!(errors(r#""\u41""#), ["E0007"]);
+        assert_eq!(errors("\"unterminated"), ["E0005"]);
+        assert_eq!(errors("r#\"unterminated\""), ["E0005"]);
+        assert_eq!(errors("\"\"\"\nunterminated"), ["E0005"]);
+
+        // an unterminated string ends with its line, and the next line is read on its own
+        for source in ["\"abc;\nauto", "r\"abc;\nauto", "\"abc;\r\nauto"] {
+            let mut lexer = Lexer::new(source.to_string());
+
+            assert!(
+                matches!(lexer.next_token().kind, TokenKind::String(string) if string.trim_end() == "abc;"),
+                "{source}"
+            );
+            assert_eq!(lexer.next_token().kind, TokenKind::Auto, "{source}");
         }
     }
 }
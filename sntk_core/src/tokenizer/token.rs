@@ -1,24 +1,31 @@
+use crate::parser::ast::Span;
 use std::fmt;
-use crate::parser::ast::Position;
 
 #[derive(Debug, PartialEq, Clone)]
 #[rustfmt::skip]
 pub enum TokenKind {
-    ILLEGAL(String), EOF, IDENT(String),
+    ILLEGAL(String), EOF, IDENT(String), Label(String),
 
-    Number(f64), String(String), Boolean(bool), Comment,
+    Int(i64), Float(f64), String(String), Boolean(bool), Comment(String),
 
     Assign, Plus, Minus, Bang, Asterisk, Slash, Percent, Arrow, DoubleArrow,
 
-    Dot, Comma, Colon, Semicolon,
+    DoubleAsterisk, And, Or, Ampersand, Pipe, Caret, Tilde, LShift, RShift,
+
+    PlusAssign, MinusAssign, AsteriskAssign, SlashAssign, PercentAssign, DoubleAsteriskAssign,
+    AmpersandAssign, PipeAssign, CaretAssign, LShiftAssign, RShiftAssign,
+
+    Dot, Comma, Colon, DoubleColon, Semicolon, Question,
 
     LParen, RParen, LBrace, RBrace, LBracket, RBracket,
 
     LT, GT, LTE, GTE, EQ, NEQ,
 
-    Let, Auto, If, Else, Return, Function, Type, Declare, Struct, Typeof, Spread,
+    Let, Auto, Mut, If, Else, Return, Function, Type, Declare, Struct, Enum, Typeof, Spread,
 
-    NumberType, StringType, BooleanType
+    While, For, In, Loop, Break, Continue, Match, Import, Export,
+
+    IntType, FloatType, StringType, BooleanType, VoidType
 }
 
 impl From<String> for TokenKind {
@@ -26,6 +33,7 @@ impl From<String> for TokenKind {
         match s.as_str() {
             "let" => TokenKind::Let,
             "auto" => TokenKind::Auto,
+            "mut" => TokenKind::Mut,
             "if" => TokenKind::If,
             "else" => TokenKind::Else,
             "return" => TokenKind::Return,
@@ -33,13 +41,25 @@ impl From<String> for TokenKind {
             "type" => TokenKind::Type,
             "declare" => TokenKind::Declare,
             "struct" => TokenKind::Struct,
+            "enum" => TokenKind::Enum,
             "typeof" => TokenKind::Typeof,
             "spread" => TokenKind::Spread,
+            "while" => TokenKind::While,
+            "for" => TokenKind::For,
+            "in" => TokenKind::In,
+            "loop" => TokenKind::Loop,
+            "break" => TokenKind::Break,
+            "continue" => TokenKind::Continue,
+            "match" => TokenKind::Match,
+            "import" => TokenKind::Import,
+            "export" => TokenKind::Export,
             "true" => TokenKind::Boolean(true),
             "false" => TokenKind::Boolean(false),
-            "number" => TokenKind::NumberType,
+            "int" => TokenKind::IntType,
+            "float" => TokenKind::FloatType,
             "string" => TokenKind::StringType,
             "boolean" => TokenKind::BooleanType,
+            "void" => TokenKind::VoidType,
             s => TokenKind::IDENT(s.to_string()),
         }
     }
@@ -53,6 +73,10 @@ impl fmt::Display for TokenKind {
 
 impl TokenKind {
     pub fn stringify(&self) -> String {
+        if let TokenKind::Label(label) = self {
+            return format!("'{label}");
+        }
+
         macro_rules! to_s {
                 ($( $x:ident )*) => {
                     match &self {
@@ -62,31 +86,49 @@ impl TokenKind {
                 }
             }
 
-        to_s! { IDENT String Number Boolean }
+        to_s! { IDENT String Int Float Boolean }
+    }
+
+    /// The operator a compound assignment applies, as `Plus` for `+=`.
+    pub fn compound_operator(&self) -> Option<TokenKind> {
+        Some(match self {
+            TokenKind::PlusAssign => TokenKind::Plus,
+            TokenKind::MinusAssign => TokenKind::Minus,
+            TokenKind::AsteriskAssign => TokenKind::Asterisk,
+            TokenKind::SlashAssign => TokenKind::Slash,
+            TokenKind::PercentAssign => TokenKind::Percent,
+            TokenKind::DoubleAsteriskAssign => TokenKind::DoubleAsterisk,
+            TokenKind::AmpersandAssign => TokenKind::Ampersand,
+            TokenKind::PipeAssign => TokenKind::Pipe,
+            TokenKind::CaretAssign => TokenKind::Caret,
+            TokenKind::LShiftAssign => TokenKind::LShift,
+            TokenKind::RShiftAssign => TokenKind::RShift,
+            _ => return None,
+        })
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Token {
     pub kind: TokenKind,
-    pub position: Position,
+    pub span: Span,
 }
 
 impl Default for Token {
     fn default() -> Self {
-        Token::new(TokenKind::ILLEGAL(String::from("")), Position(0, 0))
+        Token::new(TokenKind::ILLEGAL(String::from("")), Span::default())
     }
 }
 
 impl std::fmt::Display for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Token {:?} at {:?}", self.kind, self.position)
+        write!(f, "Token {:?} at {}", self.kind, self.span)
     }
 }
 
 impl Token {
     #[inline]
-    pub fn new(token_type: TokenKind, position: Position) -> Self {
-        Token { kind: token_type, position }
+    pub fn new(token_type: TokenKind, span: Span) -> Self {
+        Token { kind: token_type, span }
     }
 }
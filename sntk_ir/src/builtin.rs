@@ -1,21 +1,47 @@
-use crate::instruction::LiteralValue;
+use crate::{instruction::LiteralValue, RuntimeErrorKind};
+use sntk_core::parser::ast::{DataType, DataTypeKind, FunctionType, Span};
 
 trait BuiltIn {
-    fn call(arguments: Vec<&LiteralValue>) -> LiteralValue;
+    fn call(arguments: Vec<&LiteralValue>) -> Result<LiteralValue, RuntimeErrorKind>;
 }
 
 struct Print;
 impl BuiltIn for Print {
-    fn call(arguments: Vec<&LiteralValue>) -> LiteralValue {
+    fn call(arguments: Vec<&LiteralValue>) -> Result<LiteralValue, RuntimeErrorKind> {
         let arguments = arguments.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ");
 
         println!("{arguments}");
 
-        LiteralValue::Boolean(true)
+        Ok(LiteralValue::Void)
     }
 }
 
-type BoxedCall = Box<dyn FnOnce(Vec<&LiteralValue>) -> LiteralValue>;
+/// Truncates a float toward zero.
+struct ToInt;
+impl BuiltIn for ToInt {
+    fn call(arguments: Vec<&LiteralValue>) -> Result<LiteralValue, RuntimeErrorKind> {
+        match arguments.as_slice() {
+            // `i64::MAX as f64` rounds up to 2^63, which is already out of range
+            [LiteralValue::Float(float)] if float.is_finite() && *float >= i64::MIN as f64 && *float < i64::MAX as f64 => {
+                Ok(LiteralValue::Int(*float as i64))
+            }
+            [LiteralValue::Float(float)] => Err(RuntimeErrorKind::IntegerOverflow(format!("to_int({float:?})"))),
+            _ => unreachable!(),
+        }
+    }
+}
+
+struct ToFloat;
+impl BuiltIn for ToFloat {
+    fn call(arguments: Vec<&LiteralValue>) -> Result<LiteralValue, RuntimeErrorKind> {
+        match arguments.as_slice() {
+            [LiteralValue::Int(int)] => Ok(LiteralValue::Float(*int as f64)),
+            _ => unreachable!(),
+        }
+    }
+}
+
+type BoxedCall = Box<dyn FnOnce(Vec<&LiteralValue>) -> Result<LiteralValue, RuntimeErrorKind>>;
 
 #[allow(clippy::type_complexity)]
 #[inline]
@@ -29,6 +55,27 @@ where
 pub fn builtin_function(name: &str) -> Option<BoxedCall> {
     match name {
         "println" => Some(boxed_call::<Print>()),
+        "to_int" => Some(boxed_call::<ToInt>()),
+        "to_float" => Some(boxed_call::<ToFloat>()),
         _ => None,
     }
 }
+
+/// Types of the builtins that do not have to be declared, as `println` (which takes any arguments) does.
+pub fn builtin_types() -> Vec<(&'static str, DataType)> {
+    let function = |parameter: DataTypeKind, return_type: DataTypeKind| {
+        DataType::new(
+            DataTypeKind::Fn(FunctionType::new(
+                None,
+                vec![(DataType::new(parameter, Span::default()), false)],
+                DataType::new(return_type, Span::default()),
+            )),
+            Span::default(),
+        )
+    };
+
+    vec![
+        ("to_int", function(DataTypeKind::Float, DataTypeKind::Int)),
+        ("to_float", function(DataTypeKind::Int, DataTypeKind::Float)),
+    ]
+}
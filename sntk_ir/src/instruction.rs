@@ -1,22 +1,36 @@
 use crate::interpreter::IrEnvironment;
 use sntk_core::{
-    parser::ast::{DataTypeKind, Parameter, Position},
+    parser::ast::{DataType, DataTypeKind, IdentifierGeneric, Parameter, Span},
     tokenizer::token::TokenKind,
 };
-use std::fmt;
+use std::{fmt, rc::Rc};
 
 pub type Block = Vec<Instruction>;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Instruction {
     pub instruction: InstructionType,
-    pub position: Position,
+    pub span: Span,
 }
 
 impl Instruction {
     #[inline]
-    pub fn new(instruction: InstructionType, position: Position) -> Self {
-        Self { instruction, position }
+    pub fn new(instruction: InstructionType, span: Span) -> Self {
+        Self { instruction, span }
+    }
+
+    pub fn expression(&self) -> Option<&IrExpression> {
+        match &self.instruction {
+            InstructionType::StoreName(_, expression, ..)
+            | InstructionType::Return(expression)
+            | InstructionType::Value(expression)
+            | InstructionType::Expression(expression) => Some(expression),
+            InstructionType::DeclareName(..)
+            | InstructionType::DeclareType(..)
+            | InstructionType::Module(..)
+            | InstructionType::Import(..)
+            | InstructionType::None => None,
+        }
     }
 }
 
@@ -28,17 +42,37 @@ impl fmt::Display for Instruction {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum InstructionType {
-    StoreName(String, IrExpression), /* identifier, literal */
-    Return(IrExpression),            /* literal */
-    Expression(IrExpression),        /* expression */
-    None,                            /* none */
+    StoreName(String, IrExpression, DataType, bool), /* identifier, literal, type, mutable */
+    DeclareName(String, DataType),                   /* identifier, type of a value the host provides */
+    DeclareType(String, Vec<String>, DataType),      /* name, type parameters, type */
+    Module(String, Block, Vec<String>),              /* path, instructions, exported values */
+    Import(String, Vec<String>),                     /* path of a module run before, imported values */
+    Return(IrExpression),                            /* value of the function */
+    Value(IrExpression),                             /* value of the block, always its last instruction */
+    Expression(IrExpression),                        /* expression */
+    None,                                            /* none */
 }
 
 impl fmt::Display for InstructionType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::StoreName(identifier, expression) => write!(f, "store_name({}, {})", identifier, expression),
+            Self::StoreName(identifier, expression, data_type, mutable) => write!(
+                f,
+                "store_name({}{}: {}, {})",
+                if *mutable { "mut " } else { "" },
+                identifier,
+                data_type,
+                expression
+            ),
+            Self::DeclareName(identifier, data_type) => write!(f, "declare_name({}: {})", identifier, data_type),
+            Self::DeclareType(name, generics, data_type) => match generics.is_empty() {
+                true => write!(f, "declare_type({}, {})", name, data_type),
+                false => write!(f, "declare_type({}<{}>, {})", name, generics.join(", "), data_type),
+            },
+            Self::Module(path, instructions, exports) => write!(f, "module({:?}, {}, [{}])", path, format_block(instructions), exports.join(", ")),
+            Self::Import(path, names) => write!(f, "import({:?}, [{}])", path, names.join(", ")),
             Self::Return(expression) => write!(f, "return({})", expression),
+            Self::Value(expression) => write!(f, "value({})", expression),
             Self::Expression(expression) => write!(f, "expression({})", expression),
             Self::None => write!(f, "none"),
         }
@@ -46,18 +80,84 @@ impl fmt::Display for InstructionType {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum IrExpression {
-    Identifier(String),                                                  /* identifier */
-    Literal(LiteralValue),                                               /* literal */
-    Block(Block),                                                        /* block */
-    If(Box<IrExpression>, Box<IrExpression>, Box<Option<IrExpression>>), /* condition, consequence, alternative */
-    Call(Box<IrExpression>, Vec<IrExpression>),                          /* function, arguments */
-    Index(Box<IrExpression>, Box<IrExpression>),                         /* left, index */
-    Prefix(TokenKind, Box<IrExpression>),                                /* operator, right */
-    Infix(Box<IrExpression>, TokenKind, Box<IrExpression>),              /* left, operator, right */
+pub struct IrExpression {
+    pub expression: IrExpressionKind,
+    pub span: Span,
+}
+
+impl IrExpression {
+    #[inline]
+    pub fn new(expression: IrExpressionKind, span: Span) -> Self {
+        Self { expression, span }
+    }
+
+    /// The expressions directly inside this one, including those of the instructions of its blocks and function bodies.
+    pub fn children(&self) -> Vec<&IrExpression> {
+        fn block(block: &Block) -> Vec<&IrExpression> {
+            block.iter().filter_map(Instruction::expression).collect()
+        }
+
+        match &self.expression {
+            IrExpressionKind::Identifier(_) | IrExpressionKind::Continue(_) => Vec::new(),
+            IrExpressionKind::Literal(literal) => match literal {
+                LiteralValue::Array(elements) => elements.iter().collect(),
+                LiteralValue::Struct(_, fields) => fields.iter().map(|(_, value)| value).collect(),
+                LiteralValue::Enum(_, _, payload) => payload.iter().collect(),
+                LiteralValue::Function(_, _, body, _, _) => block(body),
+                _ => Vec::new(),
+            },
+            IrExpressionKind::Block(body) => block(body),
+            IrExpressionKind::If(condition, consequence, alternative) => {
+                [&**condition, &**consequence].into_iter().chain(alternative.as_ref().as_ref()).collect()
+            }
+            IrExpressionKind::Call(function, arguments) => std::iter::once(&**function).chain(arguments).collect(),
+            IrExpressionKind::Index(left, right)
+            | IrExpressionKind::Infix(left, _, right)
+            | IrExpressionKind::Logical(left, _, right)
+            | IrExpressionKind::Assign(left, _, right) => vec![left, right],
+            IrExpressionKind::Field(expression, _)
+            | IrExpressionKind::Prefix(_, expression)
+            | IrExpressionKind::Spread(expression)
+            | IrExpressionKind::Try(expression) => vec![expression],
+            IrExpressionKind::Loop(_, condition, body) => condition.as_ref().as_ref().into_iter().chain(block(body)).collect(),
+            IrExpressionKind::For(_, _, array, body) => std::iter::once(&**array).chain(block(body)).collect(),
+            IrExpressionKind::Match(value, arms) => std::iter::once(&**value)
+                .chain(arms.iter().flat_map(|(_, guard, body)| guard.iter().chain(std::iter::once(body))))
+                .collect(),
+            IrExpressionKind::Break(_, value) => value.as_ref().as_ref().into_iter().collect(),
+        }
+    }
 }
 
 impl fmt::Display for IrExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrExpressionKind {
+    Identifier(String),                                                             /* identifier */
+    Literal(LiteralValue),                                                          /* literal */
+    Block(Block),                                                                   /* block */
+    If(Box<IrExpression>, Box<IrExpression>, Box<Option<IrExpression>>),            /* condition, consequence, alternative */
+    Call(Box<IrExpression>, Vec<IrExpression>),                                     /* function, arguments */
+    Index(Box<IrExpression>, Box<IrExpression>),                                    /* left, index */
+    Field(Box<IrExpression>, String),                                               /* left, field */
+    Prefix(TokenKind, Box<IrExpression>),                                           /* operator, right */
+    Infix(Box<IrExpression>, TokenKind, Box<IrExpression>),                         /* left, operator, right */
+    Logical(Box<IrExpression>, TokenKind, Box<IrExpression>), /* left, `&&` or `||`, right evaluated only if left does not decide */
+    Assign(Box<IrExpression>, TokenKind, Box<IrExpression>), /* variable, element or field, `Assign` or the operator of a compound assignment, value */
+    Spread(Box<IrExpression>),                               /* arguments packed into an array for a spread parameter */
+    Loop(Option<String>, Box<Option<IrExpression>>, Block),  /* label, condition checked before each iteration (none for `loop`), body */
+    For(Option<String>, String, Box<IrExpression>, Block),   /* label, variable, array, body */
+    Match(Box<IrExpression>, Vec<(IrPattern, Option<IrExpression>, IrExpression)>), /* value, arms of a pattern, guard and body */
+    Break(Option<String>, Box<Option<IrExpression>>),        /* label if not the innermost loop, value */
+    Continue(Option<String>),                                /* label if not the innermost loop */
+    Try(Box<IrExpression>),                                  /* `Option` or `Result` whose `None` or `Err` is returned from the function */
+}
+
+impl fmt::Display for IrExpressionKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Identifier(identifier) => write!(f, "{}", identifier),
@@ -69,15 +169,7 @@ impl fmt::Display for IrExpression {
                     _ => format!("{}", literal),
                 }
             ),
-            Self::Block(block) => write!(
-                f,
-                "block({})",
-                block
-                    .iter()
-                    .map(|instruction| format!("{}", instruction))
-                    .collect::<Vec<String>>()
-                    .join(", ")
-            ),
+            Self::Block(block) => write!(f, "{}", format_block(block)),
             Self::If(condition, consequence, alternative) => write!(
                 f,
                 "if({}, {}, {})",
@@ -99,27 +191,137 @@ impl fmt::Display for IrExpression {
                     .join(", ")
             ),
             Self::Index(left, index) => write!(f, "index({}, {})", left, index),
+            Self::Field(left, field) => write!(f, "field({}, {})", left, field),
             Self::Prefix(operator, right) => write!(f, "prefix({}, {})", operator, right),
             Self::Infix(left, operator, right) => write!(f, "infix({}, {}, {})", left, operator, right),
+            Self::Logical(left, operator, right) => write!(f, "logical({}, {}, {})", left, operator, right),
+            Self::Assign(target, operator, value) => write!(f, "assign({}, {}, {})", target, operator, value),
+            Self::Spread(arguments) => write!(f, "spread({})", arguments),
+            Self::Loop(label, condition, body) => write!(
+                f,
+                "loop({}, {}, {})",
+                format_label(label),
+                condition
+                    .as_ref()
+                    .as_ref()
+                    .map_or_else(|| "None".to_string(), |condition| condition.to_string()),
+                format_block(body)
+            ),
+            Self::For(label, name, array, body) => write!(f, "for({}, {}, {}, {})", format_label(label), name, array, format_block(body)),
+            Self::Break(label, value) => write!(
+                f,
+                "break({}, {})",
+                format_label(label),
+                value.as_ref().as_ref().map_or_else(|| "None".to_string(), |value| value.to_string())
+            ),
+            Self::Continue(label) => write!(f, "continue({})", format_label(label)),
+            Self::Try(value) => write!(f, "try({})", value),
+            Self::Match(value, arms) => write!(
+                f,
+                "match({}, {})",
+                value,
+                arms.iter()
+                    .map(|(pattern, guard, body)| match guard {
+                        Some(guard) => format!("{} if {} => {}", pattern, guard, body),
+                        None => format!("{} => {}", pattern, body),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrPattern {
+    pub pattern: IrPatternKind,
+    pub span: Span,
+}
+
+impl IrPattern {
+    #[inline]
+    pub fn new(pattern: IrPatternKind, span: Span) -> Self {
+        Self { pattern, span }
+    }
+
+    /// The names the pattern binds, in the order they are written.
+    pub fn names(&self) -> Vec<&str> {
+        match &self.pattern {
+            IrPatternKind::Wildcard | IrPatternKind::Literal(_) => Vec::new(),
+            IrPatternKind::Binding(name) => vec![name],
+            IrPatternKind::Array(elements, rest) => elements.iter().chain(rest.as_deref()).flat_map(IrPattern::names).collect(),
+            IrPatternKind::Struct(_, fields) => fields.iter().flat_map(|(_, pattern)| pattern.names()).collect(),
+            IrPatternKind::Enum(_, _, payload) => payload.iter().flat_map(IrPattern::names).collect(),
+        }
+    }
+}
+
+impl fmt::Display for IrPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrPatternKind {
+    Wildcard,                                      /* `_` */
+    Literal(LiteralValue),                         /* int, float, string or boolean */
+    Binding(String),                               /* name */
+    Array(Vec<IrPattern>, Option<Box<IrPattern>>), /* first elements, binding or wildcard for the rest if they can be more */
+    Struct(String, Vec<(String, IrPattern)>),      /* name, fields */
+    Enum(String, String, Vec<IrPattern>),          /* name, variant, payload */
+}
+
+impl fmt::Display for IrPatternKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |patterns: &[IrPattern]| patterns.iter().map(|pattern| pattern.to_string()).collect::<Vec<String>>().join(", ");
+
+        match self {
+            Self::Wildcard => write!(f, "_"),
+            Self::Literal(LiteralValue::String(string)) => write!(f, "\"{}\"", string),
+            Self::Literal(literal) => write!(f, "{}", literal),
+            Self::Binding(name) => write!(f, "{}", name),
+            Self::Array(elements, None) => write!(f, "[{}]", list(elements)),
+            Self::Array(elements, Some(rest)) if elements.is_empty() => write!(f, "[...{}]", rest),
+            Self::Array(elements, Some(rest)) => write!(f, "[{}, ...{}]", list(elements), rest),
+            Self::Struct(name, fields) => write!(
+                f,
+                "struct {} {{ {} }}",
+                name,
+                fields
+                    .iter()
+                    .map(|(field, pattern)| format!("{}: {}", field, pattern))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Self::Enum(name, variant, payload) if payload.is_empty() => write!(f, "{}::{}", name, variant),
+            Self::Enum(name, variant, payload) => write!(f, "{}::{}({})", name, variant, list(payload)),
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum LiteralValue {
-    Number(f64),                                                          /* number */
-    String(String),                                                       /* string */
-    Boolean(bool),                                                        /* boolean */
-    Array(Vec<IrExpression>),                                             /* array */
-    Function(Vec<Parameter>, Block, DataTypeKind, Option<IrEnvironment>), /* parameters, block, return type, environment */
+    Int(i64),                                                                                            /* int */
+    Float(f64),                                                                                          /* float */
+    String(String),                                                                                      /* string */
+    Boolean(bool),                                                                                       /* boolean */
+    Void,                                                                                                /* void */
+    Array(Rc<Vec<IrExpression>>),                                                                        /* elements, shared until one is assigned */
+    Struct(String, Vec<(String, IrExpression)>),                                                         /* name, fields */
+    Enum(String, String, Vec<IrExpression>),                                                             /* name, variant, payload */
+    Function(Option<IdentifierGeneric>, Vec<Parameter>, Rc<Block>, DataTypeKind, Option<IrEnvironment>), /* generics, parameters, block, return type, environment */
 }
 
 impl fmt::Display for LiteralValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            LiteralValue::Number(number) => write!(f, "{}", number),
+            LiteralValue::Int(int) => write!(f, "{}", int),
+            // `{:?}` keeps the `.0` of whole floats, so that they read differently from ints
+            LiteralValue::Float(float) => write!(f, "{:?}", float),
             LiteralValue::String(string) => write!(f, "{}", string),
             LiteralValue::Boolean(boolean) => write!(f, "{}", boolean),
+            LiteralValue::Void => write!(f, "void"),
             LiteralValue::Array(array) => write!(
                 f,
                 "[{}]",
@@ -129,10 +331,39 @@ impl fmt::Display for LiteralValue {
                     .collect::<Vec<String>>()
                     .join(", ")
             ),
-            LiteralValue::Function(parameters, _, data_type, _) => {
+            LiteralValue::Struct(name, fields) => write!(
+                f,
+                "{} {{ {} }}",
+                name,
+                fields
+                    .iter()
+                    .map(|(field, expression)| format!("{}: {}", field, expression))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            LiteralValue::Enum(name, variant, payload) if payload.is_empty() => write!(f, "{}::{}", name, variant),
+            LiteralValue::Enum(name, variant, payload) => write!(
+                f,
+                "{}::{}({})",
+                name,
+                variant,
+                payload
+                    .iter()
+                    .map(|expression| format!("{}", expression))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            LiteralValue::Function(generics, parameters, _, data_type, _) => {
                 write!(
                     f,
-                    "fn({}) -> {}",
+                    "fn{}({}) -> {}",
+                    match generics {
+                        Some(generics) => format!(
+                            "<{}>",
+                            generics.iter().map(|generic| generic.value.clone()).collect::<Vec<String>>().join(", ")
+                        ),
+                        None => String::new(),
+                    },
                     parameters
                         .iter()
                         .map(|parameter| if parameter.spread {
@@ -140,10 +371,29 @@ impl fmt::Display for LiteralValue {
                         } else {
                             parameter.name.value.to_string()
                         })
-                        .collect::<String>(),
+                        .collect::<Vec<String>>()
+                        .join(", "),
                     data_type
                 )
             }
         }
     }
 }
+
+fn format_block(block: &Block) -> String {
+    format!(
+        "block({})",
+        block
+            .iter()
+            .map(|instruction| format!("{}", instruction))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+fn format_label(label: &Option<String>) -> String {
+    match label {
+        Some(label) => format!("'{label}"),
+        None => "None".to_string(),
+    }
+}
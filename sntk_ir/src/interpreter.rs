@@ -1,14 +1,16 @@
 use crate::{
     builtin::builtin_function,
-    instruction::{Instruction, InstructionType, IrExpression, LiteralValue},
+    instruction::{Block, Instruction, InstructionType, IrExpression, IrExpressionKind, IrPattern, IrPatternKind, LiteralValue},
     RuntimeError, RuntimeErrorKind,
 };
-use sntk_core::{parser::ast::Position, tokenizer::token::TokenKind};
-use std::{collections::HashMap, fmt};
+use sntk_core::{parser::ast::Span, tokenizer::token::TokenKind};
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
 
-#[derive(Clone, PartialEq)]
+/// A scope of variables; clones share their values, so that blocks and closures see assignments made through any of them, and
+/// bindings made after them, as a function sees the name it is bound to.
+#[derive(Clone)]
 pub struct IrEnvironment {
-    pub values: HashMap<String, LiteralValue>,
+    pub values: Rc<RefCell<HashMap<String, LiteralValue>>>,
     pub parent: Option<Box<IrEnvironment>>,
 }
 
@@ -16,13 +18,13 @@ impl IrEnvironment {
     #[inline]
     pub fn new(parent: Option<IrEnvironment>) -> Self {
         Self {
-            values: HashMap::new(),
+            values: Rc::new(RefCell::new(HashMap::new())),
             parent: parent.map(Box::new),
         }
     }
 
     pub fn get(&self, name: String) -> Option<LiteralValue> {
-        match self.values.get(&name) {
+        match self.values.borrow().get(&name) {
             Some(value) => Some(value.clone()),
             None => match &self.parent {
                 Some(parent) => parent.get(name),
@@ -31,14 +33,30 @@ impl IrEnvironment {
         }
     }
 
+    /// Declares `name` in this scope, shadowing any outer variable of the same name.
     pub fn set(&mut self, name: String, value: LiteralValue) {
-        self.values.insert(name, value);
+        self.values.borrow_mut().insert(name, value);
+    }
+
+    /// Changes `name` in place in the scope that declares it, returning `None` if no scope does.
+    pub fn update<T>(&self, name: &str, update: impl FnOnce(&mut LiteralValue) -> T) -> Option<T> {
+        match self.values.borrow_mut().get_mut(name) {
+            Some(value) => Some(update(value)),
+            None => self.parent.as_ref().and_then(|parent| parent.update(name, update)),
+        }
+    }
+}
+
+/// Environments are the same when they share their values, not when they hold equal ones.
+impl PartialEq for IrEnvironment {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.values, &other.values)
     }
 }
 
 impl fmt::Debug for IrEnvironment {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{{ values: {:?}, parent: {:?} }}", self.values.keys(), self.parent)
+        write!(f, "{{ values: {:?}, parent: {:?} }}", self.values.borrow().keys(), self.parent)
     }
 }
 
@@ -46,213 +64,694 @@ impl fmt::Debug for IrEnvironment {
 pub struct IrInterpreter {
     pub instructions: Vec<Instruction>,
     pub environment: IrEnvironment,
+    depth: usize, /* expressions, and the calls among them, the instructions are nested in */
 }
 
+/// How deeply expressions can nest, counting those in the body of each function called, before a program is stopped with
+/// an error. Calls and nested expressions share this one budget, so that however a program nests them, it needs no more than
+/// `STACK_SIZE` of stack.
+pub const MAX_DEPTH: usize = 10_000;
+
+/// The stack the interpreter should be run with, which is far more than a main thread has. A nested expression takes up to
+/// about 20KB of it in a debug build, through a call into a block, so this leaves room for `MAX_DEPTH` of them with some to spare.
+pub const STACK_SIZE: usize = MAX_DEPTH * 32 * 1024;
+
 pub type Result<T> = std::result::Result<T, crate::RuntimeError>;
 
+/// Why an expression stopped before giving a value: an error, a `break` or `continue` on its way to its loop, or a `return`
+/// on its way to its function.
+#[derive(Debug)]
+enum Unwind {
+    Error(RuntimeError),
+    Break(Option<String>, Box<LiteralValue>),
+    Continue(Option<String>),
+    Return(Box<LiteralValue>),
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Self {
+        Unwind::Error(error)
+    }
+}
+
+impl Unwind {
+    /// `break`, `continue` and `return` are checked to be inside a loop or function when compiling, so only errors leave a program.
+    fn into_error(self) -> RuntimeError {
+        match self {
+            Unwind::Error(error) => error,
+            unwind => unreachable!("{unwind:?} outside of a loop or function"),
+        }
+    }
+}
+
+type Flow<T> = std::result::Result<T, Unwind>;
+
 impl IrInterpreter {
     pub fn new(instructions: Vec<Instruction>) -> Self {
+        Self::new_with_environment(instructions, IrEnvironment::new(None))
+    }
+
+    pub fn new_with_environment(instructions: Vec<Instruction>, environment: IrEnvironment) -> Self {
         Self {
             instructions,
-            environment: IrEnvironment::new(None),
+            environment,
+            depth: 0,
         }
     }
 
-    pub fn new_with_environment(instructions: Vec<Instruction>, environment: IrEnvironment) -> Self {
-        Self { instructions, environment }
+    pub fn eval(&mut self) -> Result<()> {
+        self.run().map(|_| ()).map_err(Unwind::into_error)
     }
 
-    pub fn eval(&mut self) -> Result<()> {
-        for instruction in self.instructions.clone().iter() {
-            self.eval_instruction(instruction)?;
+    pub fn eval_instruction(&mut self, instruction: &Instruction) -> Result<()> {
+        self.execute(instruction).map(|_| ()).map_err(Unwind::into_error)
+    }
+
+    pub fn eval_expression(&mut self, expression: &IrExpression) -> Result<LiteralValue> {
+        self.evaluate(expression).map_err(Unwind::into_error)
+    }
+
+    /// Runs the instructions, giving the value of the block they make up.
+    fn run(&mut self) -> Flow<LiteralValue> {
+        let instructions = std::mem::take(&mut self.instructions);
+        let value = self.run_instructions(&instructions);
+        self.instructions = instructions;
+
+        value
+    }
+
+    fn run_instructions(&mut self, instructions: &[Instruction]) -> Flow<LiteralValue> {
+        let mut value = LiteralValue::Void;
+
+        for instruction in instructions.iter() {
+            value = self.execute(instruction)?;
         }
 
-        Ok(())
+        Ok(value)
     }
 
-    pub fn last(&mut self) -> Result<LiteralValue> {
-        self.clone()
-            .instructions
-            .last()
-            .map(|instruction| {
-                let position = Position(instruction.position.0, instruction.position.1);
-                match instruction.instruction.clone() {
-                    InstructionType::Return(expression) => self.eval_expression(&expression, &position),
-                    _ => Ok(LiteralValue::Boolean(false)),
-                }
-            })
-            .unwrap_or(Ok(LiteralValue::Boolean(false)))
+    /// Runs a block in `environment`, giving the value it returns.
+    fn run_block(&self, block: &[Instruction], environment: IrEnvironment) -> Flow<LiteralValue> {
+        self.nested(environment).run_instructions(block)
     }
 
-    pub fn eval_instruction(&mut self, instruction: &Instruction) -> Result<()> {
-        let position = Position(instruction.position.0, instruction.position.1);
+    /// An interpreter for a scope inside this one, at the same depth.
+    fn nested(&self, environment: IrEnvironment) -> IrInterpreter {
+        IrInterpreter {
+            instructions: Vec::new(),
+            environment,
+            depth: self.depth,
+        }
+    }
 
-        match instruction.instruction.clone() {
-            InstructionType::StoreName(name, expression) => {
-                let expression = self.eval_expression(&expression, &position)?;
-                self.environment.set(name, expression);
+    fn execute(&mut self, instruction: &Instruction) -> Flow<LiteralValue> {
+        match &instruction.instruction {
+            InstructionType::StoreName(name, expression, ..) => {
+                let expression = self.evaluate(expression)?;
+                self.environment.set(name.clone(), expression);
             }
             InstructionType::Expression(expression) => {
-                self.eval_expression(&expression, &position)?;
+                self.evaluate(expression)?;
+            }
+            InstructionType::Value(expression) => return self.evaluate(expression),
+            InstructionType::Return(expression) => return Err(Unwind::Return(Box::new(self.evaluate(expression)?))),
+            // a module runs in a scope of its own, and its exported values are kept as a struct under its path, which is never a name
+            InstructionType::Module(path, instructions, exports) => {
+                let environment = IrEnvironment::new(Some(self.environment.clone()));
+                self.run_block(instructions, environment.clone())?;
+
+                let values = exports
+                    .iter()
+                    .filter_map(|name| {
+                        let value = environment.values.borrow().get(name).cloned()?;
+                        Some((name.clone(), IrExpression::new(IrExpressionKind::Literal(value), instruction.span)))
+                    })
+                    .collect();
+
+                self.environment.set(path.clone(), LiteralValue::Struct(path.clone(), values));
+            }
+            InstructionType::Import(path, names) => {
+                if let Some(LiteralValue::Struct(_, values)) = self.environment.get(path.clone()) {
+                    for (name, value) in values.into_iter().filter(|(name, _)| names.contains(name)) {
+                        let value = self.element(&value)?;
+                        self.environment.set(name, value);
+                    }
+                }
             }
-            InstructionType::Return(_) | InstructionType::None => {}
+            // declarations only matter to the checker
+            InstructionType::DeclareName(..) | InstructionType::DeclareType(..) | InstructionType::None => {}
         }
 
-        Ok(())
+        Ok(LiteralValue::Void)
     }
 
-    pub fn eval_expression(&mut self, expression: &IrExpression, position: &Position) -> Result<LiteralValue> {
+    fn evaluate(&mut self, expression: &IrExpression) -> Flow<LiteralValue> {
+        if self.depth == MAX_DEPTH {
+            return Err(RuntimeError::new(RuntimeErrorKind::DepthExceeded(MAX_DEPTH), expression.span).into());
+        }
+
+        self.depth += 1;
+        let value = self.evaluate_kind(expression);
+        self.depth -= 1;
+
+        value
+    }
+
+    /// Most kinds of expression are evaluated in methods of their own, so that the frame of this one, which every nested
+    /// expression goes through, stays small.
+    fn evaluate_kind(&mut self, expression: &IrExpression) -> Flow<LiteralValue> {
+        let IrExpression { expression, span } = expression;
+
         match expression {
-            IrExpression::Identifier(name) => match self.environment.get(name.clone()) {
+            IrExpressionKind::Identifier(name) => match self.environment.get(name.clone()) {
                 Some(value) => Ok(value),
-                None => Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(name.to_string()), *position)),
+                None => Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(name.to_string()), *span).into()),
             },
-            IrExpression::Literal(value) => match value {
-                LiteralValue::Array(array) => {
-                    let array = array
-                        .iter()
-                        .map(|expression| self.eval_expression(expression, position))
-                        .collect::<std::result::Result<Vec<LiteralValue>, RuntimeError>>()?
-                        .iter()
-                        .map(|value| IrExpression::Literal(value.clone()))
-                        .collect();
-
-                    Ok(LiteralValue::Array(array))
+            IrExpressionKind::Literal(value) => self.eval_literal(value),
+            IrExpressionKind::Block(block) => self.run_block(block, IrEnvironment::new(Some(self.environment.clone()))),
+            IrExpressionKind::If(condition, consequence, alternative) => self.eval_if(condition, consequence, alternative, *span),
+            IrExpressionKind::Call(function, arguments) => self.eval_call(function, arguments, *span),
+            IrExpressionKind::Index(left, index) => self.eval_index(left, index, *span),
+            IrExpressionKind::Field(left, field) => self.eval_field(left, field, *span),
+            IrExpressionKind::Spread(arguments) => self.evaluate(arguments),
+            // the `None` or `Err` is returned as it is, since values do not keep their type arguments
+            IrExpressionKind::Try(value) => match self.evaluate(value)? {
+                LiteralValue::Enum(_, variant, payload) if variant == "Some" || variant == "Ok" => self.element(&payload[0]),
+                value => Err(Unwind::Return(Box::new(value))),
+            },
+            IrExpressionKind::Prefix(operator, right) => self.eval_prefix(operator, right, *span),
+            IrExpressionKind::Logical(left, operator, right) => self.eval_logical(left, operator, right, *span),
+            IrExpressionKind::Infix(left, operator, right) => {
+                let (left, right) = (self.evaluate(left)?, self.evaluate(right)?);
+
+                Ok(eval_infix(left, operator, right, *span)?)
+            }
+            IrExpressionKind::Assign(target, operator, value) => self.eval_assign(target, operator, value, *span),
+            IrExpressionKind::Loop(label, condition, body) => self.eval_loop(label, condition, body),
+            IrExpressionKind::For(label, name, array, body) => self.eval_for(label, name, array, body),
+            IrExpressionKind::Break(label, value) => {
+                let value = match value.as_ref() {
+                    Some(value) => self.evaluate(value)?,
+                    None => LiteralValue::Void,
+                };
+
+                Err(Unwind::Break(label.clone(), Box::new(value)))
+            }
+            IrExpressionKind::Continue(label) => Err(Unwind::Continue(label.clone())),
+            IrExpressionKind::Match(value, arms) => self.eval_match(value, arms, *span),
+        }
+    }
+
+    fn eval_literal(&mut self, value: &LiteralValue) -> Flow<LiteralValue> {
+        match value {
+            LiteralValue::Array(array) => {
+                let array = array
+                    .iter()
+                    .map(|element| Ok(IrExpression::new(IrExpressionKind::Literal(self.evaluate(element)?), element.span)))
+                    .collect::<Flow<Vec<_>>>()?;
+
+                Ok(LiteralValue::Array(Rc::new(array)))
+            }
+            LiteralValue::Struct(name, fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, value)| {
+                        Ok((
+                            field.clone(),
+                            IrExpression::new(IrExpressionKind::Literal(self.evaluate(value)?), value.span),
+                        ))
+                    })
+                    .collect::<Flow<Vec<_>>>()?;
+
+                Ok(LiteralValue::Struct(name.clone(), fields))
+            }
+            LiteralValue::Enum(name, variant, payload) => {
+                let payload = payload
+                    .iter()
+                    .map(|value| Ok(IrExpression::new(IrExpressionKind::Literal(self.evaluate(value)?), value.span)))
+                    .collect::<Flow<Vec<_>>>()?;
+
+                Ok(LiteralValue::Enum(name.clone(), variant.clone(), payload))
+            }
+            // functions close over the scope they are created in
+            LiteralValue::Function(generics, parameters, block, return_type, None) => Ok(LiteralValue::Function(
+                generics.clone(),
+                parameters.clone(),
+                block.clone(),
+                return_type.clone(),
+                Some(self.environment.clone()),
+            )),
+            _ => Ok(value.clone()),
+        }
+    }
+
+    fn eval_if(
+        &mut self,
+        condition: &IrExpression,
+        consequence: &IrExpression,
+        alternative: &Option<IrExpression>,
+        span: Span,
+    ) -> Flow<LiteralValue> {
+        let condition = self.evaluate(condition)?;
+
+        // an `if` without an `else` has no value, whichever way it goes
+        match (condition, alternative.as_ref()) {
+            (LiteralValue::Boolean(true), Some(_)) => self.evaluate(consequence),
+            (LiteralValue::Boolean(true), None) => self.evaluate(consequence).map(|_| LiteralValue::Void),
+            (LiteralValue::Boolean(false), Some(alternative)) => self.evaluate(alternative),
+            (LiteralValue::Boolean(false), None) => Ok(LiteralValue::Void),
+            (condition, _) => Err(RuntimeError::new(RuntimeErrorKind::NotABoolean(condition.to_string()), span).into()),
+        }
+    }
+
+    fn eval_call(&mut self, function: &IrExpression, arguments: &[IrExpression], span: Span) -> Flow<LiteralValue> {
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.evaluate(argument))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let function = match &function.expression {
+            IrExpressionKind::Identifier(name) => match self.environment.get(name.clone()) {
+                Some(value) => value,
+                None => {
+                    return match builtin_function(name) {
+                        Some(function) => function(arguments.iter().collect()).map_err(|error| RuntimeError::new(error, span).into()),
+                        None => Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(name.to_string()), span).into()),
+                    };
                 }
-                _ => Ok(value.clone()),
             },
-            IrExpression::Block(block) => {
-                let mut interpreter = IrInterpreter::new_with_environment(block.clone(), IrEnvironment::new(Some(self.environment.clone())));
-                interpreter.eval()?;
-                Ok(interpreter.last()?)
+            _ => self.evaluate(function)?,
+        };
+
+        let (parameters, body, mut environment) = match function {
+            LiteralValue::Function(_, parameters, block, _, environment) => (
+                parameters.iter().map(|parameter| parameter.name.value.clone()).collect::<Vec<_>>(),
+                block,
+                IrEnvironment::new(Some(environment.unwrap_or_else(|| self.environment.clone()))),
+            ),
+            value => return Err(RuntimeError::new(RuntimeErrorKind::NotAFunction(value.to_string()), span).into()),
+        };
+
+        for (parameter, argument) in parameters.iter().zip(arguments.iter()) {
+            environment.set(parameter.clone(), argument.clone());
+        }
+
+        match self.nested(environment).run_instructions(&body) {
+            Err(Unwind::Return(value)) => Ok(*value),
+            result => result,
+        }
+    }
+
+    fn eval_index(&mut self, left: &IrExpression, index: &IrExpression, span: Span) -> Flow<LiteralValue> {
+        let array = match self.evaluate(left)? {
+            LiteralValue::Array(array) => array,
+            value => return Err(RuntimeError::new(RuntimeErrorKind::NotAnArray(value.to_string()), left.span).into()),
+        };
+        let index = self.eval_int(index)?;
+
+        match usize::try_from(index).ok().and_then(|index| array.get(index)) {
+            Some(value) => self.element(value),
+            None => Err(RuntimeError::new(RuntimeErrorKind::IndexOutOfBounds(index), span).into()),
+        }
+    }
+
+    /// The value of an element of an array, struct or enum value, which was evaluated with the value, so that reading it does
+    /// not copy the arrays inside it.
+    fn element(&mut self, element: &IrExpression) -> Flow<LiteralValue> {
+        match &element.expression {
+            IrExpressionKind::Literal(value) => Ok(value.clone()),
+            _ => self.evaluate(element),
+        }
+    }
+
+    /// Evaluates an expression that must produce an int, such as an index.
+    fn eval_int(&mut self, index: &IrExpression) -> Flow<i64> {
+        match self.evaluate(index)? {
+            LiteralValue::Int(index) => Ok(index),
+            value => Err(RuntimeError::new(RuntimeErrorKind::NotAnInt(value.to_string()), index.span).into()),
+        }
+    }
+
+    fn eval_field(&mut self, left: &IrExpression, field: &String, span: Span) -> Flow<LiteralValue> {
+        match self.evaluate(left)? {
+            LiteralValue::Struct(name, fields) => match fields.iter().find(|(name, _)| name == field) {
+                Some((_, value)) => self.element(value),
+                None => Err(RuntimeError::new(RuntimeErrorKind::UndefinedField(name, field.clone()), span).into()),
+            },
+            left => Err(RuntimeError::new(RuntimeErrorKind::NotAStruct(left.to_string()), span).into()),
+        }
+    }
+
+    fn eval_prefix(&mut self, operator: &TokenKind, right: &IrExpression, span: Span) -> Flow<LiteralValue> {
+        let right = self.evaluate(right)?;
+
+        match (operator, right) {
+            (TokenKind::Minus, LiteralValue::Int(right)) => match right.checked_neg() {
+                Some(value) => Ok(LiteralValue::Int(value)),
+                None => Err(RuntimeError::new(RuntimeErrorKind::IntegerOverflow(format!("-({right})")), span).into()),
+            },
+            (TokenKind::Minus, LiteralValue::Float(right)) => Ok(LiteralValue::Float(-right)),
+            (TokenKind::Bang, LiteralValue::Boolean(right)) => Ok(LiteralValue::Boolean(!right)),
+            (TokenKind::Tilde, LiteralValue::Int(right)) => Ok(LiteralValue::Int(!right)),
+            (operator, _) => Err(RuntimeError::new(RuntimeErrorKind::InvalidOperator(operator.to_string()), span).into()),
+        }
+    }
+
+    fn eval_logical(&mut self, left: &IrExpression, operator: &TokenKind, right: &IrExpression, span: Span) -> Flow<LiteralValue> {
+        match (operator, self.evaluate(left)?) {
+            (TokenKind::And, LiteralValue::Boolean(false)) => Ok(LiteralValue::Boolean(false)),
+            (TokenKind::Or, LiteralValue::Boolean(true)) => Ok(LiteralValue::Boolean(true)),
+            (TokenKind::And | TokenKind::Or, LiteralValue::Boolean(_)) => match self.evaluate(right)? {
+                LiteralValue::Boolean(right) => Ok(LiteralValue::Boolean(right)),
+                right => Err(RuntimeError::new(
+                    RuntimeErrorKind::InvalidOperands("boolean".to_string(), right.to_string(), operator.to_string()),
+                    span,
+                )
+                .into()),
+            },
+            (_, left) => Err(RuntimeError::new(
+                RuntimeErrorKind::InvalidOperands(left.to_string(), "boolean".to_string(), operator.to_string()),
+                span,
+            )
+            .into()),
+        }
+    }
+
+    /// Loops, and `break`s, without a value give `void`, as blocks without one do.
+    fn eval_loop(&mut self, label: &Option<String>, condition: &Option<IrExpression>, body: &Block) -> Flow<LiteralValue> {
+        loop {
+            if let Some(condition) = condition.as_ref() {
+                if let LiteralValue::Boolean(false) = self.evaluate(condition)? {
+                    break Ok(LiteralValue::Void);
+                }
             }
-            IrExpression::If(condition, consequence, alternative) => {
-                let condition = self.eval_expression(condition, position)?;
-
-                match condition {
-                    LiteralValue::Boolean(true) => self.eval_expression(consequence, position),
-                    LiteralValue::Boolean(false) => match *alternative.clone() {
-                        Some(alternative) => self.eval_expression(&alternative, position),
-                        None => Ok(LiteralValue::Boolean(false)),
-                    },
-                    _ => unreachable!(),
+
+            if let Some(value) = self.iterate(label, body, IrEnvironment::new(Some(self.environment.clone())))? {
+                break Ok(value);
+            }
+        }
+    }
+
+    fn eval_for(&mut self, label: &Option<String>, name: &str, array: &IrExpression, body: &Block) -> Flow<LiteralValue> {
+        let elements = match self.evaluate(array)? {
+            LiteralValue::Array(elements) => elements,
+            value => return Err(RuntimeError::new(RuntimeErrorKind::NotAnArray(value.to_string()), array.span).into()),
+        };
+
+        for element in elements.iter() {
+            let mut environment = IrEnvironment::new(Some(self.environment.clone()));
+            environment.set(name.to_string(), self.element(element)?);
+
+            if let Some(value) = self.iterate(label, body, environment)? {
+                return Ok(value);
+            }
+        }
+
+        Ok(LiteralValue::Void)
+    }
+
+    fn eval_match(&mut self, value: &IrExpression, arms: &[(IrPattern, Option<IrExpression>, IrExpression)], span: Span) -> Flow<LiteralValue> {
+        let value = self.evaluate(value)?;
+
+        for (pattern, guard, body) in arms.iter() {
+            let Some(bindings) = self.match_pattern(pattern, &value)? else {
+                continue;
+            };
+
+            // the names a pattern binds are seen by its guard and body only
+            let mut environment = IrEnvironment::new(Some(self.environment.clone()));
+
+            for (name, value) in bindings {
+                environment.set(name, value);
+            }
+
+            let mut arm = self.nested(environment);
+
+            if let Some(guard) = guard {
+                if let LiteralValue::Boolean(false) = arm.evaluate(guard)? {
+                    continue;
                 }
             }
-            IrExpression::Call(function, arguments) => {
-                let arguments = arguments
-                    .iter()
-                    .map(|argument| self.eval_expression(argument, position))
-                    .collect::<std::result::Result<Vec<_>, _>>()?;
-
-                let function = match *function.clone() {
-                    IrExpression::Identifier(name) => match self.environment.get(name.clone()) {
-                        Some(value) => value,
-                        None => {
-                            return match builtin_function(&name) {
-                                Some(function) => Ok(function(arguments.iter().collect())),
-                                None => Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(name.to_string()), *position)),
-                            };
-                        }
-                    },
-                    _ => self.eval_expression(function, position)?,
-                };
 
-                let (parameters, body, mut environment) = match function {
-                    LiteralValue::Function(parameters, block, _, environment) => (
-                        parameters.iter().map(|parameter| parameter.name.value.clone()).collect::<Vec<_>>(),
-                        block,
-                        match environment {
-                            Some(environment) => environment,
-                            None => IrEnvironment::new(Some(self.environment.clone())),
-                        },
-                    ),
-                    value => return Err(RuntimeError::new(RuntimeErrorKind::NotAFunction(value.to_string()), *position)),
-                };
+            return arm.evaluate(body);
+        }
 
-                for (parameter, argument) in parameters.iter().zip(arguments.iter()) {
-                    environment.set(parameter.clone(), argument.clone());
+        // matches are checked to be exhaustive when compiling
+        Err(RuntimeError::new(RuntimeErrorKind::NoMatchingArm(value.to_string()), span).into())
+    }
+
+    /// The values the names in `pattern` are bound to if `value` matches it.
+    fn match_pattern(&mut self, pattern: &IrPattern, value: &LiteralValue) -> Flow<Option<Vec<(String, LiteralValue)>>> {
+        let mut bindings = Vec::new();
+
+        let mut all = |interpreter: &mut Self, pairs: Vec<(&IrPattern, &IrExpression)>| -> Flow<bool> {
+            for (pattern, value) in pairs {
+                let value = interpreter.element(value)?;
+
+                match interpreter.match_pattern(pattern, &value)? {
+                    Some(mut bound) => bindings.append(&mut bound),
+                    None => return Ok(false),
                 }
+            }
 
-                let mut interpreter = IrInterpreter::new_with_environment(body, environment.clone());
-                interpreter.eval()?;
-
-                let last = match interpreter.last()? {
-                    LiteralValue::Function(parameters, body, return_type, function_environment) => LiteralValue::Function(
-                        parameters,
-                        body,
-                        return_type,
-                        Some(IrEnvironment::new(Some(match function_environment {
-                            Some(environment) => environment,
-                            None => environment,
-                        }))),
-                    ),
-                    value => value,
-                };
+            Ok(true)
+        };
 
-                Ok(last)
+        let matched = match (&pattern.pattern, value) {
+            (IrPatternKind::Wildcard, _) => true,
+            (IrPatternKind::Binding(name), value) => {
+                bindings.push((name.clone(), value.clone()));
+                true
             }
-            IrExpression::Index(left, index) => {
-                let (left, index) = (self.eval_expression(left, position)?, self.eval_expression(index, position)?);
+            (IrPatternKind::Literal(literal), value) => literal == value,
+            (IrPatternKind::Array(elements, rest), LiteralValue::Array(values)) => {
+                let fits = match rest {
+                    Some(_) => values.len() >= elements.len(),
+                    None => values.len() == elements.len(),
+                };
+
+                if !fits {
+                    return Ok(None);
+                }
+
+                // the rest is matched against an array of the elements after the first ones
+                let rest = rest.as_ref().map(|rest| {
+                    let value = LiteralValue::Array(Rc::new(values[elements.len()..].to_vec()));
 
-                match (left, index) {
-                    (LiteralValue::Array(array), LiteralValue::Number(index)) => {
-                        let index = index as usize;
+                    (&**rest, IrExpression::new(IrExpressionKind::Literal(value), rest.span))
+                });
+                let mut pairs = elements.iter().zip(values.iter()).collect::<Vec<_>>();
 
-                        match array.get(index) {
-                            Some(value) => self.eval_expression(value, position),
-                            None => Err(RuntimeError::new(RuntimeErrorKind::IndexOutOfBounds(index), *position)),
-                        }
+                if let Some((pattern, value)) = &rest {
+                    pairs.push((pattern, value));
+                }
+
+                all(self, pairs)?
+            }
+            (IrPatternKind::Struct(name, fields), LiteralValue::Struct(value_name, values)) if name == value_name => {
+                let mut pairs = Vec::new();
+
+                for (field, pattern) in fields {
+                    match values.iter().find(|(value_field, _)| value_field == field) {
+                        Some((_, value)) => pairs.push((pattern, value)),
+                        None => return Ok(None),
                     }
-                    (left, _) => Err(RuntimeError::new(RuntimeErrorKind::NotAnArray(left.to_string()), *position)),
                 }
+
+                all(self, pairs)?
+            }
+            (IrPatternKind::Enum(name, variant, payload), LiteralValue::Enum(value_name, value_variant, values)) => {
+                name == value_name
+                    && variant == value_variant
+                    && payload.len() == values.len()
+                    && all(self, payload.iter().zip(values.iter()).collect())?
             }
-            IrExpression::Prefix(operator, right) => {
-                let right = self.eval_expression(right, position)?;
+            _ => false,
+        };
+
+        Ok(matched.then_some(bindings))
+    }
+
+    /// Runs the body of the loop labeled `label` once, giving the value of a `break` out of the loop if there is one.
+    fn iterate(&mut self, label: &Option<String>, body: &Block, environment: IrEnvironment) -> Flow<Option<LiteralValue>> {
+        let targets = |target: &Option<String>| target.is_none() || target == label;
+
+        match self.run_block(body, environment) {
+            Ok(_) => Ok(None),
+            Err(Unwind::Break(target, value)) if targets(&target) => Ok(Some(*value)),
+            Err(Unwind::Continue(target)) if targets(&target) => Ok(None),
+            Err(unwind) => Err(unwind),
+        }
+    }
+}
+
+/// A step from a variable to the part of it an assignment replaces.
+enum Access {
+    Index(i64),
+    Field(String),
+}
+
+impl IrInterpreter {
+    /// Assigns to a variable, or to an element or field of one, in the scope that declares the variable, and returns the value
+    /// assigned.
+    fn eval_assign(&mut self, target: &IrExpression, operator: &TokenKind, value: &IrExpression, span: Span) -> Flow<LiteralValue> {
+        let mut places = Vec::new();
+        let mut place = target;
 
-                match (operator, right) {
-                    (TokenKind::Minus, LiteralValue::Number(right)) => Ok(LiteralValue::Number(-right)),
-                    (TokenKind::Bang, LiteralValue::Boolean(right)) => Ok(LiteralValue::Boolean(!right)),
-                    (operator, _) => Err(RuntimeError::new(RuntimeErrorKind::InvalidOperator(operator.to_string()), *position)),
+        let name = loop {
+            match &place.expression {
+                IrExpressionKind::Identifier(name) => break name,
+                IrExpressionKind::Index(left, _) | IrExpressionKind::Field(left, _) => {
+                    places.push(place);
+                    place = left;
                 }
+                _ => unreachable!("assignment targets are checked by the parser"),
             }
-            IrExpression::Infix(left, operator, right) => {
-                let (left, right) = (self.eval_expression(left, position)?, self.eval_expression(right, position)?);
-
-                match (left, right) {
-                    (LiteralValue::Number(left), LiteralValue::Number(right)) => match operator {
-                        TokenKind::Plus => Ok(LiteralValue::Number(left + right)),
-                        TokenKind::Minus => Ok(LiteralValue::Number(left - right)),
-                        TokenKind::Asterisk => Ok(LiteralValue::Number(left * right)),
-                        TokenKind::Slash => Ok(LiteralValue::Number(left / right)),
-                        TokenKind::EQ => Ok(LiteralValue::Boolean(left == right)),
-                        TokenKind::NEQ => Ok(LiteralValue::Boolean(left != right)),
-                        TokenKind::LT => Ok(LiteralValue::Boolean(left < right)),
-                        TokenKind::LTE => Ok(LiteralValue::Boolean(left <= right)),
-                        TokenKind::GT => Ok(LiteralValue::Boolean(left > right)),
-                        TokenKind::GTE => Ok(LiteralValue::Boolean(left >= right)),
-                        _ => Err(RuntimeError::new(RuntimeErrorKind::InvalidOperator(operator.to_string()), *position)),
-                    },
-                    (LiteralValue::String(left), LiteralValue::String(right)) => match operator {
-                        TokenKind::Plus => Ok(LiteralValue::String(format!("{}{}", left, right))),
-                        TokenKind::EQ => Ok(LiteralValue::Boolean(left == right)),
-                        TokenKind::NEQ => Ok(LiteralValue::Boolean(left != right)),
-                        TokenKind::LT => Ok(LiteralValue::Boolean(left < right)),
-                        TokenKind::LTE => Ok(LiteralValue::Boolean(left <= right)),
-                        TokenKind::GT => Ok(LiteralValue::Boolean(left > right)),
-                        TokenKind::GTE => Ok(LiteralValue::Boolean(left >= right)),
-                        _ => Err(RuntimeError::new(RuntimeErrorKind::InvalidOperator(operator.to_string()), *position)),
-                    },
-                    (LiteralValue::Boolean(left), LiteralValue::Boolean(right)) => match operator {
-                        TokenKind::EQ => Ok(LiteralValue::Boolean(left == right)),
-                        TokenKind::NEQ => Ok(LiteralValue::Boolean(left != right)),
-                        _ => Err(RuntimeError::new(RuntimeErrorKind::InvalidOperator(operator.to_string()), *position)),
-                    },
-                    (left, right) => Err(RuntimeError::new(
-                        RuntimeErrorKind::InvalidOperands(left.to_string(), right.to_string(), operator.to_string()),
-                        *position,
-                    )),
+        };
+
+        // indices are evaluated from the variable outwards, before the value
+        let mut accesses = Vec::new();
+
+        for place in places.into_iter().rev() {
+            accesses.push(match &place.expression {
+                IrExpressionKind::Index(_, index) => Access::Index(self.eval_int(index)?),
+                IrExpressionKind::Field(_, field) => Access::Field(field.clone()),
+                _ => unreachable!(),
+            });
+        }
+
+        let value = self.evaluate(value)?;
+        let mut assigned = None;
+
+        let updated = self.environment.update(name, |variable| {
+            Self::update(variable, &accesses, span, &mut |current| {
+                let value = match operator {
+                    TokenKind::Assign => value.clone(),
+                    operator => eval_infix(current, operator, value.clone(), span)?,
+                };
+
+                assigned = Some(value.clone());
+                Ok(value)
+            })
+        });
+
+        match updated {
+            Some(result) => result?,
+            None => return Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(name.clone()), target.span).into()),
+        }
+
+        Ok(assigned.unwrap_or(value))
+    }
+
+    /// Replaces the part of `current` reached through `accesses` with the result of `update` on it, in place unless an array
+    /// on the way is shared with another value, which is then copied first.
+    fn update(
+        current: &mut LiteralValue,
+        accesses: &[Access],
+        span: Span,
+        update: &mut dyn FnMut(LiteralValue) -> Result<LiteralValue>,
+    ) -> Result<()> {
+        let Some((access, accesses)) = accesses.split_first() else {
+            *current = update(current.clone())?;
+            return Ok(());
+        };
+
+        let element = match (current, access) {
+            (LiteralValue::Array(elements), Access::Index(index)) => match usize::try_from(*index).ok().filter(|index| *index < elements.len()) {
+                Some(index) => &mut Rc::make_mut(elements)[index],
+                None => return Err(RuntimeError::new(RuntimeErrorKind::IndexOutOfBounds(*index), span)),
+            },
+            (LiteralValue::Struct(name, fields), Access::Field(field)) => match fields.iter_mut().find(|(name, _)| name == field) {
+                Some((_, element)) => element,
+                None => return Err(RuntimeError::new(RuntimeErrorKind::UndefinedField(name.clone(), field.clone()), span)),
+            },
+            (current, Access::Index(_)) => return Err(RuntimeError::new(RuntimeErrorKind::NotAnArray(current.to_string()), span)),
+            (current, Access::Field(_)) => return Err(RuntimeError::new(RuntimeErrorKind::NotAStruct(current.to_string()), span)),
+        };
+
+        match &mut element.expression {
+            IrExpressionKind::Literal(value) => Self::update(value, accesses, span, update),
+            _ => unreachable!("the elements of a value are evaluated with it"),
+        }
+    }
+}
+
+fn eval_infix(left: LiteralValue, operator: &TokenKind, right: LiteralValue, span: Span) -> Result<LiteralValue> {
+    match (left, right) {
+        (LiteralValue::Int(left), LiteralValue::Int(right)) => {
+            let checked = |value: Option<i64>, operator: &str| match value {
+                Some(value) => Ok(LiteralValue::Int(value)),
+                None => Err(RuntimeError::new(
+                    RuntimeErrorKind::IntegerOverflow(format!("{left} {operator} {right}")),
+                    span,
+                )),
+            };
+
+            match operator {
+                TokenKind::Plus => checked(left.checked_add(right), "+"),
+                TokenKind::Minus => checked(left.checked_sub(right), "-"),
+                TokenKind::Asterisk => checked(left.checked_mul(right), "*"),
+                // dividing ints gives a float
+                TokenKind::Slash => Ok(LiteralValue::Float(left as f64 / right as f64)),
+                TokenKind::Percent if right == 0 => Err(RuntimeError::new(RuntimeErrorKind::DivisionByZero, span)),
+                TokenKind::Percent => checked(left.checked_rem(right), "%"),
+                TokenKind::DoubleAsterisk if right < 0 => {
+                    Err(RuntimeError::new(RuntimeErrorKind::NegativeExponent(format!("{left} ** {right}")), span))
                 }
+                TokenKind::DoubleAsterisk => checked(u32::try_from(right).ok().and_then(|right| left.checked_pow(right)), "**"),
+                TokenKind::Ampersand => Ok(LiteralValue::Int(left & right)),
+                TokenKind::Pipe => Ok(LiteralValue::Int(left | right)),
+                TokenKind::Caret => Ok(LiteralValue::Int(left ^ right)),
+                // shifting by a negative amount, or by 64 bits or more, is an overflow
+                TokenKind::LShift => checked(u32::try_from(right).ok().and_then(|right| left.checked_shl(right)), "<<"),
+                TokenKind::RShift => checked(u32::try_from(right).ok().and_then(|right| left.checked_shr(right)), ">>"),
+                TokenKind::EQ => Ok(LiteralValue::Boolean(left == right)),
+                TokenKind::NEQ => Ok(LiteralValue::Boolean(left != right)),
+                TokenKind::LT => Ok(LiteralValue::Boolean(left < right)),
+                TokenKind::LTE => Ok(LiteralValue::Boolean(left <= right)),
+                TokenKind::GT => Ok(LiteralValue::Boolean(left > right)),
+                TokenKind::GTE => Ok(LiteralValue::Boolean(left >= right)),
+                _ => Err(RuntimeError::new(RuntimeErrorKind::InvalidOperator(operator.to_string()), span)),
             }
         }
+        (LiteralValue::Float(left), LiteralValue::Float(right)) => match operator {
+            TokenKind::Plus => Ok(LiteralValue::Float(left + right)),
+            TokenKind::Minus => Ok(LiteralValue::Float(left - right)),
+            TokenKind::Asterisk => Ok(LiteralValue::Float(left * right)),
+            TokenKind::Slash => Ok(LiteralValue::Float(left / right)),
+            TokenKind::Percent => Ok(LiteralValue::Float(left % right)),
+            TokenKind::DoubleAsterisk => Ok(LiteralValue::Float(left.powf(right))),
+            TokenKind::EQ => Ok(LiteralValue::Boolean(left == right)),
+            TokenKind::NEQ => Ok(LiteralValue::Boolean(left != right)),
+            TokenKind::LT => Ok(LiteralValue::Boolean(left < right)),
+            TokenKind::LTE => Ok(LiteralValue::Boolean(left <= right)),
+            TokenKind::GT => Ok(LiteralValue::Boolean(left > right)),
+            TokenKind::GTE => Ok(LiteralValue::Boolean(left >= right)),
+            _ => Err(RuntimeError::new(RuntimeErrorKind::InvalidOperator(operator.to_string()), span)),
+        },
+        (LiteralValue::String(left), LiteralValue::String(right)) => match operator {
+            TokenKind::Plus => Ok(LiteralValue::String(format!("{}{}", left, right))),
+            TokenKind::EQ => Ok(LiteralValue::Boolean(left == right)),
+            TokenKind::NEQ => Ok(LiteralValue::Boolean(left != right)),
+            TokenKind::LT => Ok(LiteralValue::Boolean(left < right)),
+            TokenKind::LTE => Ok(LiteralValue::Boolean(left <= right)),
+            TokenKind::GT => Ok(LiteralValue::Boolean(left > right)),
+            TokenKind::GTE => Ok(LiteralValue::Boolean(left >= right)),
+            _ => Err(RuntimeError::new(RuntimeErrorKind::InvalidOperator(operator.to_string()), span)),
+        },
+        (LiteralValue::Boolean(left), LiteralValue::Boolean(right)) => match operator {
+            TokenKind::EQ => Ok(LiteralValue::Boolean(left == right)),
+            TokenKind::NEQ => Ok(LiteralValue::Boolean(left != right)),
+            _ => Err(RuntimeError::new(RuntimeErrorKind::InvalidOperator(operator.to_string()), span)),
+        },
+        (LiteralValue::Void, LiteralValue::Void) => match operator {
+            TokenKind::EQ => Ok(LiteralValue::Boolean(true)),
+            TokenKind::NEQ => Ok(LiteralValue::Boolean(false)),
+            _ => Err(RuntimeError::new(RuntimeErrorKind::InvalidOperator(operator.to_string()), span)),
+        },
+        (left, right) => Err(RuntimeError::new(
+            RuntimeErrorKind::InvalidOperands(left.to_string(), right.to_string(), operator.to_string()),
+            span,
+        )),
     }
 }
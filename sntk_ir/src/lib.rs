@@ -1,4 +1,8 @@
-use sntk_core::parser::ast::Position;
+use sntk_core::{
+    diagnostic::{Diagnostic, Label},
+    parser::ast::Span,
+};
+use std::fmt;
 use thiserror::Error;
 
 pub mod builtin;
@@ -8,12 +12,18 @@ pub mod interpreter;
 #[derive(Debug, Clone)]
 pub struct RuntimeError {
     pub message: RuntimeErrorKind,
-    pub position: Position,
+    pub span: Span,
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
+    }
 }
 
 impl RuntimeError {
-    pub fn new(message: RuntimeErrorKind, position: Position) -> Self {
-        Self { message, position }
+    pub fn new(message: RuntimeErrorKind, span: Span) -> Self {
+        Self { message, span }
     }
 }
 
@@ -22,8 +32,48 @@ impl RuntimeError {
 pub enum RuntimeErrorKind {
     #[error("Undefined variable `{0}`")] UndefinedVariable(String),
     #[error("`{0}` is not a function")] NotAFunction(String),
-    #[error("`{0}` is not a array")] NotAnArray(String),
+    #[error("`{0}` is not an array")] NotAnArray(String),
+    #[error("`{0}` is not a struct")] NotAStruct(String),
+    #[error("Undefined field `{1}` in `{0}`")] UndefinedField(String, String),
     #[error("Invalid operator `{0}`")] InvalidOperator(String),
     #[error("Invalid operands `{0}` and `{1}` for operator `{2}`")] InvalidOperands(String, String, String),
-    #[error("Index out of bounds `{0}`")] IndexOutOfBounds(usize),
+    #[error("Index out of bounds `{0}`")] IndexOutOfBounds(i64),
+    #[error("Integer overflow in `{0}`")] IntegerOverflow(String),
+    #[error("Division by zero")] DivisionByZero,
+    #[error("Negative exponent in `{0}`")] NegativeExponent(String),
+    #[error("No arm matches `{0}`")] NoMatchingArm(String),
+    #[error("`{0}` is not a boolean")] NotABoolean(String),
+    #[error("Calls and expressions nested more than {0} deep")] DepthExceeded(usize),
+    #[error("`{0}` is not an int")] NotAnInt(String),
+}
+
+impl RuntimeErrorKind {
+    /// A stable code identifying the kind of error, never reused once assigned.
+    pub fn code(&self) -> &'static str {
+        match self {
+            RuntimeErrorKind::UndefinedVariable(_) => "E0200",
+            RuntimeErrorKind::NotAFunction(_) => "E0201",
+            RuntimeErrorKind::NotAnArray(_) => "E0202",
+            RuntimeErrorKind::InvalidOperator(_) => "E0203",
+            RuntimeErrorKind::InvalidOperands(..) => "E0204",
+            RuntimeErrorKind::IndexOutOfBounds(_) => "E0205",
+            RuntimeErrorKind::NotAStruct(_) => "E0206",
+            RuntimeErrorKind::UndefinedField(..) => "E0207",
+            RuntimeErrorKind::IntegerOverflow(_) => "E0208",
+            RuntimeErrorKind::DivisionByZero => "E0209",
+            RuntimeErrorKind::NegativeExponent(_) => "E0210",
+            RuntimeErrorKind::NoMatchingArm(_) => "E0211",
+            RuntimeErrorKind::NotABoolean(_) => "E0212",
+            RuntimeErrorKind::DepthExceeded(_) => "E0213",
+            RuntimeErrorKind::NotAnInt(_) => "E0214",
+        }
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Self {
+        Diagnostic::error(error.message.code(), error.message.to_string())
+            .with_label(Label::primary(error.span, None))
+            .with_note("this error occurred while running the program".to_string())
+    }
 }
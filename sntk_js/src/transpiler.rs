@@ -0,0 +1,1132 @@
+use sntk_core::{diagnostic::json_string, tokenizer::token::TokenKind};
+use sntk_ir::instruction::{Block, Instruction, InstructionType, IrExpression, IrExpressionKind, IrPattern, IrPatternKind, LiteralValue};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+};
+
+/// Binding powers of the emitted JavaScript, following the ECMAScript operator precedence table.
+mod precedence {
+    pub const LOWEST: u8 = 0;
+    pub const ASSIGNMENT: u8 = 2; /* arrow functions, conditionals */
+    pub const OR: u8 = 3;
+    pub const AND: u8 = 4;
+    pub const BITWISE_OR: u8 = 5;
+    pub const BITWISE_XOR: u8 = 6;
+    pub const BITWISE_AND: u8 = 7;
+    pub const EQUALITY: u8 = 8;
+    pub const RELATIONAL: u8 = 9;
+    pub const SHIFT: u8 = 10;
+    pub const ADDITIVE: u8 = 11;
+    pub const MULTIPLICATIVE: u8 = 12;
+    pub const EXPONENT: u8 = 13;
+    pub const PREFIX: u8 = 14;
+    pub const CALL: u8 = 17; /* calls, member access */
+    pub const PRIMARY: u8 = 20;
+}
+
+const INDENT: &str = "    ";
+
+/// Definitions of the builtins that have no JavaScript counterpart, by their emitted names, put ahead of a program that uses them.
+/// `println` shows values as the interpreter does, though structs and enums lose their names.
+const RUNTIME: &[(&str, &str)] = &[
+    (
+        "$show",
+        "const $show = (value) => {
+    switch (typeof value) {
+        case \"number\":
+            return Number.isInteger(value) ? value.toFixed(1) : String(value);
+        case \"undefined\":
+            return \"void\";
+        case \"object\":
+            if (Array.isArray(value)) return `[${value.map($show).join(\", \")}]`;
+            if (\"tag\" in value) return value.values.length === 0 ? value.tag : `${value.tag}(${value.values.map($show).join(\", \")})`;
+            return `{ ${Object.entries(value).map(([field, value]) => `${field}: ${$show(value)}`).join(\", \")} }`;
+        default:
+            return String(value);
+    }
+};",
+    ),
+    ("$println", "const $println = (...values) => console.log(values.map($show).join(\" \"));"),
+    (
+        "$to_int",
+        "const $to_int = (float) => {
+    if (!(float >= -(2 ** 63) && float < 2 ** 63)) throw new RangeError(`Integer overflow in \\`to_int(${float})\\``);
+    return BigInt(Math.trunc(float));
+};",
+    ),
+    (
+        "$int",
+        "const $int = (value) => {
+    if (typeof value === \"bigint\" && BigInt.asIntN(64, value) !== value) throw new RangeError(\"Integer overflow\");
+    return value;
+};",
+    ),
+    (
+        "$shift",
+        "const $shift = (amount) => {
+    if (amount < 0n || amount >= 64n) throw new RangeError(`Integer overflow in a shift by \\`${amount}\\``);
+    return amount;
+};",
+    ),
+    (
+        "$at",
+        "const $at = (array, index) => {
+    if (index < 0n || index >= array.length) throw new RangeError(`Index out of bounds \\`${index}\\``);
+    return Number(index);
+};",
+    ),
+    ("$index", "const $index = (array, index) => array[$at(array, index)];"),
+];
+
+/// Identifiers that are valid in sntk but reserved (or load-bearing) in JavaScript, emitted with a `$` prefix.
+const RESERVED: &[&str] = &[
+    "arguments",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "console",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "enum",
+    "eval",
+    "export",
+    "extends",
+    "finally",
+    "for",
+    "function",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "try",
+    "undefined",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+    "Infinity",
+    "Math",
+    "NaN",
+    "Number",
+];
+
+/// Emits ES2023 source for compiled instructions.
+///
+/// Blocks, `if` expressions and loops whose value is bound to a variable become statements that assign the value to it. Inside
+/// other expressions they become conditionals where possible and immediately invoked arrow functions otherwise. A block without
+/// a value evaluates to `undefined`.
+///
+/// `int`s become BigInts, which `<<` cuts back to 64 bits and `/` converts to numbers, as it gives a float. Other arithmetic
+/// that leaves the 64 bits of an `int`, shifts by a negative amount or by 64 bits or more, and indices out of bounds throw a
+/// `RangeError`, where the interpreter stops with an error.
+///
+/// Arrays and structs are values in sntk, so assigning to an element or field builds a new array or object for the variable
+/// instead of changing one that may be shared.
+///
+/// `break` and `continue` cannot leave an immediately invoked function, so those that would are left for the JavaScript engine
+/// to reject; a `return` only leaves the immediately invoked function.
+#[derive(Debug, Clone)]
+pub struct Transpiler {
+    pub instructions: Vec<Instruction>,
+    scopes: Vec<HashSet<String>>,
+    assigned: HashSet<String>,
+    loops: Vec<Option<(Option<String>, Tail)>>, /* label and where a `break` sends its value, `None` for a function */
+    depth: usize,
+    modules: HashMap<String, String>, /* emitted names of the modules run so far, by their paths */
+    runtime: BTreeSet<&'static str>,  /* emitted names of the builtins used */
+}
+
+/// Where the value of a block goes.
+#[derive(Debug, Clone)]
+enum Tail {
+    Discard,
+    Return,
+    Assign(String), /* emitted name of the variable */
+}
+
+impl Transpiler {
+    pub fn new(instructions: Vec<Instruction>) -> Self {
+        Self {
+            instructions,
+            scopes: Vec::new(),
+            assigned: HashSet::new(),
+            loops: Vec::new(),
+            depth: 0,
+            modules: HashMap::new(),
+            runtime: BTreeSet::new(),
+        }
+    }
+
+    pub fn transpile(&mut self) -> String {
+        let instructions = self.instructions.clone();
+
+        for instruction in instructions.iter() {
+            assignments(instruction, &mut self.assigned);
+        }
+
+        self.scopes.push(bindings(&instructions, &[]));
+        let statements = self.statements(&instructions, &[], &Tail::Discard);
+        self.scopes.pop();
+
+        let runtime = RUNTIME
+            .iter()
+            .filter(|(name, _)| self.runtime.contains(name))
+            .map(|(_, definition)| definition.to_string());
+
+        runtime.chain(statements).map(|statement| format!("{statement}\n")).collect()
+    }
+
+    fn statements(&mut self, block: &Block, parameters: &[String], tail: &Tail) -> Vec<String> {
+        let mut stores = HashMap::<&String, usize>::new();
+
+        for instruction in block.iter() {
+            if let InstructionType::StoreName(name, ..) = &instruction.instruction {
+                *stores.entry(name).or_default() += 1;
+            }
+        }
+
+        let mut declared = parameters.iter().collect::<HashSet<_>>();
+        let mut statements = Vec::new();
+
+        for instruction in block.iter() {
+            match &instruction.instruction {
+                InstructionType::StoreName(name, value, ..) => {
+                    // names bound again in the same block or assigned to are declared once with `let` and reassigned after that
+                    let keyword = match (declared.insert(name), stores[name] > 1 || self.assigned.contains(name)) {
+                        (false, _) => "",
+                        (true, true) => "let ",
+                        (true, false) => "const ",
+                    };
+
+                    // a value that needs statements is assigned from inside them, unless a binding there would hide the variable
+                    if is_statement(value) && !binds(value, name) {
+                        if !keyword.is_empty() {
+                            statements.push(format!("let {};", identifier(name)));
+                        }
+
+                        statements.extend(self.tail(value, &Tail::Assign(identifier(name))));
+                    } else {
+                        statements.push(format!(
+                            "{keyword}{} = {};",
+                            identifier(name),
+                            self.expression(value, precedence::ASSIGNMENT)
+                        ));
+                    }
+                }
+                // a module runs in a function of its own, which gives back its exported values
+                InstructionType::Module(path, block, exports) => {
+                    let name = format!("$module{}", self.modules.len());
+                    let (module, _) = self.invoked(|transpiler| {
+                        transpiler.scopes.push(bindings(block, &[]));
+                        let body = transpiler.body(|transpiler| {
+                            let mut statements = transpiler.statements(block, &[], &Tail::Discard);
+                            statements.push(format!(
+                                "return {{ {} }};",
+                                exports.iter().map(|name| property(name)).collect::<Vec<_>>().join(", ")
+                            ));
+                            statements
+                        });
+                        transpiler.scopes.pop();
+
+                        body
+                    });
+
+                    statements.push(format!("const {name} = {module};"));
+                    self.modules.insert(path.clone(), name);
+                }
+                // an import of types only has nothing to bind
+                InstructionType::Import(_, names) if names.is_empty() => {}
+                InstructionType::Import(path, names) => {
+                    let keyword = match names.iter().any(|name| stores.contains_key(name) || self.assigned.contains(name)) {
+                        true => "let",
+                        false => "const",
+                    };
+                    declared.extend(names);
+
+                    statements.push(format!(
+                        "{keyword} {{ {} }} = {};",
+                        names.iter().map(|name| property(name)).collect::<Vec<_>>().join(", "),
+                        self.modules[path]
+                    ));
+                }
+                InstructionType::Value(value) => statements.extend(self.tail(value, tail)),
+                InstructionType::Return(value) => statements.extend(self.tail(value, &Tail::Return)),
+                InstructionType::Expression(value) => statements.extend(self.tail(value, &Tail::Discard)),
+                InstructionType::DeclareName(..) | InstructionType::DeclareType(..) | InstructionType::None => {}
+            }
+        }
+
+        statements
+    }
+
+    /// Statements evaluating an expression and sending its value where `tail` says, so the branches of an `if` or the `break`s
+    /// out of a loop can each send their own.
+    fn tail(&mut self, expression: &IrExpression, tail: &Tail) -> Vec<String> {
+        match &expression.expression {
+            IrExpressionKind::Block(block) => match value(block) {
+                Some(value) => self.tail(value, tail),
+                None => vec![self.block(block, &[], tail)],
+            },
+            // an `if` without an `else` has no value, whichever way it goes
+            IrExpressionKind::If(condition, consequence, alternative) if alternative.is_none() && !matches!(tail, Tail::Discard) => {
+                let mut statements = vec![self.conditional(condition, consequence, alternative, &Tail::Discard)];
+
+                match tail {
+                    Tail::Return => statements.push("return;".to_string()),
+                    Tail::Assign(name) => statements.push(format!("{name} = undefined;")),
+                    Tail::Discard => {}
+                }
+
+                statements
+            }
+            IrExpressionKind::If(condition, consequence, alternative)
+                if matches!(tail, Tail::Discard) || !is_conditional_expression(consequence, alternative) =>
+            {
+                vec![self.conditional(condition, consequence, alternative, tail)]
+            }
+            IrExpressionKind::Loop(..) | IrExpressionKind::For(..) => vec![self.repetition(expression, tail)],
+            IrExpressionKind::Break(..) | IrExpressionKind::Continue(_) => self.jump(expression),
+            IrExpressionKind::Match(value, arms) => match &value.expression {
+                IrExpressionKind::Identifier(_) => self.matching(value, arms, tail),
+                // in a block of its own, so that the matches of a block do not declare `$match` twice
+                _ => vec![self.body(|transpiler| transpiler.matching(value, arms, tail))],
+            },
+            IrExpressionKind::Assign(target, operator, value) if matches!(tail, Tail::Discard) => {
+                vec![format!("{};", self.assignment(target, operator, value, false).0)]
+            }
+            _ => vec![match tail {
+                Tail::Discard => match self.expression(expression, precedence::LOWEST) {
+                    // an object literal would be read as a block at the start of a statement
+                    expression if expression.starts_with('{') => format!("({expression});"),
+                    expression => format!("{expression};"),
+                },
+                Tail::Return if matches!(expression.expression, IrExpressionKind::Literal(LiteralValue::Void)) => "return;".to_string(),
+                Tail::Return => format!("return {};", self.expression(expression, precedence::LOWEST)),
+                Tail::Assign(name) => format!("{name} = {};", self.expression(expression, precedence::ASSIGNMENT)),
+            }],
+        }
+    }
+
+    fn conditional(&mut self, condition: &IrExpression, consequence: &IrExpression, alternative: &Option<IrExpression>, tail: &Tail) -> String {
+        let mut statement = format!(
+            "if ({}) {}",
+            self.expression(condition, precedence::LOWEST),
+            self.branch(consequence, tail)
+        );
+
+        if let Some(alternative) = alternative {
+            statement.push_str(" else ");
+
+            // `else if` is compiled to an alternative holding nothing but the value of the inner `if`
+            match &alternative.expression {
+                IrExpressionKind::Block(block) => match block.as_slice() {
+                    [Instruction {
+                        instruction:
+                            InstructionType::Value(IrExpression {
+                                expression: IrExpressionKind::If(condition, consequence, alternative),
+                                ..
+                            }),
+                        ..
+                    }] => statement.push_str(&self.conditional(condition, consequence, alternative, tail)),
+                    _ => statement.push_str(&self.branch(alternative, tail)),
+                },
+                _ => statement.push_str(&self.branch(alternative, tail)),
+            }
+        }
+
+        statement
+    }
+
+    fn branch(&mut self, branch: &IrExpression, tail: &Tail) -> String {
+        match &branch.expression {
+            IrExpressionKind::Block(block) => self.block(block, &[], tail),
+            _ => self.body(|transpiler| transpiler.tail(branch, tail)),
+        }
+    }
+
+    /// A `break` out of the loop sends its value where `tail` says.
+    fn repetition(&mut self, expression: &IrExpression, tail: &Tail) -> String {
+        let (label, header, body, parameters) = match &expression.expression {
+            IrExpressionKind::Loop(label, condition, body) => {
+                let condition = match condition.as_ref() {
+                    Some(condition) => self.expression(condition, precedence::LOWEST),
+                    None => "true".to_string(),
+                };
+
+                (label, format!("while ({condition})"), body, Vec::new())
+            }
+            IrExpressionKind::For(label, name, iterable, body) => {
+                let keyword = match bindings(body, &[]).contains(name) {
+                    true => "let",
+                    false => "const",
+                };
+
+                (
+                    label,
+                    format!(
+                        "for ({keyword} {} of {})",
+                        identifier(name),
+                        self.expression(iterable, precedence::ASSIGNMENT)
+                    ),
+                    body,
+                    vec![name.clone()],
+                )
+            }
+            _ => unreachable!(),
+        };
+
+        self.loops.push(Some((label.clone(), tail.clone())));
+        let body = self.block(body, &parameters, &Tail::Discard);
+        self.loops.pop();
+
+        match label {
+            Some(label) => format!("{}: {header} {body}", identifier(label)),
+            None => format!("{header} {body}"),
+        }
+    }
+
+    fn jump(&mut self, expression: &IrExpression) -> Vec<String> {
+        let (keyword, label, value) = match &expression.expression {
+            IrExpressionKind::Break(label, value) => ("break", label, value.as_ref().as_ref()),
+            IrExpressionKind::Continue(label) => ("continue", label, None),
+            _ => unreachable!(),
+        };
+
+        let tail = match keyword {
+            "break" => self
+                .loops
+                .iter()
+                .rev()
+                .map_while(Option::as_ref)
+                .find(|(name, _)| label.is_none() || name == label)
+                .map(|(_, tail)| tail.clone()),
+            _ => None,
+        };
+
+        let jump = match label {
+            Some(label) => format!("{keyword} {};", identifier(label)),
+            None => format!("{keyword};"),
+        };
+
+        match (tail.unwrap_or(Tail::Discard), value) {
+            (Tail::Return, Some(value)) => vec![format!("return {};", self.expression(value, precedence::LOWEST))],
+            (Tail::Return, None) => vec!["return;".to_string()],
+            (tail, value) => {
+                let mut statements = value.map(|value| self.tail(value, &tail)).unwrap_or_default();
+                statements.push(jump);
+                statements
+            }
+        }
+    }
+
+    /// An `if` for each arm, testing its pattern and guard, which declares the names the pattern binds before the arm's body.
+    /// The value matched is evaluated once, into `$match` unless it is a variable, and the last arm is the `else`, since matches
+    /// are exhaustive.
+    fn matching(&mut self, value: &IrExpression, arms: &[(IrPattern, Option<IrExpression>, IrExpression)], tail: &Tail) -> Vec<String> {
+        let (mut statements, subject) = match &value.expression {
+            IrExpressionKind::Identifier(name) => (Vec::new(), self.reference(name)),
+            _ => (
+                vec![format!("const $match = {};", self.expression(value, precedence::ASSIGNMENT))],
+                "$match".to_string(),
+            ),
+        };
+
+        let mut chain = String::new();
+
+        for (index, (pattern, guard, body)) in arms.iter().enumerate() {
+            let (mut tests, mut names) = (Vec::new(), Vec::new());
+            self.pattern(pattern, &subject, &mut tests, &mut names);
+
+            let scope = names.iter().map(|(name, _)| name.clone()).collect::<HashSet<_>>();
+
+            // the guard sees the names too, as the parameters of an arrow function
+            if let Some(guard) = guard {
+                tests.push(match names.is_empty() {
+                    true => self.expression(guard, precedence::AND + 1),
+                    false => {
+                        self.scopes.push(scope.clone());
+                        self.loops.push(None);
+                        let guard = self.expression(guard, precedence::ASSIGNMENT);
+                        self.loops.pop();
+                        self.scopes.pop();
+
+                        let (parameters, arguments): (Vec<_>, Vec<_>) = names.iter().map(|(name, access)| (identifier(name), access.clone())).unzip();
+
+                        format!("(({}) => {guard})({})", parameters.join(", "), arguments.join(", "))
+                    }
+                });
+            }
+
+            let body = match names.is_empty() {
+                true => self.branch(body, tail),
+                false => {
+                    self.scopes.push(scope);
+                    let body = self.body(|transpiler| {
+                        names
+                            .iter()
+                            .map(|(name, access)| format!("const {} = {access};", identifier(name)))
+                            .chain(transpiler.tail(body, tail))
+                            .collect()
+                    });
+                    self.scopes.pop();
+
+                    body
+                }
+            };
+
+            let last = index + 1 == arms.len() && guard.is_none();
+
+            match (tests.is_empty() || last, chain.is_empty()) {
+                (true, true) => chain = body,
+                (true, false) => chain.push_str(&format!(" else {body}")),
+                (false, true) => chain = format!("if ({}) {body}", tests.join(" && ")),
+                (false, false) => chain.push_str(&format!(" else if ({}) {body}", tests.join(" && "))),
+            }
+
+            // the arms after one that matches anything are never reached
+            if tests.is_empty() {
+                break;
+            }
+        }
+
+        statements.push(chain);
+        statements
+    }
+
+    /// Collects the tests a value, emitted as `access`, passes if it matches a pattern, and where the names it binds are found.
+    fn pattern(&mut self, pattern: &IrPattern, access: &str, tests: &mut Vec<String>, names: &mut Vec<(String, String)>) {
+        match &pattern.pattern {
+            IrPatternKind::Wildcard => {}
+            IrPatternKind::Binding(name) => names.push((name.clone(), access.to_string())),
+            IrPatternKind::Literal(literal) => tests.push(format!("{access} === {}", self.literal(literal).0)),
+            IrPatternKind::Array(elements, rest) => {
+                match rest {
+                    Some(_) if elements.is_empty() => {}
+                    Some(_) => tests.push(format!("{access}.length >= {}", elements.len())),
+                    None => tests.push(format!("{access}.length === {}", elements.len())),
+                }
+
+                for (index, element) in elements.iter().enumerate() {
+                    self.pattern(element, &format!("{access}[{index}]"), tests, names);
+                }
+
+                if let Some(rest) = rest {
+                    self.pattern(rest, &format!("{access}.slice({})", elements.len()), tests, names);
+                }
+            }
+            // the type of a struct is known when compiling, so only its fields are tested
+            IrPatternKind::Struct(_, fields) => {
+                for (field, pattern) in fields.iter() {
+                    self.pattern(pattern, &format!("{access}.{field}"), tests, names);
+                }
+            }
+            IrPatternKind::Enum(_, variant, payload) => {
+                tests.push(format!("{access}.tag === {}", json_string(variant)));
+
+                for (index, pattern) in payload.iter().enumerate() {
+                    self.pattern(pattern, &format!("{access}.values[{index}]"), tests, names);
+                }
+            }
+        }
+    }
+
+    /// An immediately invoked arrow function, which `break` and `continue` cannot leave.
+    fn invoked(&mut self, body: impl FnOnce(&mut Self) -> String) -> (String, u8) {
+        self.loops.push(None);
+        let body = body(self);
+        self.loops.pop();
+
+        (format!("(() => {body})()"), precedence::CALL)
+    }
+
+    fn block(&mut self, block: &Block, parameters: &[String], tail: &Tail) -> String {
+        self.scopes.push(bindings(block, parameters));
+        let block = self.body(|transpiler| transpiler.statements(block, parameters, tail));
+        self.scopes.pop();
+
+        block
+    }
+
+    /// Braces around statements built one level deeper than the current one.
+    fn body(&mut self, statements: impl FnOnce(&mut Self) -> Vec<String>) -> String {
+        self.depth += 1;
+        let statements = statements(self);
+        self.depth -= 1;
+
+        if statements.is_empty() {
+            return "{}".to_string();
+        }
+
+        let indent = INDENT.repeat(self.depth);
+
+        format!(
+            "{{\n{}{indent}}}",
+            statements
+                .iter()
+                .map(|statement| format!("{indent}{INDENT}{statement}\n"))
+                .collect::<String>()
+        )
+    }
+
+    /// Emits an expression, parenthesized if it binds looser than `context`.
+    fn expression(&mut self, expression: &IrExpression, context: u8) -> String {
+        let (expression, precedence) = match &expression.expression {
+            IrExpressionKind::Identifier(name) => (self.reference(name), precedence::PRIMARY),
+            IrExpressionKind::Literal(literal) => self.literal(literal),
+            IrExpressionKind::Block(block) => match value(block) {
+                Some(value) => return self.expression(value, context),
+                None => self.invoked(|transpiler| transpiler.block(block, &[], &Tail::Return)),
+            },
+            IrExpressionKind::If(condition, consequence, alternative) => {
+                match (branch_value(consequence), alternative.as_ref().as_ref().map(branch_value)) {
+                    (Some(consequence), None) => (
+                        format!(
+                            "{} ? void {} : undefined",
+                            self.expression(condition, precedence::ASSIGNMENT + 1),
+                            self.expression(consequence, precedence::PREFIX)
+                        ),
+                        precedence::ASSIGNMENT,
+                    ),
+                    (Some(consequence), Some(Some(alternative))) => (
+                        format!(
+                            "{} ? {} : {}",
+                            self.expression(condition, precedence::ASSIGNMENT + 1),
+                            self.expression(consequence, precedence::ASSIGNMENT),
+                            self.expression(alternative, precedence::ASSIGNMENT)
+                        ),
+                        precedence::ASSIGNMENT,
+                    ),
+                    _ => self.invoked(|transpiler| {
+                        transpiler.body(|transpiler| vec![transpiler.conditional(condition, consequence, alternative, &Tail::Return)])
+                    }),
+                }
+            }
+            IrExpressionKind::Call(function, arguments) => (
+                format!(
+                    "{}({})",
+                    self.expression(function, precedence::CALL),
+                    self.arguments(arguments).join(", ")
+                ),
+                precedence::CALL,
+            ),
+            IrExpressionKind::Index(left, index) => {
+                self.runtime.extend(["$at", "$index"]);
+
+                (
+                    format!(
+                        "$index({}, {})",
+                        self.expression(left, precedence::ASSIGNMENT),
+                        self.expression(index, precedence::ASSIGNMENT)
+                    ),
+                    precedence::CALL,
+                )
+            }
+            IrExpressionKind::Field(left, field) => (format!("{}.{field}", self.expression(left, precedence::CALL)), precedence::CALL),
+            IrExpressionKind::Prefix(operator, right) => {
+                let operator = match operator {
+                    TokenKind::Minus => "-",
+                    TokenKind::Bang => "!",
+                    TokenKind::Tilde => "~",
+                    operator => unreachable!("unexpected prefix operator `{operator}`"),
+                };
+
+                let negated = match self.expression(right, precedence::PREFIX) {
+                    // `- -x` must not become the decrement `--x`
+                    right if right.starts_with(operator) && operator == "-" => format!("-({right})"),
+                    right => format!("{operator}{right}"),
+                };
+
+                // negating the least `int` overflows
+                match operator {
+                    "-" => self.checked(negated),
+                    _ => (negated, precedence::PREFIX),
+                }
+            }
+            IrExpressionKind::Infix(left, operator, right) | IrExpressionKind::Logical(left, operator, right) => {
+                let left = self.expression(
+                    left,
+                    match operator {
+                        // a prefix expression on the left of `**` is a syntax error
+                        TokenKind::DoubleAsterisk => precedence::PREFIX + 1,
+                        TokenKind::Slash => precedence::LOWEST,
+                        operator => infix(operator).1,
+                    },
+                );
+
+                self.operation(&left, operator, right)
+            }
+            IrExpressionKind::Assign(target, operator, value) => self.assignment(target, operator, value, true),
+            IrExpressionKind::Loop(..) | IrExpressionKind::For(..) => {
+                self.invoked(|transpiler| transpiler.body(|transpiler| vec![transpiler.repetition(expression, &Tail::Return)]))
+            }
+            IrExpressionKind::Break(..) | IrExpressionKind::Continue(_) => {
+                self.invoked(|transpiler| transpiler.body(|transpiler| transpiler.jump(expression)))
+            }
+            IrExpressionKind::Match(value, arms) => {
+                self.invoked(|transpiler| transpiler.body(|transpiler| transpiler.matching(value, arms, &Tail::Return)))
+            }
+            IrExpressionKind::Spread(arguments) => return self.expression(arguments, context),
+            // the `None` or `Err` is thrown to the enclosing function, which returns it
+            IrExpressionKind::Try(value) => (
+                format!(
+                    "(($try) => {{ if ($try.tag === \"None\" || $try.tag === \"Err\") throw {{ $return: $try }}; return $try.values[0]; }})({})",
+                    self.expression(value, precedence::ASSIGNMENT)
+                ),
+                precedence::CALL,
+            ),
+        };
+
+        if precedence < context {
+            format!("({expression})")
+        } else {
+            expression
+        }
+    }
+
+    /// `a[i].x = v` becomes `a = a.with(i, { ...a[i], x: v })`, read back as `(...)[i].x` when its value is used, as sntk
+    /// assignments evaluate to the assigned value. Indices that are not plain names or numbers but are needed more than once
+    /// are evaluated once, as arguments to an arrow function.
+    fn assignment(&mut self, target: &IrExpression, operator: &TokenKind, value: &IrExpression, read_back: bool) -> (String, u8) {
+        let mut places = Vec::new();
+        let mut root = target;
+
+        while let IrExpressionKind::Index(left, _) | IrExpressionKind::Field(left, _) = &root.expression {
+            places.push(root);
+            root = left;
+        }
+
+        let name = self.expression(root, precedence::PRIMARY);
+
+        if places.is_empty() {
+            let value = match operator {
+                TokenKind::Assign => self.expression(value, precedence::ASSIGNMENT),
+                operator => self.operation(&name, operator, value).0,
+            };
+
+            return (format!("{name} = {value}"), precedence::ASSIGNMENT);
+        }
+
+        let hoist = places.len() > 1 || operator != &TokenKind::Assign || read_back;
+        let mut hoisted = Vec::new();
+        let mut accesses = Vec::new();
+
+        for place in places.iter().rev() {
+            accesses.push(match &place.expression {
+                IrExpressionKind::Index(_, index) => Access::Index(match &index.expression {
+                    IrExpressionKind::Identifier(_) | IrExpressionKind::Literal(LiteralValue::Int(_)) => self.expression(index, precedence::LOWEST),
+                    _ if hoist => {
+                        hoisted.push(self.expression(index, precedence::ASSIGNMENT));
+                        format!("${}", hoisted.len() - 1)
+                    }
+                    _ => self.expression(index, precedence::LOWEST),
+                }),
+                IrExpressionKind::Field(_, field) => Access::Field(field.clone()),
+                _ => unreachable!(),
+            });
+        }
+
+        // `paths[n]` reads the place reached through the first `n` accesses
+        let mut paths = vec![name.clone()];
+
+        for access in accesses.iter() {
+            paths.push(format!("{}{access}", paths[paths.len() - 1]));
+        }
+
+        let place = &paths[accesses.len()];
+        let mut rebuilt = match operator {
+            TokenKind::Assign => self.expression(value, precedence::ASSIGNMENT),
+            operator => self.operation(place, operator, value).0,
+        };
+
+        for (access, path) in accesses.iter().zip(paths.iter()).rev() {
+            rebuilt = match access {
+                Access::Index(index) => {
+                    self.runtime.insert("$at");
+                    format!("{path}.with($at({path}, {index}), {rebuilt})")
+                }
+                Access::Field(field) => format!("{{ ...{path}, {field}: {rebuilt} }}"),
+            };
+        }
+
+        let mut assignment = (format!("{name} = {rebuilt}"), precedence::ASSIGNMENT);
+
+        if read_back {
+            let accesses = accesses.iter().map(ToString::to_string).collect::<String>();
+            assignment = (format!("({}){accesses}", assignment.0), precedence::CALL);
+        }
+
+        if !hoisted.is_empty() {
+            let temporaries = (0..hoisted.len()).map(|index| format!("${index}")).collect::<Vec<_>>();
+            assignment = (
+                format!("(({}) => {})({})", temporaries.join(", "), assignment.0, hoisted.join(", ")),
+                precedence::CALL,
+            );
+        }
+
+        assignment
+    }
+
+    /// Arguments packed into an array for a spread parameter are passed one by one again, to a rest parameter.
+    fn arguments(&mut self, arguments: &[IrExpression]) -> Vec<String> {
+        arguments
+            .iter()
+            .flat_map(|argument| match &argument.expression {
+                IrExpressionKind::Spread(arguments) => match &arguments.expression {
+                    IrExpressionKind::Literal(LiteralValue::Array(elements)) => {
+                        elements.iter().map(|element| self.expression(element, precedence::ASSIGNMENT)).collect()
+                    }
+                    _ => vec![format!("...{}", self.expression(arguments, precedence::ASSIGNMENT))],
+                },
+                _ => vec![self.expression(argument, precedence::ASSIGNMENT)],
+            })
+            .collect()
+    }
+
+    fn literal(&mut self, literal: &LiteralValue) -> (String, u8) {
+        match literal {
+            LiteralValue::Int(number) if *number < 0 => (format!("{number}n"), precedence::PREFIX),
+            LiteralValue::Int(number) => (format!("{number}n"), precedence::PRIMARY),
+            LiteralValue::Float(number) => match number {
+                number if number.is_infinite() && number.is_sign_negative() => ("-Infinity".to_string(), precedence::PREFIX),
+                number if number.is_infinite() => ("Infinity".to_string(), precedence::PRIMARY),
+                number if number.is_sign_negative() => (number.to_string(), precedence::PREFIX),
+                number => (number.to_string(), precedence::PRIMARY),
+            },
+            LiteralValue::String(string) => (json_string(string), precedence::PRIMARY),
+            LiteralValue::Boolean(boolean) => (boolean.to_string(), precedence::PRIMARY),
+            LiteralValue::Void => ("undefined".to_string(), precedence::PRIMARY),
+            LiteralValue::Array(elements) => (
+                format!(
+                    "[{}]",
+                    elements
+                        .iter()
+                        .map(|element| self.expression(element, precedence::ASSIGNMENT))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                precedence::PRIMARY,
+            ),
+            LiteralValue::Struct(_, fields) if fields.is_empty() => ("{}".to_string(), precedence::PRIMARY),
+            LiteralValue::Struct(_, fields) => (
+                format!(
+                    "{{ {} }}",
+                    fields
+                        .iter()
+                        .map(|(field, value)| format!("{field}: {}", self.expression(value, precedence::ASSIGNMENT)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                precedence::PRIMARY,
+            ),
+            // tagged with their variant, so that they can be told apart
+            LiteralValue::Enum(_, variant, payload) => (
+                format!(
+                    "{{ tag: {}, values: [{}] }}",
+                    json_string(variant),
+                    payload
+                        .iter()
+                        .map(|value| self.expression(value, precedence::ASSIGNMENT))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                precedence::PRIMARY,
+            ),
+            LiteralValue::Function(_, parameters, body, _, _) => {
+                let names = parameters.iter().map(|parameter| parameter.name.value.clone()).collect::<Vec<_>>();
+                let parameters = parameters
+                    .iter()
+                    .map(|parameter| match parameter.spread {
+                        true => format!("...{}", identifier(&parameter.name.value)),
+                        false => identifier(&parameter.name.value),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                // a body made of a single `return` gives its value as well
+                let value = match body.as_slice() {
+                    [Instruction {
+                        instruction: InstructionType::Return(value),
+                        ..
+                    }] => Some(value),
+                    _ => value(body),
+                };
+
+                let body = match value.filter(|value| !is_statement(value) && !body.iter().any(propagates)) {
+                    Some(value) => {
+                        self.scopes.push(names.iter().cloned().collect());
+                        let value = self.expression(value, precedence::ASSIGNMENT);
+                        self.scopes.pop();
+
+                        match value.starts_with('{') {
+                            true => format!("({value})"),
+                            false => value,
+                        }
+                    }
+                    None if body.iter().any(propagates) => {
+                        self.loops.push(None);
+                        let body = self.body(|transpiler| {
+                            let body = transpiler.block(body, &names, &Tail::Return);
+                            let handler = transpiler.body(|transpiler| {
+                                let returned = transpiler.body(|_| vec!["return $error.$return;".to_string()]);
+
+                                vec![format!("if ($error?.$return) {returned}"), "throw $error;".to_string()]
+                            });
+
+                            vec![format!("try {body} catch ($error) {handler}")]
+                        });
+                        self.loops.pop();
+
+                        body
+                    }
+                    None => {
+                        self.loops.push(None);
+                        let body = self.block(body, &names, &Tail::Return);
+                        self.loops.pop();
+
+                        body
+                    }
+                };
+
+                (format!("({parameters}) => {body}"), precedence::ASSIGNMENT)
+            }
+        }
+    }
+
+    /// `left`, already emitted as an operand of `operator`, combined with `right`.
+    fn operation(&mut self, left: &str, operator: &TokenKind, right: &IrExpression) -> (String, u8) {
+        match operator {
+            // ints are divided as floats, and `Number` leaves floats as they are
+            TokenKind::Slash => (
+                format!("Number({left}) / Number({})", self.expression(right, precedence::LOWEST)),
+                precedence::MULTIPLICATIVE,
+            ),
+            // `**` is right associative
+            TokenKind::DoubleAsterisk => {
+                let right = self.expression(right, precedence::EXPONENT);
+                self.checked(format!("{left} ** {right}"))
+            }
+            // shifting by a negative amount, or by 64 bits or more, is an overflow, and `<<` on BigInts keeps every bit, so its
+            // result is cut back to the 64 of an `int`
+            TokenKind::LShift | TokenKind::RShift => {
+                self.runtime.insert("$shift");
+
+                let (operator, precedence) = infix(operator);
+                let shifted = format!("{left} {operator} $shift({})", self.expression(right, precedence::ASSIGNMENT));
+
+                match operator {
+                    "<<" => (format!("BigInt.asIntN(64, {shifted})"), precedence::CALL),
+                    _ => (shifted, precedence),
+                }
+            }
+            TokenKind::Plus | TokenKind::Minus | TokenKind::Asterisk => {
+                let (operator, precedence) = infix(operator);
+                let right = self.expression(right, precedence + 1);
+                self.checked(format!("{left} {operator} {right}"))
+            }
+            operator => {
+                let (operator, precedence) = infix(operator);
+                (format!("{left} {operator} {}", self.expression(right, precedence + 1)), precedence)
+            }
+        }
+    }
+
+    /// Arithmetic on BigInts keeps every bit, so a result that needs more than the 64 of an `int` throws.
+    fn checked(&mut self, value: String) -> (String, u8) {
+        self.runtime.insert("$int");
+
+        (format!("$int({value})"), precedence::CALL)
+    }
+
+    /// Builtins map to their JavaScript counterparts, or to definitions of their own, unless a binding in scope shadows them.
+    fn reference(&mut self, name: &str) -> String {
+        let runtime = match name {
+            _ if self.scopes.iter().any(|scope| scope.contains(name)) => return identifier(name),
+            "println" => vec!["$show", "$println"],
+            "to_int" => vec!["$to_int"],
+            "to_float" => return "Number".to_string(),
+            name => return identifier(name),
+        };
+
+        self.runtime.extend(runtime);
+
+        format!("${name}")
+    }
+}
+
+/// A step from an assigned variable to the element or field that is assigned.
+enum Access {
+    Index(String),
+    Field(String),
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Access::Index(index) => write!(f, "[{index}]"),
+            Access::Field(field) => write!(f, ".{field}"),
+        }
+    }
+}
+
+fn identifier(name: &str) -> String {
+    match RESERVED.contains(&name) {
+        true => format!("${name}"),
+        false => name.to_string(),
+    }
+}
+
+/// A property of an object literal or a destructuring pattern named after a binding, which keeps the sntk name as its key.
+fn property(name: &str) -> String {
+    match identifier(name) {
+        emitted if emitted == name => emitted,
+        emitted => format!("{name}: {emitted}"),
+    }
+}
+
+fn infix(operator: &TokenKind) -> (&'static str, u8) {
+    match operator {
+        TokenKind::Asterisk => ("*", precedence::MULTIPLICATIVE),
+        TokenKind::Slash => ("/", precedence::MULTIPLICATIVE),
+        TokenKind::Percent => ("%", precedence::MULTIPLICATIVE),
+        TokenKind::DoubleAsterisk => ("**", precedence::EXPONENT),
+        TokenKind::Plus => ("+", precedence::ADDITIVE),
+        TokenKind::Minus => ("-", precedence::ADDITIVE),
+        TokenKind::LT => ("<", precedence::RELATIONAL),
+        TokenKind::LTE => ("<=", precedence::RELATIONAL),
+        TokenKind::GT => (">", precedence::RELATIONAL),
+        TokenKind::GTE => (">=", precedence::RELATIONAL),
+        TokenKind::EQ => ("===", precedence::EQUALITY),
+        TokenKind::NEQ => ("!==", precedence::EQUALITY),
+        TokenKind::LShift => ("<<", precedence::SHIFT),
+        TokenKind::RShift => (">>", precedence::SHIFT),
+        TokenKind::Ampersand => ("&", precedence::BITWISE_AND),
+        TokenKind::Caret => ("^", precedence::BITWISE_XOR),
+        TokenKind::Pipe => ("|", precedence::BITWISE_OR),
+        TokenKind::And => ("&&", precedence::AND),
+        TokenKind::Or => ("||", precedence::OR),
+        operator => unreachable!("unexpected infix operator `{operator}`"),
+    }
+}
+
+fn bindings(block: &Block, parameters: &[String]) -> HashSet<String> {
+    block
+        .iter()
+        .flat_map(|instruction| match &instruction.instruction {
+            InstructionType::StoreName(name, ..) => vec![name.clone()],
+            InstructionType::Import(_, names) => names.clone(),
+            _ => Vec::new(),
+        })
+        .chain(parameters.iter().cloned())
+        .collect()
+}
+
+/// Collects the names of variables assigned to anywhere in an instruction, including in nested blocks and functions.
+fn assignments(instruction: &Instruction, names: &mut HashSet<String>) {
+    match (&instruction.instruction, instruction.expression()) {
+        (InstructionType::Module(_, block, _), _) => block.iter().for_each(|instruction| assignments(instruction, names)),
+        (_, Some(expression)) => expression_assignments(expression, names),
+        _ => {}
+    }
+}
+
+fn expression_assignments(expression: &IrExpression, names: &mut HashSet<String>) {
+    if let IrExpressionKind::Assign(target, _, _) = &expression.expression {
+        let mut root = &**target;
+
+        while let IrExpressionKind::Index(left, _) | IrExpressionKind::Field(left, _) = &root.expression {
+            root = left;
+        }
+
+        if let IrExpressionKind::Identifier(name) = &root.expression {
+            names.insert(name.clone());
+        }
+    }
+
+    for child in expression.children() {
+        expression_assignments(child, names);
+    }
+}
+
+/// Whether an `if` expression can be emitted as `condition ? consequence : alternative`.
+fn is_conditional_expression(consequence: &IrExpression, alternative: &Option<IrExpression>) -> bool {
+    branch_value(consequence).is_some() && alternative.as_ref().is_none_or(|alternative| branch_value(alternative).is_some())
+}
+
+/// The value of a block made of nothing else, which needs no statements around it.
+fn value(block: &Block) -> Option<&IrExpression> {
+    match block.as_slice() {
+        [Instruction {
+            instruction: InstructionType::Value(value),
+            ..
+        }] => Some(value),
+        _ => None,
+    }
+}
+
+/// Whether an expression needs statements to be emitted, instead of being a JavaScript expression.
+fn is_statement(expression: &IrExpression) -> bool {
+    match &expression.expression {
+        IrExpressionKind::Block(block) => value(block).is_none_or(is_statement),
+        IrExpressionKind::If(_, consequence, alternative) => !is_conditional_expression(consequence, alternative),
+        IrExpressionKind::Loop(..)
+        | IrExpressionKind::For(..)
+        | IrExpressionKind::Match(..)
+        | IrExpressionKind::Break(..)
+        | IrExpressionKind::Continue(_) => true,
+        _ => false,
+    }
+}
+
+/// Whether a name is bound in a block inside an expression, where it would hide a variable of the same name.
+fn binds(expression: &IrExpression, name: &String) -> bool {
+    let bound = match &expression.expression {
+        IrExpressionKind::Block(block) | IrExpressionKind::Loop(_, _, block) => bindings(block, &[]).contains(name),
+        IrExpressionKind::For(_, variable, _, block) => variable == name || bindings(block, &[]).contains(name),
+        IrExpressionKind::Match(_, arms) => arms.iter().any(|(pattern, ..)| pattern.names().contains(&name.as_str())),
+        IrExpressionKind::Literal(LiteralValue::Function(..)) => return false,
+        _ => false,
+    };
+
+    bound || expression.children().into_iter().any(|child| binds(child, name))
+}
+
+/// Whether an instruction uses `?`, whose `None` or `Err` is caught and returned by the function it is in.
+fn propagates(instruction: &Instruction) -> bool {
+    instruction.expression().is_some_and(expression_propagates)
+}
+
+fn expression_propagates(expression: &IrExpression) -> bool {
+    match &expression.expression {
+        IrExpressionKind::Try(_) => true,
+        IrExpressionKind::Literal(LiteralValue::Function(..)) => false,
+        _ => expression.children().into_iter().any(expression_propagates),
+    }
+}
+
+fn branch_value(branch: &IrExpression) -> Option<&IrExpression> {
+    match &branch.expression {
+        IrExpressionKind::Block(block) => value(block),
+        _ => Some(branch),
+    }
+}
@@ -0,0 +1,42 @@
+use sntk_compiler::module::Loader;
+use sntk_js::transpiler::Transpiler;
+use std::{env, fs, path::Path};
+
+/// Transpiles every program in `examples/` and compares it with `tests/golden/<name>.js`, rewriting the expected output instead
+/// when `BLESS` is set.
+#[test]
+fn golden_test() {
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples");
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let bless = env::var_os("BLESS").is_some();
+    let mut mismatches = Vec::new();
+
+    let mut paths = fs::read_dir(&examples)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "sntk"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    for path in paths {
+        let source = fs::read_to_string(&path).unwrap();
+        let instructions = Loader::new(&examples)
+            .load(&source)
+            .unwrap_or_else(|error| panic!("{}: {error}", path.display()));
+        let actual = Transpiler::new(instructions).transpile();
+        let expected_path = golden.join(path.with_extension("js").file_name().unwrap());
+
+        if bless {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => mismatches.push(format!("{}:\n--- expected\n{expected}--- actual\n{actual}", expected_path.display())),
+            Err(_) => mismatches.push(format!("{}: missing, run with `BLESS=1`", expected_path.display())),
+        }
+    }
+
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
@@ -0,0 +1,45 @@
+const $show = (value) => {
+    switch (typeof value) {
+        case "number":
+            return Number.isInteger(value) ? value.toFixed(1) : String(value);
+        case "undefined":
+            return "void";
+        case "object":
+            if (Array.isArray(value)) return `[${value.map($show).join(", ")}]`;
+            if ("tag" in value) return value.values.length === 0 ? value.tag : `${value.tag}(${value.values.map($show).join(", ")})`;
+            return `{ ${Object.entries(value).map(([field, value]) => `${field}: ${$show(value)}`).join(", ")} }`;
+        default:
+            return String(value);
+    }
+};
+const $println = (...values) => console.log(values.map($show).join(" "));
+const $int = (value) => {
+    if (typeof value === "bigint" && BigInt.asIntN(64, value) !== value) throw new RangeError("Integer overflow");
+    return value;
+};
+const $shift = (amount) => {
+    if (amount < 0n || amount >= 64n) throw new RangeError(`Integer overflow in a shift by \`${amount}\``);
+    return amount;
+};
+const $at = (array, index) => {
+    if (index < 0n || index >= array.length) throw new RangeError(`Index out of bounds \`${index}\``);
+    return Number(index);
+};
+const $index = (array, index) => array[$at(array, index)];
+let total = 1n;
+total = $int(total + 2n);
+total = $int(total ** 2n);
+total = BigInt.asIntN(64, total << $shift(1n));
+let count = 0n;
+const next = () => count = $int(count + 1n);
+next();
+next();
+let points = [{ x: 1n, y: 2n }, { x: 3n, y: 4n }];
+const first = points;
+(($0) => points = points.with($at(points, $0), { ...points[$0], x: 10n }))($int(next() - 2n));
+points = points.with($at(points, 1n), { ...points[1n], y: $int(points[1n].y * total) });
+$println(total);
+$println(count);
+$println($int($index(points, 0n).x + $index(points, 1n).y));
+$println($index(first, 0n).x);
+$println((points = points.with($at(points, 0n), { ...points[0n], y: 5n }))[0n].y);
@@ -0,0 +1,55 @@
+const $show = (value) => {
+    switch (typeof value) {
+        case "number":
+            return Number.isInteger(value) ? value.toFixed(1) : String(value);
+        case "undefined":
+            return "void";
+        case "object":
+            if (Array.isArray(value)) return `[${value.map($show).join(", ")}]`;
+            if ("tag" in value) return value.values.length === 0 ? value.tag : `${value.tag}(${value.values.map($show).join(", ")})`;
+            return `{ ${Object.entries(value).map(([field, value]) => `${field}: ${$show(value)}`).join(", ")} }`;
+        default:
+            return String(value);
+    }
+};
+const $println = (...values) => console.log(values.map($show).join(" "));
+const $to_int = (float) => {
+    if (!(float >= -(2 ** 63) && float < 2 ** 63)) throw new RangeError(`Integer overflow in \`to_int(${float})\``);
+    return BigInt(Math.trunc(float));
+};
+const $int = (value) => {
+    if (typeof value === "bigint" && BigInt.asIntN(64, value) !== value) throw new RangeError("Integer overflow");
+    return value;
+};
+const sign = (n) => n < 0n ? -1n : n === 0n ? 0n : 1n;
+const clamp = (n, low, high) => {
+    if (n < low) {
+        return low;
+    }
+    if (n > high) {
+        return high;
+    } else {
+        high = n;
+    }
+    return high;
+};
+let area;
+{
+    const width = 3n;
+    const height = 4n;
+    area = $int(width * height);
+}
+const adder = (a) => (b) => $int(a + b);
+const report = (n) => n > 10n ? void $println(sign($int(-n))) : undefined;
+report(area);
+const countdown = (n) => {
+    if (n < 0n) {
+        return;
+    }
+    $println(n);
+};
+countdown(1n);
+countdown(-1n);
+$println($int(clamp(area, 0n, 10n) - $int(-adder(1n)(2n))));
+$println(clamp($int(-area), 0n, 10n));
+$println($to_int(Number(Number(area)) / Number(2.5)));
@@ -0,0 +1,24 @@
+const $show = (value) => {
+    switch (typeof value) {
+        case "number":
+            return Number.isInteger(value) ? value.toFixed(1) : String(value);
+        case "undefined":
+            return "void";
+        case "object":
+            if (Array.isArray(value)) return `[${value.map($show).join(", ")}]`;
+            if ("tag" in value) return value.values.length === 0 ? value.tag : `${value.tag}(${value.values.map($show).join(", ")})`;
+            return `{ ${Object.entries(value).map(([field, value]) => `${field}: ${$show(value)}`).join(", ")} }`;
+        default:
+            return String(value);
+    }
+};
+const $println = (...values) => console.log(values.map($show).join(" "));
+const unit = (side) => ({ tag: "Rect", values: [side, side] });
+const shapes = [{ tag: "Circle", values: [1.5] }, unit(2), { tag: "Empty", values: [] }];
+const nothing = { tag: "None", values: [] };
+const something = { tag: "Some", values: ["sntk"] };
+const list = { tag: "Cons", values: [1n, { tag: "Cons", values: [2n, { tag: "Nil", values: [] }] }] };
+$println("Shape[]");
+$println("Option<Int>");
+$println("Option<String>");
+$println("List<Int>");
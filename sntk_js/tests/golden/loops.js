@@ -0,0 +1,81 @@
+const $show = (value) => {
+    switch (typeof value) {
+        case "number":
+            return Number.isInteger(value) ? value.toFixed(1) : String(value);
+        case "undefined":
+            return "void";
+        case "object":
+            if (Array.isArray(value)) return `[${value.map($show).join(", ")}]`;
+            if ("tag" in value) return value.values.length === 0 ? value.tag : `${value.tag}(${value.values.map($show).join(", ")})`;
+            return `{ ${Object.entries(value).map(([field, value]) => `${field}: ${$show(value)}`).join(", ")} }`;
+        default:
+            return String(value);
+    }
+};
+const $println = (...values) => console.log(values.map($show).join(" "));
+const $int = (value) => {
+    if (typeof value === "bigint" && BigInt.asIntN(64, value) !== value) throw new RangeError("Integer overflow");
+    return value;
+};
+const $shift = (amount) => {
+    if (amount < 0n || amount >= 64n) throw new RangeError(`Integer overflow in a shift by \`${amount}\``);
+    return amount;
+};
+let total = 0n;
+let i = 0n;
+while (i < 10n) {
+    i = $int(i + 1n);
+    if (i % 2n === 0n) {
+        continue;
+    }
+    total = $int(total + i);
+}
+$println(total);
+for (const row of [[1n, 2n, 3n], [4n, 5n, 6n], [7n, 8n, 9n]]) {
+    columns: for (const cell of row) {
+        if (cell === 5n) {
+            continue columns;
+        }
+        if (cell > 7n) {
+            break;
+        }
+        total = $int(total + cell);
+    }
+}
+$println(total);
+let n = 27n;
+let steps;
+while (true) {
+    if (n === 1n) {
+        steps = 0n;
+        break;
+    }
+    let count = 0n;
+    outer: while (true) {
+        while (true) {
+            count = $int(count + 1n);
+            n = n % 2n === 0n ? n >> $shift(1n) : $int($int(3n * n) + 1n);
+            if (n === 1n) {
+                break outer;
+            }
+        }
+    }
+    steps = count;
+    break;
+}
+$println(steps);
+$println($int((() => {
+    while (true) {
+        return 1n;
+    }
+})() + 1n));
+const evens = (numbers) => {
+    let count = 0n;
+    for (const number of numbers) {
+        if (number % 2n === 0n) {
+            count = $int(count + 1n);
+        }
+    }
+    return count;
+};
+$println(evens([1n, 3n, 4n, 6n]));
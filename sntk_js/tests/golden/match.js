@@ -0,0 +1,86 @@
+const $show = (value) => {
+    switch (typeof value) {
+        case "number":
+            return Number.isInteger(value) ? value.toFixed(1) : String(value);
+        case "undefined":
+            return "void";
+        case "object":
+            if (Array.isArray(value)) return `[${value.map($show).join(", ")}]`;
+            if ("tag" in value) return value.values.length === 0 ? value.tag : `${value.tag}(${value.values.map($show).join(", ")})`;
+            return `{ ${Object.entries(value).map(([field, value]) => `${field}: ${$show(value)}`).join(", ")} }`;
+        default:
+            return String(value);
+    }
+};
+const $println = (...values) => console.log(values.map($show).join(" "));
+const $int = (value) => {
+    if (typeof value === "bigint" && BigInt.asIntN(64, value) !== value) throw new RangeError("Integer overflow");
+    return value;
+};
+const describe = (shape) => {
+    if (shape.tag === "Circle" && ((radius) => radius > 10)(shape.values[0])) {
+        const radius = shape.values[0];
+        return "a large circle";
+    } else if (shape.tag === "Circle") {
+        return "a circle";
+    } else if (shape.tag === "Rect" && ((width, height) => width === height)(shape.values[0], shape.values[1])) {
+        const width = shape.values[0];
+        const height = shape.values[1];
+        return "a square";
+    } else if (shape.tag === "Rect") {
+        return "a rectangle";
+    } else {
+        return "nothing";
+    }
+};
+const locate = (point) => {
+    if (point.x === 0n && point.y === 0n) {
+        return "the origin";
+    } else if (point.x === 0n) {
+        return "on the y axis";
+    } else if (point.y === 0n) {
+        return "on the x axis";
+    } else if (((x, y) => x > 0n && y > 0n)(point.x, point.y)) {
+        const x = point.x;
+        const y = point.y;
+        return "in the first quadrant";
+    } else {
+        return "elsewhere";
+    }
+};
+const sum = (numbers) => {
+    if (numbers.length === 0) {
+        return 0n;
+    } else {
+        const first = numbers[0];
+        const rest = numbers.slice(1);
+        return $int(first + sum(rest));
+    }
+};
+const find = (numbers, target) => {
+    for (const number of numbers) {
+        {
+            const $match = number === target;
+            if ($match === true) {
+                return "found";
+            } else {}
+        }
+    }
+    return "missing";
+};
+for (const shape of [{ tag: "Circle", values: [12] }, { tag: "Circle", values: [1] }, { tag: "Rect", values: [2, 2] }, { tag: "Rect", values: [1, 2] }, { tag: "Empty", values: [] }]) {
+    $println(describe(shape));
+}
+for (const point of [{ x: 0n, y: 0n }, { x: 0n, y: 3n }, { x: 2n, y: 5n }, { x: -1n, y: 5n }]) {
+    $println(locate(point));
+}
+$println((() => {
+    const $match = sum([1n, 2n, 3n]);
+    if ($match === 6n) {
+        return "six";
+    } else {
+        return "not six";
+    }
+})());
+$println(find([1n, 2n, 3n], 2n));
+$println(find([1n, 2n, 3n], 4n));
@@ -0,0 +1,51 @@
+const $show = (value) => {
+    switch (typeof value) {
+        case "number":
+            return Number.isInteger(value) ? value.toFixed(1) : String(value);
+        case "undefined":
+            return "void";
+        case "object":
+            if (Array.isArray(value)) return `[${value.map($show).join(", ")}]`;
+            if ("tag" in value) return value.values.length === 0 ? value.tag : `${value.tag}(${value.values.map($show).join(", ")})`;
+            return `{ ${Object.entries(value).map(([field, value]) => `${field}: ${$show(value)}`).join(", ")} }`;
+        default:
+            return String(value);
+    }
+};
+const $println = (...values) => console.log(values.map($show).join(" "));
+const $int = (value) => {
+    if (typeof value === "bigint" && BigInt.asIntN(64, value) !== value) throw new RangeError("Integer overflow");
+    return value;
+};
+const $module0 = (() => {
+    const pi = 3.14159;
+    const area = (shape) => {
+        if (shape.tag === "Circle") {
+            const radius = shape.values[0];
+            return $int($int(pi * radius) * radius);
+        } else {
+            const width = shape.values[0];
+            const height = shape.values[1];
+            return $int(width * height);
+        }
+    };
+    const unit = { tag: "Rect", values: [1, 1] };
+    return { area, unit };
+})();
+const $module1 = (() => {
+    const scale = (shape, factor) => {
+        if (shape.tag === "Circle") {
+            const radius = shape.values[0];
+            return { tag: "Circle", values: [$int(radius * factor)] };
+        } else {
+            const width = shape.values[0];
+            const height = shape.values[1];
+            return { tag: "Rect", values: [$int(width * factor), $int(height * factor)] };
+        }
+    };
+    return { scale };
+})();
+const { area, unit } = $module0;
+const { scale } = $module1;
+$println(area({ tag: "Circle", values: [2] }));
+$println(area(scale(unit, 3)));
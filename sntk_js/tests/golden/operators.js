@@ -0,0 +1,44 @@
+const $show = (value) => {
+    switch (typeof value) {
+        case "number":
+            return Number.isInteger(value) ? value.toFixed(1) : String(value);
+        case "undefined":
+            return "void";
+        case "object":
+            if (Array.isArray(value)) return `[${value.map($show).join(", ")}]`;
+            if ("tag" in value) return value.values.length === 0 ? value.tag : `${value.tag}(${value.values.map($show).join(", ")})`;
+            return `{ ${Object.entries(value).map(([field, value]) => `${field}: ${$show(value)}`).join(", ")} }`;
+        default:
+            return String(value);
+    }
+};
+const $println = (...values) => console.log(values.map($show).join(" "));
+const $int = (value) => {
+    if (typeof value === "bigint" && BigInt.asIntN(64, value) !== value) throw new RangeError("Integer overflow");
+    return value;
+};
+const $shift = (amount) => {
+    if (amount < 0n || amount >= 64n) throw new RangeError(`Integer overflow in a shift by \`${amount}\``);
+    return amount;
+};
+const $at = (array, index) => {
+    if (index < 0n || index >= array.length) throw new RangeError(`Index out of bounds \`${index}\``);
+    return Number(index);
+};
+const $index = (array, index) => array[$at(array, index)];
+const flags = 13n;
+const masked = flags & -2n ^ 240n;
+const positive = (xs, i) => i < 3n && $index(xs, i) > 0n;
+const describe = (n) => n % 2n === 0n || n > 100n ? $int(n ** 2n) : $int(-n);
+$println(masked >> $shift(1n));
+$println(describe(1n));
+$println(describe($int(-masked)));
+$println(positive([1n, 2n, 3n], 5n) || !positive([1n, -2n, 3n], 1n) ? 1n : 0n);
+const one = 1n;
+const large = 4294967296n;
+$println(BigInt.asIntN(64, one << $shift(40n)));
+$println(5000000000n & large);
+$println(~large);
+const huge = 9007199254740993n;
+$println(huge);
+$println($int($int(huge * 512n) + one));
@@ -0,0 +1,64 @@
+const $show = (value) => {
+    switch (typeof value) {
+        case "number":
+            return Number.isInteger(value) ? value.toFixed(1) : String(value);
+        case "undefined":
+            return "void";
+        case "object":
+            if (Array.isArray(value)) return `[${value.map($show).join(", ")}]`;
+            if ("tag" in value) return value.values.length === 0 ? value.tag : `${value.tag}(${value.values.map($show).join(", ")})`;
+            return `{ ${Object.entries(value).map(([field, value]) => `${field}: ${$show(value)}`).join(", ")} }`;
+        default:
+            return String(value);
+    }
+};
+const $println = (...values) => console.log(values.map($show).join(" "));
+const $int = (value) => {
+    if (typeof value === "bigint" && BigInt.asIntN(64, value) !== value) throw new RangeError("Integer overflow");
+    return value;
+};
+const $at = (array, index) => {
+    if (index < 0n || index >= array.length) throw new RangeError(`Index out of bounds \`${index}\``);
+    return Number(index);
+};
+const $index = (array, index) => array[$at(array, index)];
+const find = (names, name) => {
+    for (const index of [0n, 1n, 2n]) {
+        if ($index(names, index) === name) {
+            return { tag: "Some", values: [index] };
+        }
+    }
+    return { tag: "None", values: [] };
+};
+const names = ["a", "b", "c"];
+$println("Option<Int>");
+$println("Int");
+$println("Boolean");
+const both = (first, second) => {
+    try {
+        first = (($try) => { if ($try.tag === "None" || $try.tag === "Err") throw { $return: $try }; return $try.values[0]; })(find(names, first));
+        second = (($try) => { if ($try.tag === "None" || $try.tag === "Err") throw { $return: $try }; return $try.values[0]; })(find(names, second));
+        return { tag: "Some", values: [[first, second]] };
+    } catch ($error) {
+        if ($error?.$return) {
+            return $error.$return;
+        }
+        throw $error;
+    }
+};
+const divide = (left, right) => right === 0n ? { tag: "Err", values: ["division by zero"] } : { tag: "Ok", values: [Number(left) / Number(right)] };
+const average = (total, count) => {
+    try {
+        const quotient = (($try) => { if ($try.tag === "None" || $try.tag === "Err") throw { $return: $try }; return $try.values[0]; })(divide(total, count));
+        return { tag: "Ok", values: [quotient] };
+    } catch ($error) {
+        if ($error?.$return) {
+            return $error.$return;
+        }
+        throw $error;
+    }
+};
+$println("Option<Int[]>");
+const pair = (index) => both($index(names, index), $index(names, $int(index + 1n)));
+$println("Option<Int[]>");
+$println("Result<Float, String>");
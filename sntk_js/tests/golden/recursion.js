@@ -0,0 +1,40 @@
+const $show = (value) => {
+    switch (typeof value) {
+        case "number":
+            return Number.isInteger(value) ? value.toFixed(1) : String(value);
+        case "undefined":
+            return "void";
+        case "object":
+            if (Array.isArray(value)) return `[${value.map($show).join(", ")}]`;
+            if ("tag" in value) return value.values.length === 0 ? value.tag : `${value.tag}(${value.values.map($show).join(", ")})`;
+            return `{ ${Object.entries(value).map(([field, value]) => `${field}: ${$show(value)}`).join(", ")} }`;
+        default:
+            return String(value);
+    }
+};
+const $println = (...values) => console.log(values.map($show).join(" "));
+const $int = (value) => {
+    if (typeof value === "bigint" && BigInt.asIntN(64, value) !== value) throw new RangeError("Integer overflow");
+    return value;
+};
+const factorial = (n) => {
+    if (n < 2n) {
+        return 1n;
+    }
+    return $int(n * factorial($int(n - 1n)));
+};
+const is_even = (n) => n === 0n || is_odd($int(n - 1n));
+const is_odd = (n) => n !== 0n && is_even($int(n - 1n));
+const fibonacci = (() => {
+    const fibonacci = (n) => {
+        if (n < 2n) {
+            return n;
+        }
+        return $int(fibonacci($int(n - 1n)) + fibonacci($int(n - 2n)));
+    };
+    return fibonacci(20n);
+})();
+$println(factorial(10n));
+if (is_odd(7n) && !is_even(7n)) {
+    $println(fibonacci);
+}
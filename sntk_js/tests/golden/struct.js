@@ -0,0 +1,27 @@
+const $show = (value) => {
+    switch (typeof value) {
+        case "number":
+            return Number.isInteger(value) ? value.toFixed(1) : String(value);
+        case "undefined":
+            return "void";
+        case "object":
+            if (Array.isArray(value)) return `[${value.map($show).join(", ")}]`;
+            if ("tag" in value) return value.values.length === 0 ? value.tag : `${value.tag}(${value.values.map($show).join(", ")})`;
+            return `{ ${Object.entries(value).map(([field, value]) => `${field}: ${$show(value)}`).join(", ")} }`;
+        default:
+            return String(value);
+    }
+};
+const $println = (...values) => console.log(values.map($show).join(" "));
+const $int = (value) => {
+    if (typeof value === "bigint" && BigInt.asIntN(64, value) !== value) throw new RangeError("Integer overflow");
+    return value;
+};
+const origin = { x: 0n, y: 0n };
+const line = { from: origin, to: { x: 3n, y: 4n } };
+const length = (line) => {
+    const dx = $int(line.to.x - line.from.x);
+    const dy = $int(line.to.y - line.from.y);
+    return $int($int(dx * dx) + $int(dy * dy));
+};
+$println(length(line));
@@ -0,0 +1,859 @@
+use sntk_compiler::{
+    checker::{custom_data_type, pattern_bindings, Checker},
+    compiler::{compile_pattern, CompileResult, Compiler},
+    module::{Export, Loader, Module},
+    CompileError, TypeError, TypeErrorKind,
+};
+use sntk_core::{
+    diagnostic::Diagnostic,
+    parser::{
+        ast::{
+            ArrayLiteral, ArrayPattern, AssignExpression, AutoStatement, BindingPattern, BlockExpression, BreakExpression, CallExpression, DataType,
+            DataTypeKind, DeclareStatement, EnumLiteral, EnumPattern, EnumStatement, Expression, ExpressionStatement, FieldExpression, ForExpression,
+            FunctionLiteral, FunctionType, Generic, Identifier, IfExpression, ImportStatement, IndexExpression, InfixExpression, LetStatement,
+            LoopExpression, MatchArm, MatchExpression, Pattern, PrefixExpression, Program, ReturnStatement, Span, Statement, StructLiteral,
+            StructPattern, StructStatement, TryExpression, TypeStatement, TypeofExpression, WhileExpression,
+        },
+        parser::Parser,
+    },
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    iter,
+    path::Path,
+    rc::Rc,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+    Variable,
+    Function,
+    Parameter,
+    Type,
+    Struct,
+    Field,
+    Enum,
+    Variant,
+}
+
+impl BindingKind {
+    /// Whether the binding names a type rather than a value; the two live in separate namespaces.
+    pub fn is_type(&self) -> bool {
+        matches!(self, BindingKind::Type | BindingKind::Struct | BindingKind::Enum)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding {
+    pub name: String,
+    pub kind: BindingKind,
+    pub span: Span,
+}
+
+/// A named declaration in the outline of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: BindingKind,
+    pub detail: Option<String>,
+    pub span: Span,
+    pub name_span: Span,
+    pub children: Vec<Symbol>,
+}
+
+/// A parsed document, answering the questions an editor asks about it.
+#[derive(Debug)]
+pub struct Analysis {
+    pub program: Program,
+    modules: HashMap<String, Rc<Module>>, // the modules the document imports, by the paths they are imported with
+    failures: HashMap<String, CompileError>, // why the modules that could not be loaded were not
+}
+
+impl Analysis {
+    /// A document whose imports are not loaded, as one that is not saved yet.
+    #[inline]
+    pub fn new(source: &str) -> Self {
+        Analysis {
+            program: Parser::from(source.to_string()).parse_program(),
+            modules: HashMap::new(),
+            failures: HashMap::new(),
+        }
+    }
+
+    /// A document in `directory`, with the modules it imports loaded from there.
+    pub fn in_directory(source: &str, directory: &Path) -> Self {
+        let mut analysis = Analysis::new(source);
+        let mut loader = Loader::new(directory);
+
+        for statement in analysis.program.statements.iter() {
+            let Statement::ImportStatement(statement @ ImportStatement { path, .. }) = statement else {
+                continue;
+            };
+
+            match loader.import(statement) {
+                Ok(module) => {
+                    analysis.modules.insert(path.value.clone(), module);
+                }
+                // an error in the module itself is in another file, so it is reported where the module is imported
+                Err(error) if error.file() != path.span.file => {
+                    let message = error
+                        .diagnostics()
+                        .first()
+                        .map(|diagnostic| diagnostic.message.clone())
+                        .unwrap_or_default();
+                    let error = TypeError::new(TypeErrorKind::ModuleNotFound(path.value.clone(), message), path.span);
+                    analysis.failures.insert(path.value.clone(), error);
+                }
+                Err(error) => {
+                    analysis.failures.insert(path.value.clone(), error);
+                }
+            }
+        }
+
+        analysis
+    }
+
+    /// Every parsing error, followed by the type errors of each statement that could be parsed.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.program.errors.iter().map(Diagnostic::from).collect::<Vec<_>>();
+        let mut scope = Scope::new(&self.modules);
+        scope.hoist(&self.program.statements);
+
+        for statement in self.program.statements.iter() {
+            let failure = match statement {
+                Statement::ImportStatement(ImportStatement { path, .. }) => self.failures.get(&path.value),
+                _ => None,
+            };
+
+            if let Err(error) = failure.map_or_else(|| scope.declare(statement), |failure| Err(failure.clone())) {
+                diagnostics.extend(error.diagnostics());
+            }
+        }
+
+        diagnostics
+    }
+
+    /// The identifier at `offset` and its type, as `typeof` would report it.
+    pub fn hover(&self, offset: usize) -> Option<(Span, String)> {
+        let mut scope = Scope::at(&self.program, &self.modules, offset);
+
+        match scope.target.take()? {
+            Target::Value(identifier) => scope
+                .type_of(&Expression::Identifier(identifier.clone()))
+                .map(|data_type| (identifier.span, format!("{}: {}", identifier.value, data_type))),
+            Target::Expression(expression, span) => scope.type_of(&expression).map(|data_type| (span, data_type.to_string())),
+            Target::Type(identifier) => scope
+                .compiler
+                .customs
+                .get(identifier.value.clone())
+                .map(|data_type| match data_type.data_type {
+                    DataTypeKind::TypeParameter(name) => (identifier.span, format!("type {name}")),
+                    data_type => (identifier.span, format!("type {} = {}", identifier.value, data_type)),
+                }),
+        }
+    }
+
+    /// Where the value or type named at `offset` was bound.
+    pub fn definition(&self, offset: usize) -> Option<Span> {
+        let scope = Scope::at(&self.program, &self.modules, offset);
+        let (name, is_type) = match scope.target? {
+            Target::Value(identifier) => (identifier.value, false),
+            Target::Type(identifier) => (identifier.value, true),
+            Target::Expression(..) => return None,
+        };
+
+        scope
+            .bindings
+            .iter()
+            .rev()
+            .find(|binding| binding.name == name && binding.kind.is_type() == is_type)
+            .map(|binding| binding.span)
+    }
+
+    /// The names declared at `offset`, with their types, sorted by name.
+    pub fn completions(&self, offset: usize) -> Vec<(String, DataType)> {
+        let scope = Scope::at(&self.program, &self.modules, offset);
+        let mut completions = BTreeMap::new();
+        let mut declares = Some(&scope.compiler.declares);
+
+        // inner scopes come first, so that shadowed names keep their innermost type
+        while let Some(scope) = declares {
+            for (name, data_type) in scope.types.iter() {
+                completions.entry(name.clone()).or_insert_with(|| data_type.clone());
+            }
+
+            declares = scope.parent.as_deref();
+        }
+
+        completions.into_iter().collect()
+    }
+
+    pub fn symbols(&self) -> Vec<Symbol> {
+        symbols(&self.program.statements)
+    }
+}
+
+fn symbols(statements: &[Statement]) -> Vec<Symbol> {
+    let symbol = |name: &Identifier, kind, detail: Option<&DataType>, span, children| Symbol {
+        name: name.value.clone(),
+        kind,
+        detail: detail.map(ToString::to_string),
+        span,
+        name_span: name.span,
+        children,
+    };
+
+    statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::LetStatement(LetStatement {
+                name,
+                value,
+                data_type,
+                span,
+                ..
+            }) => Some(symbol(name, value_kind(value), Some(data_type), *span, value_symbols(value))),
+            Statement::AutoStatement(AutoStatement { name, value, span, .. }) => {
+                Some(symbol(name, value_kind(value), None, *span, value_symbols(value)))
+            }
+            Statement::DeclareStatement(DeclareStatement { name, data_type, span, .. }) => Some(symbol(
+                name,
+                match data_type.data_type {
+                    DataTypeKind::Fn(_) => BindingKind::Function,
+                    _ => BindingKind::Variable,
+                },
+                Some(data_type),
+                *span,
+                Vec::new(),
+            )),
+            Statement::TypeStatement(TypeStatement { name, data_type, span, .. }) => {
+                Some(symbol(name, BindingKind::Type, Some(data_type), *span, Vec::new()))
+            }
+            Statement::StructStatement(StructStatement { name, fields, span, .. }) => Some(symbol(
+                name,
+                BindingKind::Struct,
+                None,
+                *span,
+                fields
+                    .iter()
+                    .map(|(field, data_type)| symbol(field, BindingKind::Field, Some(data_type), field.span.to(data_type.span), Vec::new()))
+                    .collect(),
+            )),
+            Statement::EnumStatement(EnumStatement { name, variants, span, .. }) => Some(symbol(
+                name,
+                BindingKind::Enum,
+                None,
+                *span,
+                variants
+                    .iter()
+                    .map(|(variant, payload)| {
+                        let span = payload.last().map_or(variant.span, |last| variant.span.to(last.span));
+                        symbol(variant, BindingKind::Variant, None, span, Vec::new())
+                    })
+                    .collect(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+fn value_kind(value: &Expression) -> BindingKind {
+    match value {
+        Expression::FunctionLiteral(_) => BindingKind::Function,
+        _ => BindingKind::Variable,
+    }
+}
+
+fn value_symbols(value: &Expression) -> Vec<Symbol> {
+    match value {
+        Expression::FunctionLiteral(function) => symbols(&function.body.statements),
+        _ => Vec::new(),
+    }
+}
+
+#[derive(Debug)]
+enum Target {
+    /// An identifier naming a value, either where it is used or where it is bound.
+    Value(Identifier),
+    /// Any other expression worth describing, with the span of the part under the cursor (e.g. the field of a field access).
+    Expression(Expression, Span),
+    Type(Identifier),
+}
+
+/// The compiler state and the bindings visible at some point of a program, found by walking down to it.
+#[derive(Debug)]
+struct Scope {
+    compiler: Compiler,
+    bindings: Vec<Binding>,
+    target: Option<Target>,
+}
+
+impl Scope {
+    fn new(modules: &HashMap<String, Rc<Module>>) -> Self {
+        let mut compiler = Compiler::new(Program::default());
+        compiler.modules = modules.clone();
+
+        Scope {
+            compiler,
+            bindings: Vec::new(),
+            target: None,
+        }
+    }
+
+    fn at(program: &Program, modules: &HashMap<String, Rc<Module>>, offset: usize) -> Self {
+        let mut scope = Scope::new(modules);
+        scope.hoist(&program.statements);
+        scope.statements(&program.statements, offset);
+        scope
+    }
+
+    fn type_of(&mut self, expression: &Expression) -> Option<DataType> {
+        let expression = self.compiler.compile_expression(expression).ok()?;
+
+        Checker::new(None, &self.compiler.declares, &self.compiler.customs)
+            .and_then(|checker| checker.get_type_from_ir_expression(&expression))
+            .ok()
+    }
+
+    fn bind(&mut self, name: &Identifier, kind: BindingKind) {
+        self.bindings.push(Binding {
+            name: name.value.clone(),
+            kind,
+            span: name.span,
+        });
+    }
+
+    /// Brings the functions bound at the top level into scope ahead of their definitions, as the compiler would.
+    fn hoist(&mut self, statements: &[Statement]) {
+        self.compiler.hoist(statements);
+
+        for statement in statements.iter() {
+            if let Statement::LetStatement(LetStatement {
+                name,
+                value: Expression::FunctionLiteral(_),
+                ..
+            })
+            | Statement::AutoStatement(AutoStatement {
+                name,
+                value: Expression::FunctionLiteral(_),
+                ..
+            }) = statement
+            {
+                self.bind(name, BindingKind::Function);
+            }
+        }
+    }
+
+    /// Brings the names a statement binds into scope, as the compiler would.
+    fn declare(&mut self, statement: &Statement) -> CompileResult<()> {
+        match statement {
+            Statement::LetStatement(LetStatement { name, value, .. }) | Statement::AutoStatement(AutoStatement { name, value, .. }) => {
+                self.bind(name, value_kind(value))
+            }
+            Statement::DeclareStatement(DeclareStatement { name, data_type, .. }) => self.bind(
+                name,
+                match data_type.data_type {
+                    DataTypeKind::Fn(_) => BindingKind::Function,
+                    _ => BindingKind::Variable,
+                },
+            ),
+            Statement::TypeStatement(TypeStatement { name, .. }) => self.bind(name, BindingKind::Type),
+            Statement::StructStatement(StructStatement { name, .. }) => self.bind(name, BindingKind::Struct),
+            Statement::EnumStatement(EnumStatement { name, .. }) => self.bind(name, BindingKind::Enum),
+            Statement::ImportStatement(ImportStatement { names, path, .. }) => {
+                for name in names.iter() {
+                    self.bind(name, self.import_kind(&path.value, &name.value));
+                }
+            }
+            _ => {}
+        }
+
+        self.compiler.compile_statement(statement).map(|_| ()).inspect_err(|_| {
+            // an ill-typed `let` still has the type it was annotated with, so later uses of it are not reported as undefined
+            if let Statement::LetStatement(LetStatement { name, data_type, .. }) = statement {
+                let data_type = custom_data_type(data_type, &self.compiler.customs).unwrap_or_else(|_| data_type.clone());
+                self.compiler.declares.set(name.value.clone(), data_type);
+            }
+        })
+    }
+
+    /// What an imported name is bound to, a variable if the module or the name is not known.
+    fn import_kind(&self, path: &str, name: &str) -> BindingKind {
+        let Some(module) = self.compiler.modules.get(path) else {
+            return BindingKind::Variable;
+        };
+
+        match module.exports.get(name) {
+            Some(Export::Type) => match module.customs.get(name.to_string()).map(|data_type| data_type.data_type) {
+                Some(DataTypeKind::Struct(_)) => BindingKind::Struct,
+                Some(DataTypeKind::Enum(_)) => BindingKind::Enum,
+                _ => BindingKind::Type,
+            },
+            Some(_) => match module.declares.get(name.to_string()).map(|data_type| data_type.data_type) {
+                Some(DataTypeKind::Fn(_)) => BindingKind::Function,
+                _ => BindingKind::Variable,
+            },
+            None => BindingKind::Variable,
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement], offset: usize) {
+        for statement in statements.iter() {
+            let span = statement.span();
+
+            if span.start > offset {
+                return;
+            }
+
+            if offset < span.end {
+                return self.statement(statement, offset);
+            }
+
+            let _ = self.declare(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement, offset: usize) {
+        match statement {
+            Statement::LetStatement(LetStatement { name, .. })
+            | Statement::AutoStatement(AutoStatement { name, .. })
+            | Statement::DeclareStatement(DeclareStatement { name, .. })
+                if contains(name.span, offset) =>
+            {
+                let _ = self.declare(statement);
+                self.target = Some(Target::Value(name.clone()));
+            }
+            Statement::TypeStatement(TypeStatement { name, .. })
+            | Statement::StructStatement(StructStatement { name, .. })
+            | Statement::EnumStatement(EnumStatement { name, .. })
+                if contains(name.span, offset) =>
+            {
+                let _ = self.declare(statement);
+                self.target = Some(Target::Type(name.clone()));
+            }
+            Statement::ImportStatement(ImportStatement { names, path, .. }) => {
+                let _ = self.declare(statement);
+
+                if let Some(name) = names.iter().find(|name| contains(name.span, offset)) {
+                    self.target = Some(match self.import_kind(&path.value, &name.value).is_type() {
+                        true => Target::Type(name.clone()),
+                        false => Target::Value(name.clone()),
+                    });
+                }
+            }
+            Statement::LetStatement(LetStatement { data_type, value, .. }) => match contains(data_type.span, offset) {
+                true => self.data_type(data_type, offset),
+                false => self.expression(value, offset),
+            },
+            Statement::DeclareStatement(DeclareStatement { data_type, .. }) => self.data_type(data_type, offset),
+            Statement::TypeStatement(TypeStatement { generics, data_type, .. }) => {
+                generics.iter().for_each(|generic| self.bind(generic, BindingKind::Type));
+                self.data_type(data_type, offset);
+            }
+            Statement::StructStatement(StructStatement { generics, fields, .. }) => {
+                generics.iter().for_each(|generic| self.bind(generic, BindingKind::Type));
+                self.data_types(fields.iter().map(|(_, data_type)| data_type), offset);
+            }
+            Statement::EnumStatement(EnumStatement { generics, variants, .. }) => {
+                generics.iter().for_each(|generic| self.bind(generic, BindingKind::Type));
+                self.data_types(variants.iter().flat_map(|(_, payload)| payload), offset);
+            }
+            Statement::ReturnStatement(ReturnStatement { value, .. }) => {
+                if let Some(value) = value {
+                    self.expression(value, offset);
+                }
+            }
+            Statement::AutoStatement(AutoStatement { value, .. }) | Statement::ExpressionStatement(ExpressionStatement { expression: value, .. }) => {
+                self.expression(value, offset)
+            }
+            Statement::ErrorStatement(_) => {}
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression, offset: usize) {
+        match expression {
+            Expression::Identifier(identifier) => self.target = Some(Target::Value(identifier.clone())),
+            Expression::BlockExpression(BlockExpression { statements, .. }) => self.statements(statements, offset),
+            Expression::PrefixExpression(PrefixExpression { right, .. }) => self.expression(right, offset),
+            Expression::InfixExpression(InfixExpression { left, right, .. }) => self.expressions([left.as_ref(), right.as_ref()], offset),
+            Expression::AssignExpression(AssignExpression { target, value, .. }) => self.expressions([target.as_ref(), value.as_ref()], offset),
+            Expression::IfExpression(IfExpression {
+                condition,
+                consequence,
+                alternative,
+                ..
+            }) => {
+                if contains(condition.span(), offset) {
+                    self.expression(condition, offset);
+                } else if contains(consequence.span, offset) {
+                    self.statements(&consequence.statements, offset);
+                } else if let Some(alternative) = alternative {
+                    self.statements(&alternative.statements, offset);
+                }
+            }
+            Expression::WhileExpression(WhileExpression { condition, body, .. }) => match contains(condition.span(), offset) {
+                true => self.expression(condition, offset),
+                false => self.statements(&body.statements, offset),
+            },
+            Expression::ForExpression(ForExpression { name, iterable, body, .. }) => {
+                if contains(iterable.span(), offset) {
+                    return self.expression(iterable, offset);
+                }
+
+                if let Some(DataType {
+                    data_type: DataTypeKind::Array(element),
+                    ..
+                }) = self.type_of(iterable)
+                {
+                    self.compiler.declares.set(name.value.clone(), *element);
+                }
+
+                self.bind(name, BindingKind::Variable);
+
+                match contains(name.span, offset) {
+                    true => self.target = Some(Target::Value(name.clone())),
+                    false => self.statements(&body.statements, offset),
+                }
+            }
+            Expression::LoopExpression(LoopExpression { body, .. }) => self.statements(&body.statements, offset),
+            Expression::MatchExpression(MatchExpression { value, arms, .. }) => {
+                if contains(value.span(), offset) {
+                    return self.expression(value, offset);
+                }
+
+                let Some(MatchArm { pattern, guard, body, .. }) = arms.iter().find(|arm| contains(arm.span, offset)) else {
+                    return;
+                };
+
+                // the names the pattern binds are visible in the guard and body of its arm
+                if let Some(value_type) = self.type_of(value) {
+                    if let Ok(bindings) = pattern_bindings(&compile_pattern(pattern), &value_type, &self.compiler.customs) {
+                        for (name, data_type) in bindings {
+                            self.compiler.declares.set(name, data_type);
+                        }
+                    }
+                }
+
+                for name in pattern_names(pattern) {
+                    self.bind(name, BindingKind::Variable);
+                }
+
+                match contains(pattern.span(), offset) {
+                    true => self.pattern(pattern, offset),
+                    false => self.expressions(guard.iter().chain(iter::once(body)), offset),
+                }
+            }
+            Expression::BreakExpression(BreakExpression { value, .. }) => {
+                if let Some(value) = value {
+                    self.expression(value, offset);
+                }
+            }
+            Expression::FunctionLiteral(function) => self.function(function, offset),
+            Expression::CallExpression(CallExpression { function, arguments, .. }) => {
+                self.expressions(iter::once(function.as_ref()).chain(arguments.iter()), offset)
+            }
+            Expression::TypeofExpression(TypeofExpression { expression, .. }) => self.expression(expression, offset),
+            Expression::IndexExpression(IndexExpression { left, index, .. }) => self.expressions([left.as_ref(), index.as_ref()], offset),
+            Expression::FieldExpression(FieldExpression { left, field, .. }) => match contains(field.span, offset) {
+                true => self.target = Some(Target::Expression(expression.clone(), field.span)),
+                false => self.expression(left, offset),
+            },
+            Expression::TryExpression(TryExpression { value, .. }) => self.expression(value, offset),
+            Expression::ArrayLiteral(ArrayLiteral { elements, .. }) => self.expressions(elements.iter(), offset),
+            Expression::StructLiteral(StructLiteral { name, fields, .. }) => match contains(name.span, offset) {
+                true => self.target = Some(Target::Type(name.clone())),
+                false => self.expressions(fields.iter().map(|(_, value)| value), offset),
+            },
+            Expression::EnumLiteral(EnumLiteral { name, arguments, .. }) => match contains(name.span, offset) {
+                true => self.target = Some(Target::Type(name.clone())),
+                false => self.expressions(arguments.iter(), offset),
+            },
+            Expression::StringLiteral(_)
+            | Expression::IntLiteral(_)
+            | Expression::FloatLiteral(_)
+            | Expression::BooleanLiteral(_)
+            | Expression::ContinueExpression(_) => {}
+        }
+    }
+
+    fn expressions<'a>(&mut self, expressions: impl IntoIterator<Item = &'a Expression>, offset: usize) {
+        if let Some(expression) = expressions.into_iter().find(|expression| contains(expression.span(), offset)) {
+            self.expression(expression, offset);
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern, offset: usize) {
+        match pattern {
+            Pattern::BindingPattern(BindingPattern { name, .. }) => self.target = Some(Target::Value(name.clone())),
+            Pattern::StructPattern(StructPattern { name, .. }) | Pattern::EnumPattern(EnumPattern { name, .. }) if contains(name.span, offset) => {
+                self.target = Some(Target::Type(name.clone()))
+            }
+            Pattern::StructPattern(StructPattern { fields, .. }) => self.patterns(fields.iter().map(|(_, pattern)| pattern), offset),
+            Pattern::EnumPattern(EnumPattern { payload, .. }) => self.patterns(payload.iter(), offset),
+            Pattern::ArrayPattern(ArrayPattern { elements, rest, .. }) => self.patterns(elements.iter().chain(rest.as_deref()), offset),
+            Pattern::WildcardPattern(_) | Pattern::LiteralPattern(_) => {}
+        }
+    }
+
+    fn patterns<'a>(&mut self, patterns: impl IntoIterator<Item = &'a Pattern>, offset: usize) {
+        if let Some(pattern) = patterns.into_iter().find(|pattern| contains(pattern.span(), offset)) {
+            self.pattern(pattern, offset);
+        }
+    }
+
+    fn function(&mut self, function: &FunctionLiteral, offset: usize) {
+        let FunctionLiteral {
+            generics,
+            parameters,
+            return_type,
+            body,
+            ..
+        } = function;
+
+        for generic in generics.iter().flatten() {
+            self.compiler.customs.set(
+                generic.value.clone(),
+                DataType::new(DataTypeKind::TypeParameter(generic.value.clone()), generic.span),
+            );
+            self.bind(generic, BindingKind::Type);
+        }
+
+        for parameter in parameters.iter() {
+            let data_type = custom_data_type(&parameter.data_type, &self.compiler.customs).unwrap_or_else(|_| parameter.data_type.clone());
+            let data_type = match parameter.spread {
+                true => DataType::new(DataTypeKind::Array(Box::new(data_type)), parameter.span),
+                false => data_type,
+            };
+
+            self.compiler.declares.set(parameter.name.value.clone(), data_type);
+            self.bind(&parameter.name, BindingKind::Parameter);
+        }
+
+        // so that `?` in the body is checked against it
+        self.compiler.return_type = Some(custom_data_type(return_type, &self.compiler.customs).unwrap_or_else(|_| return_type.clone()));
+
+        if let Some(parameter) = parameters.iter().find(|parameter| contains(parameter.span, offset)) {
+            match contains(parameter.name.span, offset) {
+                true => self.target = Some(Target::Value(parameter.name.clone())),
+                false => self.data_type(&parameter.data_type, offset),
+            }
+        } else if contains(return_type.span, offset) {
+            self.data_type(return_type, offset);
+        } else if contains(body.span, offset) {
+            self.statements(&body.statements, offset);
+        }
+    }
+
+    fn data_type(&mut self, data_type: &DataType, offset: usize) {
+        match &data_type.data_type {
+            DataTypeKind::Custom(name) => {
+                let span = Span::new(data_type.span.file, data_type.span.start, data_type.span.start + name.len());
+                self.target = Some(Target::Type(Identifier::new(name.clone(), span)));
+            }
+            DataTypeKind::Generic(Generic(base, arguments)) => self.data_types(iter::once(base.as_ref()).chain(arguments.iter()), offset),
+            DataTypeKind::Array(element) => self.data_types([element.as_ref()], offset),
+            DataTypeKind::Fn(FunctionType { parameters, return_type, .. }) => self.data_types(
+                parameters.iter().map(|(data_type, _)| data_type).chain(iter::once(return_type.as_ref())),
+                offset,
+            ),
+            _ => {}
+        }
+    }
+
+    fn data_types<'a>(&mut self, data_types: impl IntoIterator<Item = &'a DataType>, offset: usize) {
+        if let Some(data_type) = data_types.into_iter().find(|data_type| contains(data_type.span, offset)) {
+            self.data_type(data_type, offset);
+        }
+    }
+}
+
+/// The names a pattern binds.
+fn pattern_names(pattern: &Pattern) -> Vec<&Identifier> {
+    match pattern {
+        Pattern::BindingPattern(BindingPattern { name, .. }) => vec![name],
+        Pattern::StructPattern(StructPattern { fields, .. }) => fields.iter().flat_map(|(_, pattern)| pattern_names(pattern)).collect(),
+        Pattern::EnumPattern(EnumPattern { payload, .. }) => payload.iter().flat_map(pattern_names).collect(),
+        Pattern::ArrayPattern(ArrayPattern { elements, rest, .. }) => elements.iter().chain(rest.as_deref()).flat_map(pattern_names).collect(),
+        Pattern::WildcardPattern(_) | Pattern::LiteralPattern(_) => Vec::new(),
+    }
+}
+
+/// Whether the cursor at `offset` touches the span; a cursor right after an identifier still refers to it.
+fn contains(span: Span, offset: usize) -> bool {
+    span.start <= offset && offset <= span.end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+declare println = fn(string) -> void;
+type Name = string;
+struct Point { x: int, y: int }
+
+let origin: Point = struct Point { x: 0, y: 0 };
+auto greet = fn(name: Name) -> void {
+    auto message = name;
+    return println(message);
+};
+greet(\"world\");
+origin.x;
+";
+
+    fn offset(needle: &str, occurrence: usize) -> usize {
+        SOURCE.match_indices(needle).nth(occurrence).map(|(index, _)| index).unwrap()
+    }
+
+    #[test]
+    fn hover_test() {
+        let analysis = Analysis::new(SOURCE);
+
+        assert_eq!(
+            analysis.hover(offset("origin", 1)).map(|(_, hover)| hover),
+            Some("origin: Point".to_string())
+        );
+        assert_eq!(
+            analysis.hover(offset("message", 1)).map(|(_, hover)| hover),
+            Some("message: String".to_string())
+        );
+        assert_eq!(analysis.hover(offset("x;", 0)).map(|(_, hover)| hover), Some("Int".to_string()));
+        assert_eq!(
+            analysis.hover(offset("Name", 1)).map(|(_, hover)| hover),
+            Some("type Name = String".to_string())
+        );
+        assert_eq!(analysis.hover(offset("\"world\"", 0)), None);
+    }
+
+    #[test]
+    fn definition_test() {
+        let analysis = Analysis::new(SOURCE);
+        let definition = |needle, occurrence| analysis.definition(offset(needle, occurrence)).map(|span| span.start);
+
+        assert_eq!(definition("greet", 1), Some(offset("greet", 0)));
+        assert_eq!(definition("name;", 0), Some(offset("name:", 0)));
+        assert_eq!(definition("println", 1), Some(offset("println", 0)));
+        assert_eq!(definition("Point", 1), Some(offset("Point", 0)));
+        assert_eq!(definition("Name", 1), Some(offset("Name", 0)));
+    }
+
+    #[test]
+    fn completion_test() {
+        let analysis = Analysis::new(SOURCE);
+        let names = |offset| analysis.completions(offset).into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+
+        // a function can call itself, so it is in scope inside its own body, as are the functions of the prelude
+        assert_eq!(
+            names(offset("auto message", 0)),
+            vec![
+                "and_then",
+                "greet",
+                "is_some",
+                "map",
+                "name",
+                "origin",
+                "println",
+                "to_float",
+                "to_int",
+                "unwrap_or"
+            ]
+        );
+        // names declared in a function body end with it
+        assert_eq!(
+            names(SOURCE.len()),
+            vec![
+                "and_then",
+                "greet",
+                "is_some",
+                "map",
+                "origin",
+                "println",
+                "to_float",
+                "to_int",
+                "unwrap_or"
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostics_test() {
+        let analysis = Analysis::new("let x: int = \"foo\";\nlet y: string = x;\nlet = 5;\n");
+        let codes = analysis.diagnostics().iter().map(|diagnostic| diagnostic.code).collect::<Vec<_>>();
+
+        // `x` keeps its annotated type, so the second statement is checked against `int`
+        assert_eq!(codes, vec!["E0004", "E0100", "E0100"]);
+
+        // top-level functions can call each other before they are defined, but other code cannot
+        let analysis = Analysis::new(
+            "auto even = fn(n: int) -> boolean => if n == 0 { true } else { odd(n - 1) };\nodd(1);\n\
+             auto odd = fn(n: int) -> boolean => if n == 0 { false } else { even(n - 1) };\n",
+        );
+        let codes = analysis.diagnostics().iter().map(|diagnostic| diagnostic.code).collect::<Vec<_>>();
+
+        assert_eq!(codes, vec!["E0102"]);
+    }
+
+    #[test]
+    fn nesting_test() {
+        let depth = sntk_core::parser::parser::MAX_NESTING_DEPTH - 1;
+
+        // on the stack the server runs with, source nested as deeply as the parser allows is analyzed, and anything deeper
+        // is a diagnostic rather than a crash
+        let analyze = move || {
+            for (open, close) in [("(", ")"), ("[", "]"), ("-(", ")"), ("fn() -> int => ", ""), ("a + ", "")] {
+                let source = |depth: usize| format!("auto a = 1;\nauto b = {}a{};\n", open.repeat(depth), close.repeat(depth));
+
+                let analysis = Analysis::new(&source(depth));
+                analysis.diagnostics();
+                analysis.hover(source(depth).rfind('a').unwrap());
+
+                let codes = Analysis::new(&source(1000))
+                    .diagnostics()
+                    .iter()
+                    .map(|diagnostic| diagnostic.code)
+                    .collect::<Vec<_>>();
+                assert_eq!(codes, vec!["E0012"], "{open}");
+            }
+        };
+
+        std::thread::Builder::new()
+            .stack_size(crate::STACK_SIZE)
+            .spawn(analyze)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn import_test() {
+        let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples");
+        let source = "import { Shape, area } from \"./lib/shapes.sntk\";\nimport { pi } from \"./lib/shapes.sntk\";\narea(Shape::Circle(1.0));\n";
+        let analysis = Analysis::in_directory(source, &examples);
+
+        // `pi` is not exported, while `Shape` and `area` are, with their types
+        let codes = analysis.diagnostics().iter().map(|diagnostic| diagnostic.code).collect::<Vec<_>>();
+        assert_eq!(codes, vec!["E0138"]);
+
+        let offset = source.rfind("area").unwrap();
+        assert_eq!(analysis.hover(offset).unwrap().1, "area: fn(Shape) -> Float");
+        assert_eq!(analysis.definition(offset).map(|span| span.start), source.find("area"));
+    }
+
+    #[test]
+    fn symbols_test() {
+        let symbols = Analysis::new(SOURCE).symbols();
+
+        assert_eq!(
+            symbols.iter().map(|symbol| (symbol.name.as_str(), symbol.kind)).collect::<Vec<_>>(),
+            vec![
+                ("println", BindingKind::Function),
+                ("Name", BindingKind::Type),
+                ("Point", BindingKind::Struct),
+                ("origin", BindingKind::Variable),
+                ("greet", BindingKind::Function),
+            ]
+        );
+        assert_eq!(symbols[2].children.len(), 2);
+        assert_eq!(symbols[4].children[0].name, "message");
+    }
+}
@@ -0,0 +1,27 @@
+use lsp_types::{Position, Range};
+use sntk_core::{line_index, parser::ast::Span};
+
+/// Converts between byte offsets and LSP positions, whose columns count UTF-16 code units.
+#[derive(Debug)]
+pub struct LineIndex<'a>(line_index::LineIndex<'a>);
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        LineIndex(line_index::LineIndex::new(source))
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let location = self.0.location(offset);
+
+        Position::new(location.line as u32, location.utf16_column as u32 - 1)
+    }
+
+    /// The byte offset of a position, clamped to the end of its line (or of the source, past the last line).
+    pub fn offset(&self, position: Position) -> usize {
+        self.0.offset(position.line as usize, position.character as usize)
+    }
+
+    pub fn range(&self, span: Span) -> Range {
+        Range::new(self.position(span.start), self.position(span.end))
+    }
+}
@@ -0,0 +1,31 @@
+mod analysis;
+mod line_index;
+mod server;
+
+use lsp_server::Connection;
+use server::{Server, ServerResult};
+use std::thread;
+
+/// Room for analyzing source nested as deeply as the parser allows, with the large frames of a debug build.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+fn main() -> ServerResult<()> {
+    // the analysis recurses as deeply as the source nests, which takes more than the stack of the main thread
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(serve)?
+        .join()
+        .map_err(|_| "the server panicked")?
+}
+
+fn serve() -> ServerResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    connection.initialize(serde_json::to_value(server::capabilities())?)?;
+    Server::default().run(&connection)?;
+
+    drop(connection);
+    io_threads.join()?;
+
+    Ok(())
+}
@@ -0,0 +1,312 @@
+use crate::{
+    analysis::{Analysis, BindingKind, Symbol},
+    line_index::LineIndex,
+};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _, PublishDiagnostics},
+    request::{Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as _},
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse, DiagnosticRelatedInformation, DiagnosticSeverity,
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, Location, MarkupContent, MarkupKind,
+    NumberOrString, OneOf, PublishDiagnosticsParams, ServerCapabilities, SymbolKind, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use sntk_core::{
+    diagnostic::{Diagnostic, Severity},
+    parser::ast::DataTypeKind,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+
+pub type ServerResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+pub fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        ..Default::default()
+    }
+}
+
+/// Keeps the text of every open document, re-analyzing it from scratch for each request.
+#[derive(Debug, Default)]
+pub struct Server {
+    documents: HashMap<Url, String>,
+}
+
+impl Server {
+    pub fn run(&mut self, connection: &Connection) -> ServerResult<()> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+
+                    connection.sender.send(Message::Response(self.handle_request(request)))?;
+                }
+                Message::Notification(notification) => {
+                    if let Some(diagnostics) = self.handle_notification(notification) {
+                        connection.sender.send(Message::Notification(Notification::new(
+                            PublishDiagnostics::METHOD.to_string(),
+                            diagnostics,
+                        )))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+
+        // a bug in the analysis of one document should cost one answer rather than the whole session
+        let result = panic::catch_unwind(AssertUnwindSafe(|| match request.method.as_str() {
+            HoverRequest::METHOD => Some(self.dispatch::<HoverRequest>(request, Server::hover)),
+            GotoDefinition::METHOD => Some(self.dispatch::<GotoDefinition>(request, Server::definition)),
+            DocumentSymbolRequest::METHOD => Some(self.dispatch::<DocumentSymbolRequest>(request, Server::symbols)),
+            Completion::METHOD => Some(self.dispatch::<Completion>(request, Server::completion)),
+            _ => None,
+        }));
+
+        match result {
+            Ok(Some(Ok(value))) => Response::new_ok(id, value),
+            Ok(Some(Err(message))) => Response::new_err(id, ErrorCode::InvalidParams as i32, message),
+            Ok(None) => Response::new_err(id, ErrorCode::MethodNotFound as i32, "unsupported request".to_string()),
+            Err(_) => Response::new_err(id, ErrorCode::InternalError as i32, "the document could not be analyzed".to_string()),
+        }
+    }
+
+    fn dispatch<R: lsp_types::request::Request>(
+        &self,
+        request: Request,
+        handler: fn(&Self, R::Params) -> R::Result,
+    ) -> Result<serde_json::Value, String> {
+        let params = serde_json::from_value(request.params).map_err(|error| error.to_string())?;
+
+        serde_json::to_value(handler(self, params)).map_err(|error| error.to_string())
+    }
+
+    /// Updates the open documents, returning the diagnostics to publish for the one that changed.
+    fn handle_notification(&mut self, notification: Notification) -> Option<PublishDiagnosticsParams> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = serde_json::from_value::<DidOpenTextDocumentParams>(notification.params).ok()?;
+                self.documents.insert(params.text_document.uri.clone(), params.text_document.text);
+
+                Some(self.diagnostics(params.text_document.uri, Some(params.text_document.version)))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = serde_json::from_value::<DidChangeTextDocumentParams>(notification.params).ok()?;
+
+                // documents are synchronized in full, so the last change holds the whole text
+                let text = params.content_changes.into_iter().last()?.text;
+                self.documents.insert(params.text_document.uri.clone(), text);
+
+                Some(self.diagnostics(params.text_document.uri, Some(params.text_document.version)))
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = serde_json::from_value::<DidCloseTextDocumentParams>(notification.params).ok()?;
+                self.documents.remove(&params.text_document.uri);
+
+                Some(PublishDiagnosticsParams::new(params.text_document.uri, Vec::new(), None))
+            }
+            _ => None,
+        }
+    }
+
+    fn diagnostics(&self, uri: Url, version: Option<i32>) -> PublishDiagnosticsParams {
+        let source = self.documents.get(&uri).map(String::as_str).unwrap_or_default();
+        let index = LineIndex::new(source);
+
+        let diagnostics = panic::catch_unwind(|| analysis(&uri, source).diagnostics())
+            .unwrap_or_default()
+            .iter()
+            .map(|diagnostic| to_lsp_diagnostic(&uri, &index, diagnostic))
+            .collect();
+
+        PublishDiagnosticsParams::new(uri, diagnostics, version)
+    }
+
+    fn document(&self, uri: &Url) -> Option<(&str, Analysis)> {
+        let source = self.documents.get(uri)?;
+
+        Some((source, analysis(uri, source)))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let position = params.text_document_position_params;
+        let (source, analysis) = self.document(&position.text_document.uri)?;
+        let index = LineIndex::new(source);
+        let (span, hover) = analysis.hover(index.offset(position.position))?;
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```sntk\n{hover}\n```"),
+            }),
+            range: Some(index.range(span)),
+        })
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = params.text_document_position_params;
+        let (source, analysis) = self.document(&position.text_document.uri)?;
+        let index = LineIndex::new(source);
+        let span = analysis.definition(index.offset(position.position))?;
+
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            position.text_document.uri,
+            index.range(span),
+        )))
+    }
+
+    fn symbols(&self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+        let (source, analysis) = self.document(&params.text_document.uri)?;
+        let index = LineIndex::new(source);
+
+        Some(DocumentSymbolResponse::Nested(
+            analysis.symbols().iter().map(|symbol| to_lsp_symbol(&index, symbol)).collect(),
+        ))
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let position = params.text_document_position;
+        let (source, analysis) = self.document(&position.text_document.uri)?;
+        let offset = LineIndex::new(source).offset(position.position);
+
+        Some(CompletionResponse::Array(
+            analysis
+                .completions(offset)
+                .into_iter()
+                .map(|(name, data_type)| CompletionItem {
+                    kind: Some(match data_type.data_type {
+                        DataTypeKind::Fn(_) => CompletionItemKind::FUNCTION,
+                        _ => CompletionItemKind::VARIABLE,
+                    }),
+                    detail: Some(data_type.to_string()),
+                    ..CompletionItem::new_simple(name, String::new())
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// Analyzes a document, loading the modules it imports from its directory when it is a file.
+fn analysis(uri: &Url, source: &str) -> Analysis {
+    match uri.to_file_path().ok().as_deref().and_then(Path::parent) {
+        Some(directory) => Analysis::in_directory(source, directory),
+        None => Analysis::new(source),
+    }
+}
+
+fn to_lsp_diagnostic(uri: &Url, index: &LineIndex, diagnostic: &Diagnostic) -> lsp_types::Diagnostic {
+    let mut message = diagnostic.message.clone();
+
+    for note in diagnostic.notes.iter() {
+        message.push_str(&format!("\nnote: {note}"));
+    }
+
+    if let Some(help) = &diagnostic.help {
+        message.push_str(&format!("\nhelp: {help}"));
+    }
+
+    let related = diagnostic
+        .labels
+        .iter()
+        .filter(|label| !label.primary)
+        .filter_map(|label| {
+            Some(DiagnosticRelatedInformation {
+                location: Location::new(uri.clone(), index.range(label.span)),
+                message: label.message.clone()?,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    lsp_types::Diagnostic {
+        range: diagnostic.primary_span().map(|span| index.range(span)).unwrap_or_default(),
+        severity: Some(match diagnostic.severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+        }),
+        code: Some(NumberOrString::String(diagnostic.code.to_string())),
+        source: Some("sntk".to_string()),
+        message,
+        related_information: (!related.is_empty()).then_some(related),
+        ..Default::default()
+    }
+}
+
+#[allow(deprecated)] // `DocumentSymbol::deprecated` has to be initialized even though it is superseded by `tags`
+fn to_lsp_symbol(index: &LineIndex, symbol: &Symbol) -> DocumentSymbol {
+    DocumentSymbol {
+        name: symbol.name.clone(),
+        detail: symbol.detail.clone(),
+        kind: match symbol.kind {
+            BindingKind::Variable | BindingKind::Parameter => SymbolKind::VARIABLE,
+            BindingKind::Function => SymbolKind::FUNCTION,
+            BindingKind::Type => SymbolKind::TYPE_PARAMETER,
+            BindingKind::Struct => SymbolKind::STRUCT,
+            BindingKind::Field => SymbolKind::FIELD,
+            BindingKind::Enum => SymbolKind::ENUM,
+            BindingKind::Variant => SymbolKind::ENUM_MEMBER,
+        },
+        tags: None,
+        deprecated: None,
+        range: index.range(symbol.span),
+        selection_range: index.range(symbol.name_span),
+        children: (!symbol.children.is_empty()).then(|| symbol.children.iter().map(|child| to_lsp_symbol(index, child)).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Server;
+    use lsp_server::{Connection, Message, Notification};
+    use lsp_types::{
+        notification::{DidOpenTextDocument, Notification as _, PublishDiagnostics},
+        DidOpenTextDocumentParams, NumberOrString, PublishDiagnosticsParams, TextDocumentItem, Url,
+    };
+    use std::thread;
+
+    #[test]
+    fn deep_document_test() {
+        let (server, client) = Connection::memory();
+        let running = thread::Builder::new()
+            .stack_size(crate::STACK_SIZE)
+            .spawn(move || Server::default().run(&server))
+            .unwrap();
+
+        // a chain of operators far longer than the parser lets source nest is one diagnostic, not a crashed server
+        let text = format!("auto x = 1{};\n", " + 1".repeat(200_000));
+        let params = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(Url::parse("untitled:deep.sntk").unwrap(), "sntk".to_string(), 1, text),
+        };
+        client
+            .sender
+            .send(Message::Notification(Notification::new(DidOpenTextDocument::METHOD.to_string(), params)))
+            .unwrap();
+
+        let published = match client.receiver.recv().unwrap() {
+            Message::Notification(notification) if notification.method == PublishDiagnostics::METHOD => {
+                serde_json::from_value::<PublishDiagnosticsParams>(notification.params).unwrap()
+            }
+            message => panic!("expected diagnostics, got {message:?}"),
+        };
+        let codes = published.diagnostics.into_iter().map(|diagnostic| diagnostic.code).collect::<Vec<_>>();
+        assert_eq!(codes, vec![Some(NumberOrString::String("E0012".to_string()))]);
+
+        drop(client);
+        running.join().unwrap().unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+use sntk_compiler::{module::Loader, CompileError};
+use sntk_core::{
+    diagnostic::Diagnostic,
+    formatter::Formatter,
+    parser::{
+        ast::{FileId, Program},
+        parser::Parser,
+    },
+    tokenizer::{
+        lexer::Lexer,
+        token::{Token, TokenKind},
+    },
+};
+use sntk_ir::{instruction::Instruction, interpreter::IrInterpreter, RuntimeError};
+use sntk_js::transpiler::Transpiler;
+use std::{fmt, fs, io, path::Path};
+
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub path: String,
+    pub text: String,
+}
+
+impl Source {
+    #[inline]
+    pub fn new(path: &str, text: &str) -> Self {
+        Source {
+            path: path.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    pub fn read(path: &str) -> DriverResult<Self> {
+        let text = fs::read_to_string(path).map_err(|error| Failure::Io(path.to_string(), error))?;
+
+        Ok(Source::new(path, &text))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+#[derive(Debug)]
+pub enum Failure {
+    Usage(String),
+    Io(String, io::Error),
+    Compile(Box<CompileError>, Box<Source>),
+    Runtime(RuntimeError, Box<Source>),
+    Unformatted(String),
+}
+
+impl Failure {
+    #[inline]
+    pub fn compile(error: CompileError, source: &Source) -> Self {
+        Failure::Compile(Box::new(error), Box::new(source.clone()))
+    }
+
+    #[inline]
+    pub fn runtime(error: RuntimeError, source: &Source) -> Self {
+        Failure::Runtime(error, Box::new(source.clone()))
+    }
+
+    /// `1` for compile errors and unformatted files, `2` for runtime errors, `64` for usage errors and `66` for unreadable or unwritable files.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Failure::Compile(..) | Failure::Unformatted(_) => 1,
+            Failure::Runtime(..) => 2,
+            Failure::Usage(_) => 64,
+            Failure::Io(..) => 66,
+        }
+    }
+
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            Failure::Compile(error, _) => error.diagnostics(),
+            Failure::Runtime(error, _) => vec![Diagnostic::from(error)],
+            // files that cannot be read or written, or are not formatted, have no source to point into
+            Failure::Io(path, error) => vec![Diagnostic::error("E0300", format!("Cannot access `{path}`: {error}"))],
+            Failure::Unformatted(path) => {
+                vec![Diagnostic::error("E0301", format!("`{path}` is not formatted")).with_help(Some(format!("run `sntkc fmt {path}`")))]
+            }
+            Failure::Usage(_) => Vec::new(),
+        }
+    }
+
+    pub fn report(&self, format: MessageFormat) -> String {
+        let (path, text) = match self {
+            Failure::Compile(_, source) | Failure::Runtime(_, source) => (&source.path, source.text.as_str()),
+            Failure::Io(path, _) | Failure::Unformatted(path) => (path, ""),
+            Failure::Usage(message) => return message.clone(),
+        };
+
+        let diagnostics = self.diagnostics().into_iter();
+
+        match format {
+            MessageFormat::Human => diagnostics.map(|diagnostic| diagnostic.render(path, text)).collect::<Vec<_>>().join("\n"),
+            MessageFormat::Json => diagnostics
+                .map(|diagnostic| diagnostic.to_json(path, text))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.report(MessageFormat::Human).trim_end())
+    }
+}
+
+pub type DriverResult<T> = Result<T, Failure>;
+
+pub fn tokenize(source: &str) -> Vec<Token> {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut tokens = Vec::new();
+
+    loop {
+        let token = lexer.next_token();
+
+        if token.kind == TokenKind::EOF {
+            break tokens;
+        }
+
+        tokens.push(token);
+    }
+}
+
+pub fn parse(source: &Source) -> DriverResult<Program> {
+    let program = Parser::from(source.text.clone()).parse_program();
+
+    if !program.errors.is_empty() {
+        return Err(Failure::compile(CompileError::ParsingError(program.errors), source));
+    }
+
+    Ok(program)
+}
+
+/// Formats the source, keeping its expressions as written.
+pub fn format(source: &Source) -> DriverResult<String> {
+    let mut parser = Parser::from(source.text.clone());
+    parser.verbatim = true;
+
+    let program = parser.parse_program();
+
+    if !program.errors.is_empty() {
+        return Err(Failure::compile(CompileError::ParsingError(program.errors), source));
+    }
+
+    Ok(Formatter::new(&source.text).format_program(&program))
+}
+
+/// Compiles the source along with the modules it imports and the definitions it uses from the prelude.
+pub fn compile(source: &Source) -> DriverResult<Vec<Instruction>> {
+    load(source).map(|(instructions, _)| instructions)
+}
+
+pub fn run(source: &Source) -> DriverResult<()> {
+    let (instructions, loader) = load(source)?;
+
+    IrInterpreter::new(instructions).eval().map_err(|error| {
+        let module = module_source(&loader, error.span.file, source);
+        Failure::runtime(error, &module)
+    })
+}
+
+/// Compiles the source, keeping the loader so that errors in the modules it imports can be reported against their own sources.
+fn load(source: &Source) -> DriverResult<(Vec<Instruction>, Loader)> {
+    let directory = Path::new(&source.path).parent().filter(|directory| !directory.as_os_str().is_empty());
+    let mut loader = Loader::new(directory.unwrap_or(Path::new(".")));
+
+    match loader.load(&source.text) {
+        Ok(instructions) => Ok((instructions, loader)),
+        Err(error) => {
+            let module = module_source(&loader, error.file(), source);
+            Err(Failure::compile(error, &module))
+        }
+    }
+}
+
+/// The source of the module `file` is in, which is `source` itself unless the file is one of the modules it imports.
+pub fn module_source(loader: &Loader, file: FileId, source: &Source) -> Source {
+    match loader.source(file) {
+        Some((path, text)) => Source::new(&path.display().to_string(), text),
+        None => source.clone(),
+    }
+}
+
+pub fn build(source: &Source, output: &Path) -> DriverResult<()> {
+    let instructions = compile(source)?.iter().map(|instruction| format!("{instruction}\n")).collect::<String>();
+
+    fs::write(output, instructions).map_err(|error| Failure::Io(output.display().to_string(), error))
+}
+
+pub fn transpile(source: &Source, output: &Path) -> DriverResult<()> {
+    let javascript = Transpiler::new(compile(source)?).transpile();
+
+    fs::write(output, javascript).map_err(|error| Failure::Io(output.display().to_string(), error))
+}
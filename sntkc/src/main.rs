@@ -1,57 +1,187 @@
-#![allow(unused_imports)]
+mod driver;
+mod repl;
 
-use sntk_compiler::{compiler::Compiler, CompileError};
-use sntk_core::{
-    parser::parser::Parser,
-    tokenizer::{lexer::Lexer, token::TokenKind},
-};
-use sntk_ir::interpreter::IrInterpreter;
-use std::time::Instant;
+use driver::{DriverResult, Failure, MessageFormat, Source};
+use sntk_core::line_index::LineIndex;
+use sntk_ir::interpreter::STACK_SIZE;
+use std::{env, fs, path::PathBuf, process::ExitCode, thread};
 
-fn main() {
-    let mut start = Instant::now();
+const USAGE: &str = "\
+Usage: sntkc [--message-format=human|json] <command> [options] <file.sntk>
 
-    let source_code = r#"
-declare println = fn(number[]) -> boolean; // todo
+Commands:
+    run <file>                  compile and run a program
+    check <file>                parse and type check a program without running it
+    build <file> [-o <output>]  compile a program and write its IR (default: <file>.sntkir)
+    js <file> [-o <output>]     compile a program to JavaScript (default: <file>.js)
+    fmt <file> [--check]        format a program in place, or only check that it is formatted
+    tokens <file>               print the token stream
+    ast <file>                  print the abstract syntax tree
+    ir <file>                   print the compiled IR instructions
+    repl                        start an interactive session
+    help                        print this message
 
-type F = fn(boolean) -> number[];
+Options:
+    --message-format=<format>   print errors for humans (default) or as one JSON object per line
 
-auto x = fn(a: number, b: number, spread c: number) ->
-    fn(string) -> F
-{
-    return fn(x: string) -> F =>
-            fn(y: boolean) -> number[] => c;
-};
-println(x(1, 2, 3, 4, 5, 6, 7, 8, 9, 10)("foo")(true));
-    "#
-    .trim_start();
+Exit codes:
+    1   compile error (parsing or type checking), or an unformatted file with `fmt --check`
+    2   runtime error
+    64  invalid usage
+    66  file could not be read or written";
 
-    #[allow(unused_mut)]
-    let mut lexer = Lexer::new(source_code.to_string());
+#[derive(Debug)]
+enum Command {
+    Run(String),
+    Check(String),
+    Build(String, Option<String>),
+    JavaScript(String, Option<String>),
+    Format(String, bool),
+    Tokens(String),
+    Ast(String),
+    Ir(String),
+    Repl,
+    Help,
+}
 
-    // let mut next_token = lexer.next_token();
-    // while next_token.token_type != TokenKind::EOF {
-    //     println!("{:?}", next_token);
-    //     next_token = lexer.next_token();
-    // }
+impl Command {
+    fn parse(arguments: &[String]) -> DriverResult<Self> {
+        let (command, rest) = match arguments.split_first() {
+            Some((command, rest)) => (command.as_str(), rest),
+            None => return Ok(Command::Help),
+        };
 
-    let parsed = Parser::new(lexer).parse_program();
+        Ok(match command {
+            "run" => Command::Run(source_file(command, rest)?),
+            "check" => Command::Check(source_file(command, rest)?),
+            "build" => match rest {
+                [file, flag, output] | [flag, output, file] if flag == "-o" || flag == "--output" => {
+                    Command::Build(file.clone(), Some(output.clone()))
+                }
+                rest => Command::Build(source_file(command, rest)?, None),
+            },
+            "js" => match rest {
+                [file, flag, output] | [flag, output, file] if flag == "-o" || flag == "--output" => {
+                    Command::JavaScript(file.clone(), Some(output.clone()))
+                }
+                rest => Command::JavaScript(source_file(command, rest)?, None),
+            },
+            "fmt" => match rest {
+                [file, flag] | [flag, file] if flag == "--check" => Command::Format(file.clone(), true),
+                rest => Command::Format(source_file(command, rest)?, false),
+            },
+            "tokens" => Command::Tokens(source_file(command, rest)?),
+            "ast" => Command::Ast(source_file(command, rest)?),
+            "ir" => Command::Ir(source_file(command, rest)?),
+            "repl" => match rest {
+                [] => Command::Repl,
+                rest => return Err(Failure::Usage(format!("unexpected arguments for `repl`: {}\n\n{USAGE}", rest.join(" ")))),
+            },
+            "help" | "-h" | "--help" => Command::Help,
+            command => return Err(Failure::Usage(format!("unknown command `{command}`\n\n{USAGE}"))),
+        })
+    }
 
-    println!("Parsing Elapsed: {}s", start.elapsed().as_secs_f64());
-    // println!("{:#?}", parsed);
+    fn execute(self) -> DriverResult<()> {
+        match self {
+            Command::Run(path) => driver::run(&Source::read(&path)?),
+            Command::Check(path) => driver::compile(&Source::read(&path)?).map(|_| ()),
+            Command::Build(path, output) => {
+                let output = output.map(PathBuf::from).unwrap_or_else(|| PathBuf::from(&path).with_extension("sntkir"));
+                driver::build(&Source::read(&path)?, &output)
+            }
+            Command::JavaScript(path, output) => {
+                let output = output.map(PathBuf::from).unwrap_or_else(|| PathBuf::from(&path).with_extension("js"));
+                driver::transpile(&Source::read(&path)?, &output)
+            }
+            Command::Format(path, check) => {
+                let source = Source::read(&path)?;
+                let formatted = driver::format(&source)?;
 
-    match Compiler::new(parsed).compile_program() {
-        Ok(instructions) => {
-            println!("Compiling Elapsed: {}s", start.elapsed().as_secs_f64());
-            start = Instant::now();
+                match (formatted == source.text, check) {
+                    (true, _) => Ok(()),
+                    (false, true) => Err(Failure::Unformatted(path)),
+                    (false, false) => fs::write(&path, formatted).map_err(|error| Failure::Io(path, error)),
+                }
+            }
+            Command::Tokens(path) => {
+                let source = Source::read(&path)?;
+                let index = LineIndex::new(&source.text);
+
+                for token in driver::tokenize(&source.text) {
+                    let location = index.location(token.span.start);
+                    println!("{}:{} {} {:?}", location.line + 1, location.column, token.span, token.kind);
+                }
 
-            let mut ir_interpreter = IrInterpreter::new(instructions);
+                Ok(())
+            }
+            Command::Ast(path) => {
+                println!("{:#?}", driver::parse(&Source::read(&path)?)?.statements);
 
-            match ir_interpreter.eval() {
-                Ok(_) => println!("Interpreting Elapsed: {}s", start.elapsed().as_secs_f64()),
-                Err(error) => println!("{:?}", error),
+                Ok(())
             }
+            Command::Ir(path) => {
+                for instruction in driver::compile(&Source::read(&path)?)? {
+                    println!("{instruction}");
+                }
+
+                Ok(())
+            }
+            Command::Repl => repl::Repl::default().run(),
+            Command::Help => {
+                println!("{USAGE}");
+
+                Ok(())
+            }
+        }
+    }
+}
+
+fn source_file(command: &str, arguments: &[String]) -> DriverResult<String> {
+    match arguments {
+        [file] => Ok(file.clone()),
+        [] => Err(Failure::Usage(format!("`{command}` expects a source file\n\n{USAGE}"))),
+        [_, rest @ ..] => Err(Failure::Usage(format!(
+            "unexpected arguments for `{command}`: {}\n\n{USAGE}",
+            rest.join(" ")
+        ))),
+    }
+}
+
+fn main() -> ExitCode {
+    let mut format = MessageFormat::default();
+    let mut arguments = Vec::new();
+
+    for argument in env::args().skip(1) {
+        match argument.strip_prefix("--message-format=") {
+            Some("human") => format = MessageFormat::Human,
+            Some("json") => format = MessageFormat::Json,
+            Some(other) => {
+                eprintln!("unknown message format `{other}`, expected `human` or `json`");
+                return ExitCode::from(64);
+            }
+            None => arguments.push(argument),
+        }
+    }
+
+    // the interpreter recurses as deeply as the program it runs does, which takes more than the stack of the main thread
+    let command = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || match Command::parse(&arguments).and_then(Command::execute) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(failure) => {
+                eprintln!("{}", failure.report(format).trim_end());
+                ExitCode::from(failure.exit_code())
+            }
+        });
+
+    match command.map(|command| command.join()) {
+        Ok(Ok(code)) => code,
+        // a panic has already been reported by the thread
+        Ok(Err(_)) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("could not start sntkc: {error}");
+            ExitCode::FAILURE
         }
-        Err(e) => println!("{e}"),
     }
 }
@@ -0,0 +1,248 @@
+use crate::driver::{self, DriverResult, Failure, Source};
+use sntk_compiler::{
+    checker::{Checker, CustomTypes, DeclaredTypes},
+    compiler::Compiler,
+    module::Loader,
+    prelude::Prelude,
+};
+use sntk_core::parser::ast::Program;
+use sntk_ir::{
+    instruction::{Instruction, InstructionType, IrExpression, IrExpressionKind, LiteralValue},
+    interpreter::{IrEnvironment, IrInterpreter},
+};
+use std::io::{self, BufRead, Write};
+
+const REPL: &str = "<repl>";
+
+const HELP: &str = "\
+:type <expr>  print the type of an expression without evaluating it
+:ir <expr>    print the IR of an expression without evaluating it
+:reset        forget every binding and type declared so far
+:help         print this message
+:quit         leave the repl";
+
+/// A read-eval-print loop that keeps the compiler's type environments and the interpreter's value environment alive between inputs.
+#[derive(Debug)]
+pub struct Repl {
+    declares: DeclaredTypes,
+    customs: CustomTypes,
+    environment: IrEnvironment,
+    loader: Loader, // the modules imported so far, relative to the working directory
+}
+
+/// What a session declares on top of the prelude, so that values can be printed without declaring `println` first.
+const DECLARES: &str = "declare println = fn<T>(T) -> void;";
+
+/// A session starts with the whole prelude defined.
+impl Default for Repl {
+    fn default() -> Self {
+        let Prelude {
+            instructions,
+            declares,
+            customs,
+        } = Prelude::default();
+
+        let mut interpreter = IrInterpreter::new(instructions);
+        interpreter.eval().expect("the prelude runs");
+
+        let program = driver::parse(&Source::new(REPL, DECLARES)).expect("the declarations of a session parse");
+        let mut compiler = Compiler::new_with(program, declares, customs);
+        compiler.compile_program().expect("the declarations of a session compile");
+
+        Self {
+            declares: compiler.declares,
+            customs: compiler.customs,
+            environment: interpreter.environment,
+            loader: Loader::new("."),
+        }
+    }
+}
+
+impl Repl {
+    pub fn run(&mut self) -> DriverResult<()> {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut buffer = String::new();
+
+        loop {
+            print!("{}", if buffer.is_empty() { ">>> " } else { "... " });
+            io::stdout().flush().map_err(|error| Failure::Io("stdout".to_string(), error))?;
+
+            let line = match lines.next() {
+                Some(line) => line.map_err(|error| Failure::Io("stdin".to_string(), error))?,
+                None => break,
+            };
+
+            buffer.push_str(&line);
+            buffer.push('\n');
+
+            if depth(&buffer) > 0 {
+                continue;
+            }
+
+            let input = std::mem::take(&mut buffer);
+
+            match input.trim() {
+                "" => {}
+                ":quit" | ":q" => break,
+                ":help" => println!("{HELP}"),
+                ":reset" => *self = Repl::default(),
+                input => {
+                    if let Err(failure) = self.evaluate(input) {
+                        eprintln!("{failure}");
+                    }
+                }
+            }
+        }
+
+        println!();
+
+        Ok(())
+    }
+
+    fn evaluate(&mut self, input: &str) -> DriverResult<()> {
+        if let Some(expression) = input.strip_prefix(":type") {
+            let source = Source::new(REPL, expression);
+            let (expression, checker) = self.inspect(&source)?;
+            println!(
+                "{}",
+                checker
+                    .get_type_from_ir_expression(&expression)
+                    .map_err(|error| Failure::compile(error, &source))?
+            );
+
+            return Ok(());
+        }
+
+        if let Some(expression) = input.strip_prefix(":ir") {
+            println!("{}", self.inspect(&Source::new(REPL, expression))?.0);
+
+            return Ok(());
+        }
+
+        if input.starts_with(':') {
+            return Err(Failure::Usage(format!("unknown command `{input}`, see `:help`")));
+        }
+
+        let source = Source::new(REPL, input);
+        let program = parse(&source)?;
+        let modules = self.loader.imports(&program).map_err(|error| {
+            let module = driver::module_source(&self.loader, error.file(), &source);
+            Failure::compile(error, &module)
+        })?;
+
+        let mut compiler = Compiler::new_with(program, self.declares.clone(), self.customs.clone());
+        compiler.modules = modules;
+        let compiled = compiler.compile_program().map_err(|error| Failure::compile(error, &source))?;
+
+        // modules imported for the first time run ahead of the input
+        let mut instructions = self.loader.take_linked();
+        instructions.extend(compiled);
+
+        // the last bare expression is evaluated separately so that its value can be echoed back.
+        let last = match instructions.last() {
+            Some(Instruction {
+                instruction: InstructionType::Expression(_),
+                ..
+            }) => instructions.pop(),
+            _ => None,
+        };
+
+        let mut interpreter = IrInterpreter::new_with_environment(instructions, self.environment.clone());
+        interpreter.eval().map_err(|error| {
+            let module = driver::module_source(&self.loader, error.span.file, &source);
+            Failure::runtime(error, &module)
+        })?;
+
+        if let Some(Instruction {
+            instruction: InstructionType::Expression(expression),
+            ..
+        }) = last
+        {
+            let data_type = Checker::new(None, &compiler.declares, &compiler.customs)
+                .and_then(|checker| checker.get_type_from_ir_expression(&expression))
+                .map_err(|error| Failure::compile(error, &source))?;
+            let value = interpreter
+                .eval_expression(&expression)
+                .map_err(|error| Failure::runtime(error, &source))?;
+
+            // an expression without a value, as a call to `println`, has nothing to echo
+            if value != LiteralValue::Void {
+                println!("{}: {}", IrExpressionKind::Literal(value), data_type);
+            }
+        }
+
+        self.declares = compiler.declares;
+        self.customs = compiler.customs;
+        self.environment = interpreter.environment;
+
+        Ok(())
+    }
+
+    /// Compiles a single expression against a copy of the session state, so that inspecting it has no side effects.
+    fn inspect(&self, source: &Source) -> DriverResult<(IrExpression, Checker)> {
+        let mut compiler = Compiler::new_with(parse(source)?, self.declares.clone(), self.customs.clone());
+        let instructions = compiler.compile_program().map_err(|error| Failure::compile(error, source))?;
+
+        match instructions.as_slice() {
+            [Instruction {
+                instruction: InstructionType::Expression(expression),
+                ..
+            }] => Ok((
+                expression.clone(),
+                Checker::new(None, &compiler.declares, &compiler.customs).map_err(|error| Failure::compile(error, source))?,
+            )),
+            _ => Err(Failure::Usage("expected a single expression".to_string())),
+        }
+    }
+}
+
+/// Parses the input as-is, retrying with a trailing `;` so that bare expressions can be typed without one.
+fn parse(source: &Source) -> DriverResult<Program> {
+    driver::parse(source).or_else(|failure| driver::parse(&Source::new(REPL, &format!("{};", source.text.trim()))).map_err(|_| failure))
+}
+
+/// The number of unclosed brackets in the input, ignoring the contents of strings and comments.
+fn depth(input: &str) -> isize {
+    let mut depth = 0;
+    let mut chars = input.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth -= 1,
+            '"' => {
+                while let Some(char) = chars.next() {
+                    match char {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for char in chars.by_ref() {
+                    if char == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+
+                let mut previous = '\0';
+                for char in chars.by_ref() {
+                    if previous == '*' && char == '/' {
+                        break;
+                    }
+                    previous = char;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    depth
+}
@@ -0,0 +1,142 @@
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// The programs in `directory`, in a stable order.
+fn programs(directory: &Path) -> Vec<PathBuf> {
+    let mut paths = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "sntk"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    paths
+}
+
+/// What `sntkc run` prints for the program and the code it exits with, run from the directory of the program so that paths
+/// in errors do not depend on where the repository is.
+fn transcript(path: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_sntkc"))
+        .current_dir(path.parent().unwrap())
+        .arg("run")
+        .arg(path.file_name().unwrap())
+        .output()
+        .unwrap();
+
+    let mut transcript = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    if !stderr.is_empty() {
+        transcript.push_str(&format!("--- stderr\n{stderr}"));
+    }
+
+    transcript.push_str(&format!("--- exit code {}\n", output.status.code().unwrap()));
+    transcript
+}
+
+/// Runs every program in `programs` and compares what it prints with `expected/<name>.out`, rewriting the expected output
+/// instead when `BLESS` is set.
+fn compare(programs: &Path, expected: &Path) {
+    let bless = env::var_os("BLESS").is_some();
+    let mut mismatches = Vec::new();
+
+    for path in self::programs(programs) {
+        let actual = transcript(&path);
+        let expected_path = expected.join(path.with_extension("out").file_name().unwrap());
+
+        if bless {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => mismatches.push(format!("{}:\n--- expected\n{expected}--- actual\n{actual}", expected_path.display())),
+            Err(_) => mismatches.push(format!("{}: missing, run with `BLESS=1`", expected_path.display())),
+        }
+    }
+
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+#[test]
+fn examples_test() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    compare(&root.join("../examples"), &root.join("tests/examples"));
+}
+
+/// Programs for what the examples leave out, such as the runtime errors.
+#[test]
+fn programs_test() {
+    let run = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/run");
+
+    compare(&run, &run);
+}
+
+/// What a program prints when run by `sntkc run` and by Node.js on the output of `sntkc js`, and whether it succeeds, which
+/// the two backends must agree on. Skipped with a note where `node` is not installed.
+#[test]
+fn javascript_test() {
+    if Command::new("node").arg("--version").output().is_err() {
+        eprintln!("`node` is not installed, skipping");
+        return;
+    }
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let output = env::temp_dir().join(format!("sntkc-javascript-test-{}", std::process::id()));
+    fs::create_dir_all(&output).unwrap();
+    let mut mismatches = Vec::new();
+
+    for path in [root.join("../examples"), root.join("tests/run")]
+        .iter()
+        .flat_map(|directory| programs(directory))
+    {
+        let javascript = output.join(path.with_extension("js").file_name().unwrap());
+        let run = |command: &mut Command| {
+            let output = command.current_dir(path.parent().unwrap()).output().unwrap();
+            (String::from_utf8(output.stdout).unwrap(), output.status.success())
+        };
+
+        let interpreted = run(Command::new(env!("CARGO_BIN_EXE_sntkc")).arg("run").arg(&path));
+        let (_, transpiled) = run(Command::new(env!("CARGO_BIN_EXE_sntkc")).arg("js").arg(&path).arg("-o").arg(&javascript));
+        assert!(transpiled, "{}: could not be transpiled", path.display());
+        let executed = run(Command::new("node").arg(&javascript));
+
+        if interpreted != executed {
+            mismatches.push(format!(
+                "{}:\n--- sntkc run (success: {})\n{}--- node (success: {})\n{}",
+                path.display(),
+                interpreted.1,
+                interpreted.0,
+                executed.1,
+                executed.0
+            ));
+        }
+    }
+
+    fs::remove_dir_all(&output).unwrap();
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+/// A session can print without declaring `println` first.
+#[test]
+fn repl_test() {
+    let mut repl = Command::new(env!("CARGO_BIN_EXE_sntkc"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    repl.stdin.take().unwrap().write_all(b"auto x = 5;\nprintln(x + 1);\n").unwrap();
+    let output = repl.wait_with_output().unwrap();
+
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), ">>> >>> 6\n>>> \n");
+}